@@ -0,0 +1,555 @@
+//! Agent Configuration
+//! Typed backend connection settings (URL, token source, TLS, proxy, timeouts)
+//! stored in a protected config file and hot-reloadable at runtime
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Default backend (Railway production)
+pub const DEFAULT_BACKEND_URL: &str = "https://cyberguardian-backend-production.up.railway.app";
+
+/// Placeholder returned to the UI instead of secrets
+pub const REDACTED: &str = "********";
+
+// ============================================================================
+// CONFIG TYPES
+// ============================================================================
+
+/// Where the API bearer token comes from
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TokenSource {
+    /// Token of the logged-in UI session (set on login)
+    Session,
+    /// Token stored in the config file
+    Inline { token: String },
+    /// Token read from an environment variable
+    Env { var: String },
+    /// Token read from a file (first line)
+    File { path: String },
+}
+
+/// Client certificate for mutual TLS (PKCS#12 bundle)
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClientCertificate {
+    pub pkcs12_path: String,
+    #[serde(default)]
+    pub password: String,
+}
+
+// Debug output ends up in logs — never print the secrets themselves
+impl std::fmt::Debug for TokenSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenSource::Session => f.write_str("Session"),
+            TokenSource::Inline { .. } => f.debug_struct("Inline").field("token", &REDACTED).finish(),
+            TokenSource::Env { var } => f.debug_struct("Env").field("var", var).finish(),
+            TokenSource::File { path } => f.debug_struct("File").field("path", path).finish(),
+        }
+    }
+}
+
+impl std::fmt::Debug for ClientCertificate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientCertificate")
+            .field("pkcs12_path", &self.pkcs12_path)
+            .field("password", &REDACTED)
+            .finish()
+    }
+}
+
+/// Backend connection settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AgentConfig {
+    /// Set by the installer / config file only — also the isolation allowlist
+    pub backend_url: String,
+    pub token_source: TokenSource,
+    /// Extra PEM CA bundle (self-hosted backends with a private CA)
+    pub ca_bundle_path: Option<String>,
+    pub client_certificate: Option<ClientCertificate>,
+    /// e.g. http://proxy.corp:3128
+    pub proxy_url: Option<String>,
+    pub connect_timeout_secs: u64,
+    pub request_timeout_secs: u64,
+    /// Execute response commands sent by the backend (kill, quarantine, scan...).
+    /// Off by default; needs `command_public_key`
+    pub allow_remote_commands: bool,
+    /// Base64 ed25519 key that signs remote commands. Provisioned by the installer
+    /// or an administrator — never taken from the backend's enrollment reply
+    pub command_public_key: Option<String>,
+}
+
+impl Default for AgentConfig {
+    fn default() -> Self {
+        AgentConfig {
+            backend_url: DEFAULT_BACKEND_URL.to_string(),
+            token_source: TokenSource::Session,
+            ca_bundle_path: None,
+            client_certificate: None,
+            proxy_url: None,
+            connect_timeout_secs: 10,
+            request_timeout_secs: 30,
+            allow_remote_commands: false,
+            command_public_key: None,
+        }
+    }
+}
+
+impl AgentConfig {
+    /// Copy with secrets replaced by REDACTED (safe to hand to the UI)
+    pub fn redacted(&self) -> AgentConfig {
+        let mut config = self.clone();
+        if let TokenSource::Inline { token } = &mut config.token_source {
+            if !token.is_empty() {
+                *token = REDACTED.to_string();
+            }
+        }
+        if let Some(cert) = &mut config.client_certificate {
+            if !cert.password.is_empty() {
+                cert.password = REDACTED.to_string();
+            }
+        }
+        config
+    }
+
+    /// Take the installer/admin-only fields from `stored` (the config file on disk).
+    /// The UI must not be able to authorize command signers or move the backend
+    pub fn keep_admin_fields(&mut self, stored: &AgentConfig) {
+        self.backend_url = stored.backend_url.clone();
+        self.allow_remote_commands = stored.allow_remote_commands;
+        self.command_public_key = stored.command_public_key.clone();
+    }
+
+    /// Put back secrets the UI sent as REDACTED (unchanged fields)
+    pub fn restore_secrets(&mut self, current: &AgentConfig) {
+        if let (TokenSource::Inline { token }, TokenSource::Inline { token: old }) =
+            (&mut self.token_source, &current.token_source)
+        {
+            if token == REDACTED {
+                *token = old.clone();
+            }
+        }
+        if let (Some(cert), Some(old)) = (&mut self.client_certificate, &current.client_certificate) {
+            if cert.password == REDACTED {
+                cert.password = old.password.clone();
+            }
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let url = self.backend_url.trim();
+        if !(url.starts_with("https://") || url.starts_with("http://")) {
+            return Err(format!("Backend URL must start with http:// or https://: {}", url));
+        }
+        if self.connect_timeout_secs == 0 || self.request_timeout_secs == 0 {
+            return Err("Timeouts must be greater than 0 seconds".to_string());
+        }
+        match &self.token_source {
+            TokenSource::Env { var } if var.trim().is_empty() => {
+                return Err("Token environment variable name is empty".to_string());
+            }
+            TokenSource::File { path } if !Path::new(path).is_file() => {
+                return Err(format!("Token file not found: {}", path));
+            }
+            _ => {}
+        }
+        if let Some(ca) = &self.ca_bundle_path {
+            if !Path::new(ca).is_file() {
+                return Err(format!("CA bundle not found: {}", ca));
+            }
+        }
+        if let Some(cert) = &self.client_certificate {
+            if !Path::new(&cert.pkcs12_path).is_file() {
+                return Err(format!("Client certificate not found: {}", cert.pkcs12_path));
+            }
+        }
+        if let Some(key) = &self.command_public_key {
+            use base64::{engine::general_purpose::STANDARD as B64, Engine};
+            if B64.decode(key).map(|k| k.len()).unwrap_or(0) != 32 {
+                return Err("Command public key must be a base64 ed25519 key (32 bytes)".to_string());
+            }
+        } else if self.allow_remote_commands {
+            return Err("Remote commands need a provisioned command public key".to_string());
+        }
+        if let Some(proxy) = &self.proxy_url {
+            reqwest::Proxy::all(proxy.as_str())
+                .map_err(|e| format!("Invalid proxy URL {}: {}", proxy, e))?;
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// GLOBAL STATE
+// ============================================================================
+
+lazy_static::lazy_static! {
+    static ref CONFIG: RwLock<AgentConfig> = RwLock::new(load_config());
+    /// Token of the logged-in UI session (TokenSource::Session)
+    static ref SESSION_TOKEN: RwLock<Option<String>> = RwLock::new(None);
+    static ref BLOCKING_CLIENT: RwLock<Option<reqwest::blocking::Client>> = RwLock::new(None);
+}
+
+fn get_config_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("CyberGuardian")
+        .join("agent_config.json")
+}
+
+fn load_config() -> AgentConfig {
+    let path = get_config_path();
+    match fs::read_to_string(&path) {
+        Ok(content) => match serde_json::from_str::<AgentConfig>(&content) {
+            Ok(config) => config,
+            Err(e) => {
+                warn!("Invalid agent config {:?}: {} (using defaults)", path, e);
+                AgentConfig::default()
+            }
+        },
+        Err(_) => AgentConfig::default(),
+    }
+}
+
+fn save_config(config: &AgentConfig) -> Result<(), String> {
+    let path = get_config_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create config dir: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    write_protected(&path, json.as_bytes())
+}
+
+/// Write a file readable only by the current user (and SYSTEM/Administrators on Windows).
+/// Temp file + fsync + rename, so a crash mid-write keeps the previous file
+#[cfg(unix)]
+pub(crate) fn write_protected(path: &Path, data: &[u8]) -> Result<(), String> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp = path.with_file_name(tmp_name);
+
+    let write = || -> Result<(), String> {
+        // A leftover temp file may have looser permissions — never reuse it
+        let _ = fs::remove_file(&tmp);
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&tmp)
+            .map_err(|e| format!("Failed to open {:?}: {}", tmp, e))?;
+        file.write_all(data)
+            .and_then(|_| file.sync_all())
+            .map_err(|e| format!("Failed to write {:?}: {}", tmp, e))?;
+        drop(file);
+        fs::rename(&tmp, path).map_err(|e| format!("Failed to replace {:?}: {}", path, e))?;
+        // Persist the rename itself
+        if let Some(dir) = path.parent().and_then(|d| fs::File::open(d).ok()) {
+            let _ = dir.sync_all();
+        }
+        Ok(())
+    };
+    write().inspect_err(|_| {
+        let _ = fs::remove_file(&tmp);
+    })
+}
+
+#[cfg(windows)]
+pub(crate) fn write_protected(path: &Path, data: &[u8]) -> Result<(), String> {
+    use std::io::Write;
+    use std::process::Command;
+
+    // Lock down an empty temp file first, then fill it and rename it over the target,
+    // so the data never sits under the inherited ACL and a failure keeps the old file
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp = path.with_file_name(tmp_name);
+
+    let write = || -> Result<(), String> {
+        let mut file = fs::File::create(&tmp)
+            .map_err(|e| format!("Failed to create {:?}: {}", tmp, e))?;
+
+        let user = std::env::var("USERNAME").unwrap_or_else(|_| "Administrators".to_string());
+        let output = Command::new("icacls")
+            .arg(&tmp)
+            .args(["/inheritance:r", "/grant:r"])
+            .arg(format!("{}:F", user))
+            .args(["/grant:r", "*S-1-5-18:F", "/grant:r", "*S-1-5-32-544:F"])
+            .output()
+            .map_err(|e| format!("Failed to run icacls: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "icacls failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        file.write_all(data)
+            .and_then(|_| file.sync_all())
+            .map_err(|e| format!("Failed to write {:?}: {}", tmp, e))?;
+        drop(file);
+        fs::rename(&tmp, path).map_err(|e| format!("Failed to replace {:?}: {}", path, e))
+    };
+    write().inspect_err(|_| {
+        let _ = fs::remove_file(&tmp);
+    })
+}
+
+/// Current configuration (with secrets)
+pub fn current() -> AgentConfig {
+    CONFIG.read().map(|c| c.clone()).unwrap_or_default()
+}
+
+/// Backend base URL without trailing slash
+pub fn backend_url() -> String {
+    current().backend_url.trim().trim_end_matches('/').to_string()
+}
+
+/// The backend URL comes from the agent config, not from the UI
+pub fn note_ui_backend_url(ui_url: &str) {
+    let configured = backend_url();
+    if ui_url.trim().trim_end_matches('/') != configured {
+        info!("Using configured backend {} instead of {}", configured, ui_url);
+    }
+}
+
+/// Remember the token of the logged-in UI session
+pub fn set_session_token(token: &str) {
+    if let Ok(mut current) = SESSION_TOKEN.write() {
+        *current = Some(token.to_string());
+    }
+}
+
+/// Resolve the API token from the configured source (empty if unavailable)
+pub fn auth_token() -> String {
+    resolve_token(&current().token_source)
+}
+
+fn resolve_token(source: &TokenSource) -> String {
+    match source {
+        TokenSource::Session => SESSION_TOKEN.read()
+            .ok()
+            .and_then(|t| t.clone())
+            .unwrap_or_default(),
+        TokenSource::Inline { token } => token.clone(),
+        TokenSource::Env { var } => std::env::var(var).unwrap_or_default(),
+        TokenSource::File { path } => fs::read_to_string(path)
+            .map(|s| s.lines().next().unwrap_or("").trim().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+// ============================================================================
+// HTTP CLIENTS
+// ============================================================================
+
+/// TLS / proxy material for the HTTP client
+struct ClientParts {
+    ca_certs: Vec<reqwest::Certificate>,
+    identity: Option<reqwest::Identity>,
+    proxy: Option<reqwest::Proxy>,
+}
+
+fn client_parts(config: &AgentConfig) -> Result<ClientParts, String> {
+    let ca_certs = match &config.ca_bundle_path {
+        Some(path) => {
+            let pem = fs::read(path).map_err(|e| format!("Failed to read CA bundle: {}", e))?;
+            reqwest::Certificate::from_pem_bundle(&pem)
+                .map_err(|e| format!("Invalid CA bundle: {}", e))?
+        }
+        None => Vec::new(),
+    };
+
+    let identity = match &config.client_certificate {
+        Some(cert) => {
+            let der = fs::read(&cert.pkcs12_path)
+                .map_err(|e| format!("Failed to read client certificate: {}", e))?;
+            Some(reqwest::Identity::from_pkcs12_der(&der, &cert.password)
+                .map_err(|e| format!("Invalid client certificate: {}", e))?)
+        }
+        None => None,
+    };
+
+    let proxy = match &config.proxy_url {
+        Some(url) => Some(reqwest::Proxy::all(url.as_str())
+            .map_err(|e| format!("Invalid proxy URL: {}", e))?),
+        None => None,
+    };
+
+    Ok(ClientParts { ca_certs, identity, proxy })
+}
+
+fn build_blocking_client(config: &AgentConfig) -> Result<reqwest::blocking::Client, String> {
+    let parts = client_parts(config)?;
+    let mut builder = reqwest::blocking::Client::builder()
+        .connect_timeout(Duration::from_secs(config.connect_timeout_secs))
+        .timeout(Duration::from_secs(config.request_timeout_secs));
+    for cert in parts.ca_certs {
+        builder = builder.add_root_certificate(cert);
+    }
+    if let Some(identity) = parts.identity {
+        builder = builder.identity(identity);
+    }
+    if let Some(proxy) = parts.proxy {
+        builder = builder.proxy(proxy);
+    }
+    builder.build().map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+/// Shared blocking client for the current config (call from non-async threads)
+pub fn blocking_client() -> Result<reqwest::blocking::Client, String> {
+    if let Some(client) = BLOCKING_CLIENT.read().ok().and_then(|c| c.clone()) {
+        return Ok(client);
+    }
+    let client = build_blocking_client(&current())?;
+    if let Ok(mut cached) = BLOCKING_CLIENT.write() {
+        *cached = Some(client.clone());
+    }
+    Ok(client)
+}
+
+/// Validate, persist and activate a new config (client is rebuilt on next use).
+/// Backend URL and remote command settings keep their on-disk values
+pub fn apply_config(mut config: AgentConfig) -> Result<AgentConfig, String> {
+    config.restore_secrets(&current());
+    config.keep_admin_fields(&load_config());
+    config.backend_url = config.backend_url.trim().trim_end_matches('/').to_string();
+    config.validate()?;
+    // Fail early on unreadable certificates instead of on the next request
+    client_parts(&config)?;
+
+    save_config(&config)?;
+
+    let mut state = CONFIG.write().map_err(|e| format!("Lock error: {}", e))?;
+    *state = config.clone();
+    drop(state);
+
+    if let Ok(mut cached) = BLOCKING_CLIENT.write() {
+        *cached = None;
+    }
+
+    info!("Agent config updated: backend {}", config.backend_url);
+    crate::network_isolation::refresh_if_isolated();
+    Ok(config)
+}
+
+// ============================================================================
+// TAURI COMMANDS
+// ============================================================================
+
+/// Get agent config (secrets redacted)
+#[tauri::command]
+pub fn get_agent_config() -> Result<AgentConfig, String> {
+    Ok(current().redacted())
+}
+
+/// Change agent config at runtime (no restart required).
+/// `backend_url`, `allow_remote_commands` and `command_public_key` are ignored —
+/// only the config file written by the installer / an administrator changes them
+#[tauri::command]
+pub fn set_agent_config(config: AgentConfig) -> Result<AgentConfig, String> {
+    apply_config(config).map(|c| c.redacted())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config_is_valid() {
+        assert!(AgentConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_validation_rejects_bad_values() {
+        let bad_url = AgentConfig { backend_url: "ftp://example.com".into(), ..AgentConfig::default() };
+        assert!(bad_url.validate().is_err());
+
+        let bad_timeout = AgentConfig { request_timeout_secs: 0, ..AgentConfig::default() };
+        assert!(bad_timeout.validate().is_err());
+
+        let missing_ca = AgentConfig {
+            ca_bundle_path: Some("/nonexistent/ca.pem".into()),
+            ..AgentConfig::default()
+        };
+        assert!(missing_ca.validate().is_err());
+
+        let unkeyed_commands = AgentConfig { allow_remote_commands: true, ..AgentConfig::default() };
+        assert!(unkeyed_commands.validate().is_err());
+        assert!(!AgentConfig::default().allow_remote_commands);
+    }
+
+    #[test]
+    fn test_redaction_round_trip_keeps_secrets() {
+        let stored = AgentConfig {
+            token_source: TokenSource::Inline { token: "secret-token".into() },
+            client_certificate: Some(ClientCertificate {
+                pkcs12_path: "agent.p12".into(),
+                password: "p12-pass".into(),
+            }),
+            ..AgentConfig::default()
+        };
+
+        let mut from_ui = stored.redacted();
+        assert_eq!(from_ui.token_source, TokenSource::Inline { token: REDACTED.into() });
+        assert_eq!(from_ui.client_certificate.as_ref().unwrap().password, REDACTED);
+
+        from_ui.restore_secrets(&stored);
+        assert_eq!(from_ui, stored);
+
+        let debug = format!("{:?}", stored);
+        assert!(!debug.contains("secret-token") && !debug.contains("p12-pass"));
+    }
+
+    #[test]
+    fn test_ui_cannot_change_admin_fields() {
+        let stored = AgentConfig::default();
+        let mut from_ui = AgentConfig {
+            backend_url: "https://attacker.example".into(),
+            allow_remote_commands: true,
+            command_public_key: Some("AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=".into()),
+            proxy_url: Some("http://proxy.corp:3128".into()),
+            ..AgentConfig::default()
+        };
+        from_ui.keep_admin_fields(&stored);
+        assert_eq!(from_ui.backend_url, DEFAULT_BACKEND_URL);
+        assert!(!from_ui.allow_remote_commands);
+        assert!(from_ui.command_public_key.is_none());
+        // Everything else is still the UI's to change
+        assert_eq!(from_ui.proxy_url.as_deref(), Some("http://proxy.corp:3128"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_protected_replaces_atomically() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("cg_protected_{}.json", std::process::id()));
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_protected(&path, b"new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        assert!(!path.with_file_name(format!("cg_protected_{}.json.tmp", std::process::id())).exists());
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_token_from_file_uses_first_line() {
+        let path = std::env::temp_dir().join(format!("cg_token_{}.txt", std::process::id()));
+        fs::write(&path, "file-token\nignored\n").unwrap();
+        let source = TokenSource::File { path: path.to_string_lossy().to_string() };
+        assert_eq!(resolve_token(&source), "file-token");
+        let _ = fs::remove_file(&path);
+    }
+}
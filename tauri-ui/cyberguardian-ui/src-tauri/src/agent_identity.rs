@@ -0,0 +1,520 @@
+//! Agent Identity & Heartbeat
+//! Persistent agent ID + ed25519 keypair, enrollment with the backend
+//! (hostname, OS, version, capabilities) and periodic signed heartbeats
+
+use base64::{engine::general_purpose::STANDARD as B64, Engine};
+use ed25519_dalek::{Signer, SigningKey};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::backend_client::{BackendClient, BackendError, ReqwestBackendClient};
+
+pub const AGENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const DEFAULT_HEARTBEAT_SECS: u64 = 60;
+const MIN_HEARTBEAT_SECS: u64 = 10;
+
+// ============================================================================
+// IDENTITY
+// ============================================================================
+
+/// On-disk form of the identity (protected file, contains the private key)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredIdentity {
+    agent_id: String,
+    secret_key: String,
+    created_at: String,
+    registered_at: Option<String>,
+}
+
+pub struct AgentIdentity {
+    pub agent_id: String,
+    signing_key: SigningKey,
+    pub created_at: String,
+    pub registered_at: Option<String>,
+}
+
+impl AgentIdentity {
+    fn generate() -> AgentIdentity {
+        let mut secret = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut secret);
+        AgentIdentity {
+            agent_id: uuid::Uuid::new_v4().to_string(),
+            signing_key: SigningKey::from_bytes(&secret),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            registered_at: None,
+        }
+    }
+
+    fn from_stored(stored: StoredIdentity) -> Result<AgentIdentity, String> {
+        let bytes = B64.decode(&stored.secret_key)
+            .map_err(|e| format!("Invalid identity key: {}", e))?;
+        let secret: [u8; 32] = bytes.try_into()
+            .map_err(|_| "Invalid identity key length".to_string())?;
+        Ok(AgentIdentity {
+            agent_id: stored.agent_id,
+            signing_key: SigningKey::from_bytes(&secret),
+            created_at: stored.created_at,
+            registered_at: stored.registered_at,
+        })
+    }
+
+    fn to_stored(&self) -> StoredIdentity {
+        StoredIdentity {
+            agent_id: self.agent_id.clone(),
+            secret_key: B64.encode(self.signing_key.to_bytes()),
+            created_at: self.created_at.clone(),
+            registered_at: self.registered_at.clone(),
+        }
+    }
+
+    /// Base64 ed25519 public key (sent at registration)
+    pub fn public_key(&self) -> String {
+        B64.encode(self.signing_key.verifying_key().to_bytes())
+    }
+
+    /// Base64 ed25519 signature of `message`
+    pub fn sign(&self, message: &str) -> String {
+        B64.encode(self.signing_key.sign(message.as_bytes()).to_bytes())
+    }
+}
+
+fn get_identity_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("CyberGuardian")
+        .join("agent_identity.json")
+}
+
+fn load_or_create_at(path: &Path) -> Result<AgentIdentity, String> {
+    if let Ok(content) = fs::read_to_string(path) {
+        let stored: StoredIdentity = serde_json::from_str(&content)
+            .map_err(|e| format!("Corrupt agent identity {:?}: {}", path, e))?;
+        return AgentIdentity::from_stored(stored);
+    }
+
+    let identity = AgentIdentity::generate();
+    save_at(path, &identity)?;
+    info!("Generated new agent identity {}", identity.agent_id);
+    Ok(identity)
+}
+
+fn save_at(path: &Path, identity: &AgentIdentity) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create identity dir: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(&identity.to_stored())
+        .map_err(|e| format!("Failed to serialize identity: {}", e))?;
+    crate::agent_config::write_protected(path, json.as_bytes())
+}
+
+// ============================================================================
+// PROTOCOL MESSAGES
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Registration {
+    pub agent_id: String,
+    pub public_key: String,
+    pub hostname: String,
+    pub os: String,
+    pub os_version: String,
+    pub arch: String,
+    pub agent_version: String,
+    pub capabilities: Vec<String>,
+    pub timestamp: String,
+    /// Signature of `register|{agent_id}|{timestamp}|{public_key}`
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentHealth {
+    pub monitor_running: bool,
+    /// "etw" on Windows, "none" where no kernel event source is available
+    pub event_source: String,
+    pub event_source_active: bool,
+    pub blocking_enabled: bool,
+    #[serde(default)]
+    pub enforcement_mode: crate::enforcement::EnforcementMode,
+    pub spool_depth: usize,
+    /// Values removed/pseudonymized from outbound telemetry since startup
+    pub redactions: u64,
+    pub rule_version: String,
+    pub uptime_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Heartbeat {
+    pub agent_id: String,
+    pub sequence: u64,
+    pub timestamp: String,
+    pub health: AgentHealth,
+    /// Signature of `heartbeat|{agent_id}|{timestamp}|{sequence}`
+    pub signature: String,
+}
+
+/// Identity + heartbeat status for the UI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentStatus {
+    pub agent_id: String,
+    pub public_key: String,
+    pub registered: bool,
+    pub registered_at: Option<String>,
+    pub last_heartbeat_at: Option<String>,
+    pub last_error: Option<String>,
+    pub heartbeat_interval_secs: u64,
+    pub health: AgentHealth,
+}
+
+pub(crate) fn hostname() -> String {
+    hostname::get()
+        .map(|h| h.to_string_lossy().to_string())
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+#[cfg(windows)]
+fn os_version() -> String {
+    use winreg::{enums::HKEY_LOCAL_MACHINE, RegKey};
+
+    RegKey::predef(HKEY_LOCAL_MACHINE)
+        .open_subkey(r"SOFTWARE\Microsoft\Windows NT\CurrentVersion")
+        .map(|key| {
+            let product: String = key.get_value("ProductName").unwrap_or_default();
+            let build: String = key.get_value("CurrentBuild").unwrap_or_default();
+            format!("{} (build {})", product, build)
+        })
+        .unwrap_or_else(|_| "Windows".to_string())
+}
+
+#[cfg(not(windows))]
+fn os_version() -> String {
+    fs::read_to_string("/etc/os-release")
+        .ok()
+        .and_then(|content| content.lines()
+            .find_map(|l| l.strip_prefix("PRETTY_NAME="))
+            .map(|v| v.trim_matches('"').to_string()))
+        .unwrap_or_else(|| std::env::consts::OS.to_string())
+}
+
+/// Features this build can offer to the backend
+pub fn capabilities() -> Vec<String> {
+    let mut caps = vec![
+        "process_inventory",
+        "file_scan",
+        "outbound_spool",
+        "quarantine_retention",
+    ];
+    if cfg!(windows) {
+        caps.extend(["etw_events", "process_blocking", "registry_scan", "service_scan"]);
+    }
+    caps.into_iter().map(String::from).collect()
+}
+
+fn build_registration(identity: &AgentIdentity) -> Registration {
+    let timestamp = chrono::Utc::now().to_rfc3339();
+    let public_key = identity.public_key();
+    let signature = identity.sign(&format!(
+        "register|{}|{}|{}", identity.agent_id, timestamp, public_key
+    ));
+    Registration {
+        agent_id: identity.agent_id.clone(),
+        public_key,
+        hostname: hostname(),
+        os: std::env::consts::OS.to_string(),
+        os_version: os_version(),
+        arch: std::env::consts::ARCH.to_string(),
+        agent_version: AGENT_VERSION.to_string(),
+        capabilities: capabilities(),
+        timestamp,
+        signature,
+    }
+}
+
+fn build_heartbeat(identity: &AgentIdentity, sequence: u64, health: AgentHealth) -> Heartbeat {
+    let timestamp = chrono::Utc::now().to_rfc3339();
+    let signature = identity.sign(&format!(
+        "heartbeat|{}|{}|{}", identity.agent_id, timestamp, sequence
+    ));
+    Heartbeat {
+        agent_id: identity.agent_id.clone(),
+        sequence,
+        timestamp,
+        health,
+        signature,
+    }
+}
+
+// ============================================================================
+// GLOBAL STATE
+// ============================================================================
+
+struct HeartbeatState {
+    last_heartbeat_at: Option<String>,
+    last_error: Option<String>,
+}
+
+lazy_static::lazy_static! {
+    static ref IDENTITY: Mutex<Option<AgentIdentity>> = Mutex::new(None);
+    static ref HEARTBEAT_STATE: Mutex<HeartbeatState> = Mutex::new(HeartbeatState {
+        last_heartbeat_at: None,
+        last_error: None,
+    });
+    static ref STARTED_AT: Instant = Instant::now();
+}
+
+static HEARTBEAT_RUNNING: AtomicBool = AtomicBool::new(false);
+static HEARTBEAT_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+static HEARTBEAT_INTERVAL_SECS: AtomicU64 = AtomicU64::new(DEFAULT_HEARTBEAT_SECS);
+
+/// Run `f` with the loaded (or freshly generated) identity
+fn with_identity<T>(f: impl FnOnce(&mut AgentIdentity) -> T) -> Result<T, String> {
+    let mut guard = IDENTITY.lock().map_err(|e| format!("Lock error: {}", e))?;
+    if guard.is_none() {
+        *guard = Some(load_or_create_at(&get_identity_path())?);
+    }
+    Ok(f(guard.as_mut().unwrap()))
+}
+
+/// This agent's ID (generated on first use)
+pub fn agent_id() -> Result<String, String> {
+    with_identity(|identity| identity.agent_id.clone())
+}
+
+/// Sign `message` with this agent's key (base64 signature)
+pub fn sign(message: &str) -> Result<String, String> {
+    with_identity(|identity| identity.sign(message))
+}
+
+/// Whether enrollment has completed
+pub fn is_registered() -> bool {
+    with_identity(|identity| identity.registered_at.is_some()).unwrap_or(false)
+}
+
+/// Current health snapshot
+pub fn collect_health() -> AgentHealth {
+    let enforcement_mode = crate::process_monitor::enforcement_mode();
+
+    #[cfg(windows)]
+    let (event_source, event_source_active) = ("etw", crate::etw_monitor::is_etw_running());
+    #[cfg(target_os = "linux")]
+    let (event_source, event_source_active) = ("procfs", crate::linux_net_monitor::is_monitor_running());
+    #[cfg(not(any(windows, target_os = "linux")))]
+    let (event_source, event_source_active) = ("none", false);
+
+    AgentHealth {
+        monitor_running: crate::process_monitor::is_monitor_running(),
+        event_source: event_source.to_string(),
+        event_source_active,
+        blocking_enabled: enforcement_mode.blocks(),
+        enforcement_mode,
+        spool_depth: crate::outbound_spool::total_depth(),
+        redactions: crate::redaction::total_redactions(),
+        rule_version: crate::process_monitor::RULES_VERSION.to_string(),
+        uptime_secs: STARTED_AT.elapsed().as_secs(),
+    }
+}
+
+/// Server-suggested heartbeat interval (`heartbeat_interval_secs` in responses)
+fn apply_server_settings(response: &Value) {
+    if let Some(secs) = response.get("heartbeat_interval_secs").and_then(|v| v.as_u64()) {
+        HEARTBEAT_INTERVAL_SECS.store(secs.max(MIN_HEARTBEAT_SECS), Ordering::SeqCst);
+    }
+}
+
+fn record_error(error: &str) {
+    if let Ok(mut state) = HEARTBEAT_STATE.lock() {
+        state.last_error = Some(error.to_string());
+    }
+}
+
+// ============================================================================
+// PROTOCOL
+// ============================================================================
+
+/// Handshake: health check, then (re-)register this agent
+pub fn enroll(client: &dyn BackendClient) -> Result<(), BackendError> {
+    if !client.health()? {
+        return Err(BackendError::Status { status: 503, body: "Backend health check failed".into() });
+    }
+
+    let registration = with_identity(|identity| build_registration(identity))
+        .map_err(BackendError::Config)?;
+    let body = serde_json::to_value(&registration)
+        .map_err(|e| BackendError::InvalidResponse(e.to_string()))?;
+    let response = client.register_agent(&body)?;
+    apply_server_settings(&response);
+
+    // A command key in the reply is ignored: whoever answers first contact must
+    // not gain remote control — the key comes from agent_config only
+    with_identity(|identity| {
+        identity.registered_at = Some(chrono::Utc::now().to_rfc3339());
+        save_at(&get_identity_path(), identity)
+    })
+    .and_then(|r| r)
+    .map_err(BackendError::Config)?;
+
+    info!("Agent {} registered with backend", registration.agent_id);
+    Ok(())
+}
+
+/// Send one heartbeat (registers first if needed)
+pub fn send_heartbeat(client: &dyn BackendClient) -> Result<(), BackendError> {
+    let registered = with_identity(|identity| identity.registered_at.is_some())
+        .map_err(BackendError::Config)?;
+    if !registered {
+        enroll(client)?;
+    }
+
+    let sequence = HEARTBEAT_SEQUENCE.fetch_add(1, Ordering::SeqCst) + 1;
+    let heartbeat = with_identity(|identity| build_heartbeat(identity, sequence, collect_health()))
+        .map_err(BackendError::Config)?;
+    let body = serde_json::to_value(&heartbeat)
+        .map_err(|e| BackendError::InvalidResponse(e.to_string()))?;
+
+    match client.send_heartbeat(&heartbeat.agent_id, &body) {
+        Ok(response) => {
+            apply_server_settings(&response);
+            if let Ok(mut state) = HEARTBEAT_STATE.lock() {
+                state.last_heartbeat_at = Some(heartbeat.timestamp);
+                state.last_error = None;
+            }
+            Ok(())
+        }
+        // Backend forgot us (DB reset, agent deleted) → enroll again next tick
+        Err(BackendError::Status { status, body }) if status == 404 || status == 410 => {
+            let _ = with_identity(|identity| identity.registered_at = None);
+            Err(BackendError::Status { status, body })
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Start the heartbeat thread
+pub fn start_heartbeat() {
+    if HEARTBEAT_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    lazy_static::initialize(&STARTED_AT);
+
+    std::thread::spawn(|| {
+        info!("Agent heartbeat started");
+        while HEARTBEAT_RUNNING.load(Ordering::SeqCst) {
+            let result = ReqwestBackendClient::from_config()
+                .and_then(|client| send_heartbeat(&client));
+            if let Err(e) = result {
+                warn!("Heartbeat failed: {}", e);
+                record_error(&e.to_string());
+            }
+
+            // Sleep in short steps so stop_heartbeat() takes effect quickly
+            let deadline = Instant::now()
+                + Duration::from_secs(HEARTBEAT_INTERVAL_SECS.load(Ordering::SeqCst));
+            while HEARTBEAT_RUNNING.load(Ordering::SeqCst) && Instant::now() < deadline {
+                std::thread::sleep(Duration::from_secs(1));
+            }
+        }
+        info!("Agent heartbeat stopped");
+    });
+}
+
+pub fn stop_heartbeat() {
+    HEARTBEAT_RUNNING.store(false, Ordering::SeqCst);
+}
+
+// ============================================================================
+// TAURI COMMANDS
+// ============================================================================
+
+/// Agent identity, registration and last heartbeat
+#[tauri::command]
+pub fn get_agent_status() -> Result<AgentStatus, String> {
+    let (agent_id, public_key, registered_at) = with_identity(|identity| {
+        (identity.agent_id.clone(), identity.public_key(), identity.registered_at.clone())
+    })?;
+    let (last_heartbeat_at, last_error) = HEARTBEAT_STATE.lock()
+        .map(|s| (s.last_heartbeat_at.clone(), s.last_error.clone()))
+        .unwrap_or((None, None));
+
+    Ok(AgentStatus {
+        agent_id,
+        public_key,
+        registered: registered_at.is_some(),
+        registered_at,
+        last_heartbeat_at,
+        last_error,
+        heartbeat_interval_secs: HEARTBEAT_INTERVAL_SECS.load(Ordering::SeqCst),
+        health: collect_health(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+    fn verify(public_key: &str, message: &str, signature: &str) -> bool {
+        let key: [u8; 32] = B64.decode(public_key).unwrap().try_into().unwrap();
+        let sig: [u8; 64] = B64.decode(signature).unwrap().try_into().unwrap();
+        VerifyingKey::from_bytes(&key).unwrap()
+            .verify(message.as_bytes(), &Signature::from_bytes(&sig))
+            .is_ok()
+    }
+
+    #[test]
+    fn test_identity_is_persistent() {
+        let path = std::env::temp_dir()
+            .join(format!("cg_identity_{}", std::process::id()))
+            .join("agent_identity.json");
+        let first = load_or_create_at(&path).unwrap();
+        let second = load_or_create_at(&path).unwrap();
+        assert_eq!(first.agent_id, second.agent_id);
+        assert_eq!(first.public_key(), second.public_key());
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_messages_are_signed() {
+        let identity = AgentIdentity::generate();
+
+        let registration = build_registration(&identity);
+        assert_eq!(registration.agent_version, AGENT_VERSION);
+        assert!(!registration.capabilities.is_empty());
+        assert!(verify(
+            &registration.public_key,
+            &format!("register|{}|{}|{}", registration.agent_id, registration.timestamp, registration.public_key),
+            &registration.signature,
+        ));
+
+        let health = AgentHealth {
+            monitor_running: true,
+            event_source: "etw".into(),
+            event_source_active: true,
+            blocking_enabled: false,
+            enforcement_mode: crate::enforcement::EnforcementMode::Monitor,
+            spool_depth: 3,
+            redactions: 0,
+            rule_version: "test".into(),
+            uptime_secs: 1,
+        };
+        let heartbeat = build_heartbeat(&identity, 7, health);
+        assert!(verify(
+            &identity.public_key(),
+            &format!("heartbeat|{}|{}|7", identity.agent_id, heartbeat.timestamp),
+            &heartbeat.signature,
+        ));
+        // Tampered sequence must not verify
+        assert!(!verify(
+            &identity.public_key(),
+            &format!("heartbeat|{}|{}|8", identity.agent_id, heartbeat.timestamp),
+            &heartbeat.signature,
+        ));
+    }
+}
@@ -1,11 +1,7 @@
-//! API Client for communicating with Railway backend
-//! Sends process data and receives threat analysis
+//! API Client for communicating with the backend (see agent_config)
+//! Process data types shared with the inventory uploader (see process_inventory)
 
 use serde::{Deserialize, Serialize};
-use std::error::Error;
-
-/// Backend API URL (Railway production)
-const BACKEND_URL: &str = "https://cyberguardian-backend-production.up.railway.app";
 
 /// Process info structure for API communication
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -18,6 +14,9 @@ pub struct ProcessInfo {
     pub cpu_percent: f32,
     pub memory_mb: f64,
     pub username: String,
+    /// With `pid`, identifies the process instance across PID reuse (0 = unknown)
+    #[serde(default)]
+    pub start_time: u64,
 }
 
 /// API Response structure
@@ -26,45 +25,3 @@ pub struct ApiResponse {
     pub success: bool,
     pub message: Option<String>,
 }
-
-/// Send processes to backend
-pub async fn send_processes_to_backend(
-    processes: Vec<ProcessInfo>,
-    api_token: &str,
-) -> Result<(), Box<dyn Error>> {
-    let client = reqwest::Client::new();
-    
-    let url = format!("{}/api/process-monitor/upload-processes", BACKEND_URL);
-    
-    let response = client
-        .post(&url)
-        .header("Authorization", format!("Bearer {}", api_token))
-        .header("Content-Type", "application/json")
-        .json(&processes)
-        .send()
-        .await?;
-    
-    if response.status().is_success() {
-        println!("✅ Sent {} processes to backend", processes.len());
-        Ok(())
-    } else {
-        let status = response.status();
-        let error_text = response.text().await?;
-        Err(format!("Backend error {}: {}", status, error_text).into())
-    }
-}
-
-/// Test backend connection
-pub async fn test_backend_connection(api_token: &str) -> Result<bool, Box<dyn Error>> {
-    let client = reqwest::Client::new();
-    
-    let url = format!("{}/api/process-monitor/health", BACKEND_URL);
-    
-    let response = client
-        .get(&url)
-        .header("Authorization", format!("Bearer {}", api_token))
-        .send()
-        .await?;
-    
-    Ok(response.status().is_success())
-}
\ No newline at end of file
@@ -0,0 +1,526 @@
+//! Backend Client
+//! Single seam for all HTTP calls to the backend (health, process inventory,
+//! file scan verdicts, quarantine records, scan history) so business logic
+//! can be exercised against the in-process mock server in tests
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+use crate::agent_config;
+
+pub const HEALTH_PATH: &str = "/api/process-monitor/health";
+pub const FILE_SCAN_PATH: &str = "/api/protection/scan";
+pub const QUARANTINE_PATH: &str = "/api/quarantine/";
+pub const SCAN_HISTORY_PATH: &str = "/api/scans/history";
+pub const AGENT_REGISTER_PATH: &str = "/api/agents/register";
+
+pub const COMMAND_RESULT_PATH: &str = "/api/agents/commands/results";
+pub const PROCESS_INVENTORY_PATH: &str = "/api/process-monitor/inventory";
+pub const FILE_UPLOAD_PATH: &str = "/api/agents/uploads";
+
+pub fn agent_heartbeat_path(agent_id: &str) -> String {
+    format!("/api/agents/{}/heartbeat", agent_id)
+}
+
+/// Long-poll for pending response commands (server holds up to `wait_secs`)
+pub fn agent_commands_path(agent_id: &str, wait_secs: u64) -> String {
+    format!("/api/agents/{}/commands?wait={}", agent_id, wait_secs)
+}
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// Backend call failure
+#[derive(Debug, Clone, PartialEq)]
+pub enum BackendError {
+    /// Connection refused, DNS, TLS handshake, timeout...
+    Network(String),
+    /// Non-success HTTP status
+    Status { status: u16, body: String },
+    /// HTTP client could not be built from the agent config
+    Config(String),
+    /// Success status but the body could not be parsed
+    InvalidResponse(String),
+}
+
+impl BackendError {
+    /// Worth retrying later with backoff (offline, server-side trouble)
+    pub fn is_transient(&self) -> bool {
+        match self {
+            BackendError::Network(_) | BackendError::Config(_) => true,
+            BackendError::Status { status, .. } => *status >= 500 || *status == 408 || *status == 429,
+            BackendError::InvalidResponse(_) => false,
+        }
+    }
+
+    /// Token missing, expired or revoked — retrying only helps once the token changes
+    pub fn is_auth_failure(&self) -> bool {
+        matches!(self, BackendError::Status { status: 401 | 403, .. })
+    }
+
+    /// Keep the request for later (spool) instead of dropping it
+    pub fn is_retryable(&self) -> bool {
+        self.is_transient() || self.is_auth_failure()
+    }
+}
+
+impl fmt::Display for BackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackendError::Network(e) => write!(f, "Network error: {}", e),
+            BackendError::Status { status, body } => {
+                write!(f, "Backend error {}: {}", status, body.chars().take(200).collect::<String>())
+            }
+            BackendError::Config(e) => write!(f, "Client config error: {}", e),
+            BackendError::InvalidResponse(e) => write!(f, "Invalid response: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for BackendError {}
+
+/// File scan request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanRequest {
+    pub file_path: String,
+    pub file_size: u64,
+}
+
+/// File scan verdict (`data.threat_score` of the scan response)
+#[derive(Debug, Clone)]
+pub struct ScanVerdict {
+    pub threat_score: Option<f64>,
+    pub raw: Value,
+}
+
+impl ScanVerdict {
+    pub fn parse(body: &str) -> Result<ScanVerdict, BackendError> {
+        let raw: Value = serde_json::from_str(body)
+            .map_err(|e| BackendError::InvalidResponse(e.to_string()))?;
+        let threat_score = raw.get("data")
+            .and_then(|d| d.get("threat_score"))
+            .and_then(|v| v.as_f64());
+        Ok(ScanVerdict { threat_score, raw })
+    }
+}
+
+// ============================================================================
+// TRAIT
+// ============================================================================
+
+/// Blocking backend API (call from worker threads or `spawn_blocking`)
+pub trait BackendClient: Send + Sync {
+    /// GET `path`, returning the response body
+    fn get(&self, path: &str) -> Result<String, BackendError>;
+
+    /// POST JSON `body` to `path`, returning the response body
+    /// (implementations must pass it through `redaction::redact_outbound`)
+    fn post_json(&self, path: &str, body: &Value) -> Result<String, BackendError>;
+
+    /// POST pre-encoded JSON bytes (`content_encoding` = gzip / zstd / None);
+    /// callers redact before encoding
+    fn post_encoded(
+        &self,
+        path: &str,
+        body: Vec<u8>,
+        content_encoding: Option<&str>,
+    ) -> Result<String, BackendError>;
+
+    fn health(&self) -> Result<bool, BackendError> {
+        match self.get(HEALTH_PATH) {
+            Ok(_) => Ok(true),
+            Err(BackendError::Status { .. }) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn scan_file(&self, request: &ScanRequest) -> Result<ScanVerdict, BackendError> {
+        let body = serde_json::to_value(request)
+            .map_err(|e| BackendError::InvalidResponse(e.to_string()))?;
+        ScanVerdict::parse(&self.post_json(FILE_SCAN_PATH, &body)?)
+    }
+
+    fn create_quarantine_record(&self, record: &Value) -> Result<(), BackendError> {
+        self.post_json(QUARANTINE_PATH, record).map(|_| ())
+    }
+
+    fn post_scan_history(&self, history: &Value) -> Result<(), BackendError> {
+        self.post_json(SCAN_HISTORY_PATH, history).map(|_| ())
+    }
+
+    fn register_agent(&self, registration: &Value) -> Result<Value, BackendError> {
+        parse_json(&self.post_json(AGENT_REGISTER_PATH, registration)?)
+    }
+
+    fn send_heartbeat(&self, agent_id: &str, heartbeat: &Value) -> Result<Value, BackendError> {
+        parse_json(&self.post_json(&agent_heartbeat_path(agent_id), heartbeat)?)
+    }
+
+    /// Pending commands (`{"commands": [...]}` or a bare array)
+    fn poll_commands(&self, agent_id: &str, wait_secs: u64) -> Result<Vec<Value>, BackendError> {
+        let response = parse_json(&self.get(&agent_commands_path(agent_id, wait_secs))?)?;
+        let commands = match response {
+            Value::Array(items) => items,
+            Value::Object(mut map) => match map.remove("commands") {
+                Some(Value::Array(items)) => items,
+                _ => Vec::new(),
+            },
+            _ => Vec::new(),
+        };
+        Ok(commands)
+    }
+
+    fn report_command_result(&self, result: &Value) -> Result<(), BackendError> {
+        self.post_json(COMMAND_RESULT_PATH, result).map(|_| ())
+    }
+
+    /// Upload an encoded inventory snapshot/delta, returning the backend ack
+    fn upload_inventory(&self, body: Vec<u8>, content_encoding: Option<&str>) -> Result<Value, BackendError> {
+        parse_json(&self.post_encoded(PROCESS_INVENTORY_PATH, body, content_encoding)?)
+    }
+
+    /// Upload one chunk of a collected file. Metadata is redacted, the
+    /// `data_base64` payload is sent untouched
+    fn upload_file_chunk(&self, chunk: &Value) -> Result<(), BackendError> {
+        let mut body = chunk.clone();
+        let data = body.as_object_mut().and_then(|map| map.remove("data_base64"));
+        let mut body = crate::redaction::redact_outbound(&body);
+        if let (Some(map), Some(data)) = (body.as_object_mut(), data) {
+            map.insert("data_base64".to_string(), data);
+        }
+        let bytes = serde_json::to_vec(&body).map_err(|e| BackendError::InvalidResponse(e.to_string()))?;
+        self.post_encoded(FILE_UPLOAD_PATH, bytes, None).map(|_| ())
+    }
+}
+
+/// Parse a JSON response body (empty body = null)
+fn parse_json(body: &str) -> Result<Value, BackendError> {
+    if body.trim().is_empty() {
+        return Ok(Value::Null);
+    }
+    serde_json::from_str(body).map_err(|e| BackendError::InvalidResponse(e.to_string()))
+}
+
+// ============================================================================
+// REQWEST IMPLEMENTATION
+// ============================================================================
+
+pub struct ReqwestBackendClient {
+    base_url: String,
+    token: String,
+    http: reqwest::blocking::Client,
+}
+
+impl ReqwestBackendClient {
+    pub fn new(base_url: &str, token: &str, http: reqwest::blocking::Client) -> Self {
+        ReqwestBackendClient {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+            http,
+        }
+    }
+
+    /// Client for the current agent config (URL, token, TLS, proxy)
+    pub fn from_config() -> Result<Self, BackendError> {
+        let http = agent_config::blocking_client().map_err(BackendError::Config)?;
+        Ok(Self::new(&agent_config::backend_url(), &agent_config::auth_token(), http))
+    }
+
+    fn finish(response: reqwest::Result<reqwest::blocking::Response>) -> Result<String, BackendError> {
+        let response = response.map_err(|e| BackendError::Network(e.to_string()))?;
+        let status = response.status();
+        let body = response.text().map_err(|e| BackendError::Network(e.to_string()))?;
+        if status.is_success() {
+            Ok(body)
+        } else {
+            Err(BackendError::Status { status: status.as_u16(), body })
+        }
+    }
+}
+
+impl BackendClient for ReqwestBackendClient {
+    fn get(&self, path: &str) -> Result<String, BackendError> {
+        Self::finish(self.http
+            .get(format!("{}{}", self.base_url, path))
+            .header("Authorization", format!("Bearer {}", self.token))
+            .send())
+    }
+
+    fn post_json(&self, path: &str, body: &Value) -> Result<String, BackendError> {
+        Self::finish(self.http
+            .post(format!("{}{}", self.base_url, path))
+            .header("Authorization", format!("Bearer {}", self.token))
+            .header("Content-Type", "application/json")
+            .json(&crate::redaction::redact_outbound(body))
+            .send())
+    }
+
+    fn post_encoded(
+        &self,
+        path: &str,
+        body: Vec<u8>,
+        content_encoding: Option<&str>,
+    ) -> Result<String, BackendError> {
+        let mut request = self.http
+            .post(format!("{}{}", self.base_url, path))
+            .header("Authorization", format!("Bearer {}", self.token))
+            .header("Content-Type", "application/json");
+        if let Some(encoding) = content_encoding {
+            request = request.header("Content-Encoding", encoding);
+        }
+        Self::finish(request.body(body).send())
+    }
+}
+
+// ============================================================================
+// MOCK BACKEND (tests)
+// ============================================================================
+
+#[cfg(test)]
+pub mod mock {
+    //! Minimal in-process HTTP/1.1 server with scripted responses
+
+    use super::ReqwestBackendClient;
+    use std::collections::{HashMap, VecDeque};
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    #[derive(Debug, Clone)]
+    pub enum MockResponse {
+        Json { status: u16, body: String },
+        /// Close the connection without answering (network failure)
+        Disconnect,
+    }
+
+    impl MockResponse {
+        pub fn ok(body: serde_json::Value) -> Self {
+            MockResponse::Json { status: 200, body: body.to_string() }
+        }
+
+        pub fn status(status: u16) -> Self {
+            MockResponse::Json { status, body: "{}".to_string() }
+        }
+
+        /// Scan response carrying `data.threat_score`
+        pub fn verdict(threat_score: f64) -> Self {
+            Self::ok(serde_json::json!({
+                "success": true,
+                "data": { "threat_score": threat_score }
+            }))
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct RecordedRequest {
+        pub method: String,
+        pub path: String,
+        pub authorization: Option<String>,
+        pub content_encoding: Option<String>,
+        pub body: String,
+        pub body_bytes: Vec<u8>,
+    }
+
+    impl RecordedRequest {
+        pub fn json(&self) -> serde_json::Value {
+            serde_json::from_str(&self.body).unwrap_or(serde_json::Value::Null)
+        }
+    }
+
+    #[derive(Default)]
+    struct Script {
+        /// path -> responses consumed in order (last one repeats)
+        responses: HashMap<String, VecDeque<MockResponse>>,
+        requests: Vec<RecordedRequest>,
+    }
+
+    pub struct MockBackend {
+        addr: String,
+        script: Arc<Mutex<Script>>,
+        stop: Arc<AtomicBool>,
+    }
+
+    impl MockBackend {
+        pub fn start() -> MockBackend {
+            let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock backend");
+            listener.set_nonblocking(true).expect("nonblocking listener");
+            let addr = format!("http://{}", listener.local_addr().unwrap());
+            let script = Arc::new(Mutex::new(Script::default()));
+            let stop = Arc::new(AtomicBool::new(false));
+
+            let (thread_script, thread_stop) = (script.clone(), stop.clone());
+            std::thread::spawn(move || {
+                while !thread_stop.load(Ordering::SeqCst) {
+                    match listener.accept() {
+                        Ok((stream, _)) => handle(stream, &thread_script),
+                        Err(_) => std::thread::sleep(Duration::from_millis(5)),
+                    }
+                }
+            });
+
+            MockBackend { addr, script, stop }
+        }
+
+        pub fn url(&self) -> &str {
+            &self.addr
+        }
+
+        /// Queue responses for `path`; the last one keeps being served
+        pub fn on(&self, path: &str, responses: Vec<MockResponse>) -> &Self {
+            self.script.lock().unwrap()
+                .responses
+                .insert(path.to_string(), responses.into());
+            self
+        }
+
+        pub fn requests(&self) -> Vec<RecordedRequest> {
+            self.script.lock().unwrap().requests.clone()
+        }
+
+        pub fn requests_to(&self, path: &str) -> Vec<RecordedRequest> {
+            self.requests().into_iter().filter(|r| r.path == path).collect()
+        }
+
+        pub fn client(&self) -> ReqwestBackendClient {
+            let http = reqwest::blocking::Client::builder()
+                .timeout(Duration::from_secs(5))
+                .build()
+                .unwrap();
+            ReqwestBackendClient::new(&self.addr, "test-token", http)
+        }
+    }
+
+    impl Drop for MockBackend {
+        fn drop(&mut self) {
+            self.stop.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn handle(stream: TcpStream, script: &Arc<Mutex<Script>>) {
+        let _ = stream.set_nonblocking(false);
+        let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+        let mut reader = BufReader::new(stream);
+
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line).is_err() {
+            return;
+        }
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap_or("").to_string();
+        let path = parts.next().unwrap_or("").to_string();
+
+        let mut content_length = 0usize;
+        let mut authorization = None;
+        let mut content_encoding = None;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).is_err() || line == "\r\n" || line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                let value = value.trim().to_string();
+                match name.to_ascii_lowercase().as_str() {
+                    "content-length" => content_length = value.parse().unwrap_or(0),
+                    "authorization" => authorization = Some(value),
+                    "content-encoding" => content_encoding = Some(value),
+                    _ => {}
+                }
+            }
+        }
+        let mut body = vec![0u8; content_length];
+        let _ = reader.read_exact(&mut body);
+
+        let response = {
+            let mut script = script.lock().unwrap();
+            script.requests.push(RecordedRequest {
+                method,
+                path: path.clone(),
+                authorization,
+                content_encoding,
+                body: String::from_utf8_lossy(&body).to_string(),
+                body_bytes: body,
+            });
+            match script.responses.get_mut(&path) {
+                Some(queue) if queue.len() > 1 => queue.pop_front().unwrap(),
+                Some(queue) if !queue.is_empty() => queue[0].clone(),
+                _ => MockResponse::status(404),
+            }
+        };
+
+        let mut stream = reader.into_inner();
+        if let MockResponse::Json { status, body } = response {
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status, body.len(), body
+            );
+        }
+        let _ = stream.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::{MockBackend, MockResponse};
+    use super::*;
+
+    #[test]
+    fn test_transient_classification() {
+        for status in [500u16, 502, 503, 408, 429] {
+            let err = BackendError::Status { status, body: String::new() };
+            assert!(err.is_transient() && err.is_retryable(), "{} should be transient", status);
+        }
+        for status in [401u16, 403] {
+            let err = BackendError::Status { status, body: String::new() };
+            assert!(err.is_auth_failure() && !err.is_transient(), "{} should be an auth failure", status);
+            assert!(err.is_retryable());
+        }
+        for status in [400u16, 404, 413, 422] {
+            let err = BackendError::Status { status, body: String::new() };
+            assert!(!err.is_retryable(), "{} should be permanent", status);
+        }
+        assert!(BackendError::Network("refused".into()).is_transient());
+        assert!(!BackendError::InvalidResponse("bad json".into()).is_transient());
+
+        // Non-ASCII bodies are truncated on char boundaries
+        let err = BackendError::Status { status: 500, body: "грешка ".repeat(60) };
+        assert_eq!(err.to_string().chars().count(), "Backend error 500: ".len() + 200);
+    }
+
+    #[test]
+    fn test_mock_scripts_verdicts_and_failures() {
+        let mock = MockBackend::start();
+        mock.on(FILE_SCAN_PATH, vec![
+            MockResponse::status(503),
+            MockResponse::Disconnect,
+            MockResponse::verdict(85.0),
+        ]);
+        let client = mock.client();
+        let request = ScanRequest { file_path: "C:\\evil.exe".into(), file_size: 42 };
+
+        let first = client.scan_file(&request).unwrap_err();
+        assert_eq!(first, BackendError::Status { status: 503, body: "{}".into() });
+        assert!(matches!(client.scan_file(&request), Err(BackendError::Network(_))));
+        assert_eq!(client.scan_file(&request).unwrap().threat_score, Some(85.0));
+
+        let requests = mock.requests_to(FILE_SCAN_PATH);
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].authorization.as_deref(), Some("Bearer test-token"));
+        assert_eq!(requests[0].json()["file_size"], 42);
+    }
+
+    #[test]
+    fn test_health_reports_status() {
+        let mock = MockBackend::start();
+        let client = mock.client();
+        mock.on(HEALTH_PATH, vec![MockResponse::ok(serde_json::json!({"status": "ok"}))]);
+        assert_eq!(client.health(), Ok(true));
+        mock.on(HEALTH_PATH, vec![MockResponse::status(503)]);
+        assert_eq!(client.health(), Ok(false));
+    }
+}
@@ -2,29 +2,29 @@
 
 use std::time::Duration;
 use tokio::time;
+use tracing::{error, info};
 
-/// Start background process upload task
-/// Sends process list to backend every 30 seconds
-pub fn start_process_upload_task(api_token: String) {
+/// Start background process inventory upload task
+/// Full snapshot first, then deltas; interval comes from the inventory settings
+pub fn start_process_upload_task() {
     tokio::spawn(async move {
-        println!("🚀 Background process upload task started");
+        info!("Background process upload task started");
         
-        let mut interval = time::interval(Duration::from_secs(30));
+        let mut tracker = crate::process_inventory::InventoryTracker::new();
         
         loop {
-            interval.tick().await;
+            let settings = crate::process_inventory::load_settings();
             
             // Get current processes
             let processes = match crate::process_monitor::enumerate_processes() {
                 Ok(procs) => procs,
                 Err(e) => {
-                    eprintln!("❌ Failed to enumerate processes: {}", e);
+                    error!("Failed to enumerate processes: {}", e);
+                    time::sleep(Duration::from_secs(settings.interval_secs)).await;
                     continue;
                 }
             };
             
-            println!("📤 Uploading {} processes to backend...", processes.len());
-            
             // Convert to API format
             let api_processes: Vec<crate::api_client::ProcessInfo> = processes
                 .into_iter()
@@ -37,38 +37,70 @@ pub fn start_process_upload_task(api_token: String) {
     cpu_percent: p.cpu_percent,
     memory_mb: p.memory_mb,
     username: p.username.clone(),
+    start_time: p.start_time,
 })
                 .collect();
             
-            // Send to backend
-            match crate::api_client::send_processes_to_backend(api_processes, &api_token).await {
-                Ok(_) => {
-                    println!("✅ Processes uploaded successfully");
+            // Upload on a blocking thread; the tracker travels with it
+            let upload_settings = settings.clone();
+            let joined = tokio::task::spawn_blocking(move || {
+                let result = crate::agent_identity::agent_id()
+                    .map_err(crate::backend_client::BackendError::Config)
+                    .and_then(|agent_id| {
+                        let client = crate::backend_client::ReqwestBackendClient::from_config()?;
+                        crate::process_inventory::upload_step(
+                            &client, &mut tracker, &agent_id, &api_processes, &upload_settings,
+                        )
+                    });
+                (tracker, result)
+            })
+            .await;
+            
+            match joined {
+                Ok((returned, result)) => {
+                    tracker = returned;
+                    match result {
+                        Ok(Some(upload)) if upload.is_full() => {
+                            info!("Uploaded full process inventory (seq {})", upload.seq());
+                        }
+                        Ok(Some(upload)) => {
+                            info!("Uploaded process inventory delta (seq {})", upload.seq());
+                        }
+                        Ok(None) => {}
+                        Err(e) => {
+                            error!("Failed to upload process inventory: {}", e);
+                        }
+                    }
                 }
                 Err(e) => {
-                    eprintln!("❌ Failed to upload processes: {}", e);
+                    error!("Process inventory upload task panicked: {}", e);
+                    tracker = crate::process_inventory::InventoryTracker::new();
                 }
             }
+            
+            time::sleep(Duration::from_secs(settings.interval_secs)).await;
         }
     });
 }
 
-/// Test backend connection on startup
-pub async fn test_connection(api_token: &str) -> Result<(), Box<dyn std::error::Error>> {
-    println!("🔌 Testing backend connection...");
+/// Connect to the backend on startup: health check + agent registration
+pub async fn test_connection() -> Result<(), Box<dyn std::error::Error>> {
+    info!("Testing backend connection...");
     
-    match crate::api_client::test_backend_connection(api_token).await {
-        Ok(true) => {
-            println!("✅ Backend connection successful");
+    let result = tokio::task::spawn_blocking(|| {
+        let client = crate::backend_client::ReqwestBackendClient::from_config()?;
+        crate::agent_identity::enroll(&client)
+    })
+    .await?;
+    
+    match result {
+        Ok(()) => {
+            info!("Backend connection successful (agent registered)");
             Ok(())
         }
-        Ok(false) => {
-            eprintln!("⚠️ Backend returned non-success status");
-            Err("Backend not reachable".into())
-        }
         Err(e) => {
-            eprintln!("❌ Backend connection failed: {}", e);
-            Err(e)
+            error!("Backend connection failed: {}", e);
+            Err(e.into())
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,428 @@
+//! C2 Beacon Detection
+//! Tracks outbound connection timestamps per (process, destination) and scores
+//! how periodic they are: coefficient of variation of the intervals, share of
+//! intervals within the jitter tolerance of the median, and connection count.
+//! Source-agnostic — fed by ETW on Windows and the /proc/net poller on Linux
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::{Mutex, RwLock};
+use tracing::{info, warn};
+
+use crate::process_identity::ProcessKey;
+
+const MAX_TRACKED_FLOWS: usize = 10_000;
+const MAX_DETECTIONS: usize = 100;
+
+// ============================================================================
+// SETTINGS
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BeaconSettings {
+    pub enabled: bool,
+    /// Connections needed before a flow is scored
+    pub min_connections: usize,
+    /// Timestamps kept per flow
+    pub max_samples: usize,
+    /// Connections closer together than this count as one (retries, parallel sockets)
+    pub burst_merge_secs: f64,
+    pub min_interval_secs: f64,
+    pub max_interval_secs: f64,
+    /// Interval within ±this fraction of the median counts as "on schedule"
+    pub jitter_tolerance: f64,
+    /// Score (0..1) at or above which a flow is reported
+    pub threshold: f64,
+    /// Skip loopback / private / CGNAT / link-local / multicast destinations
+    pub ignore_private: bool,
+    /// Process names (case-insensitive) never reported
+    pub allowlist_processes: Vec<String>,
+    /// Quiet period per flow after a detection
+    pub cooldown_secs: f64,
+}
+
+impl Default for BeaconSettings {
+    fn default() -> Self {
+        BeaconSettings {
+            enabled: true,
+            min_connections: 8,
+            max_samples: 64,
+            burst_merge_secs: 2.0,
+            min_interval_secs: 5.0,
+            max_interval_secs: 3600.0,
+            jitter_tolerance: 0.2,
+            threshold: 0.8,
+            ignore_private: true,
+            allowlist_processes: Vec::new(),
+            cooldown_secs: 3600.0,
+        }
+    }
+}
+
+impl BeaconSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.min_connections < 3 {
+            return Err("min_connections must be at least 3".to_string());
+        }
+        if self.max_samples < self.min_connections {
+            return Err("max_samples must be at least min_connections".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.threshold) || !(0.0..=1.0).contains(&self.jitter_tolerance) {
+            return Err("threshold and jitter_tolerance must be between 0 and 1".to_string());
+        }
+        if self.min_interval_secs >= self.max_interval_secs {
+            return Err("min_interval_secs must be below max_interval_secs".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn get_settings_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("CyberGuardian")
+        .join("beacon_settings.json")
+}
+
+fn load_settings() -> BeaconSettings {
+    fs::read_to_string(get_settings_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save_settings(settings: &BeaconSettings) -> Result<(), String> {
+    let path = get_settings_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create settings dir: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write settings: {}", e))
+}
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// One outbound connection as seen by any connection source
+#[derive(Debug, Clone)]
+pub struct ConnectionObservation {
+    pub key: ProcessKey,
+    pub process_name: String,
+    pub remote_ip: IpAddr,
+    /// 0 when the source cannot tell
+    pub remote_port: u16,
+    /// Seconds on a monotonic clock
+    pub at: f64,
+    /// "etw" | "procfs"
+    pub source: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BeaconScore {
+    pub connections: usize,
+    pub mean_interval_secs: f64,
+    pub median_interval_secs: f64,
+    pub stddev_secs: f64,
+    /// stddev / mean — 0 for a perfect metronome
+    pub coefficient_of_variation: f64,
+    /// Share of intervals within the jitter tolerance of the median
+    pub on_schedule_ratio: f64,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BeaconDetection {
+    /// Serialized as `pid` + `start_time`
+    #[serde(flatten)]
+    pub key: ProcessKey,
+    pub process_name: String,
+    pub remote_ip: String,
+    pub remote_port: u16,
+    pub source: String,
+    pub score: BeaconScore,
+    pub timestamp: String,
+}
+
+impl BeaconDetection {
+    pub fn summary(&self) -> String {
+        format!(
+            "Periodic callbacks to {}:{} every ~{:.0}s ({} connections, CV {:.2}, score {:.2})",
+            self.remote_ip, self.remote_port, self.score.median_interval_secs,
+            self.score.connections, self.score.coefficient_of_variation, self.score.score,
+        )
+    }
+}
+
+// ============================================================================
+// SCORING
+// ============================================================================
+
+/// Score connection times (seconds, ascending). None when there is too little data
+/// or the cadence falls outside the configured interval range
+pub fn score_timestamps(times: &[f64], settings: &BeaconSettings) -> Option<BeaconScore> {
+    // Merge bursts into one logical callback
+    let mut merged: Vec<f64> = Vec::with_capacity(times.len());
+    for &t in times {
+        match merged.last() {
+            Some(&last) if t - last < settings.burst_merge_secs => {}
+            _ => merged.push(t),
+        }
+    }
+    if merged.len() < settings.min_connections {
+        return None;
+    }
+
+    let intervals: Vec<f64> = merged.windows(2).map(|w| w[1] - w[0]).collect();
+    let n = intervals.len() as f64;
+    let mean = intervals.iter().sum::<f64>() / n;
+    let mut sorted = intervals.clone();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let median = sorted[sorted.len() / 2];
+    if median < settings.min_interval_secs || median > settings.max_interval_secs {
+        return None;
+    }
+
+    let stddev = (intervals.iter().map(|i| (i - mean).powi(2)).sum::<f64>() / n).sqrt();
+    let cv = if mean > 0.0 { stddev / mean } else { f64::MAX };
+    let tolerance = median * settings.jitter_tolerance;
+    let on_schedule = intervals.iter().filter(|i| (*i - median).abs() <= tolerance).count() as f64 / n;
+
+    // Periodicity dominates; more evidence (connections) nudges the score up
+    let regularity = 1.0 - cv.min(1.0);
+    let volume = ((merged.len() - settings.min_connections) as f64 / settings.min_connections as f64).min(1.0);
+    let score = 0.45 * regularity + 0.45 * on_schedule + 0.1 * volume;
+
+    Some(BeaconScore {
+        connections: merged.len(),
+        mean_interval_secs: mean,
+        median_interval_secs: median,
+        stddev_secs: stddev,
+        coefficient_of_variation: cv,
+        on_schedule_ratio: on_schedule,
+        score,
+    })
+}
+
+// ============================================================================
+// TRACKER
+// ============================================================================
+
+/// Keyed by process instance so a recycled PID starts a fresh flow
+type FlowKey = (ProcessKey, IpAddr, u16);
+
+struct Flow {
+    times: VecDeque<f64>,
+    last_reported: Option<f64>,
+}
+
+#[derive(Default)]
+pub struct BeaconTracker {
+    flows: HashMap<FlowKey, Flow>,
+}
+
+impl BeaconTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drop flows idle for longer than the largest interval we would still score
+    fn evict(&mut self, now: f64, settings: &BeaconSettings) {
+        let idle = settings.max_interval_secs * 2.0;
+        self.flows.retain(|_, f| f.times.back().map(|t| now - t <= idle).unwrap_or(false));
+    }
+
+    pub fn observe(&mut self, conn: &ConnectionObservation, settings: &BeaconSettings) -> Option<BeaconDetection> {
+        if settings.ignore_private && crate::network_events::classify_ip(&conn.remote_ip).is_internal() {
+            return None;
+        }
+        if settings.allowlist_processes.iter().any(|p| p.eq_ignore_ascii_case(&conn.process_name)) {
+            return None;
+        }
+
+        let key = (conn.key, conn.remote_ip, conn.remote_port);
+        if !self.flows.contains_key(&key) && self.flows.len() >= MAX_TRACKED_FLOWS {
+            self.evict(conn.at, settings);
+            if self.flows.len() >= MAX_TRACKED_FLOWS {
+                return None;
+            }
+        }
+
+        let flow = self.flows.entry(key).or_insert_with(|| Flow { times: VecDeque::new(), last_reported: None });
+        flow.times.push_back(conn.at);
+        while flow.times.len() > settings.max_samples {
+            flow.times.pop_front();
+        }
+        if flow.last_reported.map(|t| conn.at - t < settings.cooldown_secs).unwrap_or(false) {
+            return None;
+        }
+
+        let times: Vec<f64> = flow.times.iter().copied().collect();
+        let score = score_timestamps(&times, settings)?;
+        if score.score < settings.threshold {
+            return None;
+        }
+        flow.last_reported = Some(conn.at);
+
+        Some(BeaconDetection {
+            key: conn.key,
+            process_name: conn.process_name.clone(),
+            remote_ip: conn.remote_ip.to_string(),
+            remote_port: conn.remote_port,
+            source: conn.source.to_string(),
+            score,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        })
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref SETTINGS: RwLock<BeaconSettings> = RwLock::new(load_settings());
+    static ref TRACKER: Mutex<BeaconTracker> = Mutex::new(BeaconTracker::new());
+    static ref DETECTIONS: Mutex<VecDeque<BeaconDetection>> = Mutex::new(VecDeque::new());
+    static ref STARTED: std::time::Instant = std::time::Instant::now();
+}
+
+/// Monotonic seconds for connection sources that have no timestamp of their own
+pub fn now_secs() -> f64 {
+    STARTED.elapsed().as_secs_f64()
+}
+
+/// Feed one outbound connection; returns a detection when the flow looks like a beacon
+pub fn observe(conn: &ConnectionObservation) -> Option<BeaconDetection> {
+    let settings = SETTINGS.read().unwrap().clone();
+    if !settings.enabled {
+        return None;
+    }
+    let detection = TRACKER.lock().unwrap().observe(conn, &settings)?;
+
+    let mut detections = DETECTIONS.lock().unwrap();
+    if detections.len() >= MAX_DETECTIONS {
+        detections.pop_front();
+    }
+    detections.push_back(detection.clone());
+    Some(detection)
+}
+
+/// Record a beacon detection as a T1071 network alert + detection
+pub fn report(detection: &BeaconDetection, parent_name: &str) {
+    use crate::process_monitor;
+
+    warn!(pid = detection.key.pid, process = %detection.process_name, mitre = "T1071",
+        "Beacon detected → {}:{}", detection.remote_ip, detection.remote_port);
+    let reason = detection.summary();
+
+    process_monitor::record_network_alert(process_monitor::NetworkAlert {
+        kind: "connection".to_string(),
+        indicator: detection.remote_ip.clone(),
+        port: (detection.remote_port != 0).then_some(detection.remote_port),
+        pid: detection.key.pid,
+        process_name: detection.process_name.clone(),
+        mitre_technique: "T1071".to_string(),
+        reason: reason.clone(),
+        timestamp: detection.timestamp.clone(),
+        evidence: serde_json::to_value(&detection.score).ok(),
+    });
+
+    crate::response_playbooks::respond(&crate::response_playbooks::Detection {
+        key: detection.key,
+        process_name: detection.process_name.clone(),
+        parent_name: parent_name.to_string(),
+        rule_id: "T1071".to_string(),
+        severity: "high".to_string(),
+        reason,
+        source: "network",
+        command_line: None,
+        confidence: crate::enforcement::Confidence::Heuristic,
+        suspended: false,
+    });
+}
+
+// ============================================================================
+// TAURI COMMANDS
+// ============================================================================
+
+#[tauri::command]
+pub fn get_beacon_settings() -> BeaconSettings {
+    SETTINGS.read().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn set_beacon_settings(settings: BeaconSettings) -> Result<BeaconSettings, String> {
+    settings.validate()?;
+    save_settings(&settings)?;
+    *SETTINGS.write().unwrap() = settings.clone();
+    info!("Beacon settings updated (min {} connections, threshold {:.2})", settings.min_connections, settings.threshold);
+    Ok(settings)
+}
+
+#[tauri::command]
+pub fn get_beacon_detections() -> Vec<BeaconDetection> {
+    DETECTIONS.lock().unwrap().iter().rev().cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conn(ip: &str, at: f64) -> ConnectionObservation {
+        ConnectionObservation {
+            key: ProcessKey::new(900, 1),
+            process_name: "rundll32.exe".into(),
+            remote_ip: ip.parse().unwrap(),
+            remote_port: 443,
+            at,
+            source: "etw",
+        }
+    }
+
+    #[test]
+    fn test_jittered_beacon_is_detected() {
+        let settings = BeaconSettings::default();
+        let mut tracker = BeaconTracker::new();
+        // 60s sleep with ±10% jitter, plus a retry burst that must be merged
+        let jitter = [3.0, -5.0, 1.0, 6.0, -2.0, 4.0, -6.0, 0.0, 2.0, -3.0];
+        let mut t = 0.0;
+        let mut detection = None;
+        for (i, j) in jitter.iter().enumerate() {
+            t += 60.0 + j;
+            if i == 4 {
+                assert!(tracker.observe(&conn("198.51.100.20", t - 0.5), &settings).is_none());
+            }
+            if let Some(d) = tracker.observe(&conn("198.51.100.20", t), &settings) {
+                detection = Some(d);
+            }
+        }
+        let detection = detection.expect("beacon should be detected");
+        assert_eq!(detection.remote_port, 443);
+        assert!(detection.score.coefficient_of_variation < 0.1);
+        assert!((detection.score.median_interval_secs - 60.0).abs() < 6.0);
+
+        // Cooldown: no repeat on the very next callback
+        assert!(tracker.observe(&conn("198.51.100.20", t + 60.0), &settings).is_none());
+    }
+
+    #[test]
+    fn test_irregular_and_private_traffic_is_ignored() {
+        let settings = BeaconSettings::default();
+        let mut tracker = BeaconTracker::new();
+        let irregular = [0.0, 4.0, 90.0, 97.0, 400.0, 1000.0, 1003.0, 1500.0, 2900.0, 3000.0, 3100.0];
+        for t in irregular {
+            assert!(tracker.observe(&conn("203.0.113.9", t), &settings).is_none());
+        }
+        for i in 0..20 {
+            assert!(tracker.observe(&conn("10.0.0.5", i as f64 * 30.0), &settings).is_none());
+        }
+
+        let times: Vec<f64> = (0..20).map(|i| i as f64 * 30.0).collect();
+        let score = score_timestamps(&times, &settings).unwrap();
+        assert!(score.score > 0.95);
+        // Too few connections → not scored
+        assert!(score_timestamps(&times[..5], &settings).is_none());
+    }
+}
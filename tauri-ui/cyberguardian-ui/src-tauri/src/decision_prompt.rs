@@ -0,0 +1,402 @@
+//! Process Decision Prompt
+//! Ambiguous detections in block-with-prompt mode keep the process suspended
+//! and ask the user: allow once, always allow (writes an exception) or kill.
+//! The request goes out as a Tauri event; an answer can come from the UI or
+//! the remote command channel. Without an answer the default action applies
+//! when the timeout runs out
+
+use crate::process_identity::ProcessKey;
+use crate::response_playbooks::ResponseAction;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+use tracing::{error, info};
+
+pub const EVENT_REQUESTED: &str = "process-decision-requested";
+pub const EVENT_RESOLVED: &str = "process-decision-resolved";
+
+const MAX_LINEAGE: usize = 8;
+
+// ============================================================================
+// SETTINGS
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DecisionChoice {
+    AllowOnce,
+    AlwaysAllow,
+    Kill,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PromptSettings {
+    pub timeout_secs: u64,
+    /// Applied when nobody answers in time
+    pub default_action: DecisionChoice,
+}
+
+impl Default for PromptSettings {
+    fn default() -> Self {
+        PromptSettings {
+            timeout_secs: 60,
+            // Fail open: on an unattended machine a heuristic must not kill by timeout
+            default_action: DecisionChoice::AllowOnce,
+        }
+    }
+}
+
+impl PromptSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(10..=600).contains(&self.timeout_secs) {
+            return Err("timeout_secs must be between 10 and 600".to_string());
+        }
+        if self.default_action == DecisionChoice::AlwaysAllow {
+            return Err("default_action cannot be always_allow".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn data_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("CyberGuardian")
+}
+
+fn load_json<T: Default + for<'de> Deserialize<'de>>(name: &str) -> T {
+    fs::read_to_string(data_dir().join(name))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save_json<T: Serialize>(name: &str, value: &T) -> Result<(), String> {
+    let dir = data_dir();
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create settings dir: {}", e))?;
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize {}: {}", name, e))?;
+    fs::write(dir.join(name), json).map_err(|e| format!("Failed to write {}: {}", name, e))
+}
+
+// ============================================================================
+// EXCEPTIONS
+// ============================================================================
+
+/// Written by "always allow": this image (or name, when the path was unknown) may trigger this rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessException {
+    pub id: String,
+    pub rule_id: String,
+    pub process_name: String,
+    pub image_path: String,
+    pub created_at: String,
+    /// "ui" | "remote"
+    pub created_by: String,
+}
+
+impl ProcessException {
+    pub fn matches(&self, rule_id: &str, process_name: &str, image_path: &str) -> bool {
+        if !self.rule_id.eq_ignore_ascii_case(rule_id) {
+            return false;
+        }
+        // Name-only exceptions match by name; a path exception never matches a
+        // detection whose path could not be read (same name from anywhere)
+        if self.image_path.is_empty() {
+            self.process_name.eq_ignore_ascii_case(process_name)
+        } else {
+            !image_path.is_empty() && self.image_path.eq_ignore_ascii_case(image_path)
+        }
+    }
+}
+
+pub fn is_excepted(rule_id: &str, process_name: &str, image_path: &str) -> bool {
+    EXCEPTIONS.read().unwrap().iter().any(|e| e.matches(rule_id, process_name, image_path))
+}
+
+fn add_exception(request: &DecisionRequest, source: &str) {
+    let exception = ProcessException {
+        id: uuid::Uuid::new_v4().to_string(),
+        rule_id: request.rule_id.clone(),
+        process_name: request.process_name.clone(),
+        image_path: request.image_path.clone(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        created_by: source.to_string(),
+    };
+    let mut exceptions = EXCEPTIONS.write().unwrap();
+    exceptions.push(exception);
+    if let Err(e) = save_json("process_exceptions.json", &*exceptions) {
+        error!("Failed to save process exception: {}", e);
+    }
+    info!("Exception added: {} for {}", request.process_name, request.rule_id);
+}
+
+// ============================================================================
+// LINEAGE
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LineageEntry {
+    pub pid: u32,
+    pub name: String,
+}
+
+/// Parent chain starting at `pid`; `lookup` returns (name, parent pid)
+pub fn build_lineage(pid: u32, lookup: impl Fn(u32) -> Option<(String, u32)>) -> Vec<LineageEntry> {
+    let mut chain: Vec<LineageEntry> = Vec::new();
+    let mut current = pid;
+    while chain.len() < MAX_LINEAGE && current != 0 && !chain.iter().any(|e| e.pid == current) {
+        let Some((name, parent)) = lookup(current) else { break };
+        chain.push(LineageEntry { pid: current, name });
+        current = parent;
+    }
+    chain
+}
+
+#[cfg(windows)]
+fn lineage(pid: u32) -> Vec<LineageEntry> {
+    let table: HashMap<u32, (String, u32)> = crate::process_monitor::enumerate_processes()
+        .map(|procs| procs.into_iter().map(|p| (p.pid, (p.name, p.parent_pid))).collect())
+        .unwrap_or_default();
+    build_lineage(pid, |p| table.get(&p).cloned())
+}
+
+#[cfg(not(windows))]
+fn lineage(pid: u32) -> Vec<LineageEntry> {
+    build_lineage(pid, |p| {
+        let stat = fs::read_to_string(format!("/proc/{}/stat", p)).ok()?;
+        let name = stat.split_once('(')?.1.rsplit_once(')')?.0.to_string();
+        let ppid = stat.rsplit_once(')')?.1.split_whitespace().nth(1)?.parse().ok()?;
+        Some((name, ppid))
+    })
+}
+
+// ============================================================================
+// PENDING DECISIONS
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DecisionRequest {
+    pub id: String,
+    pub pid: u32,
+    pub process_key: ProcessKey,
+    pub process_name: String,
+    pub parent_name: String,
+    pub image_path: String,
+    pub command_line: String,
+    pub lineage: Vec<LineageEntry>,
+    pub rule_id: String,
+    pub severity: String,
+    pub reason: String,
+    pub playbook_id: String,
+    /// What "kill" will run
+    pub actions: Vec<ResponseAction>,
+    pub requested_at: String,
+    pub expires_at: String,
+    pub default_action: DecisionChoice,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DecisionResolution {
+    pub id: String,
+    pub choice: DecisionChoice,
+    /// "ui" | "remote" | "timeout"
+    pub source: String,
+}
+
+/// Delivers prompt events to the desktop UI (registered at setup)
+pub type Notifier = Box<dyn Fn(&str, serde_json::Value) + Send + Sync>;
+
+lazy_static::lazy_static! {
+    static ref SETTINGS: RwLock<PromptSettings> = RwLock::new(load_json("decision_prompt_settings.json"));
+    static ref EXCEPTIONS: RwLock<Vec<ProcessException>> = RwLock::new(load_json("process_exceptions.json"));
+    static ref NOTIFIER: RwLock<Option<Notifier>> = RwLock::new(None);
+    static ref PENDING: Mutex<HashMap<String, (DecisionRequest, Sender<DecisionResolution>)>> = Mutex::new(HashMap::new());
+}
+
+pub fn set_notifier(notifier: Notifier) {
+    *NOTIFIER.write().unwrap() = Some(notifier);
+}
+
+fn notify(event: &str, payload: &impl Serialize) {
+    if let Some(notify) = NOTIFIER.read().unwrap().as_ref() {
+        notify(event, serde_json::to_value(payload).unwrap_or_default());
+    }
+}
+
+/// Details the prompt shows, filled in by the response engine
+pub struct PromptInput {
+    pub key: ProcessKey,
+    pub process_name: String,
+    pub parent_name: String,
+    pub image_path: String,
+    pub command_line: String,
+    pub rule_id: String,
+    pub severity: String,
+    pub reason: String,
+    pub playbook_id: String,
+    pub actions: Vec<ResponseAction>,
+}
+
+/// Ask for a decision; `on_decision` runs on a worker thread once it is made.
+/// None when no UI is attached to ask — the caller must decide itself
+pub fn request(input: PromptInput, on_decision: impl FnOnce(&DecisionResolution) + Send + 'static) -> Option<String> {
+    if NOTIFIER.read().unwrap().is_none() {
+        return None;
+    }
+    let settings = SETTINGS.read().unwrap().clone();
+    let now = chrono::Utc::now();
+    let request = DecisionRequest {
+        id: uuid::Uuid::new_v4().to_string(),
+        pid: input.key.pid,
+        process_key: input.key,
+        lineage: lineage(input.key.pid),
+        process_name: input.process_name,
+        parent_name: input.parent_name,
+        image_path: input.image_path,
+        command_line: input.command_line,
+        rule_id: input.rule_id,
+        severity: input.severity,
+        reason: input.reason,
+        playbook_id: input.playbook_id,
+        actions: input.actions,
+        requested_at: now.to_rfc3339(),
+        expires_at: (now + chrono::Duration::seconds(settings.timeout_secs as i64)).to_rfc3339(),
+        default_action: settings.default_action,
+    };
+    let id = request.id.clone();
+
+    let (tx, rx) = mpsc::channel();
+    PENDING.lock().unwrap().insert(id.clone(), (request.clone(), tx));
+    info!("Awaiting decision {} for {} (PID {}) [{}]", id, request.process_name, request.pid, request.rule_id);
+    notify(EVENT_REQUESTED, &request);
+
+    std::thread::spawn(move || {
+        let resolution = rx.recv_timeout(Duration::from_secs(settings.timeout_secs)).unwrap_or_else(|_| {
+            // Gone from PENDING → `resolve` sent an answer under the same lock just now
+            let answered = PENDING.lock().unwrap().remove(&request.id).is_none();
+            rx.try_recv().ok().filter(|_| answered).unwrap_or_else(|| DecisionResolution {
+                id: request.id.clone(),
+                choice: settings.default_action,
+                source: "timeout".to_string(),
+            })
+        });
+        info!("Decision {}: {:?} ({})", resolution.id, resolution.choice, resolution.source);
+        if resolution.choice == DecisionChoice::AlwaysAllow {
+            add_exception(&request, &resolution.source);
+        }
+        notify(EVENT_RESOLVED, &resolution);
+        on_decision(&resolution);
+    });
+    Some(id)
+}
+
+/// Answer a pending decision (desktop UI or remote command)
+pub fn resolve(id: &str, choice: DecisionChoice, source: &str) -> Result<DecisionResolution, String> {
+    let mut pending = PENDING.lock().unwrap();
+    let (_, tx) = pending.remove(id).ok_or_else(|| format!("No pending decision {}", id))?;
+    let resolution = DecisionResolution { id: id.to_string(), choice, source: source.to_string() };
+    tx.send(resolution.clone()).map_err(|_| format!("Decision {} already timed out", id))?;
+    Ok(resolution)
+}
+
+pub fn pending() -> Vec<DecisionRequest> {
+    let mut requests: Vec<DecisionRequest> = PENDING.lock().unwrap().values().map(|(r, _)| r.clone()).collect();
+    requests.sort_by(|a, b| a.requested_at.cmp(&b.requested_at));
+    requests
+}
+
+// ============================================================================
+// TAURI COMMANDS
+// ============================================================================
+
+#[tauri::command]
+pub fn get_pending_decisions() -> Vec<DecisionRequest> {
+    pending()
+}
+
+#[tauri::command]
+pub fn resolve_process_decision(id: String, choice: DecisionChoice) -> Result<DecisionResolution, String> {
+    resolve(&id, choice, "ui")
+}
+
+#[tauri::command]
+pub fn get_prompt_settings() -> PromptSettings {
+    SETTINGS.read().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn set_prompt_settings(settings: PromptSettings) -> Result<PromptSettings, String> {
+    settings.validate()?;
+    save_json("decision_prompt_settings.json", &settings)?;
+    *SETTINGS.write().unwrap() = settings.clone();
+    info!("Prompt settings updated ({}s timeout, default {:?})", settings.timeout_secs, settings.default_action);
+    Ok(settings)
+}
+
+#[tauri::command]
+pub fn list_process_exceptions() -> Vec<ProcessException> {
+    EXCEPTIONS.read().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn remove_process_exception(id: String) -> Result<Vec<ProcessException>, String> {
+    let mut exceptions = EXCEPTIONS.write().unwrap();
+    let before = exceptions.len();
+    exceptions.retain(|e| e.id != id);
+    if exceptions.len() == before {
+        return Err(format!("No exception {}", id));
+    }
+    save_json("process_exceptions.json", &*exceptions)?;
+    Ok(exceptions.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exception_matching() {
+        let exception = ProcessException {
+            id: "1".into(),
+            rule_id: "T1574".into(),
+            process_name: "tool.exe".into(),
+            image_path: r"C:\Users\a\AppData\Local\Tool\tool.exe".into(),
+            created_at: String::new(),
+            created_by: "ui".into(),
+        };
+        assert!(exception.matches("t1574", "TOOL.EXE", r"c:\users\a\appdata\local\tool\tool.exe"));
+        // Same name from another location is not covered
+        assert!(!exception.matches("T1574", "tool.exe", r"C:\Users\a\AppData\Local\Temp\tool.exe"));
+        assert!(!exception.matches("T1059", "tool.exe", r"C:\Users\a\AppData\Local\Tool\tool.exe"));
+        // Image path unknown at detection time → no match for a path exception
+        assert!(!exception.matches("T1574", "tool.exe", ""));
+        // Exception recorded without a path → matches by name
+        let by_name = ProcessException { image_path: String::new(), ..exception };
+        assert!(by_name.matches("T1574", "tool.exe", ""));
+        assert!(by_name.matches("T1574", "tool.exe", r"C:\Tools\tool.exe"));
+    }
+
+    #[test]
+    fn test_lineage_and_settings() {
+        let table: HashMap<u32, (String, u32)> = [
+            (300, ("powershell.exe".to_string(), 200)),
+            (200, ("winword.exe".to_string(), 100)),
+            (100, ("explorer.exe".to_string(), 300)), // recycled parent PID → cycle
+        ].into_iter().collect();
+        let chain = build_lineage(300, |p| table.get(&p).cloned());
+        let names: Vec<&str> = chain.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["powershell.exe", "winword.exe", "explorer.exe"]);
+
+        assert!(PromptSettings::default().validate().is_ok());
+        assert_eq!(PromptSettings::default().default_action, DecisionChoice::AllowOnce);
+        let settings: PromptSettings = serde_json::from_str(r#"{"default_action":"always_allow"}"#).unwrap();
+        assert_eq!(settings.timeout_secs, 60);
+        assert!(settings.validate().is_err());
+    }
+}
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use chrono::Utc;
+use tracing::{info, warn};
 
 /// Stage 1: File Analysis Result
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -609,16 +610,16 @@ pub fn perform_deep_analysis(file_path: &str) -> Result<DeepAnalysisResult, Stri
     let analysis_id = generate_analysis_id();
     let analyzed_at = chrono::Utc::now().to_rfc3339();
 
-    println!("🔍 Stage 1: Analyzing file...");
+    info!("Stage 1: Analyzing file...");
     let file_stage = analyze_file_stage(file_path)?;
 
-    println!("🔍 Stage 2: Scanning registry...");
+    info!("Stage 2: Scanning registry...");
     let registry_stage = analyze_registry_stage(file_path)?;
 
-    println!("🔍 Stage 3: Scanning services...");
+    info!("Stage 3: Scanning services...");
     let service_stage = analyze_service_stage(file_path)?;
 
-    println!("🔍 Stage 4: Scanning tasks...");
+    info!("Stage 4: Scanning tasks...");
     let task_stage = analyze_task_stage(file_path)?;
 
     let (risk_score, threat_level) = calculate_risk_score(
@@ -636,7 +637,7 @@ pub fn perform_deep_analysis(file_path: &str) -> Result<DeepAnalysisResult, Stri
         &task_stage,
     );
 
-    println!("✅ Analysis complete! Threat Level: {} | Risk Score: {}", threat_level, risk_score);
+    info!("Analysis complete! Threat Level: {} | Risk Score: {}", threat_level, risk_score);
 
     Ok(DeepAnalysisResult {
         analysis_id,
@@ -664,9 +665,9 @@ use std::path::Path;
 /// Perform complete removal with backup
 pub fn perform_complete_removal(analysis: &DeepAnalysisResult) -> Result<RemovalResult, String> {
     // Create backup first
-    println!("💾 Creating backup...");
+    info!("Creating backup...");
     let backup_file = create_backup(analysis)?;
-    println!("✅ Backup created: {}", backup_file);
+    info!("Backup created: {}", backup_file);
 
     let mut removed_items = RemovedItems {
         file_removed: false,
@@ -677,37 +678,37 @@ pub fn perform_complete_removal(analysis: &DeepAnalysisResult) -> Result<Removal
 
     // Step 1: Remove scheduled tasks (do first, as they might try to recreate file)
     if analysis.stages.task_scan.has_references {
-        println!("🗑️ Removing scheduled tasks...");
+        info!("Removing scheduled tasks...");
         let tasks_removed = remove_scheduled_tasks(&analysis.stages.task_scan)?;
         removed_items.tasks_removed = tasks_removed;
-        println!("✅ Removed {} tasks", tasks_removed);
+        info!("Removed {} tasks", tasks_removed);
     }
 
     // Step 2: Stop and remove services (do second, as they might have file locked)
     if analysis.stages.service_scan.has_dependencies {
-        println!("🗑️ Stopping and removing services...");
+        info!("Stopping and removing services...");
         let services_removed = remove_services(&analysis.stages.service_scan)?;
         removed_items.services_removed = services_removed;
-        println!("✅ Removed {} services", services_removed);
+        info!("Removed {} services", services_removed);
     }
 
     // Step 3: Remove registry entries (do third, so nothing can recreate file)
     if analysis.stages.registry_scan.has_references {
-        println!("🗑️ Cleaning registry entries...");
+        info!("Cleaning registry entries...");
         let registry_removed = remove_registry_entries(&analysis.stages.registry_scan)?;
         removed_items.registry_entries_removed = registry_removed;
-        println!("✅ Cleaned {} registry entries", registry_removed);
+        info!("Cleaned {} registry entries", registry_removed);
     }
 
     // Step 4: Remove the file itself (do last)
-    println!("🗑️ Removing target file...");
+    info!("Removing target file...");
     let file_removed = remove_file(&analysis.target_path)?;
     removed_items.file_removed = file_removed;
     
     if file_removed {
-        println!("✅ File removed successfully");
+        info!("File removed successfully");
     } else {
-        println!("⚠️ File could not be removed (may be locked or require elevated privileges)");
+        warn!("File could not be removed (may be locked or require elevated privileges)");
     }
 
     let message = format!(
@@ -772,7 +773,7 @@ fn remove_file(file_path: &str) -> Result<bool, String> {
         Ok(_) => Ok(true),
         Err(e) => {
             // File might be locked or require admin privileges
-            eprintln!("⚠️ Could not remove file: {}", e);
+            warn!("Could not remove file: {}", e);
             Ok(false)
         }
     }
@@ -790,7 +791,7 @@ fn remove_registry_entries(registry_stage: &RegistryScanStage) -> Result<usize,
         // Parse registry key path (format: "HKLM\Path\ValueName")
         let parts: Vec<&str> = registry_key.rsplitn(2, '\\').collect();
         if parts.len() != 2 {
-            eprintln!("⚠️ Invalid registry key format: {}", registry_key);
+            warn!("Invalid registry key format: {}", registry_key);
             continue;
         }
 
@@ -811,11 +812,11 @@ fn remove_registry_entries(registry_stage: &RegistryScanStage) -> Result<usize,
                 if output.status.success() {
                     removed_count += 1;
                 } else {
-                    eprintln!("⚠️ Failed to remove registry entry: {}", registry_key);
+                    warn!("Failed to remove registry entry: {}", registry_key);
                 }
             }
             Err(e) => {
-                eprintln!("⚠️ PowerShell error: {}", e);
+                warn!("PowerShell error: {}", e);
             }
         }
     }
@@ -856,11 +857,11 @@ fn remove_services(service_stage: &ServiceScanStage) -> Result<usize, String> {
                 if output.status.success() {
                     removed_count += 1;
                 } else {
-                    eprintln!("⚠️ Failed to remove service: {}", service_name);
+                    warn!("Failed to remove service: {}", service_name);
                 }
             }
             Err(e) => {
-                eprintln!("⚠️ PowerShell error: {}", e);
+                warn!("PowerShell error: {}", e);
             }
         }
     }
@@ -890,11 +891,11 @@ fn remove_scheduled_tasks(task_stage: &TaskScanStage) -> Result<usize, String> {
                 if output.status.success() {
                     removed_count += 1;
                 } else {
-                    eprintln!("⚠️ Failed to remove task: {}", task_name);
+                    warn!("Failed to remove task: {}", task_name);
                 }
             }
             Err(e) => {
-                eprintln!("⚠️ PowerShell error: {}", e);
+                warn!("PowerShell error: {}", e);
             }
         }
     }
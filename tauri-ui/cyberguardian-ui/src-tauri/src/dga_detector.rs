@@ -0,0 +1,500 @@
+//! DGA Detection
+//! Scores DNS names for algorithmically generated labels. The registrable label
+//! (eTLD+1 via the embedded ICANN section of the Public Suffix List) is scored on bigram likelihood
+//! against an embedded benign model, character entropy, consonant/digit ratios,
+//! consonant runs and length. Thresholds and weights are tunable via settings
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use tracing::info;
+
+const PUBLIC_SUFFIX_LIST: &str = include_str!("../data/dga/public_suffix_list.dat");
+const BENIGN_TRAINING: &str = include_str!("../data/dga/benign_training.txt");
+
+/// Labels use [a-z0-9] plus the end marker as bigram targets
+const TARGET_SYMBOLS: f64 = 37.0;
+const SMOOTHING: f64 = 0.1;
+const START: char = '^';
+const END: char = '$';
+
+// ============================================================================
+// PUBLIC SUFFIX LIST
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DomainParts {
+    pub subdomain: String,
+    /// Second-level label that was registered (e.g. "example" in www.example.co.uk)
+    pub label: String,
+    pub suffix: String,
+}
+
+impl DomainParts {
+    pub fn registrable(&self) -> String {
+        format!("{}.{}", self.label, self.suffix)
+    }
+}
+
+pub struct PublicSuffixList {
+    rules: HashSet<String>,
+    wildcards: HashSet<String>,
+    exceptions: HashSet<String>,
+}
+
+impl PublicSuffixList {
+    pub fn parse(text: &str) -> Self {
+        let mut psl = PublicSuffixList { rules: HashSet::new(), wildcards: HashSet::new(), exceptions: HashSet::new() };
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+            let rule = line.split_whitespace().next().unwrap_or("").to_lowercase();
+            if let Some(exception) = rule.strip_prefix('!') {
+                psl.exceptions.insert(exception.to_string());
+            } else if let Some(parent) = rule.strip_prefix("*.") {
+                psl.wildcards.insert(parent.to_string());
+            } else {
+                psl.rules.insert(rule);
+            }
+        }
+        psl
+    }
+
+    /// Number of trailing labels forming the public suffix (PSL algorithm; default rule "*")
+    fn suffix_len(&self, labels: &[&str]) -> usize {
+        let mut best = 1;
+        for i in 0..labels.len() {
+            let candidate = labels[i..].join(".");
+            let len = labels.len() - i;
+            if self.exceptions.contains(&candidate) {
+                return len - 1;
+            }
+            if self.rules.contains(&candidate) {
+                best = best.max(len);
+            }
+            if i + 1 < labels.len() && self.wildcards.contains(&labels[i + 1..].join(".")) {
+                best = best.max(len);
+            }
+        }
+        best
+    }
+
+    pub fn split(&self, domain: &str) -> Option<DomainParts> {
+        let domain = domain.trim().trim_end_matches('.').to_lowercase();
+        let labels: Vec<&str> = domain.split('.').collect();
+        if labels.iter().any(|l| l.is_empty()) {
+            return None;
+        }
+        let suffix_len = self.suffix_len(&labels);
+        if labels.len() <= suffix_len {
+            return None;
+        }
+        let label_idx = labels.len() - suffix_len - 1;
+        Some(DomainParts {
+            subdomain: labels[..label_idx].join("."),
+            label: labels[label_idx].to_string(),
+            suffix: labels[label_idx + 1..].join("."),
+        })
+    }
+}
+
+// ============================================================================
+// BIGRAM MODEL
+// ============================================================================
+
+pub struct BigramModel {
+    pairs: HashMap<(char, char), f64>,
+    totals: HashMap<char, f64>,
+}
+
+impl BigramModel {
+    /// Train on whitespace/line separated tokens; `#` starts a comment line
+    pub fn train(corpus: &str) -> Self {
+        let mut model = BigramModel { pairs: HashMap::new(), totals: HashMap::new() };
+        let tokens = corpus.lines()
+            .filter(|l| !l.trim_start().starts_with('#'))
+            .flat_map(str::split_whitespace);
+        for token in tokens {
+            let chars = Self::framed(&token.to_lowercase());
+            for pair in chars.windows(2) {
+                *model.pairs.entry((pair[0], pair[1])).or_default() += 1.0;
+                *model.totals.entry(pair[0]).or_default() += 1.0;
+            }
+        }
+        model
+    }
+
+    fn framed(label: &str) -> Vec<char> {
+        std::iter::once(START)
+            .chain(label.chars().filter(|c| c.is_ascii_alphanumeric()))
+            .chain(std::iter::once(END))
+            .collect()
+    }
+
+    /// Mean log10 P(next | current) over the label, start/end markers included
+    pub fn avg_log_likelihood(&self, label: &str) -> f64 {
+        let chars = Self::framed(label);
+        let sum: f64 = chars.windows(2)
+            .map(|p| {
+                let count = self.pairs.get(&(p[0], p[1])).copied().unwrap_or(0.0);
+                let total = self.totals.get(&p[0]).copied().unwrap_or(0.0);
+                ((count + SMOOTHING) / (total + SMOOTHING * TARGET_SYMBOLS)).log10()
+            })
+            .sum();
+        sum / (chars.len() - 1) as f64
+    }
+}
+
+// ============================================================================
+// FEATURES / SCORING
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DgaFeatures {
+    pub length: usize,
+    /// Shannon entropy, bits per character
+    pub entropy: f64,
+    pub bigram_log_likelihood: f64,
+    /// Consonants among letters
+    pub consonant_ratio: f64,
+    pub digit_ratio: f64,
+    pub max_consonant_run: usize,
+}
+
+fn is_consonant(c: char) -> bool {
+    c.is_ascii_lowercase() && !"aeiouy".contains(c)
+}
+
+/// Shannon entropy of the alphanumeric characters, bits per character
+pub fn shannon_entropy(text: &str) -> f64 {
+    let mut counts: HashMap<char, usize> = HashMap::new();
+    let mut n = 0usize;
+    for c in text.chars().filter(|c| c.is_ascii_alphanumeric()) {
+        *counts.entry(c.to_ascii_lowercase()).or_default() += 1;
+        n += 1;
+    }
+    if n == 0 {
+        return 0.0;
+    }
+    -counts.values().map(|&c| { let p = c as f64 / n as f64; p * p.log2() }).sum::<f64>()
+}
+
+pub fn extract_features(label: &str, model: &BigramModel) -> DgaFeatures {
+    let chars: Vec<char> = label.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+    let n = chars.len().max(1) as f64;
+
+    let entropy = shannon_entropy(label);
+
+    let letters = chars.iter().filter(|c| c.is_ascii_alphabetic()).count();
+    let consonants = chars.iter().filter(|&&c| is_consonant(c)).count();
+    let digits = chars.iter().filter(|c| c.is_ascii_digit()).count();
+
+    let mut run = 0;
+    let mut max_run = 0;
+    for &c in &chars {
+        run = if is_consonant(c) { run + 1 } else { 0 };
+        max_run = max_run.max(run);
+    }
+
+    DgaFeatures {
+        length: chars.len(),
+        entropy,
+        bigram_log_likelihood: model.avg_log_likelihood(label),
+        consonant_ratio: if letters == 0 { 1.0 } else { consonants as f64 / letters as f64 },
+        digit_ratio: digits as f64 / n,
+        max_consonant_run: max_run,
+    }
+}
+
+/// Linear ramp: 0 at `low`, 1 at `high`
+fn ramp(value: f64, low: f64, high: f64) -> f64 {
+    ((value - low) / (high - low)).clamp(0.0, 1.0)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DgaWeights {
+    pub bigram: f64,
+    pub entropy: f64,
+    pub consonants: f64,
+    pub digits: f64,
+    pub consonant_run: f64,
+    pub length: f64,
+}
+
+impl Default for DgaWeights {
+    fn default() -> Self {
+        DgaWeights { bigram: 0.5, entropy: 0.15, consonants: 0.1, digits: 0.1, consonant_run: 0.1, length: 0.1 }
+    }
+}
+
+/// Combined 0..1 score; each feature is ramped between benign-typical and DGA-typical values
+pub fn combine(f: &DgaFeatures, w: &DgaWeights) -> f64 {
+    let score = w.bigram * ramp(-f.bigram_log_likelihood, 1.25, 1.8)
+        + w.entropy * ramp(f.entropy, 2.0, 3.2)
+        + w.consonants * ramp(f.consonant_ratio, 0.55, 0.85)
+        + w.digits * ramp(f.digit_ratio, 0.0, 0.3)
+        + w.consonant_run * ramp(f.max_consonant_run as f64, 3.0, 6.0)
+        + w.length * ramp(f.length as f64, 7.0, 14.0);
+    let total = w.bigram + w.entropy + w.consonants + w.digits + w.consonant_run + w.length;
+    if total > 0.0 { score / total } else { 0.0 }
+}
+
+// ============================================================================
+// SETTINGS
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DgaSettings {
+    pub enabled: bool,
+    /// Score at or above which a domain is reported as DGA
+    pub threshold: f64,
+    /// Shorter registrable labels are not scored
+    pub min_label_len: usize,
+    pub weights: DgaWeights,
+    /// Registrable domains (eTLD+1) never reported
+    pub allowlist: Vec<String>,
+}
+
+impl Default for DgaSettings {
+    fn default() -> Self {
+        DgaSettings {
+            enabled: true,
+            threshold: 0.55,
+            min_label_len: 6,
+            weights: DgaWeights::default(),
+            allowlist: vec!["msftncsi.com".to_string()],
+        }
+    }
+}
+
+impl DgaSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.threshold) {
+            return Err("DGA threshold must be between 0 and 1".to_string());
+        }
+        let w = &self.weights;
+        if [w.bigram, w.entropy, w.consonants, w.digits, w.consonant_run, w.length].iter().any(|x| *x < 0.0) {
+            return Err("DGA weights must not be negative".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn get_settings_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("CyberGuardian")
+        .join("dga_settings.json")
+}
+
+fn load_settings() -> DgaSettings {
+    fs::read_to_string(get_settings_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save_settings(settings: &DgaSettings) -> Result<(), String> {
+    let path = get_settings_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create settings dir: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write settings: {}", e))
+}
+
+// ============================================================================
+// DETECTOR
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DgaVerdict {
+    pub domain: String,
+    pub registrable_domain: Option<String>,
+    pub score: f64,
+    pub is_dga: bool,
+    pub features: Option<DgaFeatures>,
+    /// Why the domain was not scored (too short, allowlisted, IDN, ...)
+    pub skipped: Option<String>,
+}
+
+pub struct DgaDetector {
+    psl: PublicSuffixList,
+    model: BigramModel,
+}
+
+impl DgaDetector {
+    pub fn embedded() -> Self {
+        DgaDetector {
+            psl: PublicSuffixList::parse(PUBLIC_SUFFIX_LIST),
+            model: BigramModel::train(BENIGN_TRAINING),
+        }
+    }
+
+    pub fn analyze(&self, domain: &str, settings: &DgaSettings) -> DgaVerdict {
+        let mut verdict = DgaVerdict {
+            domain: domain.to_string(),
+            registrable_domain: None,
+            score: 0.0,
+            is_dga: false,
+            features: None,
+            skipped: None,
+        };
+
+        let parts = match self.psl.split(domain) {
+            Some(p) => p,
+            None => {
+                verdict.skipped = Some("no registrable label".to_string());
+                return verdict;
+            }
+        };
+        let registrable = parts.registrable();
+        verdict.registrable_domain = Some(registrable.clone());
+
+        let skip = if parts.label.starts_with("xn--") {
+            Some("internationalized label")
+        } else if settings.allowlist.iter().any(|a| a.eq_ignore_ascii_case(&registrable)) {
+            Some("allowlisted")
+        } else if parts.label.chars().filter(|c| c.is_ascii_alphanumeric()).count() < settings.min_label_len {
+            Some("label too short")
+        } else {
+            None
+        };
+        if let Some(reason) = skip {
+            verdict.skipped = Some(reason.to_string());
+            return verdict;
+        }
+
+        let features = extract_features(&parts.label, &self.model);
+        verdict.score = combine(&features, &settings.weights);
+        verdict.is_dga = verdict.score >= settings.threshold;
+        verdict.features = Some(features);
+        verdict
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref DETECTOR: DgaDetector = DgaDetector::embedded();
+    static ref SETTINGS: RwLock<DgaSettings> = RwLock::new(load_settings());
+}
+
+/// eTLD+1 of a domain per the embedded suffix list
+pub fn registrable_domain(domain: &str) -> Option<DomainParts> {
+    DETECTOR.psl.split(domain)
+}
+
+/// Score a queried domain with the current settings
+pub fn analyze(domain: &str) -> DgaVerdict {
+    let settings = SETTINGS.read().unwrap();
+    if !settings.enabled {
+        return DgaVerdict {
+            domain: domain.to_string(),
+            registrable_domain: None,
+            score: 0.0,
+            is_dga: false,
+            features: None,
+            skipped: Some("disabled".to_string()),
+        };
+    }
+    DETECTOR.analyze(domain, &settings)
+}
+
+// ============================================================================
+// TAURI COMMANDS
+// ============================================================================
+
+#[tauri::command]
+pub fn get_dga_settings() -> DgaSettings {
+    SETTINGS.read().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn set_dga_settings(settings: DgaSettings) -> Result<DgaSettings, String> {
+    settings.validate()?;
+    save_settings(&settings)?;
+    *SETTINGS.write().unwrap() = settings.clone();
+    info!("DGA settings updated (threshold {:.2}, min label {})", settings.threshold, settings.min_label_len);
+    Ok(settings)
+}
+
+#[tauri::command]
+pub fn score_domain(domain: String) -> DgaVerdict {
+    analyze(&domain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LABELLED_CORPUS: &str = include_str!("../data/dga/labelled_corpus.tsv");
+
+    #[test]
+    fn test_public_suffix_split() {
+        let psl = PublicSuffixList::parse(PUBLIC_SUFFIX_LIST);
+        let parts = psl.split("www.bbc.co.uk").unwrap();
+        assert_eq!((parts.subdomain.as_str(), parts.label.as_str(), parts.suffix.as_str()), ("www", "bbc", "co.uk"));
+        assert_eq!(psl.split("d1oqxoy4ny1x7g.cloudfront.net").unwrap().registrable(), "cloudfront.net");
+        assert_eq!(psl.split("Foo.Example.").unwrap().registrable(), "foo.example");
+        // Wildcard and exception rules
+        assert_eq!(psl.split("shop.gov.ck").unwrap().registrable(), "shop.gov.ck");
+        assert_eq!(psl.split("www.ck").unwrap().registrable(), "www.ck");
+        assert!(psl.split("co.uk").is_none());
+        // Deep ICANN rules: *.kawasaki.jp with !city.kawasaki.jp, pvt.k12.ma.us
+        assert_eq!(psl.split("www.city.kawasaki.jp").unwrap().registrable(), "city.kawasaki.jp");
+        assert!(psl.split("portal.kawasaki.jp").is_none());
+        assert_eq!(psl.split("www.school.pvt.k12.ma.us").unwrap().label, "school");
+    }
+
+    #[test]
+    fn test_scores_and_skips() {
+        let detector = DgaDetector::embedded();
+        let settings = DgaSettings::default();
+
+        let cdn = detector.analyze("e8218.dscb1.akamaiedge.net", &settings);
+        assert!(!cdn.is_dga, "CDN name scored {}", cdn.score);
+        let dga = detector.analyze("xkqjvbzwtrpl.com", &settings);
+        assert!(dga.is_dga, "random label scored {}", dga.score);
+
+        assert_eq!(detector.analyze("abc.com", &settings).skipped.as_deref(), Some("label too short"));
+        assert_eq!(detector.analyze("dns.msftncsi.com", &settings).skipped.as_deref(), Some("allowlisted"));
+        assert_eq!(detector.analyze("xn--80ak6aa92e.com", &settings).skipped.as_deref(), Some("internationalized label"));
+
+        let strict = DgaSettings { threshold: 1.0, ..DgaSettings::default() };
+        assert!(!detector.analyze("xkqjvbzwtrpl.com", &strict).is_dga);
+    }
+
+    #[test]
+    fn test_labelled_corpus() {
+        let detector = DgaDetector::embedded();
+        let settings = DgaSettings::default();
+        let (mut tp, mut dga_total, mut fp, mut benign_total) = (0, 0, 0, 0);
+        let mut misses = Vec::new();
+
+        for line in LABELLED_CORPUS.lines().filter(|l| !l.starts_with('#') && !l.trim().is_empty()) {
+            let (label, domain) = line.split_once('\t').expect("label<TAB>domain");
+            let verdict = detector.analyze(domain, &settings);
+            match label {
+                "dga" => {
+                    dga_total += 1;
+                    if verdict.is_dga { tp += 1 } else { misses.push(domain) }
+                }
+                "benign" => {
+                    benign_total += 1;
+                    if verdict.is_dga { fp += 1; misses.push(domain) }
+                }
+                other => panic!("unknown label {}", other),
+            }
+        }
+
+        let recall = tp as f64 / dga_total as f64;
+        let false_positive_rate = fp as f64 / benign_total as f64;
+        assert!(recall >= 0.9, "recall {:.3}, misclassified: {:?}", recall, misses);
+        assert!(false_positive_rate <= 0.02, "FPR {:.3}, misclassified: {:?}", false_positive_rate, misses);
+    }
+}
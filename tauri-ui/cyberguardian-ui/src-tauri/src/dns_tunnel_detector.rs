@@ -0,0 +1,405 @@
+//! DNS Tunneling Detection
+//! Sliding-window statistics per process and per parent domain (eTLD+1):
+//! query volume, unique subdomains, TXT/NULL record usage and encoded labels.
+//! Signals over the window (two by default) produce a T1071.004 detection
+//! with the evidence attached
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, RwLock};
+use tracing::info;
+
+use crate::process_identity::ProcessKey;
+
+/// DNS record types commonly abused for tunnels
+pub const QTYPE_NULL: u16 = 10;
+pub const QTYPE_TXT: u16 = 16;
+
+/// Bounds so a query storm cannot grow the tracker without limit
+const MAX_TRACKED_KEYS: usize = 5_000;
+const MAX_EVENTS_PER_KEY: usize = 5_000;
+const MAX_DETECTIONS: usize = 100;
+const SAMPLE_QUERIES: usize = 5;
+
+// ============================================================================
+// SETTINGS
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TunnelSettings {
+    pub enabled: bool,
+    pub window_secs: u64,
+    /// Queries from one process to one parent domain within the window
+    pub max_queries_per_domain: usize,
+    /// Queries from one process to any domain within the window
+    pub max_queries_per_process: usize,
+    /// Distinct subdomains seen under one parent domain within the window
+    pub max_unique_subdomains: usize,
+    pub max_txt_null_queries: usize,
+    /// Encoded-looking labels under one parent domain within the window
+    pub max_encoded_labels: usize,
+    /// Labels at least this long are checked for encoding
+    pub encoded_label_min_len: usize,
+    /// Signals required for a detection
+    pub min_signals: usize,
+}
+
+impl Default for TunnelSettings {
+    fn default() -> Self {
+        TunnelSettings {
+            enabled: true,
+            window_secs: 60,
+            max_queries_per_domain: 100,
+            max_queries_per_process: 300,
+            max_unique_subdomains: 40,
+            max_txt_null_queries: 20,
+            max_encoded_labels: 10,
+            encoded_label_min_len: 20,
+            min_signals: 2,
+        }
+    }
+}
+
+impl TunnelSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(5..=3600).contains(&self.window_secs) {
+            return Err("Window must be between 5 and 3600 seconds".to_string());
+        }
+        if self.min_signals == 0 {
+            return Err("min_signals must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn get_settings_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("CyberGuardian")
+        .join("dns_tunnel_settings.json")
+}
+
+fn load_settings() -> TunnelSettings {
+    fs::read_to_string(get_settings_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save_settings(settings: &TunnelSettings) -> Result<(), String> {
+    let path = get_settings_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create settings dir: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write settings: {}", e))
+}
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone)]
+pub struct DnsQuery {
+    pub key: ProcessKey,
+    pub process_name: String,
+    pub domain: String,
+    pub qtype: u16,
+    /// Seconds (monotonic or epoch; only differences matter)
+    pub at: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TunnelEvidence {
+    pub window_secs: u64,
+    pub queries_to_domain: usize,
+    pub queries_by_process: usize,
+    pub unique_subdomains: usize,
+    pub txt_null_queries: usize,
+    pub encoded_labels: usize,
+    pub max_label_len: usize,
+    pub sample_queries: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TunnelDetection {
+    /// Serialized as `pid` + `start_time`
+    #[serde(flatten)]
+    pub key: ProcessKey,
+    pub process_name: String,
+    pub parent_domain: String,
+    pub signals: Vec<String>,
+    pub evidence: TunnelEvidence,
+    pub timestamp: String,
+}
+
+impl TunnelDetection {
+    pub fn summary(&self) -> String {
+        format!("DNS tunneling to {} ({})", self.parent_domain, self.signals.join(", "))
+    }
+}
+
+/// Hex, base32 or base64-ish label with high character diversity
+pub fn looks_encoded(label: &str, min_len: usize) -> bool {
+    if label.len() < min_len {
+        return false;
+    }
+    let hex = label.chars().all(|c| c.is_ascii_hexdigit());
+    let base32 = label.chars().all(|c| c.is_ascii_lowercase() || ('2'..='7').contains(&c));
+    let base64 = label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    let entropy = crate::dga_detector::shannon_entropy(label);
+    let has_digit = label.chars().any(|c| c.is_ascii_digit());
+    (hex && entropy >= 3.0) || ((base32 || base64) && has_digit && entropy >= 3.5)
+}
+
+// ============================================================================
+// TRACKER
+// ============================================================================
+
+struct DomainQuery {
+    at: u64,
+    subdomain: String,
+    qtype: u16,
+    encoded: bool,
+}
+
+#[derive(Default)]
+pub struct TunnelTracker {
+    /// (process, parent domain) → queries within the window
+    by_domain: HashMap<(ProcessKey, String), VecDeque<DomainQuery>>,
+    /// process → query timestamps within the window
+    by_process: HashMap<ProcessKey, VecDeque<u64>>,
+    /// (process, parent) → time of last detection, to report once per window
+    reported: HashMap<(ProcessKey, String), u64>,
+}
+
+impl TunnelTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn evict(&mut self, now: u64, window: u64) {
+        let cutoff = now.saturating_sub(window);
+        self.by_domain.retain(|_, q| {
+            while q.front().map(|e| e.at < cutoff).unwrap_or(false) {
+                q.pop_front();
+            }
+            !q.is_empty()
+        });
+        self.by_process.retain(|_, q| {
+            while q.front().map(|t| *t < cutoff).unwrap_or(false) {
+                q.pop_front();
+            }
+            !q.is_empty()
+        });
+        self.reported.retain(|_, t| *t >= cutoff);
+    }
+
+    pub fn observe(&mut self, query: &DnsQuery, settings: &TunnelSettings) -> Option<TunnelDetection> {
+        let parts = crate::dga_detector::registrable_domain(&query.domain)?;
+        let parent = parts.registrable();
+        let window = settings.window_secs;
+        self.evict(query.at, window);
+
+        let key = (query.key, parent.clone());
+        let new_key = !self.by_domain.contains_key(&key) || !self.by_process.contains_key(&query.key);
+        if new_key && (self.by_domain.len() >= MAX_TRACKED_KEYS || self.by_process.len() >= MAX_TRACKED_KEYS) {
+            return None;
+        }
+
+        let encoded = parts.subdomain.split('.').any(|l| looks_encoded(l, settings.encoded_label_min_len));
+        let domain_queries = self.by_domain.entry(key.clone()).or_default();
+        if domain_queries.len() < MAX_EVENTS_PER_KEY {
+            domain_queries.push_back(DomainQuery {
+                at: query.at,
+                subdomain: parts.subdomain.clone(),
+                qtype: query.qtype,
+                encoded,
+            });
+        }
+        let process_queries = self.by_process.entry(query.key).or_default();
+        if process_queries.len() < MAX_EVENTS_PER_KEY {
+            process_queries.push_back(query.at);
+        }
+
+        if self.reported.contains_key(&key) {
+            return None;
+        }
+
+        let domain_queries = &self.by_domain[&key];
+        let unique: HashSet<&str> = domain_queries.iter()
+            .filter(|q| !q.subdomain.is_empty())
+            .map(|q| q.subdomain.as_str())
+            .collect();
+        let evidence = TunnelEvidence {
+            window_secs: window,
+            queries_to_domain: domain_queries.len(),
+            queries_by_process: self.by_process[&query.key].len(),
+            unique_subdomains: unique.len(),
+            txt_null_queries: domain_queries.iter().filter(|q| q.qtype == QTYPE_TXT || q.qtype == QTYPE_NULL).count(),
+            encoded_labels: domain_queries.iter().filter(|q| q.encoded).count(),
+            max_label_len: domain_queries.iter()
+                .flat_map(|q| q.subdomain.split('.').map(str::len))
+                .max()
+                .unwrap_or(0),
+            sample_queries: domain_queries.iter().rev()
+                .filter(|q| !q.subdomain.is_empty())
+                .take(SAMPLE_QUERIES)
+                .map(|q| format!("{}.{}", q.subdomain, parent))
+                .collect(),
+        };
+
+        let mut signals = Vec::new();
+        if evidence.queries_to_domain >= settings.max_queries_per_domain {
+            signals.push(format!("{} queries to domain", evidence.queries_to_domain));
+        }
+        if evidence.queries_by_process >= settings.max_queries_per_process {
+            signals.push(format!("{} queries by process", evidence.queries_by_process));
+        }
+        if evidence.unique_subdomains >= settings.max_unique_subdomains {
+            signals.push(format!("{} unique subdomains", evidence.unique_subdomains));
+        }
+        if evidence.txt_null_queries >= settings.max_txt_null_queries {
+            signals.push(format!("{} TXT/NULL queries", evidence.txt_null_queries));
+        }
+        if evidence.encoded_labels >= settings.max_encoded_labels {
+            signals.push(format!("{} encoded labels", evidence.encoded_labels));
+        }
+
+        if signals.len() < settings.min_signals {
+            return None;
+        }
+        self.reported.insert(key, query.at);
+        Some(TunnelDetection {
+            key: query.key,
+            process_name: query.process_name.clone(),
+            parent_domain: parent,
+            signals,
+            evidence,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        })
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref SETTINGS: RwLock<TunnelSettings> = RwLock::new(load_settings());
+    static ref TRACKER: Mutex<TunnelTracker> = Mutex::new(TunnelTracker::new());
+    static ref DETECTIONS: Mutex<VecDeque<TunnelDetection>> = Mutex::new(VecDeque::new());
+    static ref STARTED: std::time::Instant = std::time::Instant::now();
+}
+
+/// Feed one DNS query from the monitor; returns a detection once per (process, domain) per window
+pub fn observe(key: ProcessKey, process_name: &str, domain: &str, qtype: u16) -> Option<TunnelDetection> {
+    let settings = SETTINGS.read().unwrap().clone();
+    if !settings.enabled {
+        return None;
+    }
+    let query = DnsQuery {
+        key,
+        process_name: process_name.to_string(),
+        domain: domain.to_string(),
+        qtype,
+        at: STARTED.elapsed().as_secs(),
+    };
+    let detection = TRACKER.lock().unwrap().observe(&query, &settings)?;
+
+    let mut detections = DETECTIONS.lock().unwrap();
+    if detections.len() >= MAX_DETECTIONS {
+        detections.pop_front();
+    }
+    detections.push_back(detection.clone());
+    Some(detection)
+}
+
+// ============================================================================
+// TAURI COMMANDS
+// ============================================================================
+
+#[tauri::command]
+pub fn get_dns_tunnel_settings() -> TunnelSettings {
+    SETTINGS.read().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn set_dns_tunnel_settings(settings: TunnelSettings) -> Result<TunnelSettings, String> {
+    settings.validate()?;
+    save_settings(&settings)?;
+    *SETTINGS.write().unwrap() = settings.clone();
+    info!("DNS tunnel settings updated (window {}s, {} signals)", settings.window_secs, settings.min_signals);
+    Ok(settings)
+}
+
+#[tauri::command]
+pub fn get_dns_tunnel_detections() -> Vec<TunnelDetection> {
+    DETECTIONS.lock().unwrap().iter().rev().cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::Digest;
+
+    fn query(domain: &str, qtype: u16, at: u64) -> DnsQuery {
+        DnsQuery { key: ProcessKey::new(4242, 1), process_name: "powershell.exe".into(), domain: domain.into(), qtype, at }
+    }
+
+    /// base32-style chunk like iodine/dnscat2 produce
+    fn chunk(i: usize) -> String {
+        let digest = sha2::Sha256::digest(i.to_le_bytes());
+        let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+        hex[..40].to_string()
+    }
+
+    #[test]
+    fn test_encoded_label_detection() {
+        assert!(looks_encoded("4f2a9c0d7e1b3a5c8d6e0f1a2b3c4d5e", 20));
+        assert!(looks_encoded("mzxw6ytboi2dsnrqgezdgnbvgy3tqojq", 20));
+        assert!(!looks_encoded("static-content-delivery", 20));
+        assert!(!looks_encoded("short1", 20));
+    }
+
+    #[test]
+    fn test_txt_tunnel_is_detected_with_evidence() {
+        let settings = TunnelSettings::default();
+        let mut tracker = TunnelTracker::new();
+        let mut detection = None;
+        for i in 0..60 {
+            let domain = format!("{}.t.tunnel-example.com", chunk(i));
+            if let Some(d) = tracker.observe(&query(&domain, QTYPE_TXT, i as u64 / 2), &settings) {
+                detection = Some(d);
+                break;
+            }
+        }
+        let detection = detection.expect("tunnel should be detected within the window");
+        assert_eq!(detection.parent_domain, "tunnel-example.com");
+        assert!(detection.signals.len() >= 2, "{:?}", detection.signals);
+        assert!(detection.evidence.txt_null_queries >= 20);
+        assert!(detection.evidence.encoded_labels >= 10);
+        assert_eq!(detection.evidence.sample_queries.len(), SAMPLE_QUERIES);
+
+        // Reported once per window
+        let again = tracker.observe(&query(&format!("{}.t.tunnel-example.com", chunk(99)), QTYPE_TXT, 31), &settings);
+        assert!(again.is_none());
+    }
+
+    #[test]
+    fn test_normal_browsing_and_window_expiry() {
+        let settings = TunnelSettings::default();
+        let mut tracker = TunnelTracker::new();
+        let sites = ["www.google.com", "fonts.gstatic.com", "e8218.dscb1.akamaiedge.net", "api.github.com"];
+        for i in 0..200u64 {
+            let domain = sites[i as usize % sites.len()];
+            assert!(tracker.observe(&query(domain, 1, i), &settings).is_none());
+        }
+
+        // Spread over many windows, the same burst never accumulates
+        for i in 0..60u64 {
+            let domain = format!("{}.t.tunnel-example.com", chunk(i as usize));
+            assert!(tracker.observe(&query(&domain, QTYPE_TXT, i * 120), &settings).is_none());
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! Enforcement Mode
+//! One runtime setting shared by the polling monitor, ETW and the network
+//! detectors: monitor (alert only), block, or block-with-prompt (definite
+//! detections are blocked, ambiguous ones need the user's confirmation).
+//! The decision logic is pure so it is testable without Windows APIs
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnforcementMode {
+    #[default]
+    Monitor,
+    Block,
+    BlockWithPrompt,
+}
+
+impl EnforcementMode {
+    pub fn blocks(&self) -> bool {
+        *self != EnforcementMode::Monitor
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EnforcementMode::Monitor => "monitor",
+            EnforcementMode::Block => "block",
+            EnforcementMode::BlockWithPrompt => "block_with_prompt",
+        }
+    }
+}
+
+/// Named tool, intel match or explicit command pattern vs. path / statistical heuristics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Confidence {
+    Definite,
+    Heuristic,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    /// Record only; enforcement actions are audited
+    Alert,
+    /// Run the playbook's enforcement actions
+    Enforce,
+    /// Ask the user before enforcing
+    Prompt,
+}
+
+pub fn decide(mode: EnforcementMode, severity: &str, confidence: Confidence) -> Verdict {
+    match mode {
+        EnforcementMode::Monitor => Verdict::Alert,
+        // A heuristic alone never kills without a human in the loop
+        EnforcementMode::Block if confidence == Confidence::Definite => Verdict::Enforce,
+        EnforcementMode::Block => Verdict::Alert,
+        EnforcementMode::BlockWithPrompt
+            if confidence == Confidence::Definite && severity.eq_ignore_ascii_case("critical") => Verdict::Enforce,
+        EnforcementMode::BlockWithPrompt => Verdict::Prompt,
+    }
+}
+
+/// Suspend suspicious new processes while they are analysed (ETW and Linux polling)
+pub fn presuspend(mode: EnforcementMode) -> bool {
+    mode.blocks()
+}
+
+/// Why enforcement actions were not executed, for the audit trail
+pub fn audit_reason(mode: EnforcementMode, verdict: Verdict) -> Option<&'static str> {
+    match verdict {
+        Verdict::Enforce => None,
+        Verdict::Prompt => Some("needs user confirmation"),
+        Verdict::Alert if mode == EnforcementMode::Monitor => Some("monitor mode"),
+        Verdict::Alert => Some("heuristic detection"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decide_per_mode() {
+        use Confidence::*;
+        use EnforcementMode::*;
+
+        for confidence in [Definite, Heuristic] {
+            assert_eq!(decide(Monitor, "critical", confidence), Verdict::Alert);
+        }
+        assert_eq!(decide(Block, "high", Definite), Verdict::Enforce);
+        assert_eq!(decide(Block, "critical", Heuristic), Verdict::Alert);
+        assert_eq!(decide(BlockWithPrompt, "Critical", Definite), Verdict::Enforce);
+        assert_eq!(decide(BlockWithPrompt, "high", Definite), Verdict::Prompt);
+        assert_eq!(decide(BlockWithPrompt, "critical", Heuristic), Verdict::Prompt);
+
+        assert!(!presuspend(Monitor) && presuspend(Block) && presuspend(BlockWithPrompt));
+        assert_eq!(audit_reason(Block, Verdict::Alert), Some("heuristic detection"));
+        assert_eq!(audit_reason(Monitor, Verdict::Alert), Some("monitor mode"));
+        assert_eq!(audit_reason(Block, Verdict::Enforce), None);
+    }
+
+    #[test]
+    fn test_mode_wire_format() {
+        let mode: EnforcementMode = serde_json::from_str("\"block_with_prompt\"").unwrap();
+        assert_eq!(mode, EnforcementMode::BlockWithPrompt);
+        assert_eq!(serde_json::to_string(&mode).unwrap(), format!("\"{}\"", mode.as_str()));
+        assert_eq!(EnforcementMode::default(), EnforcementMode::Monitor);
+    }
+}
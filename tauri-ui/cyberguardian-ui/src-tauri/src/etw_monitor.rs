@@ -3,6 +3,9 @@
 
 use std::sync::atomic::{AtomicBool, Ordering};
 use crate::process_monitor::get_process_exe_path;
+use crate::process_identity::ProcessKey;
+use crate::enforcement::{presuspend, Confidence};
+use crate::response_playbooks::Detection;
 use windows::Win32::System::Diagnostics::Etw::{
     EVENT_TRACE_PROPERTIES, EVENT_TRACE_REAL_TIME_MODE,
     WNODE_FLAG_TRACED_GUID, CONTROLTRACE_HANDLE, PROCESSTRACE_HANDLE,
@@ -13,6 +16,7 @@ use windows::Win32::System::Diagnostics::Etw::{
 };
 use windows::Win32::Foundation::*;
 use windows::core::GUID;
+use tracing::{debug, error, info, warn};
 
 static ETW_RUNNING: AtomicBool = AtomicBool::new(false);
 
@@ -63,7 +67,7 @@ pub fn start_etw_monitor() {
     ETW_RUNNING.store(true, Ordering::SeqCst);
 
     std::thread::spawn(|| {
-        println!("🔬 ETW Process Monitor starting...");
+        debug!("ETW Process Monitor starting...");
         unsafe { run_etw_session(); }
     });
 }
@@ -72,6 +76,10 @@ pub fn stop_etw_monitor() {
     ETW_RUNNING.store(false, Ordering::SeqCst);
 }
 
+pub fn is_etw_running() -> bool {
+    ETW_RUNNING.load(Ordering::SeqCst)
+}
+
 #[cfg(target_os = "windows")]
 unsafe fn run_etw_session() {
     use std::mem;
@@ -117,10 +125,10 @@ unsafe fn run_etw_session() {
 
     let err = StartTraceW(&mut session_handle, name_ptr, props);
     if err.0 != 0 {
-        println!("❌ ETW StartTrace failed: {}", err.0);
+        error!("ETW StartTrace failed: {}", err.0);
         return;
     }
-    println!("✅ ETW session started");
+    info!("ETW session started");
 
     let result = EnableTraceEx2(
         session_handle,
@@ -134,10 +142,10 @@ unsafe fn run_etw_session() {
     );
 
     if result.0 != 0 {
-        println!("❌ ETW EnableTrace failed: {}", result.0);
+        error!("ETW EnableTrace failed: {}", result.0);
         return;
     }
-    println!("✅ ETW Kernel-Process provider enabled");
+    info!("ETW Kernel-Process provider enabled");
 
     // Enable WMI Activity provider на същата сесия
     let wmi_result = EnableTraceEx2(
@@ -152,9 +160,9 @@ unsafe fn run_etw_session() {
     );
 
     if wmi_result.0 != 0 {
-        println!("⚠️ WMI Activity provider failed: {} (non-critical)", wmi_result.0);
+        warn!("WMI Activity provider failed: {} (non-critical)", wmi_result.0);
     } else {
-        println!("✅ ETW WMI-Activity provider enabled");
+        info!("ETW WMI-Activity provider enabled");
     }
 
     // Enable Kernel-Registry provider
@@ -170,9 +178,9 @@ unsafe fn run_etw_session() {
     );
 
     if reg_result.0 != 0 {
-        println!("⚠️ Kernel-Registry provider failed: {} (non-critical)", reg_result.0);
+        warn!("Kernel-Registry provider failed: {} (non-critical)", reg_result.0);
     } else {
-        println!("✅ ETW Kernel-Registry provider enabled");
+        info!("ETW Kernel-Registry provider enabled");
     }
 
     // Enable Kernel-Network provider
@@ -181,16 +189,16 @@ unsafe fn run_etw_session() {
         &KERNEL_NETWORK_GUID,
         EVENT_CONTROL_CODE_ENABLE_PROVIDER.0,
         4u8,
-        0x10, // TcpIp connect keyword
+        0x30, // KERNEL_NETWORK_KEYWORD_IPV4 | KERNEL_NETWORK_KEYWORD_IPV6
         0,
         0,
         None,
     );
 
     if net_result.0 != 0 {
-        println!("⚠️ Kernel-Network provider failed: {} (non-critical)", net_result.0);
+        warn!("Kernel-Network provider failed: {} (non-critical)", net_result.0);
     } else {
-        println!("✅ ETW Kernel-Network provider enabled");
+        info!("ETW Kernel-Network provider enabled");
     }
 
     // Enable DNS Client provider
@@ -206,9 +214,9 @@ unsafe fn run_etw_session() {
     );
 
     if dns_result.0 != 0 {
-        println!("⚠️ DNS-Client provider failed: {} (non-critical)", dns_result.0);
+        warn!("DNS-Client provider failed: {} (non-critical)", dns_result.0);
     } else {
-        println!("✅ ETW DNS-Client provider enabled");
+        info!("ETW DNS-Client provider enabled");
     }
 
     let mut log_file: EVENT_TRACE_LOGFILEW = std::mem::zeroed();
@@ -236,17 +244,17 @@ unsafe fn run_etw_session() {
     let trace_handle = match open_trace_fn {
         Some(f) => f(&mut log_file),
         None => {
-            println!("❌ OpenTraceW not found");
+            error!("OpenTraceW not found");
             return;
         }
     };
 
     if trace_handle.Value == u64::MAX {
-        println!("❌ ETW OpenTrace failed");
+        error!("ETW OpenTrace failed");
         return;
     }
 
-    println!("🔬 ETW listening for process creation events...");
+    debug!("ETW listening for process creation events...");
 
     let handles = [trace_handle];
     type FnProcessTrace = unsafe extern "system" fn(*const PROCESSTRACE_HANDLE, u32, *const i64, *const i64) -> u32;
@@ -274,7 +282,7 @@ unsafe fn run_etw_session() {
         EVENT_TRACE_CONTROL_STOP,
     );
 
-    println!("🛑 ETW Monitor stopped");
+    info!("ETW Monitor stopped");
 }
 
 unsafe extern "system" fn etw_event_callback(event_record: *mut EVENT_RECORD) {
@@ -301,6 +309,8 @@ unsafe extern "system" fn etw_event_callback(event_record: *mut EVENT_RECORD) {
         if new_pid == 0 || new_pid == 4 {
             return;
         }
+        // (pid, start_time) веднага — всички действия по-надолу проверяват идентичността
+        let key = ProcessKey::capture(new_pid);
          
         let image_name = if data.len() > 60 {
             let wide: Vec<u16> = data[60..].chunks_exact(2)
@@ -318,9 +328,10 @@ unsafe extern "system" fn etw_event_callback(event_record: *mut EVENT_RECORD) {
     "regsvr32", "rundll32", "wscript", "cscript", "mimikatz",
      "net", "wevtutil", "vssadmin", "bcdedit", "sc", "schtasks"]
     .iter().any(|s| name_lower.contains(s));
-        if is_suspicious {
-            suspend_process(new_pid);
-        }
+        // Monitor mode → само детекция, без suspend
+        let suspended = is_suspicious
+            && presuspend(crate::process_monitor::enforcement_mode())
+            && suspend_process(&key);
          let parent_name_for_handler = {
         let mut pname = get_process_name(parent_pid);
          if pname.is_empty() {
@@ -329,7 +340,7 @@ unsafe extern "system" fn etw_event_callback(event_record: *mut EVENT_RECORD) {
         }
         pname
     };  
-handle_new_process_with_name(new_pid, parent_pid, image_name, parent_name_for_handler);
+handle_new_process_with_name(key, parent_pid, image_name, parent_name_for_handler, suspended);
 
     } else if provider == WMI_ACTIVITY_GUID {
         handle_wmi_event(event);
@@ -346,7 +357,7 @@ fn handle_dns_event(event: &EVENT_RECORD) {
     use crate::process_monitor;
 
    let event_id = event.EventHeader.EventDescriptor.Id;
-    println!("🔬 DNS Event ID={}", event_id);
+    debug!("DNS Event ID={}", event_id);
     // Event ID 3006 = DNS Query
     if event_id != 3006 {
         return;
@@ -354,8 +365,8 @@ fn handle_dns_event(event: &EVENT_RECORD) {
 
     let pid = event.EventHeader.ProcessId;
 
-    // Извличаме domain от UserData (UTF-16)
-    let domain = if !event.UserData.is_null() && event.UserDataLength > 4 {
+    // Извличаме domain от UserData (UTF-16), последван от QueryType (u32)
+    let (domain, qtype) = if !event.UserData.is_null() && event.UserDataLength > 4 {
         unsafe {
             let data = std::slice::from_raw_parts(
                 event.UserData as *const u8,
@@ -366,58 +377,105 @@ fn handle_dns_event(event: &EVENT_RECORD) {
                     .map(|c| u16::from_le_bytes([c[0], c[1]]))
                     .take_while(|&c| c != 0)
                     .collect();
-                String::from_utf16_lossy(&wide).to_lowercase()
+                let type_offset = 4 + (wide.len() + 1) * 2;
+                let qtype = data.get(type_offset..type_offset + 4)
+                    .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as u16)
+                    .unwrap_or(0);
+                (String::from_utf16_lossy(&wide).to_lowercase(), qtype)
             } else {
-                String::new()
+                (String::new(), 0)
             }
         }
     } else {
-        String::new()
+        (String::new(), 0)
     };
 
     if domain.is_empty() {
         return;
     }
 
-    println!("🔬 DNS: PID={} Query={}", pid, &domain[..domain.len().min(80)]);
+    debug!("DNS: PID={} Query={}", pid, truncate(&domain, 80));
+    let key = ProcessKey::capture(pid);
+
+    // Threat intel — известни лоши домейни (вкл. родителски домейни)
+    let intel = crate::threat_intel::lookup_domain(&domain);
+    if !intel.is_empty() {
+        warn!(pid, domain = %domain, source = %intel[0].source, "DNS threat intel match");
+
+        let name = get_process_name(pid);
+        let parent_name = get_parent_name(pid);
+        let reason = format!("Threat intel DNS match {} ({})", intel[0].indicator, crate::threat_intel::describe(&intel));
+
+        process_monitor::record_network_alert(process_monitor::NetworkAlert {
+            kind: "dns".to_string(),
+            indicator: domain.clone(),
+            port: None,
+            pid,
+            process_name: name.clone(),
+            mitre_technique: "T1071.004".to_string(),
+            reason: reason.clone(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            evidence: None,
+        });
+
+        respond(detection(key, &name, &parent_name, &reason, "T1071.004", "critical"));
+        return;
+    }
 
-    // DGA detection — домейни с много рандомни символи
-    let is_dga = domain.len() > 20
-        && !domain.contains("microsoft")
-        && !domain.contains("windows")
-        && !domain.contains("google")
-        && !domain.contains("cloudflare")
-        && !domain.contains("amazon");
+    // DNS tunneling — статистики за процеса / домейна в плъзгащ се прозорец
+    let name = get_process_name(pid);
+    if let Some(tunnel) = crate::dns_tunnel_detector::observe(key, &name, &domain, qtype) {
+        warn!(pid, process = %name, mitre = "T1071.004", "DNS tunnel → {}", tunnel.parent_domain);
 
-    // DNS tunneling — TXT record abuse (дълги поддомейни)
-    let is_tunneling = domain.split('.').any(|part| part.len() > 30);
+        let parent_name = get_parent_name(pid);
+        let reason = tunnel.summary();
+
+        process_monitor::record_network_alert(process_monitor::NetworkAlert {
+            kind: "dns".to_string(),
+            indicator: tunnel.parent_domain.clone(),
+            port: None,
+            pid,
+            process_name: name.clone(),
+            mitre_technique: "T1071.004".to_string(),
+            reason: reason.clone(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            evidence: serde_json::to_value(&tunnel.evidence).ok(),
+        });
+
+        respond(Detection { confidence: Confidence::Heuristic, ..detection(key, &name, &parent_name, &reason, "T1071.004", "high") });
+        return;
+    }
 
-    if is_dga || is_tunneling {
-        println!("🚨 DNS THREAT: PID={} Domain={} [T1071.004]", pid, &domain[..domain.len().min(100)]);
+    // DGA detection — статистически scorer върху eTLD+1 етикета
+    let dga = crate::dga_detector::analyze(&domain);
+    if dga.is_dga {
+        warn!(pid, domain = %truncate(&domain, 100), mitre = "T1071.004", "Possible DGA domain");
 
-        let name = get_process_name(pid);
         let parent_name = get_parent_name(pid);
-        let reason = format!("Suspicious DNS query: {}", &domain[..domain.len().min(60)]);
-
-        process_monitor::record_blocked_process(
-            pid, &name, &parent_name,
-            &reason,
-            "T1071.004",
-            "high",
-            false,
-            None,
-        );
+        let reason = format!("Possible DGA domain (score {:.2}): {}", dga.score, truncate(&domain, 60));
+
+        process_monitor::record_network_alert(process_monitor::NetworkAlert {
+            kind: "dns".to_string(),
+            indicator: domain.clone(),
+            port: None,
+            pid,
+            process_name: name.clone(),
+            mitre_technique: "T1071.004".to_string(),
+            reason: reason.clone(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            evidence: serde_json::to_value(&dga.features).ok(),
+        });
+
+        respond(Detection { confidence: Confidence::Heuristic, ..detection(key, &name, &parent_name, &reason, "T1071.004", "high") });
     }
 }
 
 fn handle_network_event(event: &EVENT_RECORD) {
-    use crate::process_monitor;
-
-    // Event ID 12 = TcpIpConnect, Event ID 15 = TcpIpConnectIPV6
+    // 12/28 = connect IPv4/IPv6 (outbound), 15/31 = accept IPv4/IPv6 (inbound)
     let event_id = event.EventHeader.EventDescriptor.Id;
-    if event_id != 12 && event_id != 15 {
+    let Some((direction, ipv6)) = crate::network_events::tcpip_event_kind(event_id) else {
         return;
-    }
+    };
 
     let pid = event.EventHeader.ProcessId;
     let name = get_process_name(pid);
@@ -426,48 +484,39 @@ fn handle_network_event(event: &EVENT_RECORD) {
         return;
     }
 
-    // Само suspicious процеси
-    if !is_suspicious_name(&name) {
-        return;
-    }
-
-    // Извличаме destination IP от UserData
-    let dest_ip = if !event.UserData.is_null() && event.UserDataLength >= 20 {
+    // Извличаме remote адрес и порт от UserData
+    let endpoints = if !event.UserData.is_null() {
         unsafe {
             let data = std::slice::from_raw_parts(
                 event.UserData as *const u8,
                 event.UserDataLength as usize,
             );
-            // Destination IP е на offset 16 (4 bytes)
-            format!("{}.{}.{}.{}", data[16], data[17], data[18], data[19])
+            crate::network_events::parse_tcpip_payload(data, ipv6)
         }
     } else {
-        String::from("unknown")
+        None
     };
 
-    println!("🔬 NET: {} (PID={}) → {}", name, pid, dest_ip);
-
-    // Засичаме suspicious outbound connections
-    let is_suspicious = !dest_ip.starts_with("127.")
-        && !dest_ip.starts_with("192.168.")
-        && !dest_ip.starts_with("10.")
-        && dest_ip != "unknown";
-
-    if is_suspicious {
-        println!("🚨 NETWORK THREAT: {} (PID={}) → {} [T1071]", name, pid, dest_ip);
+    let Some(endpoints) = endpoints else {
+        debug!("NET: {} (PID={}) → unparsed payload ({} bytes)", name, pid, event.UserDataLength);
+        return;
+    };
 
-        let parent_name = get_parent_name(pid);
-        let reason = format!("Suspicious outbound connection to {}", dest_ip);
-
-        process_monitor::record_blocked_process(
-            pid, &name, &parent_name,
-            &reason,
-            "T1071",
-            "high",
-            false,
-            None,
-        );
-    }
+    debug!("NET: {} (PID={}) → {} [{}]", name, pid,
+        std::net::SocketAddr::new(endpoints.remote_ip, endpoints.remote_port),
+        crate::network_events::classify_ip(&endpoints.remote_ip).as_str());
+
+    let event = crate::network_events::ConnectionEvent {
+        pid,
+        process_name: name,
+        protocol: "tcp",
+        direction,
+        local_port: Some(endpoints.local_port),
+        remote_ip: Some(endpoints.remote_ip),
+        remote_port: Some(endpoints.remote_port),
+        source: "etw",
+    };
+    crate::network_events::handle_connection(&event, get_parent_name);
 }
 
 fn handle_registry_event(event: &EVENT_RECORD) {
@@ -480,6 +529,7 @@ fn handle_registry_event(event: &EVENT_RECORD) {
     }
 
     let pid = event.EventHeader.ProcessId;
+    let key = ProcessKey::capture(pid);
     
     // Вземаме process name
     let name = get_process_name(pid);
@@ -493,7 +543,7 @@ fn handle_registry_event(event: &EVENT_RECORD) {
     }
 
     // SUSPEND FIRST — преди да е излязъл
-    let suspended = suspend_process(pid);
+    let suspended = presuspend(process_monitor::enforcement_mode()) && suspend_process(&key);
 
     // Вземаме cmdline и проверяваме за persistence patterns
     let cmdline = process_monitor::get_process_cmdline_pub(pid).to_lowercase();
@@ -504,28 +554,20 @@ fn handle_registry_event(event: &EVENT_RECORD) {
         || cmdline.contains("userinit");
 
    if is_persistence {
-        println!("🚨 REGISTRY THREAT: {} (PID={}) CMD={}", name, pid, &cmdline[..cmdline.len().min(100)]);
+        warn!(pid, process = %name, cmd = %truncate(&cmdline, 100), mitre = "T1547", "Registry persistence write");
 
         let parent_name = get_parent_name(pid);
-        let _ = process_monitor::block_process(pid);
-        println!("🚫 REGISTRY BLOCKED: {} (PID {})", name, pid);
-
-        process_monitor::record_blocked_process(
-            pid, &name, &parent_name,
-            "Suspicious registry persistence key write",
-            "T1547",
-            "high",
-            true,
-            None,
-        );
+        // Run key write от installer-и е нормално — евристика, не kill в block режим
+        let contained = respond(Detection { confidence: Confidence::Heuristic, suspended, ..detection(key, &name, &parent_name, "Suspicious registry persistence key write", "T1547", "high") });
+        if suspended && !contained {
+            resume_process(&key);
+        }
     } else if suspended {
-        resume_process(pid);
+        resume_process(&key);
     }
 }
 
 fn handle_wmi_event(event: &EVENT_RECORD) {
-    use crate::process_monitor;
-
     let event_id = event.EventHeader.EventDescriptor.Id;
     let pid = event.EventHeader.ProcessId;
 
@@ -553,7 +595,7 @@ fn handle_wmi_event(event: &EVENT_RECORD) {
         return;
     }
 
-    println!("🔬 WMI Event ID={} PID={} Op={}", event_id, pid, &operation[..operation.len().min(80)]);
+    debug!("WMI Event ID={} PID={} Op={}", event_id, pid, truncate(&operation, 80));
 
     let is_malicious = (operation.contains("process") && operation.contains("create"))
         || operation.contains("win32_process")
@@ -562,28 +604,21 @@ fn handle_wmi_event(event: &EVENT_RECORD) {
         || operation.contains("select * from win32_process");
 
     if is_malicious {
-        println!("🚨 WMI THREAT: PID={} Operation={}", pid, &operation[..operation.len().min(100)]);
+        warn!(pid, operation = %truncate(&operation, 100), mitre = "T1047", "Suspicious WMI activity");
 
+        let key = ProcessKey::capture(pid);
         let name = get_process_name(pid);
         let parent_name = get_parent_name(pid);
 
-        let _ = process_monitor::block_process(pid);
-        println!("🚫 WMI BLOCKED: {} (PID {})", name, pid);
-
-        process_monitor::record_blocked_process(
-            pid, &name, &parent_name,
-            "Malicious WMI operation detected",
-            "T1047",
-            "critical",
-            true,
-            None,
-        );
+        respond(detection(key, &name, &parent_name, "Malicious WMI operation detected", "T1047", "critical"));
     }
 }
 
-fn handle_new_process_with_name(pid: u32, parent_pid: u32, image_name: String, known_parent: String) {
+fn handle_new_process_with_name(key: ProcessKey, parent_pid: u32, image_name: String, known_parent: String, suspended: bool) {
     use crate::process_monitor;
 
+    let pid = key.pid;
+
     let name = if image_name.is_empty() {
         get_process_name(pid)
     } else {
@@ -592,7 +627,7 @@ fn handle_new_process_with_name(pid: u32, parent_pid: u32, image_name: String, k
 
     if name.is_empty() { return; }
 
-println!("🔬 ETW: {} (PID {})", name, pid);
+debug!("ETW: {} (PID {})", name, pid);
 
 // Засичаме процеси стартирани от suspicious locations
 let exe_path = get_process_exe_path(pid);
@@ -609,20 +644,17 @@ let appdata_whitelist = ["code.exe", "chrome.exe", "firefox.exe", "msedge.exe",
 let is_whitelisted = appdata_whitelist.iter().any(|w| name.to_lowercase().contains(w));
 
 if suspicious_path && !name.to_lowercase().contains("setup") && !is_whitelisted {
-    println!("🚨 ETW THREAT: {} — Suspicious execution path: {} [T1574]", name, exe_path);
-    let _ = process_monitor::block_process(pid);
-    process_monitor::record_blocked_process(
-        pid, &name, "", "Suspicious execution path",
-        "T1574", "high", true, None
-    );
-    resume_process(pid);
+    warn!(pid, process = %name, path = %exe_path, mitre = "T1574", "Suspicious execution path");
+    // Евристика — в block режим само alert, в block-with-prompt пита потребителя
+    let contained = respond(Detection { confidence: Confidence::Heuristic, suspended, ..detection(key, &name, "", "Suspicious execution path", "T1574", "high") });
+    if suspended && !contained {
+        resume_process(&key);
+    }
     return;
 }
 
 if !is_suspicious_name(&name) { return; }
 
-    let suspended = true; // suspend вече е направен в ETW callback-а
-
     let cmdline = {
     let mut cmd = process_monitor::get_process_cmdline_pub(pid);
    if cmd.is_empty() {
@@ -649,15 +681,13 @@ if cmd.is_empty() {
     let decision = process_monitor::analyze_process(&name, &cmdline, &parent_name);
 
     if decision.is_threat {
-        println!("🚨 ETW THREAT: {} — {} [{}]", name, decision.reason, decision.mitre);
-        let _ = process_monitor::block_process(pid);
-        println!("🚫 ETW BLOCKED: {} (PID {})", name, pid);
-        process_monitor::record_blocked_process(
-            pid, &name, &parent_name, &decision.reason,
-            &decision.mitre, &decision.severity, true, None
-        );
+        warn!(pid, process = %name, mitre = %decision.mitre, "{}", decision.reason);
+        let contained = respond(Detection { confidence: decision.confidence, suspended, ..detection(key, &name, &parent_name, &decision.reason, &decision.mitre, &decision.severity) });
+        if suspended && !contained {
+            resume_process(&key);
+        }
     } else if suspended {
-        resume_process(pid);
+        resume_process(&key);
     }
 }
 
@@ -665,22 +695,23 @@ fn handle_new_process(pid: u32) {
     use crate::process_monitor;
 
     // SUSPEND FIRST — незабавно преди всичко
-    let suspended = suspend_process(pid);
+    let key = ProcessKey::capture(pid);
+    let suspended = presuspend(process_monitor::enforcement_mode()) && suspend_process(&key);
 
     // Вземи process name
     let name = get_process_name(pid);
-    println!("🔬 ETW name for PID {}: '{}'", pid, name);
+    debug!("ETW name for PID {}: '{}'", pid, name);
     if name.is_empty() {
-        if suspended { resume_process(pid); }
+        if suspended { resume_process(&key); }
         return;
     }
 
     // Само suspicious процеси
     if !is_suspicious_name(&name) {
-        if suspended { resume_process(pid); }
+        if suspended { resume_process(&key); }
         return;
     }
-    println!("🔬 ETW suspicious: {}", name);
+    debug!("ETW suspicious: {}", name);
 
     // Вземи cmdline
     let cmdline = process_monitor::get_process_cmdline_pub(pid);
@@ -692,26 +723,24 @@ fn handle_new_process(pid: u32) {
     let decision = process_monitor::analyze_process(&name, &cmdline, &parent_name);
 
     if decision.is_threat {
-        println!(
-            "🔬 ETW THREAT: {} (PID {}) — {} [{}]",
-            name, pid, decision.reason, decision.mitre
-        );
+        warn!(pid, process = %name, mitre = %decision.mitre, "{}", decision.reason);
 
-        // Kill
-        let _ = process_monitor::block_process(pid);
-        println!("🚫 ETW BLOCKED: {} (PID {})", name, pid);
-
-        // Record в state
-        process_monitor::record_blocked_process(
-            pid, &name, &parent_name, &decision.reason,
-            &decision.mitre, &decision.severity, true, None
-        );
+        // Playbook (kill / suspend / ...) + record в state
+        let contained = respond(Detection { confidence: decision.confidence, suspended, ..detection(key, &name, &parent_name, &decision.reason, &decision.mitre, &decision.severity) });
+        if suspended && !contained {
+            resume_process(&key);
+        }
     } else if suspended {
         // Resume ако не е заплаха
-        resume_process(pid);
+        resume_process(&key);
     }
 }
 
+/// Първите `max` символа — байтов slice пада на многобайтов UTF-8 символ
+fn truncate(text: &str, max: usize) -> String {
+    text.chars().take(max).collect()
+}
+
 fn is_suspicious_name(name: &str) -> bool {
     let n = name.to_lowercase();
     ["powershell", "cmd", "wmic", "mshta", "certutil",
@@ -789,61 +818,31 @@ fn get_parent_name(pid: u32) -> String {
     }
 }
 
-fn suspend_process(pid: u32) -> bool {
-    use windows::Win32::System::Threading::OpenProcess;
-    use windows::Win32::System::Threading::PROCESS_SUSPEND_RESUME;
-    use windows::Win32::System::LibraryLoader::{GetProcAddress, GetModuleHandleW};
-    use windows::core::s;
-
-    unsafe {
-        let ntdll = match GetModuleHandleW(windows::core::w!("ntdll.dll")) {
-            Ok(h) => h,
-            Err(_) => return false,
-        };
-
-        let func = match GetProcAddress(ntdll, s!("NtSuspendProcess")) {
-            Some(f) => f,
-            None => return false,
-        };
-
-        let handle = match OpenProcess(PROCESS_SUSPEND_RESUME, false, pid) {
-            Ok(h) => h,
-            Err(_) => return false,
-        };
-
-        type NtSuspendProcess = unsafe extern "system" fn(HANDLE) -> i32;
-        let nt_suspend: NtSuspendProcess = std::mem::transmute(func);
-        let result = nt_suspend(handle);
-        let _ = windows::Win32::Foundation::CloseHandle(handle);
-        result >= 0
+/// ETW детекция — Definite, процесът не е suspend-нат; override с `..detection(...)`
+fn detection(key: ProcessKey, name: &str, parent_name: &str, reason: &str, mitre: &str, severity: &str) -> Detection {
+    Detection {
+        key,
+        process_name: name.to_string(),
+        parent_name: parent_name.to_string(),
+        rule_id: mitre.to_string(),
+        severity: severity.to_string(),
+        reason: reason.to_string(),
+        source: "etw",
+        command_line: None,
+        confidence: Confidence::Definite,
+        suspended: false,
     }
 }
 
-fn resume_process(pid: u32) {
-    use windows::Win32::System::Threading::OpenProcess;
-    use windows::Win32::System::Threading::PROCESS_SUSPEND_RESUME;
-    use windows::Win32::System::LibraryLoader::{GetProcAddress, GetModuleHandleW};
-    use windows::core::s;
-
-    unsafe {
-        let ntdll = match GetModuleHandleW(windows::core::w!("ntdll.dll")) {
-            Ok(h) => h,
-            Err(_) => return,
-        };
-
-        let func = match GetProcAddress(ntdll, s!("NtResumeProcess")) {
-            Some(f) => f,
-            None => return,
-        };
+/// Детекция → response playbook. true = процесът е спрян, оставен suspended или чака решение
+fn respond(detection: Detection) -> bool {
+    crate::response_playbooks::respond(&detection).contained()
+}
 
-        let handle = match OpenProcess(PROCESS_SUSPEND_RESUME, false, pid) {
-            Ok(h) => h,
-            Err(_) => return,
-        };
+fn suspend_process(key: &ProcessKey) -> bool {
+    crate::process_control::suspend(key).is_ok()
+}
 
-        type NtResumeProcess = unsafe extern "system" fn(HANDLE) -> i32;
-        let nt_resume: NtResumeProcess = std::mem::transmute(func);
-        let _ = nt_resume(handle);
-        let _ = windows::Win32::Foundation::CloseHandle(handle);
-    }
+fn resume_process(key: &ProcessKey) {
+    let _ = crate::process_control::resume(key);
 }
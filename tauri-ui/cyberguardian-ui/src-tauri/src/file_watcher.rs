@@ -8,6 +8,12 @@ use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use sha2::{Sha256, Digest};
 use std::io::Read;
+use tracing::{error, info, warn};
+
+use crate::backend_client::{BackendClient, BackendError, ReqwestBackendClient, ScanRequest, ScanVerdict};
+use crate::outbound_spool::{self, SpoolEndpoint};
+use crate::siem_forwarder::SecurityEvent;
+use crate::telemetry_schema::{self, FileActivity, TelemetryEvent};
 
 // Global cache of scanned files (path -> hash)
 lazy_static::lazy_static! {
@@ -24,14 +30,8 @@ pub struct FileEvent {
     pub file_hash: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
-struct ScanRequest {
-    file_path: String,
-    file_size: u64,
-}
-
 pub fn start_watching(paths: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
-    println!("🔍 Starting file watcher for paths: {:?}", paths);
+    info!("Starting file watcher for paths: {:?}", paths);
     
     // Clone paths for threads
     let paths_clone = paths.clone();
@@ -39,11 +39,11 @@ pub fn start_watching(paths: Vec<String>) -> Result<(), Box<dyn std::error::Erro
     
     // ✅ STEP 1: Initial scan in BACKGROUND thread (non-blocking)
     std::thread::spawn(move || {
-        println!("🔎 Performing smart initial scan in background...");
+        info!("Performing smart initial scan in background...");
         for path_str in &paths_for_scan {
             scan_directory(Path::new(path_str));
         }
-        println!("✅ Initial scan completed");
+        info!("Initial scan completed");
     });
     
     // ✅ STEP 2: Start watcher immediately (doesn't block)
@@ -53,16 +53,16 @@ pub fn start_watching(paths: Vec<String>) -> Result<(), Box<dyn std::error::Erro
         let mut watcher = notify::recommended_watcher(move |res: Result<Event, notify::Error>| {
             match res {
                 Ok(event) => {
-                    println!("📁 File event detected: {:?}", event);
+                    info!("File event detected: {:?}", event);
                     tx.send(event).ok();
                 }
-                Err(e) => println!("❌ Watch error: {:?}", e),
+                Err(e) => error!("Watch error: {:?}", e),
             }
         }).expect("Failed to create watcher");
         
         // Watch each path
         for path in paths_clone {
-            println!("👀 Watching: {}", path);
+            info!("Watching: {}", path);
             watcher.watch(Path::new(&path), RecursiveMode::Recursive)
                 .expect("Failed to watch path");
         }
@@ -96,7 +96,7 @@ fn scan_directory(dir: &Path) {
     let entries = match fs::read_dir(dir) {
         Ok(entries) => entries,
         Err(e) => {
-            println!("❌ Failed to read directory {:?}: {}", dir, e);
+            error!("Failed to read directory {:?}: {}", dir, e);
             return;
         }
     };
@@ -136,7 +136,7 @@ fn scan_file(path: &Path) {
     let metadata = match fs::metadata(path) {
         Ok(m) => m,
         Err(e) => {
-            println!("❌ Failed to get metadata for {:?}: {}", path, e);
+            error!("Failed to get metadata for {:?}: {}", path, e);
             return;
         }
     };
@@ -145,7 +145,7 @@ fn scan_file(path: &Path) {
     
     // Skip very large files (>100MB)
     if file_size > 100_000_000 {
-        println!("⏭️ Skipping large file: {:?} ({}MB)", path, file_size / 1_000_000);
+        info!("Skipping large file: {:?} ({}MB)", path, file_size / 1_000_000);
         return;
     }
     
@@ -163,7 +163,7 @@ fn scan_file(path: &Path) {
     let hash = match calculate_file_hash(path) {
         Ok(h) => h,
         Err(e) => {
-            println!("❌ Failed to calculate hash for {:?}: {}", path, e);
+            error!("Failed to calculate hash for {:?}: {}", path, e);
             return;
         }
     };
@@ -171,100 +171,180 @@ fn scan_file(path: &Path) {
     // ✅ SMART: Check if already scanned with same hash
     {
         let mut cache = SCANNED_FILES.lock().unwrap();
-        if let Some(cached_hash) = cache.get(&path_str) {
+        let activity = if let Some(cached_hash) = cache.get(&path_str) {
             if cached_hash == &hash {
-                println!("⏭️ File already scanned (hash match), skipping: {:?}", path);
+                info!("File already scanned (hash match), skipping: {:?}", path);
                 return;
             } else {
-                println!("🔄 File modified (hash changed), re-scanning: {:?}", path);
+                info!("File modified (hash changed), re-scanning: {:?}", path);
+                FileActivity::Update
             }
-        }
+        } else {
+            FileActivity::Create
+        };
         
         // Update cache BEFORE scanning (to prevent double-scan)
         cache.insert(path_str.clone(), hash.clone());
+        drop(cache);
+        
+        telemetry_schema::emit(TelemetryEvent::File(&telemetry_schema::FileEvent {
+            path: path_str.clone(),
+            size: Some(file_size),
+            sha256: Some(hash.clone()),
+            activity,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }));
     }
     
-    println!("📂 Scanning file: {:?} ({}KB)", path, file_size / 1024);
-    
+    // Known-bad hash from local threat intel — report it; backend still decides on quarantine
+    let intel = crate::threat_intel::lookup_hash(&hash);
+    if !intel.is_empty() {
+        warn!("Threat intel hash match for {:?}: {}", path, crate::threat_intel::describe(&intel));
+        telemetry_schema::publish_detection(SecurityEvent::file_verdict(&path_str, 100.0, "intel_match"));
+    }
+
+    info!("Scanning file: {:?} ({}KB)", path, file_size / 1024);
+
     // Send to Railway backend for analysis
     send_to_backend(path, file_size);
 }
 
-// ✅ Send file to Railway API for ML analysis
+/// Threat score at which a scanned file is quarantined automatically
+pub const AUTO_QUARANTINE_THRESHOLD: f64 = 70.0;
+
+/// Result of scanning one file against the backend
+#[derive(Debug)]
+pub enum ScanOutcome {
+    /// Below the threshold (or no score in the verdict)
+    Clean { threat_score: Option<f64> },
+    Quarantined { threat_score: f64 },
+    /// High verdict, but the quarantine record could not be created
+    QuarantineFailed { threat_score: f64, record: serde_json::Value, error: BackendError },
+}
+
+// ✅ Send file to the backend for ML analysis
 fn send_to_backend(path: &Path, file_size: u64) {
-    let path_str = path.to_string_lossy().to_string();
+    let request = ScanRequest {
+        file_path: path.to_string_lossy().to_string(),
+        file_size,
+    };
     
     // Spawn async task to not block file watcher
     std::thread::spawn(move || {
-        let client = match reqwest::blocking::Client::builder()
-            .timeout(Duration::from_secs(30))
-            .build() {
+        // Offline: keep scan order behind already spooled requests
+        if outbound_spool::has_pending(SpoolEndpoint::FileScan) {
+            spool_request(SpoolEndpoint::FileScan, &request);
+            return;
+        }
+        
+        let client = match ReqwestBackendClient::from_config() {
             Ok(c) => c,
             Err(e) => {
-                println!("❌ Failed to create HTTP client: {}", e);
+                error!("Failed to create HTTP client: {}", e);
+                spool_request(SpoolEndpoint::FileScan, &request);
                 return;
             }
         };
         
-        let backend_url = std::env::var("RAILWAY_BACKEND_URL")
-            .unwrap_or_else(|_| "https://cyberguardian-backend-production.up.railway.app".to_string());
-        
-        let scan_url = format!("{}/api/protection/scan", backend_url);
-        
-        let request_body = ScanRequest {
-            file_path: path_str.clone(),
-            file_size,
-        };
-        
-        let token = get_auth_token();
-        
-        let response = client
-            .post(&scan_url)
-            .header("Content-Type", "application/json")
-            .header("Authorization", format!("Bearer {}", token))
-            .json(&request_body)
-            .send();
-        
-        match response {
-            Ok(resp) => {
-                if resp.status().is_success() {
-                    println!("✅ File scanned successfully: {}", path_str);
-                    
-                    if let Ok(body) = resp.text() {
-                        println!("📊 Scan result: {}", body);
-                        
-                        // ✅ PARSE RESPONSE & AUTO-QUARANTINE
-                        if let Ok(scan_result) = serde_json::from_str::<serde_json::Value>(&body) {
-                            if let Some(data) = scan_result.get("data") {
-                                if let Some(threat_score) = data.get("threat_score").and_then(|v| v.as_f64()) {
-                                    println!("🎯 Threat score: {}", threat_score);
-                                    
-                                    // Auto-quarantine if threat score >= 80
-                                    if threat_score >= 70.0 {
-                                        println!("⚠️ HIGH THREAT DETECTED! Auto-quarantining file...");
-                                        quarantine_file(&path_str, threat_score, file_size);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                } else {
-                    println!("⚠️ Scan failed with status {}: {}", resp.status(), path_str);
-                }
+        match scan_and_quarantine(&client, &request) {
+            Ok(outcome) => finish_outcome(outcome),
+            Err(e) if e.is_retryable() => {
+                error!("Scan of {} failed: {} (spooled for retry)", request.file_path, e);
+                spool_request(SpoolEndpoint::FileScan, &request);
             }
             Err(e) => {
-                println!("❌ Network error scanning file {}: {}", path_str, e);
+                warn!("Scan failed for {}: {}", request.file_path, e);
             }
         }
     });
 }
 
-// ✅ QUARANTINE FILE - Backend handles physical move + DB record
-fn quarantine_file(file_path: &str, threat_score: f64, file_size: u64) {
-    let backend_url = std::env::var("RAILWAY_BACKEND_URL")
-        .unwrap_or_else(|_| "https://cyberguardian-backend-production.up.railway.app".to_string());
-    let token = get_auth_token();
+/// Scan one file and auto-quarantine it when the verdict crosses the threshold
+pub fn scan_and_quarantine(
+    client: &dyn BackendClient,
+    request: &ScanRequest,
+) -> Result<ScanOutcome, BackendError> {
+    let verdict = client.scan_file(request)?;
+    info!("File scanned successfully: {}", request.file_path);
+    Ok(apply_verdict(client, &request.file_path, request.file_size, &verdict))
+}
+
+/// Parse a replayed scan response from the spool and act on it
+pub fn handle_scan_response(path_str: &str, file_size: u64, body: &str) {
+    let verdict = match ScanVerdict::parse(body) {
+        Ok(v) => v,
+        Err(e) => {
+            warn!("Unreadable scan result for {}: {}", path_str, e);
+            return;
+        }
+    };
+    match ReqwestBackendClient::from_config() {
+        Ok(client) => finish_outcome(apply_verdict(&client, path_str, file_size, &verdict)),
+        Err(e) => error!("Failed to create HTTP client: {}", e),
+    }
+}
+
+// ✅ PARSE RESPONSE & AUTO-QUARANTINE
+fn apply_verdict(
+    client: &dyn BackendClient,
+    file_path: &str,
+    file_size: u64,
+    verdict: &ScanVerdict,
+) -> ScanOutcome {
+    info!("Scan result: {}", verdict.raw);
+    
+    let threat_score = match verdict.threat_score {
+        Some(score) => score,
+        None => return ScanOutcome::Clean { threat_score: None },
+    };
+    info!("Threat score: {}", threat_score);
+    
+    if threat_score < AUTO_QUARANTINE_THRESHOLD {
+        telemetry_schema::publish_detection(SecurityEvent::file_verdict(file_path, threat_score, "allowed"));
+        return ScanOutcome::Clean { threat_score: Some(threat_score) };
+    }
     
+    warn!("HIGH THREAT DETECTED! Auto-quarantining file...");
+    let record = quarantine_record(file_path, threat_score, file_size);
+    
+    // Backend handles physical move + DB record
+    let outcome = match client.create_quarantine_record(&record) {
+        Ok(()) => {
+            info!("Quarantine record created in backend (file moved by backend)");
+            ScanOutcome::Quarantined { threat_score }
+        }
+        Err(error) => ScanOutcome::QuarantineFailed { threat_score, record, error },
+    };
+    let siem_outcome = match outcome {
+        ScanOutcome::Quarantined { .. } => "quarantined",
+        _ => "quarantine_failed",
+    };
+    telemetry_schema::publish_detection(SecurityEvent::file_verdict(file_path, threat_score, siem_outcome));
+    outcome
+}
+
+fn finish_outcome(outcome: ScanOutcome) {
+    if let ScanOutcome::QuarantineFailed { record, error, .. } = outcome {
+        if error.is_retryable() {
+            warn!("Failed to create backend record: {} (spooled for retry)", error);
+            spool_request(SpoolEndpoint::Quarantine, &record);
+        } else {
+            warn!("Failed to create backend record: {}", error);
+        }
+    }
+}
+
+fn spool_request<T: Serialize>(endpoint: SpoolEndpoint, payload: &T) {
+    let result = serde_json::to_value(payload)
+        .map_err(|e| e.to_string())
+        .and_then(|value| outbound_spool::enqueue(endpoint, value));
+    if let Err(e) = result {
+        error!("Failed to spool {} request: {}", endpoint.as_str(), e);
+    }
+}
+
+// ✅ QUARANTINE RECORD payload
+fn quarantine_record(file_path: &str, threat_score: f64, file_size: u64) -> serde_json::Value {
     // Determine threat level
     let threat_level = if threat_score >= 90.0 {
         "CRITICAL"
@@ -276,41 +356,14 @@ fn quarantine_file(file_path: &str, threat_score: f64, file_size: u64) {
         "LOW"
     };
     
-    // Create JSON payload
-  let payload = serde_json::json!({
-    "file_path": file_path,
-    "reason": "Auto-quarantine: High threat detected",
-    "threat_score": threat_score,
-    "threat_level": threat_level,
-    "detection_method": "ML-powered scan",
-    "file_size": file_size
-    }); 
-    // Send POST request - backend will handle physical quarantine
-    let client = reqwest::blocking::Client::new();
-    let url = format!("{}/api/quarantine/", backend_url);
-    
-    println!("🔍 DEBUG: Preparing quarantine API call");
-    println!("🔍 URL: {}", url);
-    println!("🔍 Token length: {}", token.len());
-    println!("🔍 Token first 50 chars: {}", &token[..50.min(token.len())]);
-    println!("🔍 Payload: {:?}", payload);
-
-    match client.post(&url)
-        .header("Content-Type", "application/json")
-        .header("Authorization", format!("Bearer {}", token))
-        .json(&payload)
-        .send() {
-        Ok(resp) => {
-            if resp.status().is_success() {
-                println!("✅ Quarantine record created in backend (file moved by backend)");
-            } else {
-                println!("⚠️ Failed to create backend record: {}", resp.status());
-            }
-        }
-        Err(e) => {
-            println!("❌ Network error creating backend record: {}", e);
-        }
-    }
+    serde_json::json!({
+        "file_path": file_path,
+        "reason": "Auto-quarantine: High threat detected",
+        "threat_score": threat_score,
+        "threat_level": threat_level,
+        "detection_method": "ML-powered scan",
+        "file_size": file_size
+    })
 }
 
 // ✅ Process real-time file events
@@ -319,7 +372,7 @@ fn process_event(event: Event) {
         EventKind::Create(_) => {
             for path in event.paths {
                 if path.is_file() {
-                    println!("✅ File created: {:?}", path);
+                    info!("File created: {:?}", path);
                     scan_file(&path);
                 }
             }
@@ -327,28 +380,90 @@ fn process_event(event: Event) {
         EventKind::Modify(_) => {
             for path in event.paths {
                 if path.is_file() {
-                    println!("✏️ File modified: {:?}", path);
+                    info!("File modified: {:?}", path);
                     scan_file(&path);
                 }
             }
         }
         EventKind::Remove(_) => {
             for path in event.paths {
-                println!("🗑️ File removed: {:?}", path);
+                info!("File removed: {:?}", path);
                 
                 // Remove from cache
                 let path_str = path.to_string_lossy().to_string();
                 SCANNED_FILES.lock().unwrap().remove(&path_str);
+                
+                telemetry_schema::emit(TelemetryEvent::File(&telemetry_schema::FileEvent {
+                    path: path_str,
+                    size: None,
+                    sha256: None,
+                    activity: FileActivity::Delete,
+                    timestamp: chrono::Utc::now().to_rfc3339(),
+                }));
             }
         }
         _ => {}
     }
 }
-fn get_auth_token() -> String {
-    // Read token from environment variable (set by start_file_protection)
-    std::env::var("AUTH_TOKEN")
-        .unwrap_or_else(|_| {
-            println!("⚠️ AUTH_TOKEN not found in environment, using empty token");
-            String::new()
-        })
-}
\ No newline at end of file
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend_client::mock::{MockBackend, MockResponse};
+    use crate::backend_client::{FILE_SCAN_PATH, QUARANTINE_PATH};
+
+    fn request() -> ScanRequest {
+        ScanRequest { file_path: r"C:\Users\admin\Downloads\invoice.exe".into(), file_size: 2048 }
+    }
+
+    #[test]
+    fn test_high_verdict_is_quarantined() {
+        let mock = MockBackend::start();
+        mock.on(FILE_SCAN_PATH, vec![MockResponse::verdict(85.0)]);
+        mock.on(QUARANTINE_PATH, vec![MockResponse::ok(serde_json::json!({"success": true}))]);
+
+        let outcome = scan_and_quarantine(&mock.client(), &request()).unwrap();
+        assert!(matches!(outcome, ScanOutcome::Quarantined { threat_score } if threat_score == 85.0));
+
+        let records = mock.requests_to(QUARANTINE_PATH);
+        assert_eq!(records.len(), 1);
+        let record = records[0].json();
+        // Profile name is pseudonymized on the way out
+        let uploaded_path = record["file_path"].as_str().unwrap();
+        assert!(uploaded_path.starts_with(r"C:\Users\user-"));
+        assert!(uploaded_path.ends_with(r"\Downloads\invoice.exe"));
+        assert_eq!(record["threat_level"], "HIGH");
+        assert_eq!(record["file_size"], 2048);
+    }
+
+    #[test]
+    fn test_verdict_below_threshold_is_not_quarantined() {
+        let mock = MockBackend::start();
+        mock.on(FILE_SCAN_PATH, vec![MockResponse::verdict(69.0)]);
+
+        let outcome = scan_and_quarantine(&mock.client(), &request()).unwrap();
+        assert!(matches!(outcome, ScanOutcome::Clean { threat_score: Some(s) } if s == 69.0));
+        assert!(mock.requests_to(QUARANTINE_PATH).is_empty());
+    }
+
+    #[test]
+    fn test_backend_failures_surface_as_transient() {
+        let mock = MockBackend::start();
+        mock.on(FILE_SCAN_PATH, vec![MockResponse::status(503), MockResponse::verdict(95.0)]);
+        mock.on(QUARANTINE_PATH, vec![MockResponse::Disconnect]);
+        let client = mock.client();
+
+        let err = scan_and_quarantine(&client, &request()).unwrap_err();
+        assert!(err.is_transient());
+        assert!(mock.requests_to(QUARANTINE_PATH).is_empty());
+
+        match scan_and_quarantine(&client, &request()).unwrap() {
+            ScanOutcome::QuarantineFailed { threat_score, record, error } => {
+                assert_eq!(threat_score, 95.0);
+                assert_eq!(record["threat_level"], "CRITICAL");
+                assert!(error.is_transient());
+            }
+            other => panic!("unexpected outcome {:?}", other),
+        }
+    }
+}
@@ -8,13 +8,42 @@ mod deep_quarantine;
 #[cfg(windows)]
 mod windows_service;
 mod process_monitor;
+#[cfg(windows)]
 mod etw_monitor;
+#[cfg(target_os = "linux")]
+mod linux_net_monitor;
 mod backup_monitor;
 mod vuln_scanner;
 mod api_client; 
 mod background_tasks;
+mod quarantine_retention;
+mod outbound_spool;
+mod agent_config;
+mod backend_client;
+mod agent_identity;
+mod local_scan;
+mod remote_commands;
+mod process_inventory;
+mod redaction;
+mod siem_forwarder;
+mod telemetry_schema;
+mod stix_export;
+mod threat_intel;
+mod dga_detector;
+mod dns_tunnel_detector;
+mod beacon_detector;
+mod network_events;
+mod listening_ports;
+mod network_isolation;
+mod process_control;
+mod process_identity;
+mod response_playbooks;
+mod enforcement;
+mod decision_prompt;
+mod logging;
 
 use tauri::{
+    Emitter,
     Manager,
     menu::{Menu, MenuItem, PredefinedMenuItem},
     tray::{TrayIconBuilder, TrayIconEvent, MouseButton, MouseButtonState},
@@ -23,6 +52,7 @@ use tauri::{
 use registry_scanner::{scan_registry, calculate_statistics};
 use service_scanner::{scan_services, calculate_statistics as calculate_service_stats};
 use task_scanner::{scan_tasks, calculate_statistics as calculate_task_stats};
+use tracing::{debug, error, info};
 
 
 #[tauri::command]
@@ -52,12 +82,11 @@ fn start_file_protection(
     backend_url: String,
     token: String
 ) -> Result<String, String> {
-    println!("🛡️ Starting file protection for: {:?}", paths);
-    println!("🔗 Backend URL: {}", backend_url);
-    println!("🔑 Token length: {}", token.len());
+    info!("Starting file protection for: {:?}", paths);
+    info!("Backend URL: {}", backend_url);
     
-    std::env::set_var("RAILWAY_BACKEND_URL", backend_url);
-    std::env::set_var("AUTH_TOKEN", token);
+    agent_config::note_ui_backend_url(&backend_url);
+    agent_config::set_session_token(&token);
     
     match file_watcher::start_watching(paths) {
         Ok(_) => Ok("File protection started".to_string()),
@@ -73,9 +102,7 @@ async fn create_quarantine_record(
     _detection_method: String,
     _reason: String,
 ) -> Result<String, String> {
-    println!("📝 Tauri command called: create_quarantine_record");
-    println!("   File: {}", file_path);
-    println!("   Score: {}", threat_score);
+    info!(file = %file_path, score = threat_score, "Tauri command called: create_quarantine_record");
     Ok("Command received by Rust".to_string())
 }
 
@@ -84,6 +111,10 @@ async fn scan_windows_registry() -> Result<serde_json::Value, String> {
     match scan_registry() {
         Ok(entries) => {
             let stats = calculate_statistics(&entries);
+            // Only suspicious entries are detections; the rest is inventory
+            for event in entries.iter().filter_map(siem_forwarder::SecurityEvent::from_registry) {
+                telemetry_schema::publish_detection(event);
+            }
             Ok(serde_json::json!({
                 "entries": entries,
                 "statistics": stats,
@@ -99,6 +130,9 @@ async fn scan_windows_services() -> Result<serde_json::Value, String> {
     match scan_services() {
         Ok(services) => {
             let stats = calculate_service_stats(&services);
+            for event in services.iter().filter_map(siem_forwarder::SecurityEvent::from_service) {
+                telemetry_schema::publish_detection(event);
+            }
             Ok(serde_json::json!({
                 "services": services,
                 "statistics": stats,
@@ -114,6 +148,9 @@ async fn scan_windows_tasks() -> Result<serde_json::Value, String> {
     match scan_tasks() {
         Ok(tasks) => {
             let stats = calculate_task_stats(&tasks);
+            for event in tasks.iter().filter_map(siem_forwarder::SecurityEvent::from_task) {
+                telemetry_schema::publish_detection(event);
+            }
             Ok(serde_json::json!({
                 "tasks": tasks,
                 "statistics": stats,
@@ -130,162 +167,12 @@ async fn start_local_scan(
     backend_url: String,
     token: String,
 ) -> Result<serde_json::Value, String> {
-    use std::time::Instant;
-    use std::path::Path;
-    use std::fs;
-    
-    println!("🔍 Starting LOCAL {} scan on Windows", profile);
-    
-    let start_time = Instant::now();
-    let mut files_scanned = 0;
-    let mut threats_found = 0;
-    
-    let (max_files, scan_paths, extensions, recursive): (usize, Vec<&str>, Vec<&str>, bool) = match profile.as_str() {
-        "quick" => (
-            100,
-            vec![
-                r"C:\Users\admin\Downloads",
-                r"C:\Users\admin\AppData\Local\Temp",
-                r"C:\Windows\Temp",
-            ],
-            vec![".exe", ".dll", ".bat", ".ps1", ".cmd", ".vbs", ".js"],
-            false
-        ),
-        "standard" => (
-            1000,
-            vec![
-                r"C:\Users\admin\Downloads",
-                r"C:\Users\admin\Documents",
-                r"C:\Users\admin\Desktop",
-                r"C:\Users\admin\AppData",
-            ],
-            vec![".exe", ".dll", ".bat", ".ps1", ".zip", ".rar", ".7z", ".jar"],
-            true
-        ),
-        "deep" => (
-            10000,
-            vec![r"C:\"],
-            vec!["*"],
-            true
-        ),
-        _ => (100, vec![r"C:\Users\admin\Downloads"], vec![".exe", ".dll"], false),
-    };
-    
-    println!("📁 Scanning {} paths (max {} files)", scan_paths.len(), max_files);
-    
-    for scan_path in &scan_paths {
-        if files_scanned >= max_files {
-            break;
-        }
-        
-        let path = Path::new(scan_path);
-        if !path.exists() {
-            println!("⚠️ Path does not exist: {}", scan_path);
-            continue;
-        }
-        
-        println!("📂 Scanning: {}", scan_path);
-        
-        fn scan_directory(
-            path: &Path,
-            extensions: &Vec<&str>,
-            recursive: bool,
-            files_scanned: &mut usize,
-            threats_found: &mut usize,
-            max_files: usize,
-        ) {
-            if *files_scanned >= max_files {
-                return;
-            }
-            
-            if let Ok(entries) = fs::read_dir(path) {
-                for entry in entries {
-                    if *files_scanned >= max_files {
-                        break;
-                    }
-                    
-                    if let Ok(entry) = entry {
-                        let file_path = entry.path();
-                        
-                        if file_path.is_file() {
-                            let should_scan = extensions.contains(&"*") || 
-                                file_path.extension()
-                                    .and_then(|ext| ext.to_str())
-                                    .map(|ext| {
-                                        let ext_with_dot = format!(".{}", ext);
-                                        extensions.contains(&ext_with_dot.as_str())
-                                    })
-                                    .unwrap_or(false);
-                            
-                            if should_scan {
-                                *files_scanned += 1;
-                                
-                                if let Some(file_name) = file_path.file_name() {
-                                    let name = file_name.to_string_lossy().to_lowercase();
-                                    if name.contains("virus") || name.contains("malware") || 
-                                       name.contains("trojan") || name.contains("hack") ||
-                                       name.contains("ransom") || name.contains("keylog") {
-                                        *threats_found += 1;
-                                        println!("🚨 Potential threat: {:?}", file_path);
-                                    }
-                                }
-                                
-                                if *files_scanned % 100 == 0 {
-                                    println!("   Progress: {} files scanned", files_scanned);
-                                }
-                            }
-                        } else if file_path.is_dir() && recursive {
-                            scan_directory(&file_path, extensions, recursive, files_scanned, threats_found, max_files);
-                        }
-                    }
-                }
-            }
-        }
-        
-        scan_directory(path, &extensions, recursive, &mut files_scanned, &mut threats_found, max_files);
-    }
-    
-    let duration = start_time.elapsed().as_secs();
-    
-    println!("✅ Scan completed: {} files, {} threats, {}s", 
-             files_scanned, threats_found, duration);
-    
-    let client = reqwest::Client::new();
-    let target_path = scan_paths.join("; ");
+    agent_config::note_ui_backend_url(&backend_url);
+    agent_config::set_session_token(&token);
     
-    let history_data = serde_json::json!({
-        "schedule_id": null,
-        "scan_type": profile,
-        "target_path": target_path,
-        "started_at": chrono::Utc::now().to_rfc3339(),
-        "status": "completed",
-        "files_scanned": files_scanned,
-        "threats_found": threats_found,
-        "duration_seconds": duration,
-    });
-    
-    match client
-        .post(format!("{}/api/scans/history", backend_url))
-        .header("Authorization", format!("Bearer {}", token))
-        .header("Content-Type", "application/json")
-        .json(&history_data)
-        .send()
+    tokio::task::spawn_blocking(move || local_scan::run_local_scan(&profile))
         .await
-    {
-        Ok(response) => {
-            println!("✅ Results sent to backend: {}", response.status());
-            Ok(serde_json::json!({
-                "success": true,
-                "files_scanned": files_scanned,
-                "threats_found": threats_found,
-                "duration": duration
-            }))
-        }
-        Err(e) => {
-            println!("❌ Failed to send results: {}", e);
-            Err(format!("Failed to send results: {}", e))
-        }
-    }
+        .map_err(|e| format!("Scan task failed: {}", e))?
 }
 
 // ============================================================================
@@ -451,15 +338,15 @@ use crate::deep_quarantine::*;
 /// Analyze file with all 4 stages
 #[tauri::command]
 async fn deep_quarantine_analyze(file_path: String) -> Result<DeepAnalysisResult, String> {
-    println!("🔍 Starting deep analysis for: {}", file_path);
+    info!("Starting deep analysis for: {}", file_path);
     
     match perform_deep_analysis(&file_path) {
         Ok(result) => {
-            println!("✅ Deep analysis completed successfully");
+            info!("Deep analysis completed successfully");
             Ok(result)
         }
         Err(e) => {
-            eprintln!("❌ Deep analysis failed: {}", e);
+            error!("Deep analysis failed: {}", e);
             Err(e)
         }
     }
@@ -468,15 +355,15 @@ async fn deep_quarantine_analyze(file_path: String) -> Result<DeepAnalysisResult
 /// Perform complete removal with backup
 #[tauri::command]
 async fn deep_quarantine_remove(analysis_data: DeepAnalysisResult) -> Result<RemovalResult, String> {
-    println!("🗑️ Starting complete removal...");
+    info!("Starting complete removal...");
     
     match perform_complete_removal(&analysis_data) {
         Ok(result) => {
-            println!("✅ Complete removal finished successfully");
+            info!("Complete removal finished successfully");
             Ok(result)
         }
         Err(e) => {
-            eprintln!("❌ Removal failed: {}", e);
+            error!("Removal failed: {}", e);
             Err(e)
         }
     }
@@ -485,36 +372,37 @@ async fn deep_quarantine_remove(analysis_data: DeepAnalysisResult) -> Result<Rem
 /// List all Deep Quarantine backups
 #[tauri::command]
 async fn deep_quarantine_list_backups() -> Result<BackupList, String> {
-    println!("📋 Listing Deep Quarantine backups...");
+    info!("Listing Deep Quarantine backups...");
     
     match list_backups() {
         Ok(list) => {
-            println!("✅ Found {} backups", list.backups.len());
+            info!("Found {} backups", list.backups.len());
             Ok(list)
         }
         Err(e) => {
-            eprintln!("❌ Failed to list backups: {}", e);
+            error!("Failed to list backups: {}", e);
             Err(e)
         }
     }
 }
 #[tauri::command]
 async fn start_background_upload(api_token: String) -> Result<String, String> {
-    println!("🚀 Starting background process upload task...");
+    info!("Starting background process upload task...");
+    agent_config::set_session_token(&api_token);
     
     // Test connection first
-    match background_tasks::test_connection(&api_token).await {
+    match background_tasks::test_connection().await {
         Ok(_) => {
-            println!("✅ Backend connection verified");
+            info!("Backend connection verified");
             
             // Start periodic upload task
-            background_tasks::start_process_upload_task(api_token);
+            background_tasks::start_process_upload_task();
             
             Ok("Background upload started".to_string())
         }
         Err(e) => {
             let error_msg = format!("Failed to connect to backend: {}", e);
-            eprintln!("❌ {}", error_msg);
+            error!("{}", error_msg);
             Err(error_msg)
         }
     }
@@ -523,74 +411,108 @@ async fn start_background_upload(api_token: String) -> Result<String, String> {
 // RUNTIME BLOCKING COMMANDS
 // ============================================================================
 
+/// Runtime blocking status — mode + real counters (not the length of the recent list)
+fn runtime_blocking_status(message: Option<&str>) -> serde_json::Value {
+    let (mode, threats, blocked) = process_monitor::get_enforcement_status();
+    let (_, procs, _) = process_monitor::get_blocking_status();
+    let mut status = serde_json::json!({
+        "success": true,
+        "mode": mode,
+        "blocking_enabled": mode.blocks(),
+        "total_blocked": blocked,
+        "threats_detected": threats,
+        "blocked_processes": procs,
+        "monitor_running": process_monitor::is_monitor_running(),
+    });
+    #[cfg(windows)]
+    {
+        status["etw_running"] = serde_json::json!(etw_monitor::is_etw_running());
+    }
+    if let Some(message) = message {
+        status["message"] = serde_json::json!(message);
+    }
+    status
+}
+
 #[tauri::command]
 fn enable_runtime_blocking() -> Result<serde_json::Value, String> {
     process_monitor::enable_blocking();
-    let (enabled, procs, threats) = process_monitor::get_blocking_status();
-    Ok(serde_json::json!({
-        "success": true,
-        "blocking_enabled": enabled,
-        "total_blocked": procs.len(),
-        "blocked_processes": procs,
-        "message": "Runtime blocking enabled"
-    }))
+    Ok(runtime_blocking_status(Some("Runtime blocking enabled")))
 }
 
 #[tauri::command]
 fn disable_runtime_blocking() -> Result<serde_json::Value, String> {
     process_monitor::disable_blocking();
-    let (enabled, procs, threats) = process_monitor::get_blocking_status();
-    Ok(serde_json::json!({
-        "success": true,
-        "blocking_enabled": enabled,
-        "total_blocked": procs.len(),
-        "blocked_processes": procs,
-        "message": "Runtime blocking disabled"
-    }))
+    Ok(runtime_blocking_status(Some("Runtime blocking disabled")))
+}
+
+#[tauri::command]
+fn set_runtime_blocking_mode(mode: enforcement::EnforcementMode) -> Result<serde_json::Value, String> {
+    process_monitor::set_enforcement_mode(mode);
+    Ok(runtime_blocking_status(Some(&format!("Enforcement mode: {}", mode.as_str()))))
 }
 
 #[tauri::command]
 fn get_runtime_blocking_status() -> Result<serde_json::Value, String> {
-    let (enabled, procs, threats) = process_monitor::get_blocking_status();
-    Ok(serde_json::json!({
-        "success": true,
-        "blocking_enabled": enabled,
-        "total_blocked": procs.len(),
-        "blocked_processes": procs,
-        "threats_detected": threats
-    }))
+    Ok(runtime_blocking_status(None))
 }
+
+/// Signal the background workers to stop before the process exits
+fn stop_background_workers() {
+    quarantine_retention::stop_retention_scheduler();
+    outbound_spool::stop_spool_flusher();
+    agent_identity::stop_heartbeat();
+    remote_commands::stop_command_poller();
+    #[cfg(target_os = "linux")]
+    linux_net_monitor::stop_linux_net_monitor();
+}
+
 // ============================================================================
 // MAIN APPLICATION
 // ============================================================================
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    logging::init();
+
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_http::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .setup(|app| {
-            println!("🔧 Setup starting...");
+            info!("Setup starting...");
+            // Allow / deny prompts for suspended processes → desktop UI
+            let prompt_handle = app.handle().clone();
+            decision_prompt::set_notifier(Box::new(move |event, payload| {
+                let _ = prompt_handle.emit(event, payload);
+            }));
             process_monitor::start_monitor_loop();
+            #[cfg(windows)]
             etw_monitor::start_etw_monitor();
-            println!("✅ Real-time process monitor started");
+            #[cfg(target_os = "linux")]
+            linux_net_monitor::start_linux_net_monitor();
+            info!("Real-time process monitor started");
+            quarantine_retention::start_retention_scheduler();
+            outbound_spool::start_spool_flusher();
+            agent_identity::start_heartbeat();
+            remote_commands::start_command_poller();
+            std::thread::spawn(network_isolation::restore_isolation);
 
             // If protection was enabled but app started without admin → request UAC
             if !process_protection::ProcessProtection::check_admin_privileges()
                 && process_protection::load_protection_state()
             {
-                println!("🛡️ Protection was enabled - requesting UAC elevation...");
+                info!("Protection was enabled - requesting UAC elevation...");
                 let _ = process_protection::restart_as_admin();
             }
 
             // If running as admin → auto-initialize protection
             if process_protection::ProcessProtection::check_admin_privileges() {
-                println!("🛡️ Admin detected - auto-initializing protection...");
+                info!("Admin detected - auto-initializing protection...");
                 let _ = process_protection::init_protection();
                 let _ = process_protection::enable_max_protection();
-                println!("✅ Protection auto-enabled");
+                info!("Protection auto-enabled");
             }
 
             let dashboard_item =
@@ -614,7 +536,7 @@ pub fn run() {
                 &quit_item,
             ])?;
 
-            println!("✅ Tray menu created");
+            info!("Tray menu created");
 
             let app_devtools_item =
                 MenuItem::with_id(app, "toggle_devtools", "Toggle DevTools", true, None::<&str>)?;
@@ -624,31 +546,31 @@ pub fn run() {
             ])?;
             app.set_menu(app_menu)?;
 
-            println!("✅ App menu set");
+            info!("App menu set");
 
             let _tray = TrayIconBuilder::new()
                 .menu(&tray_menu)
                 .tooltip("CyberGuardian XDR")
                 .on_tray_icon_event(|tray, event| {
-                    println!("🖱️ Tray icon event: {:?}", event);
+                    debug!("Tray icon event: {:?}", event);
 
                     match event {
                         TrayIconEvent::Click { button, button_state, .. } => {
-                            println!(
-                                "🖱️ Click detected - Button: {:?}, State: {:?}",
+                            debug!(
+                                "Click detected - Button: {:?}, State: {:?}",
                                 button, button_state
                             );
 
                             if button == MouseButton::Right
                                 && button_state == MouseButtonState::Down
                             {
-                                println!("🖱️ Right click detected - menu should show");
+                                debug!("Right click detected - menu should show");
                             }
 
                             if button == MouseButton::Left
                                 && button_state == MouseButtonState::Down
                             {
-                                println!("🖱️ Left click detected");
+                                debug!("Left click detected");
                                 if let Some(window) = tray.app_handle().get_webview_window("main") {
                                     let _ = window.show();
                                     let _ = window.set_focus();
@@ -659,45 +581,46 @@ pub fn run() {
                     }
                 })
                 .on_menu_event(|app, event| {
-                    println!("🖱️ Menu event: {}", event.id.as_ref());
+                    debug!("Menu event: {}", event.id.as_ref());
                     match event.id.as_ref() {
                         "dashboard" => {
-                            println!("📊 Opening dashboard...");
+                            info!("Opening dashboard...");
                             if let Some(window) = app.get_webview_window("main") {
                                 let _ = window.show();
                                 let _ = window.set_focus();
                             }
                         }
                         "settings" => {
-                            println!("⚙️ Opening settings...");
+                            info!("Opening settings...");
                             if let Some(window) = app.get_webview_window("main") {
                                 let _ = window.show();
                                 let _ = window.set_focus();
                             }
                         }
                         "toggle_devtools" => {
-                            println!("🛠️ Toggling DevTools...");
+                            info!("Toggling DevTools...");
                             if let Some(window) = app.get_webview_window("main") {
                                 window.open_devtools();
                             }
                         }
                         "quit" => {
-                            println!("🛑 Quitting...");
+                            info!("Quitting...");
+                            stop_background_workers();
                             std::process::exit(0);
                         }
                         _ => {
-                            println!("❓ Unknown menu item: {}", event.id.as_ref());
+                            info!("Unknown menu item: {}", event.id.as_ref());
                         }
                     }
                 })
                 .build(app)?;
 
-            println!("✅ Tray icon created");
+            info!("Tray icon created");
             Ok(())
         })
         .on_window_event(|window, event| {
             if let tauri::WindowEvent::CloseRequested { api, .. } = event {
-                println!("🔒 Window close requested - hiding instead");
+                info!("Window close requested - hiding instead");
                 window.hide().unwrap();
                 api.prevent_close();
             }
@@ -735,6 +658,85 @@ pub fn run() {
             deep_quarantine_analyze,
             deep_quarantine_remove,
             deep_quarantine_list_backups,
+            // Quarantine Retention
+            quarantine_retention::get_retention_policy,
+            quarantine_retention::set_retention_policy,
+            quarantine_retention::preview_retention_purge,
+            quarantine_retention::run_retention_purge,
+            quarantine_retention::get_purge_audit_log,
+            quarantine_retention::get_last_retention_report,
+            // Outbound Spool
+            outbound_spool::get_spool_status,
+            outbound_spool::flush_spool_now,
+            // Agent Config
+            agent_config::get_agent_config,
+            agent_config::set_agent_config,
+            // Agent Identity
+            agent_identity::get_agent_status,
+            // Remote Commands
+            remote_commands::get_command_history,
+            // Process Inventory
+            process_inventory::get_inventory_settings,
+            process_inventory::set_inventory_settings,
+            // Privacy Redaction
+            redaction::get_redaction_settings,
+            redaction::set_redaction_settings,
+            redaction::get_redaction_stats,
+            redaction::preview_redaction,
+            // SIEM Forwarding
+            siem_forwarder::get_siem_settings,
+            siem_forwarder::set_siem_settings,
+            siem_forwarder::get_siem_status,
+            siem_forwarder::send_siem_test_event,
+            // Telemetry Normalization
+            telemetry_schema::get_telemetry_settings,
+            telemetry_schema::set_telemetry_settings,
+            // STIX Export
+            stix_export::export_stix_bundle,
+            // Threat Intel
+            threat_intel::import_threat_intel,
+            threat_intel::list_intel_sources,
+            threat_intel::remove_intel_source,
+            threat_intel::prune_expired_intel,
+            threat_intel::check_indicator,
+            threat_intel::get_intel_stats,
+            // DGA Detection
+            dga_detector::get_dga_settings,
+            dga_detector::set_dga_settings,
+            dga_detector::score_domain,
+            // DNS Tunneling Detection
+            dns_tunnel_detector::get_dns_tunnel_settings,
+            dns_tunnel_detector::set_dns_tunnel_settings,
+            dns_tunnel_detector::get_dns_tunnel_detections,
+            // Beacon Detection
+            beacon_detector::get_beacon_settings,
+            beacon_detector::set_beacon_settings,
+            beacon_detector::get_beacon_detections,
+            // Listening Ports
+            listening_ports::get_listening_ports,
+            // Network Isolation
+            network_isolation::isolate_host,
+            network_isolation::unisolate_host,
+            network_isolation::get_isolation_status,
+            network_isolation::get_firewall_posture,
+            // Process Response
+            process_control::suspend_process,
+            process_control::resume_process,
+            process_control::kill_process_tree,
+            // Response Playbooks
+            response_playbooks::get_response_playbooks,
+            response_playbooks::set_response_playbooks,
+            response_playbooks::get_response_history,
+            // Decision Prompts
+            decision_prompt::get_pending_decisions,
+            decision_prompt::resolve_process_decision,
+            decision_prompt::get_prompt_settings,
+            decision_prompt::set_prompt_settings,
+            decision_prompt::list_process_exceptions,
+            decision_prompt::remove_process_exception,
+            // Logging
+            logging::get_logging_status,
+            logging::set_log_level,
             // Background Upload
             start_background_upload,
             // Backup Security Monitor
@@ -742,6 +744,7 @@ pub fn run() {
             vuln_scanner::scan_vulnerabilities,
             enable_runtime_blocking,
             disable_runtime_blocking,
+            set_runtime_blocking_mode,
             get_runtime_blocking_status,
         ])
         .run(tauri::generate_context!())
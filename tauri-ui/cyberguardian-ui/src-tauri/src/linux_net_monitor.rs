@@ -0,0 +1,332 @@
+//! Linux Network Connection Monitor
+//! Polls /proc/net/{tcp,tcp6,udp,udp6}, maps socket inodes to processes via
+//! /proc/<pid>/fd, and feeds new outbound connections and listeners into the
+//! shared `network_events` detection path. Plain procfs parsing — no netlink
+//! dependency — so it also works in containers without CAP_NET_ADMIN
+
+use crate::network_events::{self, ConnectionDirection, ConnectionEvent};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tracing::info;
+
+static MONITOR_RUNNING: AtomicBool = AtomicBool::new(false);
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+const TCP_ESTABLISHED: u8 = 0x01;
+const TCP_SYN_SENT: u8 = 0x02;
+const TCP_LISTEN: u8 = 0x0A;
+/// UDP sockets report TCP_CLOSE (7) when unconnected
+const UDP_UNCONNECTED: u8 = 0x07;
+
+// ============================================================================
+// /proc/net PARSING
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Protocol {
+    Tcp,
+    Udp,
+}
+
+impl Protocol {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Protocol::Tcp => "tcp",
+            Protocol::Udp => "udp",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SocketEntry {
+    pub protocol: Protocol,
+    pub local_ip: IpAddr,
+    pub local_port: u16,
+    pub remote_ip: IpAddr,
+    pub remote_port: u16,
+    pub state: u8,
+    pub uid: u32,
+    pub inode: u64,
+}
+
+impl SocketEntry {
+    pub fn is_listening(&self) -> bool {
+        match self.protocol {
+            Protocol::Tcp => self.state == TCP_LISTEN,
+            Protocol::Udp => self.state == UDP_UNCONNECTED && self.remote_port == 0,
+        }
+    }
+
+    pub fn is_outbound_candidate(&self) -> bool {
+        if self.remote_ip.is_unspecified() || self.remote_port == 0 {
+            return false;
+        }
+        match self.protocol {
+            Protocol::Tcp => self.state == TCP_ESTABLISHED || self.state == TCP_SYN_SENT,
+            Protocol::Udp => true,
+        }
+    }
+}
+
+/// "0100007F:0035" → 127.0.0.1:53; IPv6 is four little-endian 32-bit words
+pub fn parse_address(field: &str) -> Option<(IpAddr, u16)> {
+    let (addr, port) = field.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    let ip = match addr.len() {
+        8 => IpAddr::V4(Ipv4Addr::from(u32::from_str_radix(addr, 16).ok()?.swap_bytes())),
+        32 => {
+            let mut bytes = [0u8; 16];
+            for (i, word) in bytes.chunks_exact_mut(4).enumerate() {
+                let w = u32::from_str_radix(&addr[i * 8..i * 8 + 8], 16).ok()?;
+                word.copy_from_slice(&w.to_le_bytes());
+            }
+            IpAddr::V6(Ipv6Addr::from(bytes))
+        }
+        _ => return None,
+    };
+    Some((ip, port))
+}
+
+/// Parse one /proc/net/{tcp,udp}[6] table (header line included)
+pub fn parse_proc_net(content: &str, protocol: Protocol) -> Vec<SocketEntry> {
+    content.lines().skip(1).filter_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 10 {
+            return None;
+        }
+        let (local_ip, local_port) = parse_address(fields[1])?;
+        let (remote_ip, remote_port) = parse_address(fields[2])?;
+        Some(SocketEntry {
+            protocol,
+            local_ip,
+            local_port,
+            remote_ip,
+            remote_port,
+            state: u8::from_str_radix(fields[3], 16).ok()?,
+            uid: fields[7].parse().ok()?,
+            inode: fields[9].parse().ok()?,
+        })
+    }).collect()
+}
+
+pub fn read_socket_table() -> Vec<SocketEntry> {
+    [("tcp", Protocol::Tcp), ("tcp6", Protocol::Tcp), ("udp", Protocol::Udp), ("udp6", Protocol::Udp)]
+        .iter()
+        .flat_map(|(file, protocol)| {
+            fs::read_to_string(format!("/proc/net/{}", file))
+                .map(|c| parse_proc_net(&c, *protocol))
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+// ============================================================================
+// INODE → PID
+// ============================================================================
+
+/// "socket:[12345]" → 12345
+pub fn parse_socket_link(link: &str) -> Option<u64> {
+    link.strip_prefix("socket:[")?.strip_suffix(']')?.parse().ok()
+}
+
+/// Walk /proc/<pid>/fd for socket links. Processes we cannot read (other users
+/// without root) are skipped
+pub fn socket_owners() -> HashMap<u64, u32> {
+    let mut owners = HashMap::new();
+    let Ok(procs) = fs::read_dir("/proc") else { return owners };
+    for entry in procs.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|n| n.parse::<u32>().ok()) else { continue };
+        let Ok(fds) = fs::read_dir(entry.path().join("fd")) else { continue };
+        for fd in fds.flatten() {
+            if let Some(inode) = fs::read_link(fd.path()).ok()
+                .and_then(|l| l.to_str().and_then(parse_socket_link))
+            {
+                owners.entry(inode).or_insert(pid);
+            }
+        }
+    }
+    owners
+}
+
+pub fn process_name(pid: u32) -> String {
+    fs::read_to_string(format!("/proc/{}/comm", pid))
+        .map(|s| s.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Field 4 of /proc/<pid>/stat; the command name may contain spaces and ')'
+pub fn parse_stat_ppid(stat: &str) -> Option<u32> {
+    stat.rsplit_once(')')?.1.split_whitespace().nth(1)?.parse().ok()
+}
+
+fn parent_name(pid: u32) -> String {
+    fs::read_to_string(format!("/proc/{}/stat", pid))
+        .ok()
+        .and_then(|s| parse_stat_ppid(&s))
+        .map(process_name)
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+// ============================================================================
+// POLLER
+// ============================================================================
+
+/// Socket identity without state, so SYN_SENT → ESTABLISHED is not a new connection
+type SocketKey = (Protocol, IpAddr, u16, IpAddr, u16, u64);
+
+fn socket_key(s: &SocketEntry) -> SocketKey {
+    (s.protocol, s.local_ip, s.local_port, s.remote_ip, s.remote_port, s.inode)
+}
+
+/// Sockets seen on the previous poll; only new ones produce events
+#[derive(Default)]
+pub struct SocketDiff {
+    seen: HashSet<SocketKey>,
+    primed: bool,
+}
+
+impl SocketDiff {
+    /// New sockets since the last call. The first call only records the baseline,
+    /// except for listeners which are always reported once
+    pub fn update(&mut self, current: Vec<SocketEntry>) -> Vec<(SocketEntry, ConnectionDirection)> {
+        let listening_ports: HashSet<(Protocol, u16)> = current.iter()
+            .filter(|s| s.is_listening())
+            .map(|s| (s.protocol, s.local_port))
+            .collect();
+
+        let mut events = Vec::new();
+        for socket in &current {
+            if self.seen.contains(&socket_key(socket)) {
+                continue;
+            }
+            if socket.is_listening() {
+                events.push((socket.clone(), ConnectionDirection::Listen));
+            } else if self.primed
+                && socket.is_outbound_candidate()
+                // Accepted inbound connections share the listener's local port
+                && !listening_ports.contains(&(socket.protocol, socket.local_port))
+            {
+                events.push((socket.clone(), ConnectionDirection::Outbound));
+            }
+        }
+        self.seen = current.iter().map(socket_key).collect();
+        self.primed = true;
+        events
+    }
+}
+
+fn poll_once(diff: &mut SocketDiff) {
+    let events = diff.update(read_socket_table());
+    if events.is_empty() {
+        return;
+    }
+    let owners = socket_owners();
+    for (socket, direction) in events {
+        // Inode 0 = TIME_WAIT / orphaned; no owner to attribute to
+        let Some(&pid) = owners.get(&socket.inode) else { continue };
+        let event = ConnectionEvent {
+            pid,
+            process_name: process_name(pid),
+            protocol: socket.protocol.as_str(),
+            direction,
+            local_port: Some(socket.local_port),
+            remote_ip: (direction == ConnectionDirection::Outbound).then_some(socket.remote_ip),
+            remote_port: (direction == ConnectionDirection::Outbound).then_some(socket.remote_port),
+            source: "procfs",
+        };
+        network_events::handle_connection(&event, parent_name);
+    }
+}
+
+pub fn start_linux_net_monitor() {
+    if MONITOR_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    std::thread::spawn(|| {
+        info!("Linux network monitor started (/proc/net, {}s poll)", POLL_INTERVAL.as_secs());
+        let mut diff = SocketDiff::default();
+        while MONITOR_RUNNING.load(Ordering::SeqCst) {
+            poll_once(&mut diff);
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
+
+pub fn is_monitor_running() -> bool {
+    MONITOR_RUNNING.load(Ordering::SeqCst)
+}
+
+pub fn stop_linux_net_monitor() {
+    MONITOR_RUNNING.store(false, Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TCP: &str = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:0CEA 00000000:0000 0A 00000000:00000000 00:00000000 00000000   998        0 31337 1 0000000000000000 100 0 0 10 0
+   1: 0F02000A:A1B2 5DB8D822:01BB 01 00000000:00000000 02:000A7B2D 00000000  1000        0 44221 2 0000000000000000 20 4 30 10 -1
+   2: 0F02000A:0CEA 6400A8C0:D431 01 00000000:00000000 02:000A7B2D 00000000   998        0 44300 2 0000000000000000 20 4 30 10 -1";
+
+    const TCP6: &str = "  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000000000000000000001000000:1F90 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 5555 1 0000000000000000 100 0 0 10 0
+   1: B80D0120000000000000000001000000:C350 6048012000000000000000006688000:01BB 01 00000000:00000000 00:00000000 00000000     0        0 6666 1 0000000000000000 100 0 0 10 0";
+
+    #[test]
+    fn test_parse_proc_net_v4_v6() {
+        let v4 = parse_proc_net(TCP, Protocol::Tcp);
+        assert_eq!(v4.len(), 3);
+        assert_eq!(v4[0].local_ip, "127.0.0.1".parse::<IpAddr>().unwrap());
+        assert_eq!(v4[0].local_port, 3306);
+        assert!(v4[0].is_listening());
+        assert_eq!(v4[1].remote_ip, "34.216.184.93".parse::<IpAddr>().unwrap());
+        assert_eq!(v4[1].remote_port, 443);
+        assert_eq!((v4[1].uid, v4[1].inode), (1000, 44221));
+        assert!(v4[1].is_outbound_candidate());
+
+        let v6 = parse_proc_net(TCP6, Protocol::Tcp);
+        assert_eq!(v6[0].local_ip, "::1".parse::<IpAddr>().unwrap());
+        assert_eq!(v6[0].local_port, 8080);
+        // Malformed remote (31 hex chars) is skipped
+        assert_eq!(v6.len(), 1);
+        assert_eq!(parse_address("B80D0120000000000000000001000000:0050"),
+            Some(("2001:db8::1".parse().unwrap(), 80)));
+    }
+
+    #[test]
+    fn test_socket_diff_reports_new_outbound_and_listeners() {
+        let sockets = parse_proc_net(TCP, Protocol::Tcp);
+        let mut diff = SocketDiff::default();
+
+        // Baseline: only the listener is reported
+        let first = diff.update(vec![sockets[0].clone()]);
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].1, ConnectionDirection::Listen);
+
+        // New outbound + an accepted inbound on the listening port (ignored)
+        let events = diff.update(sockets.clone());
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].0.inode, 44221);
+        assert_eq!(events[0].1, ConnectionDirection::Outbound);
+
+        assert!(diff.update(sockets.clone()).is_empty());
+
+        // State change on a known socket is not a new connection
+        let mut handshake_done = sockets;
+        handshake_done[1].state = TCP_SYN_SENT;
+        assert!(diff.update(handshake_done).is_empty());
+    }
+
+    #[test]
+    fn test_procfs_helpers() {
+        assert_eq!(parse_socket_link("socket:[44221]"), Some(44221));
+        assert_eq!(parse_socket_link("pipe:[1]"), None);
+        assert_eq!(parse_stat_ppid("1234 (my (weird) proc) S 987 1234 1234 0 -1"), Some(987));
+    }
+}
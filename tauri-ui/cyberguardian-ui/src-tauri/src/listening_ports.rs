@@ -0,0 +1,444 @@
+//! Listening Ports Inventory
+//! Lists listening sockets with owning process, bind address and protocol,
+//! flags risky exposures (remote admin on all interfaces, database ports,
+//! listeners running from user-writable paths) and diffs against the last
+//! snapshot. Windows: IP Helper owner-PID tables (locale-independent, unlike
+//! netstat output); Linux: /proc/net via linux_net_monitor
+
+use crate::network_events::classify_ip;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use tracing::info;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Exposure {
+    pub rule: String,
+    /// "medium" | "high" | "critical"
+    pub severity: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Listener {
+    /// "tcp" | "udp"
+    pub protocol: String,
+    pub bind_address: String,
+    pub port: u16,
+    pub pid: u32,
+    pub process_name: String,
+    pub exe_path: String,
+    /// Bound to 0.0.0.0 / ::
+    pub all_interfaces: bool,
+    pub address_class: String,
+    #[serde(default)]
+    pub exposures: Vec<Exposure>,
+}
+
+impl Listener {
+    /// Identity across restarts (PIDs change, the service does not)
+    fn key(&self) -> String {
+        format!("{}/{}:{}/{}", self.protocol, self.bind_address, self.port, self.process_name.to_lowercase())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ListenerChanges {
+    pub added: Vec<Listener>,
+    pub removed: Vec<Listener>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ListeningReport {
+    pub generated_at: String,
+    pub listeners: Vec<Listener>,
+    pub exposure_count: usize,
+    /// None on the first run (no previous snapshot)
+    pub changes: Option<ListenerChanges>,
+    pub previous_snapshot_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Snapshot {
+    taken_at: String,
+    listeners: Vec<Listener>,
+}
+
+/// Raw socket before process attribution
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawListener {
+    pub protocol: &'static str,
+    pub ip: IpAddr,
+    pub port: u16,
+    pub pid: u32,
+}
+
+/// pid → (process name, executable path)
+pub type ProcessMap = HashMap<u32, (String, String)>;
+
+// ============================================================================
+// RISK RULES
+// ============================================================================
+
+/// Remote administration / file sharing — should not face every interface
+const REMOTE_ADMIN_PORTS: &[(u16, &str, &str)] = &[
+    (22, "SSH", "high"),
+    (23, "Telnet", "critical"),
+    (135, "RPC endpoint mapper", "high"),
+    (139, "NetBIOS/SMB", "high"),
+    (445, "SMB", "high"),
+    (3389, "RDP", "high"),
+    (5900, "VNC", "high"),
+    (5985, "WinRM (HTTP)", "high"),
+    (5986, "WinRM (HTTPS)", "high"),
+];
+
+const DATABASE_PORTS: &[(u16, &str)] = &[
+    (1433, "Microsoft SQL Server"),
+    (1521, "Oracle"),
+    (3306, "MySQL/MariaDB"),
+    (5432, "PostgreSQL"),
+    (6379, "Redis"),
+    (9200, "Elasticsearch"),
+    (11211, "Memcached"),
+    (27017, "MongoDB"),
+];
+
+const USER_WRITABLE_MARKERS: &[&str] = &[
+    "\\appdata\\", "\\temp\\", "\\downloads\\", "\\users\\public\\", "\\programdata\\",
+    "/tmp/", "/var/tmp/", "/dev/shm/", "/home/",
+];
+
+pub fn is_user_writable_path(path: &str) -> bool {
+    let lower = path.to_lowercase();
+    USER_WRITABLE_MARKERS.iter().any(|m| lower.contains(m))
+}
+
+pub fn assess(listener: &Listener) -> Vec<Exposure> {
+    let mut exposures = Vec::new();
+    let loopback_only = listener.address_class == "loopback";
+
+    if let Some((_, service, severity)) = REMOTE_ADMIN_PORTS.iter().find(|(p, _, _)| *p == listener.port) {
+        if listener.all_interfaces {
+            exposures.push(Exposure {
+                rule: "remote_admin_all_interfaces".to_string(),
+                severity: severity.to_string(),
+                reason: format!("{} listening on all interfaces ({}:{})", service, listener.bind_address, listener.port),
+            });
+        }
+    }
+
+    if let Some((_, db)) = DATABASE_PORTS.iter().find(|(p, _)| *p == listener.port) {
+        if !loopback_only {
+            exposures.push(Exposure {
+                rule: "database_exposed".to_string(),
+                severity: if listener.all_interfaces { "high" } else { "medium" }.to_string(),
+                reason: format!("{} port reachable beyond localhost ({}:{})", db, listener.bind_address, listener.port),
+            });
+        }
+    }
+
+    if !listener.exe_path.is_empty() && is_user_writable_path(&listener.exe_path) && !loopback_only {
+        exposures.push(Exposure {
+            rule: "listener_from_user_writable_path".to_string(),
+            severity: "high".to_string(),
+            reason: format!("{} listens on {} from user-writable path {}", listener.process_name, listener.port, listener.exe_path),
+        });
+    }
+    exposures
+}
+
+// ============================================================================
+// COLLECTION
+// ============================================================================
+
+/// Ports in the IP Helper tables are in network byte order in the low 16 bits
+#[cfg(any(test, windows))]
+fn mib_port(port: u32) -> u16 {
+    u16::from_be(port as u16)
+}
+
+/// IPv4 addresses in the IP Helper tables are stored in network byte order
+#[cfg(any(test, windows))]
+fn mib_ipv4(addr: u32) -> IpAddr {
+    IpAddr::from(addr.to_ne_bytes())
+}
+
+/// Fetch one IP Helper table, growing the buffer until it fits.
+/// `u32` storage keeps the rows 4-byte aligned
+#[cfg(windows)]
+fn fetch_table(name: &str, fetch: impl Fn(Option<*mut std::ffi::c_void>, *mut u32) -> u32) -> Result<Vec<u32>, String> {
+    use windows::Win32::Foundation::{ERROR_INSUFFICIENT_BUFFER, NO_ERROR};
+
+    let mut size = 0u32;
+    let mut buf: Vec<u32> = Vec::new();
+    // The table can grow between the size query and the read — retry a few times
+    for _ in 0..4 {
+        let ptr = (!buf.is_empty()).then(|| buf.as_mut_ptr() as *mut std::ffi::c_void);
+        match fetch(ptr, &mut size) {
+            r if r == NO_ERROR.0 && !buf.is_empty() => return Ok(buf),
+            r if r == NO_ERROR.0 || r == ERROR_INSUFFICIENT_BUFFER.0 => {
+                buf = vec![0u32; (size as usize).div_ceil(4) + 1];
+            }
+            r => return Err(format!("{} failed: error {}", name, r)),
+        }
+    }
+    Err(format!("{} failed: table kept growing", name))
+}
+
+/// Rows of a MIB_*TABLE_OWNER_PID buffer: `dwNumEntries` followed by the rows
+#[cfg(windows)]
+fn table_rows<Row: Copy>(buf: &[u32]) -> Vec<Row> {
+    let count = buf.first().copied().unwrap_or(0) as usize;
+    let capacity = (buf.len().saturating_sub(1) * 4) / std::mem::size_of::<Row>();
+    // SAFETY: rows start right after the 4-byte count and are 4-byte aligned; count is
+    // clamped to what the buffer holds
+    unsafe { std::slice::from_raw_parts(buf.as_ptr().add(1) as *const Row, count.min(capacity)).to_vec() }
+}
+
+/// TCP listeners and bound UDP sockets from GetExtendedTcpTable / GetExtendedUdpTable
+#[cfg(windows)]
+fn read_ip_helper_tables() -> Result<Vec<RawListener>, String> {
+    use windows::Win32::Foundation::BOOL;
+    use windows::Win32::NetworkManagement::IpHelper::{
+        GetExtendedTcpTable, GetExtendedUdpTable, MIB_TCP6ROW_OWNER_PID, MIB_TCPROW_OWNER_PID,
+        MIB_UDP6ROW_OWNER_PID, MIB_UDPROW_OWNER_PID, TCP_TABLE_OWNER_PID_LISTENER, UDP_TABLE_OWNER_PID,
+    };
+    use windows::Win32::Networking::WinSock::{AF_INET, AF_INET6};
+
+    let tcp = |af: u32| fetch_table("GetExtendedTcpTable", |ptr, size| unsafe {
+        GetExtendedTcpTable(ptr, size, BOOL(0), af, TCP_TABLE_OWNER_PID_LISTENER, 0)
+    });
+    let udp = |af: u32| fetch_table("GetExtendedUdpTable", |ptr, size| unsafe {
+        GetExtendedUdpTable(ptr, size, BOOL(0), af, UDP_TABLE_OWNER_PID, 0)
+    });
+
+    let mut raw = Vec::new();
+    for row in table_rows::<MIB_TCPROW_OWNER_PID>(&tcp(AF_INET.0 as u32)?) {
+        raw.push(RawListener { protocol: "tcp", ip: mib_ipv4(row.dwLocalAddr), port: mib_port(row.dwLocalPort), pid: row.dwOwningPid });
+    }
+    for row in table_rows::<MIB_TCP6ROW_OWNER_PID>(&tcp(AF_INET6.0 as u32)?) {
+        raw.push(RawListener { protocol: "tcp", ip: IpAddr::from(row.ucLocalAddr), port: mib_port(row.dwLocalPort), pid: row.dwOwningPid });
+    }
+    for row in table_rows::<MIB_UDPROW_OWNER_PID>(&udp(AF_INET.0 as u32)?) {
+        raw.push(RawListener { protocol: "udp", ip: mib_ipv4(row.dwLocalAddr), port: mib_port(row.dwLocalPort), pid: row.dwOwningPid });
+    }
+    for row in table_rows::<MIB_UDP6ROW_OWNER_PID>(&udp(AF_INET6.0 as u32)?) {
+        raw.push(RawListener { protocol: "udp", ip: IpAddr::from(row.ucLocalAddr), port: mib_port(row.dwLocalPort), pid: row.dwOwningPid });
+    }
+    Ok(raw)
+}
+
+#[cfg(windows)]
+fn collect_raw() -> Result<(Vec<RawListener>, ProcessMap), String> {
+    let raw = read_ip_helper_tables()?;
+
+    let processes = crate::process_monitor::enumerate_processes()?
+        .into_iter()
+        .map(|p| (p.pid, (p.name, p.exe_path)))
+        .collect();
+    Ok((raw, processes))
+}
+
+#[cfg(target_os = "linux")]
+fn collect_raw() -> Result<(Vec<RawListener>, ProcessMap), String> {
+    use crate::linux_net_monitor as net;
+
+    let owners = net::socket_owners();
+    let raw: Vec<RawListener> = net::read_socket_table().into_iter()
+        .filter(|s| s.is_listening())
+        .map(|s| RawListener {
+            protocol: s.protocol.as_str(),
+            ip: s.local_ip,
+            port: s.local_port,
+            pid: owners.get(&s.inode).copied().unwrap_or(0),
+        })
+        .collect();
+
+    let processes = raw.iter()
+        .map(|r| r.pid)
+        .filter(|pid| *pid != 0)
+        .map(|pid| {
+            let exe = fs::read_link(format!("/proc/{}/exe", pid))
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default();
+            (pid, (net::process_name(pid), exe))
+        })
+        .collect();
+    Ok((raw, processes))
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn collect_raw() -> Result<(Vec<RawListener>, ProcessMap), String> {
+    Err("Listening port inventory is not supported on this platform".to_string())
+}
+
+pub fn build_listeners(raw: Vec<RawListener>, processes: &ProcessMap) -> Vec<Listener> {
+    let mut listeners: Vec<Listener> = raw.into_iter().map(|r| {
+        let (process_name, exe_path) = processes.get(&r.pid).cloned()
+            .unwrap_or_else(|| (if r.pid == 4 { "System".to_string() } else { "unknown".to_string() }, String::new()));
+        let mut listener = Listener {
+            protocol: r.protocol.to_string(),
+            bind_address: r.ip.to_string(),
+            port: r.port,
+            pid: r.pid,
+            process_name,
+            exe_path,
+            all_interfaces: r.ip.is_unspecified(),
+            address_class: classify_ip(&r.ip).as_str().to_string(),
+            exposures: Vec::new(),
+        };
+        listener.exposures = assess(&listener);
+        listener
+    }).collect();
+
+    listeners.sort_by(|a, b| (a.port, &a.protocol, &a.bind_address).cmp(&(b.port, &b.protocol, &b.bind_address)));
+    listeners.dedup_by(|a, b| a.key() == b.key());
+    listeners
+}
+
+pub fn diff(previous: &[Listener], current: &[Listener]) -> ListenerChanges {
+    let before: BTreeSet<String> = previous.iter().map(Listener::key).collect();
+    let after: BTreeSet<String> = current.iter().map(Listener::key).collect();
+    ListenerChanges {
+        added: current.iter().filter(|l| !before.contains(&l.key())).cloned().collect(),
+        removed: previous.iter().filter(|l| !after.contains(&l.key())).cloned().collect(),
+    }
+}
+
+// ============================================================================
+// SNAPSHOT
+// ============================================================================
+
+fn get_snapshot_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("CyberGuardian")
+        .join("listening_ports_snapshot.json")
+}
+
+fn load_snapshot() -> Option<Snapshot> {
+    fs::read_to_string(get_snapshot_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+}
+
+fn save_snapshot(snapshot: &Snapshot) -> Result<(), String> {
+    let path = get_snapshot_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create snapshot dir: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(snapshot)
+        .map_err(|e| format!("Failed to serialize snapshot: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write snapshot: {}", e))
+}
+
+// ============================================================================
+// TAURI COMMANDS
+// ============================================================================
+
+/// Current listeners with exposures and changes since the last call (snapshot is updated)
+#[tauri::command]
+pub fn get_listening_ports() -> Result<ListeningReport, String> {
+    let (raw, processes) = collect_raw()?;
+    let listeners = build_listeners(raw, &processes);
+    let previous = load_snapshot();
+    let now = chrono::Utc::now().to_rfc3339();
+
+    let report = ListeningReport {
+        generated_at: now.clone(),
+        exposure_count: listeners.iter().map(|l| l.exposures.len()).sum(),
+        changes: previous.as_ref().map(|p| diff(&p.listeners, &listeners)),
+        previous_snapshot_at: previous.map(|p| p.taken_at),
+        listeners: listeners.clone(),
+    };
+    save_snapshot(&Snapshot { taken_at: now, listeners })?;
+
+    if let Some(changes) = &report.changes {
+        if !changes.added.is_empty() || !changes.removed.is_empty() {
+            info!("Listening ports changed: +{} / -{}", changes.added.len(), changes.removed.len());
+        }
+    }
+    info!("{} listeners, {} exposures", report.listeners.len(), report.exposure_count);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw() -> Vec<RawListener> {
+        [
+            ("tcp", "0.0.0.0", 135, 1012),
+            ("tcp", "0.0.0.0", 3389, 1288),
+            ("tcp", "127.0.0.1", 5432, 4410),
+            ("tcp", "192.168.1.20", 3306, 4520),
+            ("tcp", "0.0.0.0", 8443, 7777),
+            ("tcp", "::", 445, 4),
+            ("udp", "0.0.0.0", 5353, 2200),
+            ("udp", "fe80::1", 546, 2300),
+        ].into_iter().map(|(protocol, ip, port, pid)| RawListener { protocol, ip: ip.parse().unwrap(), port, pid }).collect()
+    }
+
+    fn processes() -> ProcessMap {
+        [
+            (1012, ("svchost.exe", r"C:\Windows\System32\svchost.exe")),
+            (1288, ("svchost.exe", r"C:\Windows\System32\svchost.exe")),
+            (4410, ("postgres.exe", r"C:\Program Files\PostgreSQL\16\bin\postgres.exe")),
+            (4520, ("mysqld.exe", r"C:\Program Files\MySQL\bin\mysqld.exe")),
+            (7777, ("updater.exe", r"C:\Users\bob\AppData\Local\Temp\updater.exe")),
+        ].into_iter().map(|(pid, (n, p))| (pid, (n.to_string(), p.to_string()))).collect()
+    }
+
+    #[test]
+    fn test_ip_helper_decoding_and_exposures() {
+        // 3389 as stored by the IP Helper tables (network byte order)
+        assert_eq!(mib_port(u16::from_ne_bytes([0x0d, 0x3d]) as u32), 3389);
+        assert_eq!(mib_ipv4(u32::from_ne_bytes([192, 168, 1, 20])), "192.168.1.20".parse::<IpAddr>().unwrap());
+
+        let listeners = build_listeners(raw(), &processes());
+        let rules = |port: u16| -> Vec<String> {
+            listeners.iter().filter(|l| l.port == port).flat_map(|l| l.exposures.iter().map(|e| e.rule.clone())).collect()
+        };
+        assert_eq!(rules(3389), vec!["remote_admin_all_interfaces"]);
+        assert_eq!(rules(445), vec!["remote_admin_all_interfaces"]);
+        assert!(rules(5432).is_empty(), "loopback database is fine");
+        assert_eq!(rules(3306), vec!["database_exposed"]);
+        assert_eq!(rules(8443), vec!["listener_from_user_writable_path"]);
+        assert!(rules(5353).is_empty());
+
+        let smb = listeners.iter().find(|l| l.port == 445).unwrap();
+        assert_eq!((smb.process_name.as_str(), smb.bind_address.as_str()), ("System", "::"));
+        let mysql = listeners.iter().find(|l| l.port == 3306).unwrap();
+        assert_eq!((mysql.exposures[0].severity.as_str(), mysql.address_class.as_str()), ("medium", "private"));
+    }
+
+    #[test]
+    fn test_diff_ignores_pid_changes() {
+        let before = build_listeners(raw(), &processes());
+        let mut after = before.clone();
+        after.iter_mut().for_each(|l| l.pid += 1);
+        after.retain(|l| l.port != 3389);
+        after.push(Listener {
+            protocol: "tcp".into(),
+            bind_address: "0.0.0.0".into(),
+            port: 5985,
+            pid: 640,
+            process_name: "svchost.exe".into(),
+            exe_path: String::new(),
+            all_interfaces: true,
+            address_class: "unspecified".into(),
+            exposures: Vec::new(),
+        });
+
+        let changes = diff(&before, &after);
+        assert_eq!(changes.added.iter().map(|l| l.port).collect::<Vec<_>>(), vec![5985]);
+        assert_eq!(changes.removed.iter().map(|l| l.port).collect::<Vec<_>>(), vec![3389]);
+    }
+}
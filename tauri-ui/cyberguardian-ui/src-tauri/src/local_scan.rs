@@ -0,0 +1,182 @@
+//! Local Scan
+//! Filesystem scan by profile (quick / standard / deep) with the summary
+//! reported to scan history (spooled when the backend is unreachable)
+
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+use tracing::{error, info, warn};
+
+use crate::backend_client::{BackendClient, ReqwestBackendClient};
+use crate::outbound_spool::{self, SpoolEndpoint};
+
+/// Run a local scan and report it (blocking — call from a worker thread)
+pub fn run_local_scan(profile: &str) -> Result<serde_json::Value, String> {
+    info!("Starting LOCAL {} scan on Windows", profile);
+    
+    let start_time = Instant::now();
+    let mut files_scanned = 0;
+    let mut threats_found = 0;
+    
+    let (max_files, scan_paths, extensions, recursive): (usize, Vec<&str>, Vec<&str>, bool) = match profile {
+        "quick" => (
+            100,
+            vec![
+                r"C:\Users\admin\Downloads",
+                r"C:\Users\admin\AppData\Local\Temp",
+                r"C:\Windows\Temp",
+            ],
+            vec![".exe", ".dll", ".bat", ".ps1", ".cmd", ".vbs", ".js"],
+            false
+        ),
+        "standard" => (
+            1000,
+            vec![
+                r"C:\Users\admin\Downloads",
+                r"C:\Users\admin\Documents",
+                r"C:\Users\admin\Desktop",
+                r"C:\Users\admin\AppData",
+            ],
+            vec![".exe", ".dll", ".bat", ".ps1", ".zip", ".rar", ".7z", ".jar"],
+            true
+        ),
+        "deep" => (
+            10000,
+            vec![r"C:\"],
+            vec!["*"],
+            true
+        ),
+        _ => (100, vec![r"C:\Users\admin\Downloads"], vec![".exe", ".dll"], false),
+    };
+    
+    info!("Scanning {} paths (max {} files)", scan_paths.len(), max_files);
+    
+    for scan_path in &scan_paths {
+        if files_scanned >= max_files {
+            break;
+        }
+        
+        let path = Path::new(scan_path);
+        if !path.exists() {
+            warn!("Path does not exist: {}", scan_path);
+            continue;
+        }
+        
+        info!("Scanning: {}", scan_path);
+        
+        fn scan_directory(
+            path: &Path,
+            extensions: &Vec<&str>,
+            recursive: bool,
+            files_scanned: &mut usize,
+            threats_found: &mut usize,
+            max_files: usize,
+        ) {
+            if *files_scanned >= max_files {
+                return;
+            }
+            
+            if let Ok(entries) = fs::read_dir(path) {
+                for entry in entries {
+                    if *files_scanned >= max_files {
+                        break;
+                    }
+                    
+                    if let Ok(entry) = entry {
+                        let file_path = entry.path();
+                        
+                        if file_path.is_file() {
+                            let should_scan = extensions.contains(&"*") || 
+                                file_path.extension()
+                                    .and_then(|ext| ext.to_str())
+                                    .map(|ext| {
+                                        let ext_with_dot = format!(".{}", ext);
+                                        extensions.contains(&ext_with_dot.as_str())
+                                    })
+                                    .unwrap_or(false);
+                            
+                            if should_scan {
+                                *files_scanned += 1;
+                                
+                                if let Some(file_name) = file_path.file_name() {
+                                    let name = file_name.to_string_lossy().to_lowercase();
+                                    if name.contains("virus") || name.contains("malware") || 
+                                       name.contains("trojan") || name.contains("hack") ||
+                                       name.contains("ransom") || name.contains("keylog") {
+                                        *threats_found += 1;
+                                        warn!("Potential threat: {:?}", file_path);
+                                    }
+                                }
+                                
+                                if *files_scanned % 100 == 0 {
+                                    info!("Progress: {} files scanned", files_scanned);
+                                }
+                            }
+                        } else if file_path.is_dir() && recursive {
+                            scan_directory(&file_path, extensions, recursive, files_scanned, threats_found, max_files);
+                        }
+                    }
+                }
+            }
+        }
+        
+        scan_directory(path, &extensions, recursive, &mut files_scanned, &mut threats_found, max_files);
+    }
+    
+    let duration = start_time.elapsed().as_secs();
+    
+    info!("Scan completed: {} files, {} threats, {}s", 
+             files_scanned, threats_found, duration);
+    
+    let target_path = scan_paths.join("; ");
+    
+    let history_data = serde_json::json!({
+        "schedule_id": null,
+        "scan_type": profile,
+        "target_path": target_path,
+        "started_at": chrono::Utc::now().to_rfc3339(),
+        "status": "completed",
+        "files_scanned": files_scanned,
+        "threats_found": threats_found,
+        "duration_seconds": duration,
+    });
+    
+    let scan_result = serde_json::json!({
+        "success": true,
+        "files_scanned": files_scanned,
+        "threats_found": threats_found,
+        "duration": duration
+    });
+    
+    let queued_result = || {
+        let mut result = scan_result.clone();
+        result["results_queued"] = serde_json::json!(true);
+        result
+    };
+    
+    // Keep history order behind results that are still waiting offline
+    if outbound_spool::has_pending(SpoolEndpoint::ScanHistory) {
+        outbound_spool::enqueue(SpoolEndpoint::ScanHistory, history_data)?;
+        info!("Scan results queued behind pending uploads");
+        return Ok(queued_result());
+    }
+    
+    let result = ReqwestBackendClient::from_config()
+        .and_then(|client| client.post_scan_history(&history_data));
+    
+    match result {
+        Ok(()) => {
+            info!("Results sent to backend");
+            Ok(scan_result)
+        }
+        Err(e) if e.is_retryable() => {
+            error!("Failed to send results: {} (queued for retry)", e);
+            outbound_spool::enqueue(SpoolEndpoint::ScanHistory, history_data)?;
+            Ok(queued_result())
+        }
+        Err(e) => {
+            warn!("Backend rejected scan results: {}", e);
+            Ok(scan_result)
+        }
+    }
+}
@@ -0,0 +1,334 @@
+//! Network Connection Events
+//! Platform-neutral detection path for socket activity. ETW (Windows) and the
+//! /proc/net poller (Linux) both hand their connect/accept/listen events to
+//! `handle_connection`: telemetry, address classification, threat-intel match, beaconing
+
+use std::net::IpAddr;
+use tracing::warn;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionDirection {
+    Outbound,
+    Inbound,
+    Listen,
+}
+
+impl ConnectionDirection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConnectionDirection::Outbound => "outbound",
+            ConnectionDirection::Inbound => "inbound",
+            ConnectionDirection::Listen => "listen",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConnectionEvent {
+    pub pid: u32,
+    pub process_name: String,
+    /// "tcp" | "udp"
+    pub protocol: &'static str,
+    pub direction: ConnectionDirection,
+    pub local_port: Option<u16>,
+    pub remote_ip: Option<IpAddr>,
+    pub remote_port: Option<u16>,
+    /// "etw" | "procfs"
+    pub source: &'static str,
+}
+
+// ============================================================================
+// ADDRESS CLASSIFICATION
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AddressClass {
+    Unspecified,
+    Loopback,
+    /// RFC 1918 / IPv6 ULA (fc00::/7)
+    Private,
+    /// RFC 6598 shared address space 100.64.0.0/10
+    Cgnat,
+    LinkLocal,
+    Multicast,
+    Broadcast,
+    /// TEST-NET-1/2/3, 2001:db8::/32
+    Documentation,
+    Public,
+}
+
+impl AddressClass {
+    /// Never leaves the host / local network — skipped by beacon detection and intel
+    pub fn is_internal(&self) -> bool {
+        !matches!(self, AddressClass::Public | AddressClass::Documentation)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AddressClass::Unspecified => "unspecified",
+            AddressClass::Loopback => "loopback",
+            AddressClass::Private => "private",
+            AddressClass::Cgnat => "cgnat",
+            AddressClass::LinkLocal => "link_local",
+            AddressClass::Multicast => "multicast",
+            AddressClass::Broadcast => "broadcast",
+            AddressClass::Documentation => "documentation",
+            AddressClass::Public => "public",
+        }
+    }
+}
+
+pub fn classify_ip(ip: &IpAddr) -> AddressClass {
+    match ip {
+        IpAddr::V4(v4) => {
+            let o = v4.octets();
+            if v4.is_unspecified() {
+                AddressClass::Unspecified
+            } else if v4.is_loopback() {
+                AddressClass::Loopback
+            } else if v4.is_private() {
+                AddressClass::Private
+            } else if o[0] == 100 && (o[1] & 0xc0) == 64 {
+                AddressClass::Cgnat
+            } else if v4.is_link_local() {
+                AddressClass::LinkLocal
+            } else if v4.is_multicast() {
+                AddressClass::Multicast
+            } else if v4.is_broadcast() {
+                AddressClass::Broadcast
+            } else if matches!((o[0], o[1], o[2]), (192, 0, 2) | (198, 51, 100) | (203, 0, 113)) {
+                AddressClass::Documentation
+            } else {
+                AddressClass::Public
+            }
+        }
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return classify_ip(&IpAddr::V4(v4));
+            }
+            let first = v6.segments()[0];
+            if v6.is_unspecified() {
+                AddressClass::Unspecified
+            } else if v6.is_loopback() {
+                AddressClass::Loopback
+            } else if (first & 0xfe00) == 0xfc00 {
+                AddressClass::Private
+            } else if (first & 0xffc0) == 0xfe80 {
+                AddressClass::LinkLocal
+            } else if v6.is_multicast() {
+                AddressClass::Multicast
+            } else if first == 0x2001 && v6.segments()[1] == 0x0db8 {
+                AddressClass::Documentation
+            } else {
+                AddressClass::Public
+            }
+        }
+    }
+}
+
+// ============================================================================
+// ETW TCP/IP PAYLOAD
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TcpIpEndpoints {
+    pub remote_ip: IpAddr,
+    pub remote_port: u16,
+    pub local_ip: IpAddr,
+    pub local_port: u16,
+}
+
+// Microsoft-Windows-Kernel-Network TCP event IDs
+pub const TCPIP_CONNECT_V4: u16 = 12;
+pub const TCPIP_ACCEPT_V4: u16 = 15;
+pub const TCPIP_CONNECT_V6: u16 = 28;
+pub const TCPIP_ACCEPT_V6: u16 = 31;
+
+/// Direction and address family (true = IPv6) of a Kernel-Network TCP event we handle
+pub fn tcpip_event_kind(event_id: u16) -> Option<(ConnectionDirection, bool)> {
+    match event_id {
+        TCPIP_CONNECT_V4 => Some((ConnectionDirection::Outbound, false)),
+        TCPIP_CONNECT_V6 => Some((ConnectionDirection::Outbound, true)),
+        TCPIP_ACCEPT_V4 => Some((ConnectionDirection::Inbound, false)),
+        TCPIP_ACCEPT_V6 => Some((ConnectionDirection::Inbound, true)),
+        _ => None,
+    }
+}
+
+/// Kernel-Network TcpIp connect / accept payload: PID, size, daddr, saddr, dport, sport.
+/// IPv4: daddr@8 saddr@12 dport@16 sport@18; IPv6: daddr@8 saddr@24 dport@40 sport@42.
+/// daddr is always the remote peer. Addresses and ports are in network byte order
+pub fn parse_tcpip_payload(data: &[u8], ipv6: bool) -> Option<TcpIpEndpoints> {
+    let port = |at: usize| data.get(at..at + 2).map(|b| u16::from_be_bytes([b[0], b[1]]));
+    if ipv6 {
+        let daddr: [u8; 16] = data.get(8..24)?.try_into().ok()?;
+        let saddr: [u8; 16] = data.get(24..40)?.try_into().ok()?;
+        Some(TcpIpEndpoints {
+            remote_ip: IpAddr::from(daddr),
+            remote_port: port(40)?,
+            local_ip: IpAddr::from(saddr),
+            local_port: port(42)?,
+        })
+    } else {
+        let daddr: [u8; 4] = data.get(8..12)?.try_into().ok()?;
+        let saddr: [u8; 4] = data.get(12..16)?.try_into().ok()?;
+        Some(TcpIpEndpoints {
+            remote_ip: IpAddr::from(daddr),
+            remote_port: port(16)?,
+            local_ip: IpAddr::from(saddr),
+            local_port: port(18)?,
+        })
+    }
+}
+
+// ============================================================================
+// DETECTION PATH
+// ============================================================================
+
+/// Single entry point for connection sources. `parent_name` is resolved only when a detection fires
+pub fn handle_connection(event: &ConnectionEvent, parent_name: fn(u32) -> String) {
+    use crate::process_monitor;
+
+    crate::telemetry_schema::emit(crate::telemetry_schema::TelemetryEvent::Network(
+        &crate::telemetry_schema::NetworkConnection {
+            pid: event.pid,
+            process_name: event.process_name.clone(),
+            protocol: event.protocol.to_string(),
+            remote_ip: event.remote_ip.map(|ip| ip.to_string()).unwrap_or_default(),
+            remote_port: event.remote_port,
+            local_port: event.local_port,
+            direction: event.direction.as_str().to_string(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        },
+    ));
+
+    let remote_ip = match (event.direction, event.remote_ip) {
+        (ConnectionDirection::Outbound | ConnectionDirection::Inbound, Some(ip)) => ip,
+        _ => return,
+    };
+    let class = classify_ip(&remote_ip);
+    let key = crate::process_identity::ProcessKey::capture(event.pid);
+
+    // Known-bad destination from local intel, any process (internal addresses are never intel)
+    let intel = if class.is_internal() { Vec::new() } else { crate::threat_intel::lookup_ip(remote_ip) };
+    if !intel.is_empty() {
+        warn!(pid = event.pid, process = %event.process_name, source = %intel[0].source, "Network threat intel match → {}", remote_ip);
+
+        let parent = parent_name(event.pid);
+        let reason = format!("Threat intel match for {} ({})", remote_ip, crate::threat_intel::describe(&intel));
+
+        process_monitor::record_network_alert(process_monitor::NetworkAlert {
+            kind: "connection".to_string(),
+            indicator: remote_ip.to_string(),
+            port: event.remote_port,
+            pid: event.pid,
+            process_name: event.process_name.clone(),
+            mitre_technique: "T1071".to_string(),
+            reason: reason.clone(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            evidence: Some(serde_json::json!({ "address_class": class.as_str() })),
+        });
+
+        crate::response_playbooks::respond(&crate::response_playbooks::Detection {
+            key,
+            process_name: event.process_name.clone(),
+            parent_name: parent,
+            rule_id: "T1071".to_string(),
+            severity: "critical".to_string(),
+            reason,
+            source: "network",
+            command_line: None,
+            confidence: crate::enforcement::Confidence::Definite,
+            suspended: false,
+        });
+        return;
+    }
+
+    // Periodic callbacks, any process (only connections this host opens)
+    if event.direction != ConnectionDirection::Outbound {
+        return;
+    }
+    let observation = crate::beacon_detector::ConnectionObservation {
+        key,
+        process_name: event.process_name.clone(),
+        remote_ip,
+        remote_port: event.remote_port.unwrap_or(0),
+        at: crate::beacon_detector::now_secs(),
+        source: event.source,
+    };
+    if let Some(beacon) = crate::beacon_detector::observe(&observation) {
+        crate::beacon_detector::report(&beacon, &parent_name(event.pid));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_address_classes() {
+        let cases = [
+            ("0.0.0.0", AddressClass::Unspecified),
+            ("127.0.0.1", AddressClass::Loopback),
+            ("10.1.2.3", AddressClass::Private),
+            ("172.16.5.5", AddressClass::Private),
+            ("172.31.255.1", AddressClass::Private),
+            ("192.168.0.1", AddressClass::Private),
+            ("100.64.0.1", AddressClass::Cgnat),
+            ("100.127.255.254", AddressClass::Cgnat),
+            ("169.254.1.1", AddressClass::LinkLocal),
+            ("224.0.0.251", AddressClass::Multicast),
+            ("255.255.255.255", AddressClass::Broadcast),
+            ("198.51.100.7", AddressClass::Documentation),
+            ("8.8.8.8", AddressClass::Public),
+            ("172.32.0.1", AddressClass::Public),
+            ("100.128.0.1", AddressClass::Public),
+            ("::1", AddressClass::Loopback),
+            ("fd00::1", AddressClass::Private),
+            ("fe80::1", AddressClass::LinkLocal),
+            ("ff02::fb", AddressClass::Multicast),
+            ("2001:db8::1", AddressClass::Documentation),
+            ("::ffff:10.0.0.1", AddressClass::Private),
+            ("2606:4700:4700::1111", AddressClass::Public),
+        ];
+        for (ip, class) in cases {
+            assert_eq!(classify_ip(&ip.parse().unwrap()), class, "{}", ip);
+        }
+        assert!(AddressClass::Cgnat.is_internal());
+        assert!(!AddressClass::Public.is_internal());
+    }
+
+    #[test]
+    fn test_parse_tcpip_payload() {
+        let mut v4 = vec![0u8; 20];
+        v4[0..4].copy_from_slice(&1234u32.to_le_bytes());
+        v4[8..12].copy_from_slice(&[93, 184, 216, 34]);
+        v4[12..16].copy_from_slice(&[10, 0, 2, 15]);
+        v4[16..18].copy_from_slice(&443u16.to_be_bytes());
+        v4[18..20].copy_from_slice(&50123u16.to_be_bytes());
+        let parsed = parse_tcpip_payload(&v4, false).unwrap();
+        assert_eq!(parsed.remote_ip, "93.184.216.34".parse::<IpAddr>().unwrap());
+        assert_eq!((parsed.remote_port, parsed.local_port), (443, 50123));
+        assert_eq!(parsed.local_ip, "10.0.2.15".parse::<IpAddr>().unwrap());
+
+        let mut v6 = vec![0u8; 44];
+        let remote: std::net::Ipv6Addr = "2606:4700:4700::1111".parse().unwrap();
+        v6[8..24].copy_from_slice(&remote.octets());
+        v6[40..42].copy_from_slice(&853u16.to_be_bytes());
+        v6[42..44].copy_from_slice(&40000u16.to_be_bytes());
+        let parsed = parse_tcpip_payload(&v6, true).unwrap();
+        assert_eq!(parsed.remote_ip, IpAddr::V6(remote));
+        assert_eq!(parsed.remote_port, 853);
+
+        // Truncated payloads are rejected instead of read out of bounds
+        assert!(parse_tcpip_payload(&v4[..17], false).is_none());
+        assert!(parse_tcpip_payload(&v6[..41], true).is_none());
+
+        assert_eq!(tcpip_event_kind(12), Some((ConnectionDirection::Outbound, false)));
+        assert_eq!(tcpip_event_kind(15), Some((ConnectionDirection::Inbound, false)));
+        assert_eq!(tcpip_event_kind(28), Some((ConnectionDirection::Outbound, true)));
+        assert_eq!(tcpip_event_kind(31), Some((ConnectionDirection::Inbound, true)));
+        assert_eq!(tcpip_event_kind(10), None);
+    }
+}
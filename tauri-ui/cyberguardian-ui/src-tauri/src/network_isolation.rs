@@ -0,0 +1,798 @@
+//! Network Isolation + Firewall Posture
+//! Contains a compromised host by blocking all traffic except the backend
+//! (and the proxy, when one is configured). One `FirewallBackend` trait with
+//! nftables / iptables (Linux) and Windows Firewall (netsh) implementations.
+//! Isolation state is persisted and re-applied on startup, because nftables
+//! and iptables rules do not survive a reboot
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::net::{IpAddr, ToSocketAddrs};
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Mutex;
+use tracing::{error, info, warn};
+
+/// nftables table / iptables chain prefix / Windows rule name owned by the agent
+#[cfg(any(test, target_os = "linux"))]
+const ISOLATION_NAME: &str = "cyberguardian_isolation";
+#[cfg(windows)]
+const WINDOWS_RULE_NAME: &str = "CyberGuardian Isolation";
+#[cfg(any(test, target_os = "linux"))]
+const IPTABLES_IN_CHAIN: &str = "CG_ISOLATION_IN";
+#[cfg(any(test, target_os = "linux"))]
+const IPTABLES_OUT_CHAIN: &str = "CG_ISOLATION_OUT";
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+/// Endpoint that stays reachable while isolated
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AllowedEndpoint {
+    pub ip: IpAddr,
+    pub port: u16,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IsolationState {
+    pub isolated: bool,
+    /// "nftables" | "iptables" | "windows_firewall"
+    pub backend: Option<String>,
+    pub allowed: Vec<AllowedEndpoint>,
+    pub reason: Option<String>,
+    pub since: Option<String>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FirewallProfile {
+    pub name: String,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FirewallRule {
+    pub name: String,
+    pub protocol: String,
+    pub local_ports: String,
+    pub remote_addresses: String,
+    pub program: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FirewallPosture {
+    pub backend: String,
+    /// Every profile on (Windows) / inbound filtering present (Linux)
+    pub enabled: bool,
+    pub profiles: Vec<FirewallProfile>,
+    pub inbound_allow_rules: Vec<FirewallRule>,
+    pub isolated: bool,
+    pub checked_at: String,
+}
+
+// ============================================================================
+// BACKEND TRAIT
+// ============================================================================
+
+pub trait FirewallBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Block everything except loopback, DHCP and `allowed`. Must be idempotent
+    fn isolate(&self, allowed: &[AllowedEndpoint]) -> Result<(), String>;
+
+    /// Remove the isolation rules and restore normal traffic
+    fn unisolate(&self) -> Result<(), String>;
+
+    /// Host firewall state and inbound allow rules
+    fn posture(&self) -> Result<(bool, Vec<FirewallProfile>, Vec<FirewallRule>), String>;
+}
+
+/// Run a firewall tool, returning stdout (stderr becomes the error)
+fn run(program: &str, args: &[String], stdin: Option<&str>) -> Result<String, String> {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(if stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        pipe.write_all(input.as_bytes()).map_err(|e| format!("Failed to write to {}: {}", program, e))?;
+    }
+    let output = child.wait_with_output().map_err(|e| format!("{} failed: {}", program, e))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(format!("{} {} failed: {}", program, args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()))
+    }
+}
+
+fn args(parts: &[&str]) -> Vec<String> {
+    parts.iter().map(|s| s.to_string()).collect()
+}
+
+// ============================================================================
+// NFTABLES
+// ============================================================================
+
+#[cfg(target_os = "linux")]
+pub struct NftablesBackend;
+
+/// ICMPv6 neighbor discovery — without it IPv6 cannot resolve the gateway or
+/// the backend's link-layer address, so an isolated host loses IPv6 entirely
+#[cfg(any(test, target_os = "linux"))]
+const ND_INPUT_TYPES: [&str; 3] = ["nd-neighbor-solicit", "nd-neighbor-advert", "nd-router-advert"];
+#[cfg(any(test, target_os = "linux"))]
+const ND_OUTPUT_TYPES: [&str; 3] = ["nd-neighbor-solicit", "nd-neighbor-advert", "nd-router-solicit"];
+
+/// Dedicated `inet` table; the leading add+delete makes `nft -f` replace it atomically
+#[cfg(any(test, target_os = "linux"))]
+pub fn nft_ruleset(allowed: &[AllowedEndpoint]) -> String {
+    let mut input = vec![
+        "type filter hook input priority -100; policy drop;".to_string(),
+        "iif \"lo\" accept".to_string(),
+        "udp sport 67 udp dport 68 accept".to_string(),
+        format!("icmpv6 type {{ {} }} accept", ND_INPUT_TYPES.join(", ")),
+    ];
+    let mut output = vec![
+        "type filter hook output priority -100; policy drop;".to_string(),
+        "oif \"lo\" accept".to_string(),
+        "udp sport 68 udp dport 67 accept".to_string(),
+        format!("icmpv6 type {{ {} }} accept", ND_OUTPUT_TYPES.join(", ")),
+    ];
+    for endpoint in allowed {
+        let family = if endpoint.ip.is_ipv4() { "ip" } else { "ip6" };
+        input.push(format!("{} saddr {} tcp sport {} ct state established accept", family, endpoint.ip, endpoint.port));
+        output.push(format!("{} daddr {} tcp dport {} accept", family, endpoint.ip, endpoint.port));
+    }
+
+    let chain = |name: &str, rules: &[String]| {
+        format!("  chain {} {{\n{}\n  }}\n", name,
+            rules.iter().map(|r| format!("    {}", r)).collect::<Vec<_>>().join("\n"))
+    };
+    format!(
+        "table inet {name}\ndelete table inet {name}\ntable inet {name} {{\n{}{}}}\n",
+        chain("input", &input),
+        chain("output", &output),
+        name = ISOLATION_NAME,
+    )
+}
+
+/// Input-hook chains from `nft list ruleset`: (filtering active, accept rules)
+#[cfg(any(test, target_os = "linux"))]
+pub fn parse_nft_input(ruleset: &str) -> (bool, Vec<FirewallRule>) {
+    let mut enabled = false;
+    let mut rules = Vec::new();
+    let mut chain: Option<String> = None;
+    let mut in_input = false;
+
+    for line in ruleset.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix("chain ") {
+            chain = Some(name.trim_end_matches('{').trim().to_string());
+            in_input = false;
+        } else if line == "}" {
+            chain = None;
+            in_input = false;
+        } else if chain.is_some() && line.contains("hook input") {
+            in_input = true;
+            enabled |= line.contains("policy drop");
+        } else if in_input {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            if tokens.last() == Some(&"drop") || tokens.last() == Some(&"reject") {
+                enabled = true;
+            } else if tokens.last() == Some(&"accept") && !line.starts_with("iif \"lo\"") && !line.contains("established") {
+                let after = |key: &str| tokens.windows(2).find(|w| w[0] == key).map(|w| w[1].to_string());
+                rules.push(FirewallRule {
+                    name: format!("{}: {}", chain.as_deref().unwrap_or("?"), line),
+                    protocol: tokens.iter().find(|t| matches!(**t, "tcp" | "udp" | "icmp")).map(|t| t.to_string()).unwrap_or_else(|| "any".to_string()),
+                    local_ports: after("dport").unwrap_or_else(|| "any".to_string()),
+                    remote_addresses: after("saddr").unwrap_or_else(|| "any".to_string()),
+                    program: None,
+                });
+            }
+        }
+    }
+    (enabled, rules)
+}
+
+#[cfg(target_os = "linux")]
+impl FirewallBackend for NftablesBackend {
+    fn name(&self) -> &'static str {
+        "nftables"
+    }
+
+    fn isolate(&self, allowed: &[AllowedEndpoint]) -> Result<(), String> {
+        run("nft", &args(&["-f", "-"]), Some(&nft_ruleset(allowed))).map(|_| ())
+    }
+
+    fn unisolate(&self) -> Result<(), String> {
+        match run("nft", &args(&["delete", "table", "inet", ISOLATION_NAME]), None) {
+            Ok(_) => Ok(()),
+            // Already gone (e.g. after a reboot)
+            Err(e) if e.contains("No such file") => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn posture(&self) -> Result<(bool, Vec<FirewallProfile>, Vec<FirewallRule>), String> {
+        let (enabled, rules) = parse_nft_input(&run("nft", &args(&["list", "ruleset"]), None)?);
+        Ok((enabled, Vec::new(), rules))
+    }
+}
+
+// ============================================================================
+// IPTABLES (fallback when nft is unavailable)
+// ============================================================================
+
+#[cfg(target_os = "linux")]
+pub struct IptablesBackend;
+
+/// Commands for one family (`iptables` or `ip6tables`), in order
+#[cfg(any(test, target_os = "linux"))]
+pub fn iptables_commands(allowed: &[AllowedEndpoint], ipv6: bool) -> Vec<Vec<String>> {
+    let mut cmds = Vec::new();
+    for (chain, hook) in [(IPTABLES_IN_CHAIN, "INPUT"), (IPTABLES_OUT_CHAIN, "OUTPUT")] {
+        cmds.push(args(&["-N", chain]));
+        cmds.push(args(&["-F", chain]));
+        let lo = if hook == "INPUT" { "-i" } else { "-o" };
+        cmds.push(args(&["-A", chain, lo, "lo", "-j", "ACCEPT"]));
+        let (sport, dport) = if hook == "INPUT" { ("67", "68") } else { ("68", "67") };
+        if !ipv6 {
+            cmds.push(args(&["-A", chain, "-p", "udp", "--sport", sport, "--dport", dport, "-j", "ACCEPT"]));
+        } else {
+            let nd_types = if hook == "INPUT" {
+                ["neighbour-solicitation", "neighbour-advertisement", "router-advertisement"]
+            } else {
+                ["neighbour-solicitation", "neighbour-advertisement", "router-solicitation"]
+            };
+            for icmp_type in nd_types {
+                cmds.push(args(&["-A", chain, "-p", "ipv6-icmp", "--icmpv6-type", icmp_type, "-j", "ACCEPT"]));
+            }
+        }
+        for endpoint in allowed.iter().filter(|e| e.ip.is_ipv6() == ipv6) {
+            let ip = endpoint.ip.to_string();
+            let port = endpoint.port.to_string();
+            if hook == "INPUT" {
+                cmds.push(args(&["-A", chain, "-s", &ip, "-p", "tcp", "--sport", &port,
+                    "-m", "conntrack", "--ctstate", "ESTABLISHED", "-j", "ACCEPT"]));
+            } else {
+                cmds.push(args(&["-A", chain, "-d", &ip, "-p", "tcp", "--dport", &port, "-j", "ACCEPT"]));
+            }
+        }
+        cmds.push(args(&["-A", chain, "-j", "DROP"]));
+        cmds.push(args(&["-I", hook, "1", "-j", chain]));
+    }
+    cmds
+}
+
+/// `iptables -S INPUT`: (DROP policy or DROP/REJECT rules present, ACCEPT rules)
+#[cfg(any(test, target_os = "linux"))]
+pub fn parse_iptables_input(listing: &str) -> (bool, Vec<FirewallRule>) {
+    let mut enabled = false;
+    let mut rules = Vec::new();
+    for line in listing.lines().map(str::trim) {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let after = |key: &str| tokens.windows(2).find(|w| w[0] == key).map(|w| w[1].to_string());
+        match tokens.as_slice() {
+            ["-P", "INPUT", "DROP" | "REJECT"] => enabled = true,
+            ["-A", "INPUT", ..] => match after("-j").as_deref() {
+                Some("DROP" | "REJECT") => enabled = true,
+                Some("ACCEPT") if after("-i").as_deref() != Some("lo") && !line.contains("ESTABLISHED") => {
+                    rules.push(FirewallRule {
+                        name: line.to_string(),
+                        protocol: after("-p").unwrap_or_else(|| "any".to_string()),
+                        local_ports: after("--dport").or_else(|| after("--dports")).unwrap_or_else(|| "any".to_string()),
+                        remote_addresses: after("-s").unwrap_or_else(|| "any".to_string()),
+                        program: None,
+                    });
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+    (enabled, rules)
+}
+
+#[cfg(target_os = "linux")]
+impl IptablesBackend {
+    fn remove(&self, program: &str) {
+        for (chain, hook) in [(IPTABLES_IN_CHAIN, "INPUT"), (IPTABLES_OUT_CHAIN, "OUTPUT")] {
+            // Remove every jump (re-isolation may have inserted more than one)
+            while run(program, &args(&["-D", hook, "-j", chain]), None).is_ok() {}
+            let _ = run(program, &args(&["-F", chain]), None);
+            let _ = run(program, &args(&["-X", chain]), None);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl FirewallBackend for IptablesBackend {
+    fn name(&self) -> &'static str {
+        "iptables"
+    }
+
+    fn isolate(&self, allowed: &[AllowedEndpoint]) -> Result<(), String> {
+        for (program, ipv6) in [("iptables", false), ("ip6tables", true)] {
+            self.remove(program);
+            for cmd in iptables_commands(allowed, ipv6) {
+                if let Err(e) = run(program, &cmd, None) {
+                    // -N on an existing chain is harmless
+                    if cmd[0] != "-N" {
+                        // Half-applied rules (IPv4 closed, IPv6 open) would report a
+                        // contained host that is not — take everything back out
+                        self.unisolate()?;
+                        return Err(format!("{} failed, isolation rolled back: {}", program, e));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn unisolate(&self) -> Result<(), String> {
+        self.remove("iptables");
+        self.remove("ip6tables");
+        Ok(())
+    }
+
+    fn posture(&self) -> Result<(bool, Vec<FirewallProfile>, Vec<FirewallRule>), String> {
+        let (enabled, rules) = parse_iptables_input(&run("iptables", &args(&["-S", "INPUT"]), None)?);
+        Ok((enabled, Vec::new(), rules))
+    }
+}
+
+// ============================================================================
+// WINDOWS FIREWALL (netsh advfirewall)
+// ============================================================================
+
+#[cfg(windows)]
+pub struct WindowsFirewallBackend;
+
+/// `netsh advfirewall show allprofiles state`
+#[cfg(any(test, windows))]
+pub fn parse_netsh_profiles(output: &str) -> Vec<FirewallProfile> {
+    let mut profiles = Vec::new();
+    let mut current: Option<String> = None;
+    for line in output.lines().map(str::trim) {
+        if let Some(name) = line.strip_suffix(" Profile Settings:") {
+            current = Some(name.to_string());
+        } else if let (Some(name), Some(state)) = (&current, line.strip_prefix("State")) {
+            profiles.push(FirewallProfile { name: name.clone(), enabled: state.trim().eq_ignore_ascii_case("ON") });
+            current = None;
+        }
+    }
+    profiles
+}
+
+/// `netsh advfirewall firewall show rule name=all dir=in verbose` → enabled Allow rules.
+/// Field names are only stable on English Windows
+#[cfg(any(test, windows))]
+pub fn parse_netsh_rules(output: &str) -> Vec<FirewallRule> {
+    let mut rules = Vec::new();
+    let mut fields: Vec<(String, String)> = Vec::new();
+
+    let flush = |fields: &mut Vec<(String, String)>, rules: &mut Vec<FirewallRule>| {
+        let get = |key: &str| fields.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone());
+        let allow = get("Action").is_some_and(|a| a.eq_ignore_ascii_case("Allow"));
+        let enabled = get("Enabled").is_some_and(|e| e.eq_ignore_ascii_case("Yes"));
+        if let (true, true, Some(name)) = (allow, enabled, get("Rule Name")) {
+            rules.push(FirewallRule {
+                name,
+                protocol: get("Protocol").unwrap_or_else(|| "Any".to_string()),
+                local_ports: get("LocalPort").unwrap_or_else(|| "Any".to_string()),
+                remote_addresses: get("RemoteIP").unwrap_or_else(|| "Any".to_string()),
+                program: get("Program").filter(|p| !p.eq_ignore_ascii_case("Any")),
+            });
+        }
+        fields.clear();
+    };
+
+    for line in output.lines() {
+        let Some((key, value)) = line.split_once(':') else { continue };
+        let key = key.trim();
+        if key == "Rule Name" {
+            flush(&mut fields, &mut rules);
+        }
+        fields.push((key.to_string(), value.trim().to_string()));
+    }
+    flush(&mut fields, &mut rules);
+    rules
+}
+
+/// Policy backup taken before isolating; `import` restores the exact previous policy
+#[cfg(windows)]
+fn windows_backup_path() -> PathBuf {
+    data_dir().join("network_isolation_backup.wfw")
+}
+
+#[cfg(windows)]
+impl FirewallBackend for WindowsFirewallBackend {
+    fn name(&self) -> &'static str {
+        "windows_firewall"
+    }
+
+    fn isolate(&self, allowed: &[AllowedEndpoint]) -> Result<(), String> {
+        let backup = windows_backup_path();
+        // Re-isolation (startup, config change) must not overwrite the pre-isolation backup
+        if !backup.exists() {
+            let _ = fs::create_dir_all(data_dir());
+            run("netsh", &args(&["advfirewall", "export", &backup.to_string_lossy()]), None)?;
+        }
+
+        // Block rules beat allow rules in Windows Firewall, so existing outbound
+        // allow rules are disabled instead; the import on unisolate restores them
+        let name = format!("name={}", WINDOWS_RULE_NAME);
+        let _ = run("netsh", &args(&["advfirewall", "firewall", "delete", "rule", &name]), None);
+        run("netsh", &args(&["advfirewall", "set", "allprofiles", "state", "on"]), None)?;
+        run("netsh", &args(&["advfirewall", "firewall", "set", "rule", "name=all", "dir=out", "new", "enable=no"]), None)?;
+        run("netsh", &args(&["advfirewall", "set", "allprofiles", "firewallpolicy", "blockinboundalways,blockoutbound"]), None)?;
+
+        let mut ports: Vec<u16> = allowed.iter().map(|e| e.port).collect();
+        ports.sort_unstable();
+        ports.dedup();
+        for port in ports {
+            let ips: Vec<String> = allowed.iter().filter(|e| e.port == port).map(|e| e.ip.to_string()).collect();
+            run("netsh", &args(&[
+                "advfirewall", "firewall", "add", "rule", &name, "dir=out", "action=allow", "protocol=TCP",
+                &format!("remoteip={}", ips.join(",")), &format!("remoteport={}", port),
+            ]), None)?;
+        }
+        run("netsh", &args(&[
+            "advfirewall", "firewall", "add", "rule", &name, "dir=out", "action=allow", "protocol=UDP",
+            "localport=68", "remoteport=67",
+        ]), None)?;
+        Ok(())
+    }
+
+    fn unisolate(&self) -> Result<(), String> {
+        let backup = windows_backup_path();
+        if backup.exists() {
+            run("netsh", &args(&["advfirewall", "import", &backup.to_string_lossy()]), None)?;
+            let _ = fs::remove_file(&backup);
+        } else {
+            // No backup: fall back to the Windows defaults and re-enable outbound rules
+            run("netsh", &args(&["advfirewall", "set", "allprofiles", "firewallpolicy", "blockinbound,allowoutbound"]), None)?;
+            run("netsh", &args(&["advfirewall", "firewall", "set", "rule", "name=all", "dir=out", "new", "enable=yes"]), None)?;
+        }
+        let _ = run("netsh", &args(&["advfirewall", "firewall", "delete", "rule", &format!("name={}", WINDOWS_RULE_NAME)]), None);
+        Ok(())
+    }
+
+    fn posture(&self) -> Result<(bool, Vec<FirewallProfile>, Vec<FirewallRule>), String> {
+        let profiles = parse_netsh_profiles(&run("netsh", &args(&["advfirewall", "show", "allprofiles", "state"]), None)?);
+        let rules = parse_netsh_rules(&run("netsh", &args(&["advfirewall", "firewall", "show", "rule", "name=all", "dir=in", "verbose"]), None)?);
+        let enabled = !profiles.is_empty() && profiles.iter().all(|p| p.enabled);
+        Ok((enabled, profiles, rules))
+    }
+}
+
+// ============================================================================
+// BACKEND SELECTION + ALLOWLIST
+// ============================================================================
+
+#[cfg(windows)]
+fn select_backend() -> Result<Box<dyn FirewallBackend>, String> {
+    Ok(Box::new(WindowsFirewallBackend))
+}
+
+#[cfg(target_os = "linux")]
+fn select_backend() -> Result<Box<dyn FirewallBackend>, String> {
+    if run("nft", &args(&["--version"]), None).is_ok() {
+        Ok(Box::new(NftablesBackend))
+    } else if run("iptables", &args(&["--version"]), None).is_ok() {
+        Ok(Box::new(IptablesBackend))
+    } else {
+        Err("Neither nft nor iptables is available".to_string())
+    }
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn select_backend() -> Result<Box<dyn FirewallBackend>, String> {
+    Err("Network isolation is not supported on this platform".to_string())
+}
+
+/// Resolve the backend (and proxy) to concrete endpoints. Resolution happens
+/// before any rule is applied — DNS is blocked once isolated
+pub fn resolve_endpoints(urls: &[String]) -> Result<Vec<AllowedEndpoint>, String> {
+    let mut endpoints = Vec::new();
+    for raw in urls {
+        let url = reqwest::Url::parse(raw).map_err(|e| format!("Invalid URL {}: {}", raw, e))?;
+        let host = url.host_str().ok_or_else(|| format!("URL has no host: {}", raw))?;
+        let port = url.port_or_known_default().ok_or_else(|| format!("URL has no port: {}", raw))?;
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let addrs = (host, port).to_socket_addrs()
+            .map_err(|e| format!("Failed to resolve {}: {}", host, e))?;
+        for addr in addrs {
+            let endpoint = AllowedEndpoint { ip: addr.ip(), port };
+            if !endpoints.contains(&endpoint) {
+                endpoints.push(endpoint);
+            }
+        }
+    }
+    if endpoints.is_empty() {
+        return Err("Backend did not resolve to any address".to_string());
+    }
+    Ok(endpoints)
+}
+
+/// Resolution fails while isolated (DNS is blocked); re-isolation then keeps
+/// the last applied addresses rather than dropping the backend from the allowlist
+fn allowlist(resolved: Result<Vec<AllowedEndpoint>, String>, state: &IsolationState) -> Result<Vec<AllowedEndpoint>, String> {
+    match resolved {
+        Err(e) if state.isolated && !state.allowed.is_empty() => {
+            warn!("Cannot resolve backend while isolated ({}), keeping the last known addresses", e);
+            Ok(state.allowed.clone())
+        }
+        other => other,
+    }
+}
+
+fn backend_endpoints() -> Result<Vec<AllowedEndpoint>, String> {
+    let config = crate::agent_config::current();
+    let mut urls = vec![crate::agent_config::backend_url()];
+    if let Some(proxy) = config.proxy_url.filter(|p| !p.trim().is_empty()) {
+        urls.push(proxy);
+    }
+    resolve_endpoints(&urls)
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+lazy_static::lazy_static! {
+    static ref STATE: Mutex<Option<IsolationState>> = Mutex::new(None);
+}
+
+fn data_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("CyberGuardian")
+}
+
+fn get_state_path() -> PathBuf {
+    data_dir().join("network_isolation_state.json")
+}
+
+fn load_state() -> IsolationState {
+    fs::read_to_string(get_state_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save_state(state: &IsolationState) -> Result<(), String> {
+    fs::create_dir_all(data_dir()).map_err(|e| format!("Failed to create data dir: {}", e))?;
+    let json = serde_json::to_string_pretty(state)
+        .map_err(|e| format!("Failed to serialize isolation state: {}", e))?;
+    fs::write(get_state_path(), json).map_err(|e| format!("Failed to write isolation state: {}", e))
+}
+
+fn with_state<T>(f: impl FnOnce(&mut IsolationState) -> T) -> Result<T, String> {
+    let mut guard = STATE.lock().map_err(|e| format!("Lock error: {}", e))?;
+    Ok(f(guard.get_or_insert_with(load_state)))
+}
+
+pub fn current_state() -> IsolationState {
+    with_state(|s| s.clone()).unwrap_or_default()
+}
+
+pub fn is_isolated() -> bool {
+    current_state().isolated
+}
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+pub fn isolate(reason: &str) -> Result<IsolationState, String> {
+    let allowed = allowlist(backend_endpoints(), &current_state())?;
+    let backend = select_backend()?;
+    backend.isolate(&allowed)?;
+
+    let state = with_state(|s| {
+        if !s.isolated {
+            s.since = Some(chrono::Utc::now().to_rfc3339());
+        }
+        s.isolated = true;
+        s.backend = Some(backend.name().to_string());
+        s.allowed = allowed;
+        s.reason = Some(reason.to_string());
+        s.last_error = None;
+        s.clone()
+    })?;
+    save_state(&state)?;
+    info!("Host isolated via {} ({} backend endpoints allowed): {}", backend.name(), state.allowed.len(), reason);
+    Ok(state)
+}
+
+pub fn unisolate() -> Result<IsolationState, String> {
+    let backend = select_backend()?;
+    backend.unisolate()?;
+
+    let state = with_state(|s| {
+        *s = IsolationState { backend: Some(backend.name().to_string()), ..IsolationState::default() };
+        s.clone()
+    })?;
+    save_state(&state)?;
+    info!("Network isolation lifted");
+    Ok(state)
+}
+
+/// Re-apply persisted isolation at startup. A failure is recorded, the flag stays set
+pub fn restore_isolation() {
+    let state = current_state();
+    if !state.isolated {
+        return;
+    }
+    let reason = state.reason.unwrap_or_else(|| "restored after restart".to_string());
+    if let Err(e) = isolate(&reason) {
+        error!("Failed to restore network isolation: {}", e);
+        record_error(e);
+    }
+}
+
+/// Keep the isolated flag but remember why the rules could not be applied
+fn record_error(error: String) {
+    if let Ok(state) = with_state(|s| {
+        s.last_error = Some(error);
+        s.clone()
+    }) {
+        let _ = save_state(&state);
+    }
+}
+
+/// Backend URL/proxy changed while isolated → move the allowlist along.
+/// A new host name cannot be resolved while DNS is blocked: the last known
+/// addresses stay allowed, so point the agent at an IP (or unisolate) to move it
+pub fn refresh_if_isolated() {
+    if is_isolated() {
+        let reason = current_state().reason.unwrap_or_default();
+        if let Err(e) = isolate(&reason) {
+            error!("Failed to update isolation allowlist: {}", e);
+            record_error(e);
+        }
+    }
+}
+
+pub fn firewall_posture() -> Result<FirewallPosture, String> {
+    let backend = select_backend()?;
+    let (enabled, profiles, inbound_allow_rules) = backend.posture()?;
+    Ok(FirewallPosture {
+        backend: backend.name().to_string(),
+        enabled,
+        profiles,
+        inbound_allow_rules,
+        isolated: is_isolated(),
+        checked_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+// ============================================================================
+// TAURI COMMANDS
+// ============================================================================
+
+#[tauri::command]
+pub fn isolate_host(reason: Option<String>) -> Result<IsolationState, String> {
+    isolate(reason.as_deref().unwrap_or("Isolated from desktop UI"))
+}
+
+#[tauri::command]
+pub fn unisolate_host() -> Result<IsolationState, String> {
+    unisolate()
+}
+
+#[tauri::command]
+pub fn get_isolation_status() -> Result<IsolationState, String> {
+    Ok(current_state())
+}
+
+#[tauri::command]
+pub fn get_firewall_posture() -> Result<FirewallPosture, String> {
+    firewall_posture()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoints() -> Vec<AllowedEndpoint> {
+        vec![
+            AllowedEndpoint { ip: "203.0.113.10".parse().unwrap(), port: 443 },
+            AllowedEndpoint { ip: "2001:db8::10".parse().unwrap(), port: 443 },
+        ]
+    }
+
+    #[test]
+    fn test_isolation_rules_allow_only_backend() {
+        let ruleset = nft_ruleset(&endpoints());
+        assert!(ruleset.starts_with("table inet cyberguardian_isolation\ndelete table inet cyberguardian_isolation\n"));
+        assert_eq!(ruleset.matches("policy drop").count(), 2);
+        assert!(ruleset.contains("ip daddr 203.0.113.10 tcp dport 443 accept"));
+        assert!(ruleset.contains("ip6 daddr 2001:db8::10 tcp dport 443 accept"));
+        assert!(ruleset.contains("icmpv6 type { nd-neighbor-solicit, nd-neighbor-advert, nd-router-advert } accept"));
+
+        let v4 = iptables_commands(&endpoints(), false);
+        assert!(v4.iter().any(|c| c.join(" ") == "-A CG_ISOLATION_OUT -d 203.0.113.10 -p tcp --dport 443 -j ACCEPT"));
+        assert!(!v4.iter().any(|c| c.contains(&"2001:db8::10".to_string())));
+        assert_eq!(v4.last().unwrap().join(" "), "-I OUTPUT 1 -j CG_ISOLATION_OUT");
+        // Every chain ends in DROP before it is hooked in
+        let drops = v4.iter().filter(|c| c.join(" ").ends_with("-j DROP")).count();
+        assert_eq!(drops, 2);
+
+        let v6 = iptables_commands(&endpoints(), true);
+        assert!(v6.iter().any(|c| c.join(" ") == "-A CG_ISOLATION_IN -p ipv6-icmp --icmpv6-type neighbour-advertisement -j ACCEPT"));
+        assert!(v6.iter().any(|c| c.join(" ") == "-A CG_ISOLATION_OUT -p ipv6-icmp --icmpv6-type neighbour-solicitation -j ACCEPT"));
+        assert!(!v4.iter().any(|c| c.contains(&"ipv6-icmp".to_string())));
+    }
+
+    #[test]
+    fn test_posture_parsers() {
+        let netsh_state = "\nDomain Profile Settings:\n----------------\nState                                 ON\n\n\
+            Private Profile Settings:\n----------------\nState                                 ON\n\n\
+            Public Profile Settings:\n----------------\nState                                 OFF\nOk.\n";
+        let profiles = parse_netsh_profiles(netsh_state);
+        assert_eq!(profiles.len(), 3);
+        assert!(!profiles[2].enabled && profiles[2].name == "Public");
+
+        let netsh_rules = "\nRule Name:                            Remote Desktop - User Mode (TCP-In)\n\
+            ----------------------------------------------------------------------\n\
+            Enabled:                              Yes\nDirection:                            In\n\
+            RemoteIP:                             Any\nProtocol:                             TCP\n\
+            LocalPort:                            3389\nProgram:                              C:\\Windows\\system32\\svchost.exe\n\
+            Action:                               Allow\n\n\
+            Rule Name:                            Disabled rule\nEnabled:                              No\nAction:                               Allow\n\n\
+            Rule Name:                            Block telnet\nEnabled:                              Yes\nAction:                               Block\nOk.\n";
+        let rules = parse_netsh_rules(netsh_rules);
+        assert_eq!(rules.len(), 1);
+        assert_eq!((rules[0].local_ports.as_str(), rules[0].protocol.as_str()), ("3389", "TCP"));
+        assert_eq!(rules[0].program.as_deref(), Some("C:\\Windows\\system32\\svchost.exe"));
+
+        let iptables = "-P INPUT DROP\n-A INPUT -i lo -j ACCEPT\n\
+            -A INPUT -m conntrack --ctstate RELATED,ESTABLISHED -j ACCEPT\n\
+            -A INPUT -s 10.0.0.0/8 -p tcp -m tcp --dport 22 -j ACCEPT\n";
+        let (enabled, rules) = parse_iptables_input(iptables);
+        assert!(enabled);
+        assert_eq!(rules.len(), 1);
+        assert_eq!((rules[0].local_ports.as_str(), rules[0].remote_addresses.as_str()), ("22", "10.0.0.0/8"));
+
+        let nft = "table inet filter {\n\tchain input {\n\t\ttype filter hook input priority filter; policy accept;\n\
+            \t\tiif \"lo\" accept\n\t\ttcp dport 22 accept\n\t}\n\tchain output {\n\t\ttype filter hook output priority filter; policy accept;\n\
+            \t\ttcp dport 443 accept\n\t}\n}\n";
+        let (enabled, rules) = parse_nft_input(nft);
+        assert!(!enabled, "accept policy without drops is not filtering");
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].local_ports, "22");
+    }
+
+    #[test]
+    fn test_resolve_endpoints_literal_addresses() {
+        let resolved = resolve_endpoints(&[
+            "https://203.0.113.10/api".to_string(),
+            "http://[2001:db8::10]:3128".to_string(),
+            "https://203.0.113.10".to_string(),
+        ]).unwrap();
+        assert_eq!(resolved.len(), 2);
+        assert_eq!(resolved[1], AllowedEndpoint { ip: "2001:db8::10".parse().unwrap(), port: 3128 });
+        assert!(resolve_endpoints(&["not a url".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_allowlist_keeps_known_addresses_while_isolated() {
+        let known = vec![AllowedEndpoint { ip: "203.0.113.10".parse().unwrap(), port: 443 }];
+        let mut state = IsolationState { allowed: known.clone(), ..Default::default() };
+        // Not isolated yet: a resolution failure must surface
+        assert!(allowlist(Err("dns".into()), &state).is_err());
+        state.isolated = true;
+        assert_eq!(allowlist(Err("dns".into()), &state).unwrap(), known);
+        let fresh = vec![AllowedEndpoint { ip: "198.51.100.7".parse().unwrap(), port: 8443 }];
+        assert_eq!(allowlist(Ok(fresh.clone()), &state).unwrap(), fresh);
+    }
+}
@@ -0,0 +1,608 @@
+//! Offline-first Outbound Spool
+//! Persists failed backend calls on disk (one queue per endpoint) and replays
+//! them in order with exponential backoff + jitter once connectivity returns.
+//! A 401/403 pauses replay until the agent token changes
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use rand::Rng;
+use tracing::{error, info, warn};
+
+use crate::backend_client::{self, BackendClient, BackendError, ReqwestBackendClient};
+
+// ============================================================================
+// ENDPOINTS
+// ============================================================================
+
+/// Backend endpoints that are spooled when offline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpoolEndpoint {
+    FileScan,
+    Quarantine,
+    ScanHistory,
+    CommandResult,
+    FileUpload,
+}
+
+impl SpoolEndpoint {
+    pub const ALL: [SpoolEndpoint; 5] = [
+        SpoolEndpoint::FileScan,
+        SpoolEndpoint::Quarantine,
+        SpoolEndpoint::ScanHistory,
+        SpoolEndpoint::CommandResult,
+        SpoolEndpoint::FileUpload,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SpoolEndpoint::FileScan => "file_scan",
+            SpoolEndpoint::Quarantine => "quarantine",
+            SpoolEndpoint::ScanHistory => "scan_history",
+            SpoolEndpoint::CommandResult => "command_result",
+            SpoolEndpoint::FileUpload => "file_upload",
+        }
+    }
+
+    /// API path relative to the backend base URL
+    pub fn api_path(&self) -> &'static str {
+        match self {
+            SpoolEndpoint::FileScan => backend_client::FILE_SCAN_PATH,
+            SpoolEndpoint::Quarantine => backend_client::QUARANTINE_PATH,
+            SpoolEndpoint::ScanHistory => backend_client::SCAN_HISTORY_PATH,
+            SpoolEndpoint::CommandResult => backend_client::COMMAND_RESULT_PATH,
+            SpoolEndpoint::FileUpload => backend_client::FILE_UPLOAD_PATH,
+        }
+    }
+}
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+/// One spooled request (spool/<endpoint>/<id>.json)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpoolEntry {
+    pub id: String,
+    pub endpoint: SpoolEndpoint,
+    pub body: serde_json::Value,
+    pub created_at: String,
+    pub attempts: u32,
+}
+
+/// Queue status exposed to the UI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueStatus {
+    pub endpoint: SpoolEndpoint,
+    pub depth: usize,
+    pub bytes: u64,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<String>,
+    pub last_success_at: Option<String>,
+    pub next_retry_in_secs: Option<u64>,
+    pub dropped: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpoolStatus {
+    pub online: bool,
+    /// Backend rejected the agent token; replay resumes once it changes
+    pub auth_paused: bool,
+    pub total_depth: usize,
+    pub queues: Vec<QueueStatus>,
+}
+
+#[derive(Default)]
+struct QueueRuntime {
+    attempts: u32,
+    next_attempt_at: Option<Instant>,
+    last_error: Option<String>,
+    last_error_at: Option<String>,
+    last_success_at: Option<String>,
+    dropped: u64,
+}
+
+const BACKOFF_BASE_SECS: u64 = 5;
+const BACKOFF_MAX_SECS: u64 = 15 * 60;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+/// Per-queue caps; the oldest entries are dropped beyond them
+const MAX_QUEUE_ENTRIES: usize = 5000;
+const MAX_QUEUE_BYTES: u64 = 50 * 1024 * 1024;
+/// Queues of removed endpoints (process snapshots now go out as inventory
+/// deltas) — whatever is still queued there is stale and dropped on start
+const RETIRED_QUEUES: [&str; 1] = ["process_upload"];
+
+static FLUSHER_RUNNING: AtomicBool = AtomicBool::new(false);
+static ONLINE: AtomicBool = AtomicBool::new(true);
+static ENTRY_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+lazy_static::lazy_static! {
+    static ref QUEUES: Mutex<HashMap<SpoolEndpoint, QueueRuntime>> = Mutex::new(HashMap::new());
+    // Serializes disk access between enqueue and replay
+    static ref SPOOL_IO: Mutex<()> = Mutex::new(());
+    // Fingerprint of the token the backend rejected (401/403); replay waits for a new one
+    static ref AUTH_REJECTED: Mutex<Option<u64>> = Mutex::new(None);
+}
+
+// ============================================================================
+// PATHS
+// ============================================================================
+
+fn get_spool_dir() -> PathBuf {
+    let mut path = dirs::data_local_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push("CyberGuardian");
+    path.push("spool");
+    path
+}
+
+fn get_queue_dir(root: &Path, endpoint: SpoolEndpoint) -> PathBuf {
+    root.join(endpoint.as_str())
+}
+
+fn get_dead_letter_dir(root: &Path) -> PathBuf {
+    root.join("dead_letter")
+}
+
+fn queue_files(endpoint: SpoolEndpoint) -> Vec<(PathBuf, u64)> {
+    list_queue_files(&get_queue_dir(&get_spool_dir(), endpoint))
+}
+
+/// Queue files sorted oldest first (file names are time-ordered)
+fn list_queue_files(dir: &Path) -> Vec<(PathBuf, u64)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<(PathBuf, u64)> = entries
+        .flatten()
+        .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("json"))
+        .map(|e| {
+            let size = e.metadata().map(|m| m.len()).unwrap_or(0);
+            (e.path(), size)
+        })
+        .collect();
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    files
+}
+
+// ============================================================================
+// BACKOFF
+// ============================================================================
+
+/// Exponential backoff with "equal jitter": half fixed, half random
+pub fn backoff_delay(attempts: u32) -> Duration {
+    let exp = BACKOFF_BASE_SECS.saturating_mul(1u64 << attempts.min(16));
+    let capped = exp.min(BACKOFF_MAX_SECS);
+    let half = capped / 2;
+    let jitter = rand::thread_rng().gen_range(0..=half.max(1));
+    Duration::from_secs(half + jitter)
+}
+
+// ============================================================================
+// ENQUEUE
+// ============================================================================
+
+/// True when an endpoint still has unsent entries — new requests must be
+/// queued behind them to keep replay order
+pub fn has_pending(endpoint: SpoolEndpoint) -> bool {
+    !queue_files(endpoint).is_empty()
+}
+
+/// Persist a request for later replay
+pub fn enqueue(endpoint: SpoolEndpoint, body: serde_json::Value) -> Result<(), String> {
+    enqueue_in(&get_spool_dir(), endpoint, body)
+}
+
+fn enqueue_in(root: &Path, endpoint: SpoolEndpoint, body: serde_json::Value) -> Result<(), String> {
+    let _io = SPOOL_IO.lock().map_err(|_| "Spool lock poisoned".to_string())?;
+
+    let dir = get_queue_dir(root, endpoint);
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create spool directory: {}", e))?;
+
+    let id = format!(
+        "{:020}_{:08}",
+        chrono::Utc::now().timestamp_millis(),
+        ENTRY_COUNTER.fetch_add(1, Ordering::SeqCst) % 100_000_000
+    );
+    let entry = SpoolEntry {
+        id: id.clone(),
+        endpoint,
+        body,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        attempts: 0,
+    };
+
+    let json = serde_json::to_vec(&entry)
+        .map_err(|e| format!("Failed to serialize spool entry: {}", e))?;
+    fs::write(dir.join(format!("{}.json", id)), json)
+        .map_err(|e| format!("Failed to write spool entry: {}", e))?;
+
+    enforce_caps(&dir, endpoint, MAX_QUEUE_ENTRIES, MAX_QUEUE_BYTES);
+    info!("Spooled {} request (queue depth: {})", endpoint.as_str(), list_queue_files(&dir).len());
+    Ok(())
+}
+
+/// Drop oldest entries when a queue exceeds its count or size cap
+fn enforce_caps(dir: &Path, endpoint: SpoolEndpoint, max_entries: usize, max_bytes: u64) {
+    let files = list_queue_files(dir);
+    let mut count = files.len();
+    let mut bytes: u64 = files.iter().map(|(_, size)| size).sum();
+    let mut dropped = 0u64;
+
+    for (path, size) in files {
+        if count <= max_entries && bytes <= max_bytes {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            count -= 1;
+            bytes = bytes.saturating_sub(size);
+            dropped += 1;
+        }
+    }
+
+    if dropped > 0 {
+        warn!("Spool cap reached for {} — dropped {} oldest entries", endpoint.as_str(), dropped);
+        if let Ok(mut queues) = QUEUES.lock() {
+            queues.entry(endpoint).or_default().dropped += dropped;
+        }
+    }
+}
+
+// ============================================================================
+// REPLAY
+// ============================================================================
+
+/// Send one spooled entry, returning the response body on success
+fn send_entry(client: &dyn BackendClient, entry: &SpoolEntry) -> Result<String, BackendError> {
+    match entry.endpoint {
+        SpoolEndpoint::FileUpload => client.upload_file_chunk(&entry.body).map(|_| String::new()),
+        _ => client.post_json(entry.endpoint.api_path(), &entry.body),
+    }
+}
+
+/// Post-processing for replayed responses (e.g. auto-quarantine verdicts)
+fn on_replayed(entry: &SpoolEntry, response_body: &str) {
+    if entry.endpoint == SpoolEndpoint::FileScan {
+        let file_path = entry.body.get("file_path").and_then(|v| v.as_str()).unwrap_or("");
+        let file_size = entry.body.get("file_size").and_then(|v| v.as_u64()).unwrap_or(0);
+        crate::file_watcher::handle_scan_response(file_path, file_size, response_body);
+    }
+}
+
+fn move_to_dead_letter(path: &Path, dead_dir: &Path) {
+    if fs::create_dir_all(dead_dir).is_ok() {
+        if let Some(name) = path.file_name() {
+            let _ = fs::rename(path, dead_dir.join(name));
+            return;
+        }
+    }
+    let _ = fs::remove_file(path);
+}
+
+/// Replay the queue in `dir` oldest first. Permanently rejected entries go to
+/// `dead_dir`; the first retryable failure stops the replay (never skip past
+/// it, that would break ordering) and is returned with the number replayed
+fn replay_queue(client: &dyn BackendClient, dir: &Path, dead_dir: &Path) -> (usize, Option<BackendError>) {
+    let mut replayed = 0;
+    for (path, _) in list_queue_files(dir) {
+        let mut entry: SpoolEntry = match fs::read(&path)
+            .ok()
+            .and_then(|b| serde_json::from_slice(&b).ok())
+        {
+            Some(e) => e,
+            None => {
+                warn!("Corrupt spool entry, moving to dead letter: {:?}", path);
+                move_to_dead_letter(&path, dead_dir);
+                continue;
+            }
+        };
+
+        match send_entry(client, &entry) {
+            Ok(body) => {
+                let _io = SPOOL_IO.lock();
+                let _ = fs::remove_file(&path);
+                drop(_io);
+
+                replayed += 1;
+                info!("Replayed spooled {} request {}", entry.endpoint.as_str(), entry.id);
+                on_replayed(&entry, &body);
+            }
+            Err(e) if !e.is_retryable() => {
+                error!("Spooled {} request rejected permanently: {}", entry.endpoint.as_str(), e);
+                move_to_dead_letter(&path, dead_dir);
+                if let Ok(mut queues) = QUEUES.lock() {
+                    let runtime = queues.entry(entry.endpoint).or_default();
+                    runtime.last_error = Some(e.to_string());
+                    runtime.last_error_at = Some(chrono::Utc::now().to_rfc3339());
+                }
+            }
+            Err(e) => {
+                entry.attempts += 1;
+                if let Ok(json) = serde_json::to_vec(&entry) {
+                    let _ = fs::write(&path, json);
+                }
+                return (replayed, Some(e));
+            }
+        }
+    }
+    (replayed, None)
+}
+
+fn token_fingerprint(token: &str) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    token.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Still waiting on a rejected token? Clears the pause once `token` differs
+fn auth_still_rejected(rejected: &mut Option<u64>, token: &str) -> bool {
+    match *rejected {
+        Some(fingerprint) if fingerprint == token_fingerprint(token) => true,
+        Some(_) => {
+            *rejected = None;
+            info!("Agent token changed — resuming spool replay");
+            false
+        }
+        None => false,
+    }
+}
+
+/// Replay is paused after a 401/403 until the agent token changes
+fn auth_paused() -> bool {
+    AUTH_REJECTED.lock()
+        .map(|mut rejected| auth_still_rejected(&mut rejected, &crate::agent_config::auth_token()))
+        .unwrap_or(false)
+}
+
+/// Replay one endpoint queue and update its runtime state
+fn flush_queue(endpoint: SpoolEndpoint) {
+    let due = QUEUES.lock()
+        .map(|q| q.get(&endpoint)
+            .and_then(|r| r.next_attempt_at)
+            .map(|t| Instant::now() >= t)
+            .unwrap_or(true))
+        .unwrap_or(true);
+    if !due {
+        return;
+    }
+
+    let root = get_spool_dir();
+    let (replayed, failure) = match ReqwestBackendClient::from_config() {
+        Ok(client) => replay_queue(&client, &get_queue_dir(&root, endpoint), &get_dead_letter_dir(&root)),
+        Err(e) => (0, Some(e)),
+    };
+
+    let Ok(mut queues) = QUEUES.lock() else { return };
+    let runtime = queues.entry(endpoint).or_default();
+    if replayed > 0 {
+        ONLINE.store(true, Ordering::SeqCst);
+        runtime.attempts = 0;
+        runtime.next_attempt_at = None;
+        runtime.last_success_at = Some(chrono::Utc::now().to_rfc3339());
+    }
+    let Some(e) = failure else { return };
+    runtime.last_error = Some(e.to_string());
+    runtime.last_error_at = Some(chrono::Utc::now().to_rfc3339());
+
+    if e.is_auth_failure() {
+        if let Ok(mut rejected) = AUTH_REJECTED.lock() {
+            *rejected = Some(token_fingerprint(&crate::agent_config::auth_token()));
+        }
+        warn!("Spool {} rejected by the backend ({}) — paused until the agent token changes", endpoint.as_str(), e);
+        return;
+    }
+
+    ONLINE.store(false, Ordering::SeqCst);
+    let delay = backoff_delay(runtime.attempts);
+    runtime.attempts += 1;
+    runtime.next_attempt_at = Some(Instant::now() + delay);
+    info!("Spool {} offline ({}), retry in {}s", endpoint.as_str(), e, delay.as_secs());
+}
+
+fn drop_retired_queues() {
+    for name in RETIRED_QUEUES {
+        let dir = get_spool_dir().join(name);
+        if !dir.exists() {
+            continue;
+        }
+        match fs::remove_dir_all(&dir) {
+            Ok(()) => info!("Dropped retired spool queue {}", name),
+            Err(e) => warn!("Failed to drop retired spool queue {}: {}", name, e),
+        }
+    }
+}
+
+/// Start the background replay thread
+pub fn start_spool_flusher() {
+    if FLUSHER_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    drop_retired_queues();
+
+    std::thread::spawn(|| {
+        info!("Outbound spool flusher started");
+        while FLUSHER_RUNNING.load(Ordering::SeqCst) {
+            if !auth_paused() {
+                for endpoint in SpoolEndpoint::ALL {
+                    flush_queue(endpoint);
+                }
+            }
+            std::thread::sleep(FLUSH_INTERVAL);
+        }
+        info!("Outbound spool flusher stopped");
+    });
+}
+
+pub fn stop_spool_flusher() {
+    FLUSHER_RUNNING.store(false, Ordering::SeqCst);
+}
+
+/// Clear backoff timers so the next flush cycle retries immediately
+pub fn retry_now() {
+    if let Ok(mut queues) = QUEUES.lock() {
+        for runtime in queues.values_mut() {
+            runtime.next_attempt_at = None;
+        }
+    }
+}
+
+// ============================================================================
+// STATUS
+// ============================================================================
+
+pub fn get_status() -> SpoolStatus {
+    let queues = QUEUES.lock();
+    let mut result = Vec::new();
+
+    for endpoint in SpoolEndpoint::ALL {
+        let files = queue_files(endpoint);
+        let runtime = queues.as_ref().ok().and_then(|q| q.get(&endpoint));
+
+        result.push(QueueStatus {
+            endpoint,
+            depth: files.len(),
+            bytes: files.iter().map(|(_, size)| size).sum(),
+            attempts: runtime.map(|r| r.attempts).unwrap_or(0),
+            last_error: runtime.and_then(|r| r.last_error.clone()),
+            last_error_at: runtime.and_then(|r| r.last_error_at.clone()),
+            last_success_at: runtime.and_then(|r| r.last_success_at.clone()),
+            next_retry_in_secs: runtime
+                .and_then(|r| r.next_attempt_at)
+                .map(|t| t.saturating_duration_since(Instant::now()).as_secs()),
+            dropped: runtime.map(|r| r.dropped).unwrap_or(0),
+        });
+    }
+
+    SpoolStatus {
+        online: ONLINE.load(Ordering::SeqCst),
+        auth_paused: auth_paused(),
+        total_depth: result.iter().map(|q| q.depth).sum(),
+        queues: result,
+    }
+}
+
+pub fn total_depth() -> usize {
+    SpoolEndpoint::ALL.iter().map(|e| queue_files(*e).len()).sum()
+}
+
+// ============================================================================
+// TAURI COMMANDS
+// ============================================================================
+
+#[tauri::command]
+pub fn get_spool_status() -> SpoolStatus {
+    get_status()
+}
+
+#[tauri::command]
+pub fn flush_spool_now() -> SpoolStatus {
+    retry_now();
+    get_status()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend_client::mock::{MockBackend, MockResponse};
+    use crate::backend_client::COMMAND_RESULT_PATH;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("cg_spool_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        root
+    }
+
+    fn queued(root: &Path) -> Vec<serde_json::Value> {
+        list_queue_files(&get_queue_dir(root, SpoolEndpoint::CommandResult))
+            .iter()
+            .map(|(path, _)| serde_json::from_slice::<SpoolEntry>(&fs::read(path).unwrap()).unwrap().body)
+            .collect()
+    }
+
+    #[test]
+    fn test_replay_keeps_order_and_dead_letters_rejects() {
+        let root = temp_root("replay");
+        for n in 1..=4 {
+            enqueue_in(&root, SpoolEndpoint::CommandResult, serde_json::json!({ "n": n })).unwrap();
+        }
+        let mock = MockBackend::start();
+        mock.on(COMMAND_RESULT_PATH, vec![
+            MockResponse::ok(serde_json::json!({})),
+            MockResponse::status(422),
+            MockResponse::status(503),
+            MockResponse::ok(serde_json::json!({})),
+        ]);
+        let (dir, dead) = (get_queue_dir(&root, SpoolEndpoint::CommandResult), get_dead_letter_dir(&root));
+
+        // 1 sent, 2 rejected for good, 3 hits an outage and blocks 4
+        let (replayed, failure) = replay_queue(&mock.client(), &dir, &dead);
+        assert_eq!(replayed, 1);
+        assert!(failure.unwrap().is_transient());
+        assert_eq!(queued(&root), vec![serde_json::json!({ "n": 3 }), serde_json::json!({ "n": 4 })]);
+        assert_eq!(list_queue_files(&dead).len(), 1);
+
+        let (replayed, failure) = replay_queue(&mock.client(), &dir, &dead);
+        assert_eq!((replayed, failure), (2, None));
+        let sent: Vec<i64> = mock.requests_to(COMMAND_RESULT_PATH).iter().map(|r| r.json()["n"].as_i64().unwrap()).collect();
+        assert_eq!(sent, vec![1, 2, 3, 3, 4]);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_auth_failure_keeps_entries_until_token_changes() {
+        let root = temp_root("auth");
+        enqueue_in(&root, SpoolEndpoint::CommandResult, serde_json::json!({ "n": 1 })).unwrap();
+        let mock = MockBackend::start();
+        mock.on(COMMAND_RESULT_PATH, vec![MockResponse::status(401)]);
+
+        let dir = get_queue_dir(&root, SpoolEndpoint::CommandResult);
+        let (replayed, failure) = replay_queue(&mock.client(), &dir, &get_dead_letter_dir(&root));
+        assert_eq!(replayed, 0);
+        assert!(failure.unwrap().is_auth_failure());
+        assert_eq!(queued(&root).len(), 1);
+        assert!(!get_dead_letter_dir(&root).exists());
+
+        let mut rejected = Some(token_fingerprint("old-token"));
+        assert!(auth_still_rejected(&mut rejected, "old-token"));
+        assert!(!auth_still_rejected(&mut rejected, "new-token"));
+        assert_eq!(rejected, None);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_caps_drop_oldest_entries() {
+        let root = temp_root("caps");
+        for n in 1..=5 {
+            enqueue_in(&root, SpoolEndpoint::CommandResult, serde_json::json!({ "n": n })).unwrap();
+        }
+        let dir = get_queue_dir(&root, SpoolEndpoint::CommandResult);
+
+        enforce_caps(&dir, SpoolEndpoint::CommandResult, 3, u64::MAX);
+        let numbers = |root: &Path| -> Vec<i64> { queued(root).iter().map(|b| b["n"].as_i64().unwrap()).collect() };
+        assert_eq!(numbers(&root), vec![3, 4, 5]);
+
+        let entry_size = list_queue_files(&dir)[0].1;
+        enforce_caps(&dir, SpoolEndpoint::CommandResult, 100, entry_size * 2);
+        assert_eq!(numbers(&root), vec![4, 5]);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_backoff_grows_and_caps() {
+        for attempts in 0..20 {
+            let delay = backoff_delay(attempts).as_secs();
+            let ceiling = (BACKOFF_BASE_SECS << attempts.min(16)).min(BACKOFF_MAX_SECS);
+            assert!(delay >= ceiling / 2, "attempt {} delay {} too small", attempts, delay);
+            assert!(delay <= ceiling.max(1), "attempt {} delay {} too large", attempts, delay);
+        }
+        assert!(backoff_delay(30).as_secs() <= BACKOFF_MAX_SECS);
+    }
+}
@@ -0,0 +1,330 @@
+//! Process Response Actions
+//! Suspend / resume / terminate / kill-tree on both platforms.
+//! Windows: NtSuspendProcess / NtResumeProcess + TerminateProcess.
+//! Linux: SIGSTOP / SIGCONT / SIGKILL delivered through a pidfd, so a PID
+//! recycled between lookup and signal is never hit (kill(2) fallback on
+//! kernels older than 5.3). Every action takes a `ProcessKey` and confirms
+//! the target is still that process instance before touching it
+
+use crate::process_identity::ProcessKey;
+use serde::Serialize;
+use std::collections::HashMap;
+use tracing::info;
+
+/// Process table row: (pid, parent pid, start time — 0 when unknown)
+pub type ProcessRow = (u32, u32, u64);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KillTreeReport {
+    pub root_pid: u32,
+    pub killed: Vec<u32>,
+    pub failed: Vec<(u32, String)>,
+}
+
+/// Never act on ourselves, init or the Windows System/Idle processes
+fn is_protected(pid: u32) -> bool {
+    pid == std::process::id() || pid <= 1 || (cfg!(windows) && pid == 4)
+}
+
+fn guard(pid: u32) -> Result<(), String> {
+    if is_protected(pid) {
+        Err(format!("Refusing to act on protected process (PID: {})", pid))
+    } else {
+        Ok(())
+    }
+}
+
+/// Root first, then descendants breadth-first. A child that started before its
+/// "parent" is a recycled-PID orphan, not a descendant
+pub fn descendants(root: u32, table: &[ProcessRow]) -> Vec<ProcessRow> {
+    let mut children: HashMap<u32, Vec<ProcessRow>> = HashMap::new();
+    for row in table {
+        if row.0 != row.1 {
+            children.entry(row.1).or_default().push(*row);
+        }
+    }
+    let Some(root_row) = table.iter().find(|r| r.0 == root).copied() else {
+        return Vec::new();
+    };
+
+    let mut out = vec![root_row];
+    let mut i = 0;
+    while i < out.len() {
+        let (pid, _, start) = out[i];
+        for child in children.get(&pid).into_iter().flatten() {
+            if child.2 >= start && !out.iter().any(|r| r.0 == child.0) {
+                out.push(*child);
+            }
+        }
+        i += 1;
+    }
+    out
+}
+
+// ============================================================================
+// LINUX (pidfd + signals)
+// ============================================================================
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::ProcessRow;
+    use crate::process_identity::{parse_stat_start_time, start_time, ProcessKey};
+    use std::fs;
+    use std::io;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+    pub fn process_table() -> Vec<ProcessRow> {
+        let Ok(entries) = fs::read_dir("/proc") else { return Vec::new() };
+        entries
+            .flatten()
+            .filter_map(|e| e.file_name().to_str()?.parse::<u32>().ok())
+            .filter_map(|pid| {
+                let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+                let ppid = crate::linux_net_monitor::parse_stat_ppid(&stat)?;
+                Some((pid, ppid, parse_stat_start_time(&stat)?))
+            })
+            .collect()
+    }
+
+    pub struct PidFd {
+        fd: OwnedFd,
+        pid: u32,
+    }
+
+    impl PidFd {
+        pub fn open(pid: u32) -> io::Result<PidFd> {
+            let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid as libc::pid_t, 0) };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(PidFd { fd: unsafe { OwnedFd::from_raw_fd(fd as i32) }, pid })
+        }
+
+        pub fn signal(&self, sig: i32) -> io::Result<()> {
+            let rc = unsafe {
+                libc::syscall(
+                    libc::SYS_pidfd_send_signal,
+                    self.fd.as_raw_fd(),
+                    sig,
+                    std::ptr::null::<libc::siginfo_t>(),
+                    0,
+                )
+            };
+            if rc < 0 { Err(io::Error::last_os_error()) } else { Ok(()) }
+        }
+    }
+
+    fn describe(pid: u32, e: io::Error) -> String {
+        match e.raw_os_error() {
+            Some(libc::ESRCH) => format!("Process not found (PID: {})", pid),
+            Some(libc::EPERM) => format!("Permission denied (PID: {})", pid),
+            _ => format!("Signal failed (PID: {}): {}", pid, e),
+        }
+    }
+
+    /// Pin the process with a pidfd, check it is still `key`, then signal
+    /// through the pidfd (the check cannot race with PID reuse once pinned)
+    pub fn signal(key: &ProcessKey, sig: i32) -> Result<(), String> {
+        let pid = key.pid;
+        let pidfd = match PidFd::open(pid) {
+            Ok(fd) => Some(fd),
+            Err(e) if e.raw_os_error() == Some(libc::ENOSYS) => None,
+            Err(e) => return Err(describe(pid, e)),
+        };
+        key.matches(start_time(pid))?;
+        match pidfd {
+            Some(fd) => fd.signal(sig).map_err(|e| describe(fd.pid, e)),
+            None => {
+                let rc = unsafe { libc::kill(pid as libc::pid_t, sig) };
+                if rc < 0 { Err(describe(pid, io::Error::last_os_error())) } else { Ok(()) }
+            }
+        }
+    }
+}
+
+// ============================================================================
+// WINDOWS (ntdll suspend/resume)
+// ============================================================================
+
+#[cfg(windows)]
+fn nt_suspend_resume(key: &ProcessKey, export: windows::core::PCSTR) -> Result<(), String> {
+    use windows::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows::Win32::System::LibraryLoader::{GetModuleHandleW, GetProcAddress};
+    use windows::Win32::System::Threading::{
+        OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_SUSPEND_RESUME,
+    };
+
+    let pid = key.pid;
+
+    unsafe {
+        let ntdll = GetModuleHandleW(windows::core::w!("ntdll.dll"))
+            .map_err(|e| format!("ntdll not loaded: {:?}", e))?;
+        let func = GetProcAddress(ntdll, export)
+            .ok_or_else(|| "ntdll export not found".to_string())?;
+        let handle = OpenProcess(PROCESS_SUSPEND_RESUME | PROCESS_QUERY_LIMITED_INFORMATION, false, pid)
+            .map_err(|e| format!("OpenProcess failed (PID: {}): {:?}", pid, e))?;
+        // Identity check on the open handle — it pins the process
+        if let Err(e) = key.matches(crate::process_identity::handle_start_time(handle)) {
+            let _ = CloseHandle(handle);
+            return Err(e);
+        }
+
+        type NtProcessFn = unsafe extern "system" fn(HANDLE) -> i32;
+        let nt_fn: NtProcessFn = std::mem::transmute(func);
+        let status = nt_fn(handle);
+        let _ = CloseHandle(handle);
+        if status >= 0 { Ok(()) } else { Err(format!("NTSTATUS 0x{:08X} (PID: {})", status, pid)) }
+    }
+}
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+pub fn suspend(key: &ProcessKey) -> Result<(), String> {
+    guard(key.pid)?;
+    #[cfg(windows)]
+    { nt_suspend_resume(key, windows::core::s!("NtSuspendProcess")) }
+    #[cfg(target_os = "linux")]
+    { linux::signal(key, libc::SIGSTOP) }
+    #[cfg(not(any(windows, target_os = "linux")))]
+    { Err(format!("Suspend not supported on this platform (PID: {})", key.pid)) }
+}
+
+pub fn resume(key: &ProcessKey) -> Result<(), String> {
+    #[cfg(windows)]
+    { nt_suspend_resume(key, windows::core::s!("NtResumeProcess")) }
+    #[cfg(target_os = "linux")]
+    { linux::signal(key, libc::SIGCONT) }
+    #[cfg(not(any(windows, target_os = "linux")))]
+    { Err(format!("Resume not supported on this platform (PID: {})", key.pid)) }
+}
+
+pub fn terminate(key: &ProcessKey) -> Result<(), String> {
+    guard(key.pid)?;
+    #[cfg(windows)]
+    { crate::process_monitor::block_process(key) }
+    #[cfg(target_os = "linux")]
+    { linux::signal(key, libc::SIGKILL) }
+    #[cfg(not(any(windows, target_os = "linux")))]
+    { Err(format!("Blocking not supported on this platform (PID: {})", key.pid)) }
+}
+
+#[cfg(windows)]
+fn process_table() -> Vec<ProcessRow> {
+    crate::process_monitor::enumerate_processes()
+        .map(|procs| procs.into_iter()
+            .map(|p| (p.pid, p.parent_pid, ProcessKey::capture(p.pid).start_time))
+            .collect())
+        .unwrap_or_default()
+}
+
+#[cfg(target_os = "linux")]
+fn process_table() -> Vec<ProcessRow> {
+    linux::process_table()
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn process_table() -> Vec<ProcessRow> {
+    Vec::new()
+}
+
+/// Freeze the whole tree first (nothing can fork or respawn a sibling while we
+/// work), then kill every member. Children forked between the first snapshot
+/// and the freeze are picked up by a second pass
+pub fn kill_tree(root_key: &ProcessKey) -> Result<KillTreeReport, String> {
+    let root = root_key.pid;
+    guard(root)?;
+    root_key.verify()?;
+    let mut tree = descendants(root, &process_table());
+    if tree.is_empty() {
+        return Err(format!("Process not found (PID: {})", root));
+    }
+
+    let mut frozen: Vec<ProcessRow> = Vec::new();
+    for _ in 0..2 {
+        for row in &tree {
+            if !frozen.iter().any(|f| f.0 == row.0) && !is_protected(row.0) {
+                let _ = suspend(&ProcessKey::new(row.0, row.2));
+                frozen.push(*row);
+            }
+        }
+        tree = descendants(root, &process_table());
+    }
+
+    let mut report = KillTreeReport { root_pid: root, killed: Vec::new(), failed: Vec::new() };
+    for (pid, _, start) in frozen {
+        match terminate(&ProcessKey::new(pid, start)) {
+            Ok(()) => report.killed.push(pid),
+            Err(e) => report.failed.push((pid, e)),
+        }
+    }
+    info!("Process tree {} killed: {} processes, {} failures", root, report.killed.len(), report.failed.len());
+    Ok(report)
+}
+
+// ============================================================================
+// TAURI COMMANDS
+// ============================================================================
+
+// `start_time` (from a process listing or detection) pins the exact instance;
+// without it the process currently holding `pid` is targeted
+
+#[tauri::command]
+pub fn suspend_process(pid: u32, start_time: Option<u64>) -> Result<String, String> {
+    suspend(&ProcessKey::resolve(pid, start_time))?;
+    info!("Suspended PID {}", pid);
+    Ok(format!("Process {} suspended", pid))
+}
+
+#[tauri::command]
+pub fn resume_process(pid: u32, start_time: Option<u64>) -> Result<String, String> {
+    resume(&ProcessKey::resolve(pid, start_time))?;
+    info!("Resumed PID {}", pid);
+    Ok(format!("Process {} resumed", pid))
+}
+
+#[tauri::command]
+pub fn kill_process_tree(pid: u32, start_time: Option<u64>) -> Result<KillTreeReport, String> {
+    kill_tree(&ProcessKey::resolve(pid, start_time))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_descendants_skip_recycled_pids() {
+        let table = [
+            (1, 0, 0),
+            (100, 1, 500),
+            (101, 100, 600),
+            (102, 101, 700),
+            // PID 100's "child" that started before it: parent PID was recycled
+            (103, 100, 400),
+            (104, 103, 800),
+            (200, 1, 550),
+        ];
+        let pids: Vec<u32> = descendants(100, &table).iter().map(|r| r.0).collect();
+        assert_eq!(pids, vec![100, 101, 102]);
+        assert!(descendants(999, &table).is_empty());
+        assert!(is_protected(1) && is_protected(std::process::id()));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_linux_signals_confirm_identity() {
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+        let pid = child.id();
+        let key = ProcessKey::capture(pid);
+        suspend(&key).unwrap();
+        resume(&key).unwrap();
+        // A stale start time (PID reuse) must not be signalled
+        let stale = ProcessKey::new(pid, key.start_time + 1);
+        assert!(terminate(&stale).is_err());
+        assert!(kill_tree(&stale).is_err());
+        let report = kill_tree(&key).unwrap();
+        assert_eq!(report.killed, vec![pid]);
+        assert!(!child.wait().unwrap().success());
+    }
+}
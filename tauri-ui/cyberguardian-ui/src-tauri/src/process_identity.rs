@@ -0,0 +1,138 @@
+//! Process Identity
+//! PIDs are recycled, so a bare PID cannot key caches, detections or response
+//! actions. `ProcessKey` = (pid, start time) names one process instance:
+//! Windows creation FILETIME, Linux /proc/<pid>/stat starttime (clock ticks
+//! since boot). A start time of 0 means it could not be read
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct ProcessKey {
+    pub pid: u32,
+    pub start_time: u64,
+}
+
+impl ProcessKey {
+    pub fn new(pid: u32, start_time: u64) -> Self {
+        ProcessKey { pid, start_time }
+    }
+
+    /// Key of whatever process currently owns `pid`
+    pub fn capture(pid: u32) -> Self {
+        ProcessKey { pid, start_time: start_time(pid).unwrap_or(0) }
+    }
+
+    /// `start_time` from the caller if it has one, otherwise capture now
+    pub fn resolve(pid: u32, start_time: Option<u64>) -> Self {
+        match start_time {
+            Some(start) => ProcessKey::new(pid, start),
+            None => ProcessKey::capture(pid),
+        }
+    }
+
+    pub fn is_known(&self) -> bool {
+        self.start_time != 0
+    }
+
+    /// Still the same process instance (unknown identities never match)
+    pub fn is_current(&self) -> bool {
+        self.is_known() && start_time(self.pid) == Some(self.start_time)
+    }
+
+    /// Gate for response actions
+    pub fn verify(&self) -> Result<(), String> {
+        if !self.is_known() {
+            Err(format!("Cannot confirm identity of PID {}", self.pid))
+        } else if !self.is_current() {
+            Err(format!("PID {} no longer refers to the same process", self.pid))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Compare against a start time read from an already-open handle / pidfd
+    pub fn matches(&self, start_time: Option<u64>) -> Result<(), String> {
+        match start_time {
+            Some(start) if self.is_known() && start == self.start_time => Ok(()),
+            Some(_) => Err(format!("PID {} no longer refers to the same process", self.pid)),
+            None => Err(format!("Cannot confirm identity of PID {}", self.pid)),
+        }
+    }
+}
+
+/// "pid@start_time" — stable process entity id for telemetry
+impl fmt::Display for ProcessKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.pid, self.start_time)
+    }
+}
+
+// ============================================================================
+// START TIME
+// ============================================================================
+
+/// Field 22 (starttime) of /proc/<pid>/stat; comm may contain spaces and ')'
+pub fn parse_stat_start_time(stat: &str) -> Option<u64> {
+    stat.rsplit_once(')')?.1.split_whitespace().nth(19)?.parse().ok()
+}
+
+#[cfg(target_os = "linux")]
+pub fn start_time(pid: u32) -> Option<u64> {
+    parse_stat_start_time(&std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?)
+}
+
+/// Creation time of an already-open handle (needs PROCESS_QUERY_LIMITED_INFORMATION)
+#[cfg(windows)]
+pub fn handle_start_time(handle: windows::Win32::Foundation::HANDLE) -> Option<u64> {
+    use windows::Win32::Foundation::FILETIME;
+    use windows::Win32::System::Threading::GetProcessTimes;
+
+    let (mut creation, mut exit, mut kernel, mut user) =
+        (FILETIME::default(), FILETIME::default(), FILETIME::default(), FILETIME::default());
+    unsafe { GetProcessTimes(handle, &mut creation, &mut exit, &mut kernel, &mut user) }.ok()?;
+    Some(((creation.dwHighDateTime as u64) << 32) | creation.dwLowDateTime as u64)
+}
+
+#[cfg(windows)]
+pub fn start_time(pid: u32) -> Option<u64> {
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::{OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION};
+
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let start = handle_start_time(handle);
+        let _ = CloseHandle(handle);
+        start
+    }
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+pub fn start_time(_pid: u32) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_identity_checks() {
+        let stat = "4242 (evil (x) proc) S 1 4242 4242 0 -1 4194560 100 0 0 0 1 2 0 0 20 0 1 0 987654 1000 10";
+        assert_eq!(parse_stat_start_time(stat), Some(987654));
+        assert_eq!(parse_stat_start_time("garbage"), None);
+
+        let key = ProcessKey::new(4242, 987654);
+        assert!(key.matches(Some(987654)).is_ok());
+        assert!(key.matches(Some(987655)).unwrap_err().contains("no longer"));
+        assert!(ProcessKey::new(4242, 0).matches(Some(0)).is_err());
+        assert_eq!(key.to_string(), "4242@987654");
+
+        #[cfg(target_os = "linux")]
+        {
+            let me = ProcessKey::capture(std::process::id());
+            assert!(me.is_known() && me.verify().is_ok());
+            assert!(ProcessKey::new(me.pid, me.start_time + 1).verify().is_err());
+        }
+    }
+}
@@ -0,0 +1,536 @@
+//! Process Inventory Uploads
+//! Full snapshot first, then only started / exited / changed processes (an
+//! empty delta goes out only now and then as a keepalive).
+//! Every upload carries a sequence number (and the base it applies to) so the
+//! backend can detect gaps and request a resync; payloads are gzip/zstd compressed
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use tracing::info;
+
+use crate::api_client::ProcessInfo;
+use crate::backend_client::{BackendClient, BackendError};
+use crate::process_identity::ProcessKey;
+use crate::telemetry_schema::{self, ProcessActivity, TelemetryEvent, TelemetrySchema};
+
+/// CPU change (percentage points) that counts as "changed"
+const CPU_CHANGE_THRESHOLD: f32 = 5.0;
+/// Memory change (MB) that counts as "changed"
+const MEMORY_CHANGE_THRESHOLD_MB: f64 = 10.0;
+/// Empty deltas are not sent, except every Nth in a row as a keepalive
+const EMPTY_DELTA_KEEPALIVE: u64 = 10;
+
+// ============================================================================
+// SETTINGS
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Value for the Content-Encoding header
+    pub fn content_encoding(&self) -> Option<&'static str> {
+        match self {
+            Compression::None => None,
+            Compression::Gzip => Some("gzip"),
+            Compression::Zstd => Some("zstd"),
+        }
+    }
+}
+
+/// Inventory upload settings (persisted in inventory_settings.json)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InventorySettings {
+    pub interval_secs: u64,
+    pub compression: Compression,
+    /// Send a full snapshot after this many deltas even without a resync request
+    pub full_snapshot_every: u64,
+}
+
+impl Default for InventorySettings {
+    fn default() -> Self {
+        InventorySettings {
+            interval_secs: 30,
+            compression: Compression::Zstd,
+            full_snapshot_every: 120,
+        }
+    }
+}
+
+impl InventorySettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(5..=3600).contains(&self.interval_secs) {
+            return Err("Upload interval must be between 5 and 3600 seconds".to_string());
+        }
+        if self.full_snapshot_every == 0 {
+            return Err("full_snapshot_every must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn get_settings_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("CyberGuardian")
+        .join("inventory_settings.json")
+}
+
+pub fn load_settings() -> InventorySettings {
+    fs::read_to_string(get_settings_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_settings(settings: &InventorySettings) -> Result<(), String> {
+    settings.validate()?;
+    let path = get_settings_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create settings dir: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write settings: {}", e))
+}
+
+// ============================================================================
+// SNAPSHOT / DELTA TRACKING
+// ============================================================================
+
+/// One inventory upload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum InventoryUpload {
+    Full {
+        agent_id: String,
+        seq: u64,
+        captured_at: String,
+        processes: Vec<ProcessInfo>,
+    },
+    Delta {
+        agent_id: String,
+        seq: u64,
+        /// Sequence this delta applies on top of — a mismatch means a gap
+        base_seq: u64,
+        captured_at: String,
+        started: Vec<ProcessInfo>,
+        exited: Vec<ProcessKey>,
+        changed: Vec<ProcessInfo>,
+    },
+}
+
+impl InventoryUpload {
+    pub fn seq(&self) -> u64 {
+        match self {
+            InventoryUpload::Full { seq, .. } | InventoryUpload::Delta { seq, .. } => *seq,
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        matches!(self, InventoryUpload::Full { .. })
+    }
+
+    /// Nothing started, exited or changed
+    pub fn is_empty_delta(&self) -> bool {
+        matches!(self, InventoryUpload::Delta { started, exited, changed, .. }
+            if started.is_empty() && exited.is_empty() && changed.is_empty())
+    }
+}
+
+fn has_changed(old: &ProcessInfo, new: &ProcessInfo) -> bool {
+    old.name != new.name
+        || old.exe_path != new.exe_path
+        || old.parent_pid != new.parent_pid
+        || old.username != new.username
+        || old.thread_count != new.thread_count
+        || (old.cpu_percent - new.cpu_percent).abs() >= CPU_CHANGE_THRESHOLD
+        || (old.memory_mb - new.memory_mb).abs() >= MEMORY_CHANGE_THRESHOLD_MB
+}
+
+/// A recycled PID shows up as exited + started, never as changed
+fn process_key(process: &ProcessInfo) -> ProcessKey {
+    ProcessKey::new(process.pid, process.start_time)
+}
+
+/// Tracks what the backend has acknowledged and builds the next upload
+#[derive(Debug, Default)]
+pub struct InventoryTracker {
+    /// Last acknowledged state (process instance -> process)
+    baseline: HashMap<ProcessKey, ProcessInfo>,
+    /// Sequence of the last acknowledged upload (0 = nothing acknowledged)
+    acked_seq: u64,
+    next_seq: u64,
+    deltas_since_full: u64,
+    needs_full: bool,
+    /// Empty deltas skipped since the last acknowledged upload
+    skipped_empty: u64,
+}
+
+impl InventoryTracker {
+    pub fn new() -> Self {
+        InventoryTracker { needs_full: true, next_seq: 1, ..Default::default() }
+    }
+
+    /// Build the next upload from the current process list
+    pub fn next_upload(
+        &mut self,
+        agent_id: &str,
+        current: &[ProcessInfo],
+        full_snapshot_every: u64,
+    ) -> InventoryUpload {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        let captured_at = chrono::Utc::now().to_rfc3339();
+
+        if self.needs_full || self.deltas_since_full >= full_snapshot_every {
+            return InventoryUpload::Full {
+                agent_id: agent_id.to_string(),
+                seq,
+                captured_at,
+                processes: current.to_vec(),
+            };
+        }
+
+        let mut started = Vec::new();
+        let mut changed = Vec::new();
+        for process in current {
+            match self.baseline.get(&process_key(process)) {
+                None => started.push(process.clone()),
+                Some(old) if has_changed(old, process) => changed.push(process.clone()),
+                Some(_) => {}
+            }
+        }
+        let mut exited: Vec<ProcessKey> = self.baseline.keys()
+            .filter(|key| !current.iter().any(|p| process_key(p) == **key))
+            .copied()
+            .collect();
+        exited.sort_unstable();
+
+        InventoryUpload::Delta {
+            agent_id: agent_id.to_string(),
+            seq,
+            base_seq: self.acked_seq,
+            captured_at,
+            started,
+            exited,
+            changed,
+        }
+    }
+
+    /// Drop `upload` unsent if it is an empty delta and no keepalive is due.
+    /// Its sequence number is handed out again, so the backend sees no gap
+    pub fn skip_empty(&mut self, upload: &InventoryUpload) -> bool {
+        if !upload.is_empty_delta() || self.skipped_empty + 1 >= EMPTY_DELTA_KEEPALIVE {
+            return false;
+        }
+        self.skipped_empty += 1;
+        self.next_seq = upload.seq();
+        true
+    }
+
+    /// The backend accepted `upload` — it becomes the new baseline
+    pub fn acknowledge(&mut self, upload: &InventoryUpload) {
+        self.skipped_empty = 0;
+        match upload {
+            InventoryUpload::Full { seq, processes, .. } => {
+                self.baseline = processes.iter().map(|p| (process_key(p), p.clone())).collect();
+                self.acked_seq = *seq;
+                self.deltas_since_full = 0;
+                self.needs_full = false;
+            }
+            InventoryUpload::Delta { seq, started, exited, changed, .. } => {
+                for key in exited {
+                    self.baseline.remove(key);
+                }
+                for process in started.iter().chain(changed.iter()) {
+                    self.baseline.insert(process_key(process), process.clone());
+                }
+                self.acked_seq = *seq;
+                self.deltas_since_full += 1;
+            }
+        }
+    }
+
+    /// Upload lost or backend asked for a resync — next upload is a full snapshot
+    pub fn request_resync(&mut self) {
+        self.needs_full = true;
+    }
+}
+
+// ============================================================================
+// ENCODING + UPLOAD
+// ============================================================================
+
+/// Re-shape process entries into OCSF / ECS (Native leaves the payload untouched)
+fn apply_schema(upload: &InventoryUpload, value: &mut Value, schema: TelemetrySchema) {
+    if schema == TelemetrySchema::Native {
+        return;
+    }
+    let lists: [(&str, &[ProcessInfo], ProcessActivity); 3] = match upload {
+        InventoryUpload::Full { processes, .. } => [
+            ("processes", processes, ProcessActivity::Observed),
+            ("started", &[], ProcessActivity::Launch),
+            ("changed", &[], ProcessActivity::Observed),
+        ],
+        InventoryUpload::Delta { started, changed, .. } => [
+            ("processes", &[], ProcessActivity::Observed),
+            ("started", started, ProcessActivity::Launch),
+            ("changed", changed, ProcessActivity::Observed),
+        ],
+    };
+    for (key, processes, activity) in lists {
+        if let Some(slot) = value.get_mut(key) {
+            *slot = processes.iter()
+                .map(|p| telemetry_schema::normalize(&TelemetryEvent::Process(p, activity), schema))
+                .collect();
+        }
+    }
+    value["schema"] = Value::from(schema.as_str());
+}
+
+pub fn encode(
+    upload: &InventoryUpload,
+    compression: Compression,
+    schema: TelemetrySchema,
+) -> Result<Vec<u8>, String> {
+    let mut value = serde_json::to_value(upload).map_err(|e| format!("Failed to serialize inventory: {}", e))?;
+    apply_schema(upload, &mut value, schema);
+    let json = serde_json::to_vec(&crate::redaction::redact_outbound(&value))
+        .map_err(|e| format!("Failed to serialize inventory: {}", e))?;
+    match compression {
+        Compression::None => Ok(json),
+        Compression::Gzip => {
+            let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(&json).map_err(|e| format!("gzip failed: {}", e))?;
+            encoder.finish().map_err(|e| format!("gzip failed: {}", e))
+        }
+        Compression::Zstd => zstd::encode_all(json.as_slice(), 3).map_err(|e| format!("zstd failed: {}", e)),
+    }
+}
+
+/// Backend asks for a full snapshot (`{"resync": true}` or 409 Conflict)
+fn wants_resync(ack: &Value) -> bool {
+    ack.get("resync").and_then(|v| v.as_bool()).unwrap_or(false)
+}
+
+/// Upload one inventory step and update the tracker (None = nothing changed, skipped)
+pub fn upload_step(
+    client: &dyn BackendClient,
+    tracker: &mut InventoryTracker,
+    agent_id: &str,
+    current: &[ProcessInfo],
+    settings: &InventorySettings,
+) -> Result<Option<InventoryUpload>, BackendError> {
+    let upload = tracker.next_upload(agent_id, current, settings.full_snapshot_every);
+    if tracker.skip_empty(&upload) {
+        return Ok(None);
+    }
+    let body = encode(&upload, settings.compression, telemetry_schema::upload_schema())
+        .map_err(BackendError::InvalidResponse)?;
+
+    match client.upload_inventory(body, settings.compression.content_encoding()) {
+        Ok(ack) => {
+            tracker.acknowledge(&upload);
+            if wants_resync(&ack) {
+                info!("Backend requested inventory resync");
+                tracker.request_resync();
+            }
+            Ok(Some(upload))
+        }
+        Err(BackendError::Status { status: 409, body }) => {
+            info!("Backend detected inventory gap — resyncing");
+            tracker.request_resync();
+            Err(BackendError::Status { status: 409, body })
+        }
+        Err(e) => {
+            // The backend may or may not have applied it — start over from a full snapshot
+            tracker.request_resync();
+            Err(e)
+        }
+    }
+}
+
+// ============================================================================
+// TAURI COMMANDS
+// ============================================================================
+
+#[tauri::command]
+pub fn get_inventory_settings() -> InventorySettings {
+    load_settings()
+}
+
+/// Takes effect on the next upload cycle
+#[tauri::command]
+pub fn set_inventory_settings(settings: InventorySettings) -> Result<InventorySettings, String> {
+    save_settings(&settings)?;
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend_client::mock::{MockBackend, MockResponse};
+    use crate::backend_client::PROCESS_INVENTORY_PATH;
+    use std::io::Read;
+
+    fn proc(pid: u32, name: &str, cpu: f32) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: name.to_string(),
+            parent_pid: 1,
+            thread_count: 4,
+            exe_path: format!("C:\\{}", name),
+            cpu_percent: cpu,
+            memory_mb: 50.0,
+            username: "admin".to_string(),
+            start_time: 0,
+        }
+    }
+
+    #[test]
+    fn test_full_then_delta() {
+        let mut tracker = InventoryTracker::new();
+        let first = vec![proc(10, "a.exe", 1.0), proc(20, "b.exe", 1.0), proc(30, "c.exe", 1.0)];
+
+        let full = tracker.next_upload("agent", &first, 100);
+        assert!(full.is_full());
+        assert_eq!(full.seq(), 1);
+        tracker.acknowledge(&full);
+
+        // 20 exits, 40 starts, 30 spikes CPU, 10 jitters below threshold
+        let second = vec![proc(10, "a.exe", 2.0), proc(30, "c.exe", 50.0), proc(40, "d.exe", 0.0)];
+        match tracker.next_upload("agent", &second, 100) {
+            InventoryUpload::Delta { seq, base_seq, started, exited, changed, .. } => {
+                assert_eq!((seq, base_seq), (2, 1));
+                assert_eq!(started.iter().map(|p| p.pid).collect::<Vec<_>>(), vec![40]);
+                assert_eq!(exited, vec![ProcessKey::new(20, 0)]);
+                assert_eq!(changed.iter().map(|p| p.pid).collect::<Vec<_>>(), vec![30]);
+            }
+            other => panic!("expected delta, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_recycled_pid_is_exit_and_start() {
+        let mut tracker = InventoryTracker::new();
+        let old = ProcessInfo { start_time: 100, ..proc(10, "a.exe", 1.0) };
+        let full = tracker.next_upload("agent", &[old], 100);
+        tracker.acknowledge(&full);
+
+        // Same PID, different instance — not a "changed" a.exe
+        let new = ProcessInfo { start_time: 200, ..proc(10, "evil.exe", 1.0) };
+        match tracker.next_upload("agent", &[new], 100) {
+            InventoryUpload::Delta { started, exited, changed, .. } => {
+                assert_eq!(started.iter().map(|p| p.start_time).collect::<Vec<_>>(), vec![200]);
+                assert_eq!(exited, vec![ProcessKey::new(10, 100)]);
+                assert!(changed.is_empty());
+            }
+            other => panic!("expected delta, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_resync_and_periodic_full() {
+        let mut tracker = InventoryTracker::new();
+        let procs = vec![proc(10, "a.exe", 1.0)];
+        let full = tracker.next_upload("agent", &procs, 2);
+        tracker.acknowledge(&full);
+
+        let delta = tracker.next_upload("agent", &procs, 2);
+        assert!(delta.is_empty_delta());
+        tracker.acknowledge(&delta);
+        let delta = tracker.next_upload("agent", &procs, 2);
+        tracker.acknowledge(&delta);
+        assert!(tracker.next_upload("agent", &procs, 2).is_full());
+
+        tracker.request_resync();
+        assert!(tracker.next_upload("agent", &procs, 100).is_full());
+    }
+
+    #[test]
+    fn test_compression_round_trip() {
+        let upload = InventoryTracker::new().next_upload("agent", &[proc(10, "a.exe", 1.0)], 10);
+        let plain = encode(&upload, Compression::None, TelemetrySchema::Native).unwrap();
+
+        let gz = encode(&upload, Compression::Gzip, TelemetrySchema::Native).unwrap();
+        let mut decoded = Vec::new();
+        flate2::read::GzDecoder::new(gz.as_slice()).read_to_end(&mut decoded).unwrap();
+        assert_eq!(decoded, plain);
+
+        let zst = encode(&upload, Compression::Zstd, TelemetrySchema::Native).unwrap();
+        assert_eq!(zstd::decode_all(zst.as_slice()).unwrap(), plain);
+    }
+
+    #[test]
+    fn test_ocsf_inventory_shape() {
+        let upload = InventoryTracker::new().next_upload("agent", &[proc(10, "a.exe", 1.0)], 10);
+        let body: Value = serde_json::from_slice(&encode(&upload, Compression::None, TelemetrySchema::Ocsf).unwrap()).unwrap();
+        assert_eq!(body["schema"], "ocsf");
+        assert_eq!(body["seq"], 1);
+        assert_eq!(body["processes"][0]["class_uid"], 1007);
+        assert_eq!(body["processes"][0]["process"]["pid"], 10);
+    }
+
+    #[test]
+    fn test_upload_gap_triggers_full_resync() {
+        let mock = MockBackend::start();
+        mock.on(PROCESS_INVENTORY_PATH, vec![
+            MockResponse::ok(serde_json::json!({"ok": true})),
+            MockResponse::status(409),
+            MockResponse::ok(serde_json::json!({"ok": true})),
+        ]);
+        let client = mock.client();
+        let settings = InventorySettings::default();
+        let mut tracker = InventoryTracker::new();
+        let procs = vec![proc(10, "a.exe", 1.0)];
+
+        let changed = vec![proc(10, "a.exe", 1.0), proc(20, "b.exe", 1.0)];
+
+        assert!(upload_step(&client, &mut tracker, "agent", &procs, &settings).unwrap().unwrap().is_full());
+        assert!(upload_step(&client, &mut tracker, "agent", &changed, &settings).is_err());
+        let resync = upload_step(&client, &mut tracker, "agent", &changed, &settings).unwrap().unwrap();
+        assert!(resync.is_full());
+        assert_eq!(resync.seq(), 3);
+
+        let requests = mock.requests_to(PROCESS_INVENTORY_PATH);
+        assert_eq!(requests[0].content_encoding.as_deref(), Some("zstd"));
+        let body: Value = serde_json::from_slice(&zstd::decode_all(requests[0].body_bytes.as_slice()).unwrap()).unwrap();
+        assert_eq!(body["kind"], "full");
+        assert_eq!(body["seq"], 1);
+    }
+
+    #[test]
+    fn test_empty_deltas_are_skipped_except_keepalive() {
+        let mock = MockBackend::start();
+        mock.on(PROCESS_INVENTORY_PATH, vec![MockResponse::ok(serde_json::json!({"ok": true}))]);
+        let client = mock.client();
+        let settings = InventorySettings::default();
+        let mut tracker = InventoryTracker::new();
+        let procs = vec![proc(10, "a.exe", 1.0)];
+
+        upload_step(&client, &mut tracker, "agent", &procs, &settings).unwrap().unwrap();
+        for _ in 1..EMPTY_DELTA_KEEPALIVE {
+            assert!(upload_step(&client, &mut tracker, "agent", &procs, &settings).unwrap().is_none());
+        }
+        let keepalive = upload_step(&client, &mut tracker, "agent", &procs, &settings).unwrap().unwrap();
+        assert!(keepalive.is_empty_delta());
+        // Skipped deltas never consumed a sequence number
+        assert_eq!(keepalive.seq(), 2);
+        assert_eq!(mock.requests_to(PROCESS_INVENTORY_PATH).len(), 2);
+
+        let started = vec![proc(10, "a.exe", 1.0), proc(20, "b.exe", 1.0)];
+        let delta = upload_step(&client, &mut tracker, "agent", &started, &settings).unwrap().unwrap();
+        assert_eq!(delta.seq(), 3);
+    }
+}
@@ -4,6 +4,9 @@
     use serde::{Deserialize, Serialize};
     use std::collections::HashMap;
     use std::sync::Mutex;
+    use tracing::{info, warn};
+    use crate::process_identity::ProcessKey;
+    use crate::enforcement::{Confidence, EnforcementMode};
 
     #[cfg(target_os = "windows")]
     use windows::Win32::System::Diagnostics::ToolHelp::{
@@ -27,8 +30,9 @@
     #[cfg(target_os = "windows")]
     const PROCESS_QUERY_LIMITED: PROCESS_ACCESS_RIGHTS = PROCESS_ACCESS_RIGHTS(0x1000);
 
+    /// Ключ (pid, start time) — рециклиран PID започва с чист sample
     struct CpuCache {
-        times: HashMap<u32, (u64, std::time::Instant)>,
+        times: HashMap<ProcessKey, (u64, std::time::Instant)>,
     }
 
     lazy_static::lazy_static! {
@@ -48,6 +52,9 @@
         pub cpu_percent: f32,
         pub memory_mb: f64,
         pub username: String,
+        /// Време на стартиране — заедно с pid идентифицира процеса (ProcessKey)
+        #[serde(default)]
+        pub start_time: u64,
     }
 
     /// Process monitoring statistics
@@ -63,12 +70,13 @@
         ((ft.dwHighDateTime as u64) << 32) | (ft.dwLowDateTime as u64)
     }
 
+    /// (kernel+user CPU време, време на създаване)
     #[cfg(target_os = "windows")]
-    fn get_raw_cpu_time(pid: u32) -> u64 {
+    fn get_raw_cpu_time(pid: u32) -> (u64, u64) {
         unsafe {
             let handle = match OpenProcess(PROCESS_QUERY_LIMITED, false, pid) {
                 Ok(h) => h,
-                Err(_) => return 0,
+                Err(_) => return (0, 0),
             };
 
             let mut creation = FILETIME::default();
@@ -80,28 +88,30 @@
             let _ = CloseHandle(handle);
 
             if result.is_ok() {
-                filetime_to_u64(kernel).saturating_add(filetime_to_u64(user))
+                (filetime_to_u64(kernel).saturating_add(filetime_to_u64(user)), filetime_to_u64(creation))
             } else {
-                0
+                (0, 0)
             }
         }
     }
 
     #[cfg(not(target_os = "windows"))]
-    fn get_raw_cpu_time(_pid: u32) -> u64 {
-        0
+    fn get_raw_cpu_time(pid: u32) -> (u64, u64) {
+        (0, crate::process_identity::start_time(pid).unwrap_or(0))
     }
 
-    fn calculate_cpu_percent(pid: u32) -> f32 {
-        let current_time = get_raw_cpu_time(pid);
+    /// CPU % спрямо предишния sample на същия процес
+    fn calculate_cpu_percent(pid: u32) -> (f32, ProcessKey) {
+        let (current_time, start_time) = get_raw_cpu_time(pid);
+        let key = ProcessKey::new(pid, start_time);
         let now = std::time::Instant::now();
 
         let mut cache = match CPU_CACHE.lock() {
             Ok(c) => c,
-            Err(_) => return 0.0,
+            Err(_) => return (0.0, key),
         };
 
-        let cpu = if let Some((prev_time, prev_instant)) = cache.times.get(&pid) {
+        let cpu = if let Some((prev_time, prev_instant)) = cache.times.get(&key) {
             let elapsed_ns = now.duration_since(*prev_instant).as_nanos() as f64;
             let cpu_delta = current_time.saturating_sub(*prev_time) as f64;
 
@@ -116,8 +126,12 @@
             0.0
         };
 
-        cache.times.insert(pid, (current_time, now));
-        cpu
+        // Излезлите процеси не се връщат със същия ключ — чистим старите
+        if cache.times.len() > 4096 {
+            cache.times.retain(|_, (_, at)| now.duration_since(*at).as_secs() < 60);
+        }
+        cache.times.insert(key, (current_time, now));
+        (cpu, key)
     }
 
     /// Get memory usage for a process (in MB)
@@ -302,19 +316,12 @@
         }
     }
 
+    /// argv от /proc/<pid>/cmdline — аргументите са разделени с NUL
     #[cfg(not(target_os = "windows"))]
-    fn get_process_cmdline(_pid: u32) -> String {
-        String::new()
-    }
-
-    #[cfg(not(target_os = "windows"))]
-    fn get_process_cmdline(_pid: u32) -> String {
-        String::new()
-    }
-
-    #[cfg(not(target_os = "windows"))]
-    fn get_process_cmdline(_pid: u32) -> String {
-        String::new()
+    fn get_process_cmdline(pid: u32) -> String {
+        std::fs::read(format!("/proc/{}/cmdline", pid))
+            .map(|raw| String::from_utf8_lossy(&raw).trim_end_matches('\0').replace('\0', " "))
+            .unwrap_or_default()
     }
       
 #[cfg(target_os = "windows")]
@@ -347,7 +354,22 @@ fn enumerate_pids_fast() -> Vec<(u32, String, u32)> {
     }
 }
 
-#[cfg(not(target_os = "windows"))]
+/// (pid, comm, ppid) за всеки числов запис в /proc
+#[cfg(target_os = "linux")]
+fn enumerate_pids_fast() -> Vec<(u32, String, u32)> {
+    use crate::linux_net_monitor::{parse_stat_ppid, process_name};
+    let Ok(entries) = std::fs::read_dir("/proc") else { return Vec::new() };
+    entries
+        .flatten()
+        .filter_map(|e| e.file_name().to_str()?.parse::<u32>().ok())
+        .filter_map(|pid| {
+            let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+            Some((pid, process_name(pid), parse_stat_ppid(&stat)?))
+        })
+        .collect()
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
 fn enumerate_pids_fast() -> Vec<(u32, String, u32)> {
     Vec::new()
 }
@@ -356,7 +378,7 @@ fn enumerate_pids_fast() -> Vec<(u32, String, u32)> {
     pub fn enumerate_processes() -> Result<Vec<ProcessInfo>, String> {
         use std::mem;
 
-        println!("🔍 Starting Windows process enumeration...");
+        info!("Starting Windows process enumeration...");
 
         unsafe {
             let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0)
@@ -383,7 +405,7 @@ fn enumerate_pids_fast() -> Vec<(u32, String, u32)> {
                     let pid = entry.th32ProcessID;
                     let memory_mb = get_memory_usage(pid);
                     let username = get_username(pid);
-                    let cpu_percent = calculate_cpu_percent(pid);
+                    let (cpu_percent, key) = calculate_cpu_percent(pid);
 
                     let process = ProcessInfo {
                         pid,
@@ -394,6 +416,7 @@ fn enumerate_pids_fast() -> Vec<(u32, String, u32)> {
                         cpu_percent,
                         memory_mb,
                         username,
+                        start_time: key.start_time,
             };
 
                     processes.push(process);
@@ -406,7 +429,7 @@ fn enumerate_pids_fast() -> Vec<(u32, String, u32)> {
 
             let _ = CloseHandle(snapshot);
 
-            println!("✅ Enumerated {} processes", processes.len());
+            info!("Enumerated {} processes", processes.len());
             Ok(processes)
         }
     }
@@ -457,7 +480,7 @@ fn enumerate_pids_fast() -> Vec<(u32, String, u32)> {
    /// Събитие за процес — за Event Sequence Engine
     #[derive(Clone)]
     pub struct ProcessEvent {
-        pub pid: u32,
+        pub key: ProcessKey,
         pub name: String,
         pub parent_name: String,
         pub cmdline: String,
@@ -471,12 +494,17 @@ fn enumerate_pids_fast() -> Vec<(u32, String, u32)> {
         pub reason: String,
         pub mitre: String,
         pub severity: String,
+        /// Chains и registry cmdline patterns са евристика — в block режим само alert
+        pub confidence: Confidence,
     }
 
     /// Блокиран процес — записва се в памет и се репортва
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub struct BlockedProcess {
         pub pid: u32,
+        /// (pid, start_time) на процеса при детекцията
+        #[serde(default)]
+        pub process_key: Option<ProcessKey>,
         pub process_name: String,
         pub parent_name: String,
         pub reason: String,
@@ -485,8 +513,35 @@ fn enumerate_pids_fast() -> Vec<(u32, String, u32)> {
         pub timestamp: String,
         pub success: bool,
         pub error: Option<String>,
+        /// Command line ако процесът още е бил жив при записа
+        #[serde(default)]
+        pub command_line: Option<String>,
+        /// Увереност на детекцията; None за ръчни (remote) действия
+        #[serde(default)]
+        pub confidence: Option<Confidence>,
+    }
+
+    /// DNS / network alert от ETW монитора — пази се за STIX export
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct NetworkAlert {
+        /// "dns" | "connection"
+        pub kind: String,
+        /// Домейн или IP адрес
+        pub indicator: String,
+        pub port: Option<u16>,
+        pub pid: u32,
+        pub process_name: String,
+        pub mitre_technique: String,
+        pub reason: String,
+        pub timestamp: String,
+        /// Допълнителни доказателства от детектора (напр. DNS tunneling статистики)
+        #[serde(default)]
+        pub evidence: Option<serde_json::Value>,
     }
 
+    /// Версия на detection правилата (reported in agent heartbeats) — bump при промяна
+    pub const RULES_VERSION: &str = "2024.11.1";
+
     /// Анализира процес и връща ThreatDecision
     pub fn analyze_process(name: &str, cmdline: &str, parent_name: &str) -> ThreatDecision {
         let name_l = name.to_lowercase();
@@ -502,6 +557,7 @@ fn enumerate_pids_fast() -> Vec<(u32, String, u32)> {
                     reason: format!("Known credential dumping tool: {}", name),
                     mitre: "T1003".to_string(),
                     severity: "critical".to_string(),
+                    confidence: Confidence::Definite,
                 };
             }
         }
@@ -517,6 +573,7 @@ fn enumerate_pids_fast() -> Vec<(u32, String, u32)> {
                     reason: format!("Credential dumping pattern: {}", p),
                     mitre: "T1003".to_string(),
                     severity: "critical".to_string(),
+                    confidence: Confidence::Definite,
                 };
             }
         }
@@ -536,6 +593,7 @@ fn enumerate_pids_fast() -> Vec<(u32, String, u32)> {
                         reason: format!("Malicious PowerShell: {}", p),
                         mitre: "T1059.001".to_string(),
                         severity: "critical".to_string(),
+                        confidence: Confidence::Definite,
                     };
                 }
             }
@@ -548,6 +606,7 @@ fn enumerate_pids_fast() -> Vec<(u32, String, u32)> {
                         reason: format!("Suspicious PowerShell: {}", p),
                         mitre: "T1059.001".to_string(),
                         severity: "high".to_string(),
+                        confidence: Confidence::Definite,
                     };
                 }
             }
@@ -563,6 +622,7 @@ fn enumerate_pids_fast() -> Vec<(u32, String, u32)> {
                         reason: format!("WMI abuse via cmd: {}", p),
                         mitre: "T1047".to_string(),
                         severity: "critical".to_string(),
+                        confidence: Confidence::Definite,
                     };
                 }
             }
@@ -577,6 +637,7 @@ fn enumerate_pids_fast() -> Vec<(u32, String, u32)> {
                     reason: "WMI abuse: wmic.exe launched from shell".to_string(),
                     mitre: "T1047".to_string(),
                     severity: "critical".to_string(),
+                    confidence: Confidence::Definite,
                 };
             }
             let wmi = ["process call create", "shadowcopy delete", "/node:", "/format:"];
@@ -587,6 +648,7 @@ fn enumerate_pids_fast() -> Vec<(u32, String, u32)> {
                         reason: format!("WMI abuse: {}", p),
                         mitre: "T1047".to_string(),
                         severity: "critical".to_string(),
+                        confidence: Confidence::Definite,
                     };
                 }
             }
@@ -598,6 +660,7 @@ fn enumerate_pids_fast() -> Vec<(u32, String, u32)> {
                 reason: format!("LOLBin execution: mshta.exe"),
                 mitre: "T1218.005".to_string(),
                 severity: "critical".to_string(),
+                confidence: Confidence::Definite,
             };
         }
 
@@ -618,6 +681,7 @@ fn enumerate_pids_fast() -> Vec<(u32, String, u32)> {
                             reason: format!("LOLBin abuse: {} with {}", name, p),
                             mitre: "T1218".to_string(),
                             severity: "critical".to_string(),
+                            confidence: Confidence::Definite,
                         };
                     }
                 }
@@ -631,6 +695,7 @@ fn enumerate_pids_fast() -> Vec<(u32, String, u32)> {
                     reason: "Service tampering: sc config binPath modification".to_string(),
                     mitre: "T1543".to_string(),
                     severity: "critical".to_string(),
+                    confidence: Confidence::Definite,
                 };
             }
             if parent_l.contains("cmd") || parent_l.contains("powershell") {
@@ -639,6 +704,7 @@ fn enumerate_pids_fast() -> Vec<(u32, String, u32)> {
                     reason: "Service tampering: sc.exe launched from shell".to_string(),
                     mitre: "T1543".to_string(),
                     severity: "critical".to_string(),
+                    confidence: Confidence::Definite,
                 };
             }
         }
@@ -653,6 +719,7 @@ fn enumerate_pids_fast() -> Vec<(u32, String, u32)> {
                         reason: format!("Defense tampering: sc stop security service [{}]", target),
                         mitre: "T1562".to_string(),
                         severity: "critical".to_string(),
+                        confidence: Confidence::Definite,
                     };
                 }
             }
@@ -666,6 +733,7 @@ fn enumerate_pids_fast() -> Vec<(u32, String, u32)> {
                     reason: "Defense tampering: WMIC Defender exclusion".to_string(),
                     mitre: "T1562".to_string(),
                     severity: "critical".to_string(),
+                    confidence: Confidence::Definite,
                 };
             }
         }
@@ -691,6 +759,7 @@ fn enumerate_pids_fast() -> Vec<(u32, String, u32)> {
                         reason: format!("Defense tampering via registry: {}", pattern),
                         mitre: "T1562".to_string(),
                         severity: "critical".to_string(),
+                        confidence: Confidence::Definite,
                     };
                 }
             }
@@ -703,6 +772,7 @@ if name_l.contains("wevtutil") {
         reason: "Defense evasion: wevtutil clearing event logs".to_string(),
         mitre: "T1070".to_string(),
         severity: "critical".to_string(),
+        confidence: Confidence::Definite,
     };
 }
 if name_l.contains("cmd") && cmd_l.contains("wevtutil") {
@@ -711,6 +781,7 @@ if name_l.contains("cmd") && cmd_l.contains("wevtutil") {
         reason: "Defense evasion: wevtutil clearing event logs".to_string(),
         mitre: "T1070".to_string(),
         severity: "critical".to_string(),
+        confidence: Confidence::Definite,
     };
 }
 
@@ -722,6 +793,7 @@ if name_l.contains("cmd") && cmd_l.contains("wevtutil") {
                     reason: "Persistence: net user /add - creating local account".to_string(),
                     mitre: "T1136".to_string(),
                     severity: "critical".to_string(),
+                    confidence: Confidence::Definite,
                 };
             }
             if cmd_l.contains("localgroup") && cmd_l.contains("administrators") && cmd_l.contains("/add") {
@@ -730,6 +802,7 @@ if name_l.contains("cmd") && cmd_l.contains("wevtutil") {
                     reason: "Privilege escalation: adding user to Administrators group".to_string(),
                     mitre: "T1136".to_string(),
                     severity: "critical".to_string(),
+                    confidence: Confidence::Definite,
                 };
             }
         }
@@ -742,6 +815,7 @@ if name_l.contains("cmd") && cmd_l.contains("wevtutil") {
                     reason: "Ingress tool transfer: certutil download/decode".to_string(),
                     mitre: "T1105".to_string(),
                     severity: "critical".to_string(),
+                    confidence: Confidence::Definite,
                 };
             }
         }
@@ -754,6 +828,7 @@ if name_l.contains("cmd") && cmd_l.contains("wevtutil") {
                     reason: "Ransomware indicator: shadow copy deletion".to_string(),
                     mitre: "T1490".to_string(),
                     severity: "critical".to_string(),
+                    confidence: Confidence::Definite,
                 };
             }
         }
@@ -764,6 +839,7 @@ if name_l.contains("cmd") && cmd_l.contains("wevtutil") {
                     reason: "Ransomware indicator: bcdedit disable recovery".to_string(),
                     mitre: "T1490".to_string(),
                     severity: "critical".to_string(),
+                    confidence: Confidence::Definite,
                 };
             }
         }
@@ -783,6 +859,7 @@ for p in &impair {
                     reason: format!("Defense impairment: {}", p),
                     mitre: "T1562".to_string(),
                     severity: "critical".to_string(),
+                    confidence: Confidence::Definite,
                 };
             }
         }
@@ -794,6 +871,7 @@ if name_l.contains("schtasks") {
             reason: "Persistence: schtasks /create - scheduled task creation".to_string(),
             mitre: "T1053.005".to_string(),
             severity: "critical".to_string(),
+            confidence: Confidence::Definite,
         };
     }
     if parent_l.contains("cmd") || parent_l.contains("powershell") {
@@ -802,6 +880,7 @@ if name_l.contains("schtasks") {
             reason: "Persistence: schtasks launched from shell".to_string(),
             mitre: "T1053.005".to_string(),
             severity: "critical".to_string(),
+            confidence: Confidence::Definite,
         };
     }
 }
@@ -820,6 +899,7 @@ if name_l.contains("schtasks") {
                     reason: format!("Persistence mechanism: {}", p),
                     mitre: "T1547".to_string(),
                     severity: "high".to_string(),
+                    confidence: Confidence::Heuristic,
                 };
             }
         }
@@ -841,6 +921,7 @@ if name_l.contains("schtasks") {
                             reason: format!("Suspicious chain: {} → {}", parent_name, name),
                             mitre: "T1059".to_string(),
                             severity: "high".to_string(),
+                            confidence: Confidence::Heuristic,
                         };
                     }
                 }
@@ -853,15 +934,16 @@ if name_l.contains("schtasks") {
             reason: String::new(),
             mitre: String::new(),
             severity: String::new(),
+            confidence: Confidence::Definite,
         }
     }
 
-    /// Kill процес по PID
+    /// Kill процес — само ако PID-ът още е същият процес (ProcessKey)
    #[cfg(target_os = "windows")]
-pub fn block_process(pid: u32) -> Result<(), String> {
+pub fn block_process(key: &ProcessKey) -> Result<(), String> {
     use windows::Win32::System::Threading::{
         OpenProcess, TerminateProcess, PROCESS_TERMINATE,
-        GetCurrentProcess, OpenProcessToken,
+        GetCurrentProcess, OpenProcessToken, PROCESS_QUERY_LIMITED_INFORMATION,
     };
     use windows::Win32::Foundation::CloseHandle;
     use windows::Win32::Security::{
@@ -899,9 +981,13 @@ pub fn block_process(pid: u32) -> Result<(), String> {
         let _ = AdjustTokenPrivileges(token, false, Some(&mut tp), 0, None, None);
         let _ = CloseHandle(token);
 
-        // Сега terminate
-        let handle = OpenProcess(PROCESS_TERMINATE, false, pid)
+        // Сега terminate — проверка на идентичността върху отворения handle
+        let handle = OpenProcess(PROCESS_TERMINATE | PROCESS_QUERY_LIMITED_INFORMATION, false, key.pid)
             .map_err(|e| format!("OpenProcess failed: {:?}", e))?;
+        if let Err(e) = key.matches(crate::process_identity::handle_start_time(handle)) {
+            let _ = CloseHandle(handle);
+            return Err(e);
+        }
         let result = TerminateProcess(handle, 1);
         let _ = CloseHandle(handle);
         result.map_err(|e| format!("TerminateProcess failed: {:?}", e))
@@ -909,8 +995,8 @@ pub fn block_process(pid: u32) -> Result<(), String> {
 }
 
     #[cfg(not(target_os = "windows"))]
-    pub fn block_process(pid: u32) -> Result<(), String> {
-        Err(format!("Blocking not supported on this platform (PID: {})", pid))
+    pub fn block_process(key: &ProcessKey) -> Result<(), String> {
+        crate::process_control::terminate(key)
     }
     // ============================================================================
     // GLOBAL MONITOR STATE + BACKGROUND LOOP (500ms polling)
@@ -920,7 +1006,7 @@ pub fn block_process(pid: u32) -> Result<(), String> {
 
     /// Глобален state на монитора
     pub struct MonitorState {
-        pub blocking_enabled: bool,
+        pub mode: EnforcementMode,
         pub blocked_processes: Vec<BlockedProcess>,
         pub threats_detected: u64,
         pub processes_blocked: u64,
@@ -934,7 +1020,7 @@ pub fn block_process(pid: u32) -> Result<(), String> {
 
     lazy_static::lazy_static! {
         static ref MONITOR_STATE: Mutex<MonitorState> = Mutex::new(MonitorState {
-            blocking_enabled: false,
+            mode: EnforcementMode::Monitor,
             blocked_processes: Vec::new(),
             threats_detected: 0,
             processes_blocked: 0,
@@ -946,23 +1032,22 @@ pub fn block_process(pid: u32) -> Result<(), String> {
  /// Стартира background monitoring loop
     pub fn start_monitor_loop() {
         if MONITOR_RUNNING.load(Ordering::SeqCst) {
-            println!("⚠️ Monitor loop already running");
+            warn!("Monitor loop already running");
             return;
         }
 
         MONITOR_RUNNING.store(true, Ordering::SeqCst);
 
         std::thread::spawn(|| {
-            println!("🔄 Process monitor loop started (500ms interval)");
+            info!("Process monitor loop started (500ms interval)");
 
             // Вземи snapshot на съществуващите процеси — не ги анализираме
-            let mut known_pids: std::collections::HashSet<u32> = std::collections::HashSet::new();
-            if let Ok(procs) = get_running_processes() {
-                for p in procs {
-                    known_pids.insert(p.pid);
-                }
-            }
-            println!("✅ {} existing processes ignored", known_pids.len());
+            // (pid, start_time) — рециклиран PID е нов процес, не "вече познат"
+            let mut known_pids: std::collections::HashSet<ProcessKey> = enumerate_pids_fast()
+                .iter()
+                .map(|(pid, _, _)| ProcessKey::capture(*pid))
+                .collect();
+            info!("{} existing processes ignored", known_pids.len());
 
             loop {
                 std::thread::sleep(std::time::Duration::from_millis(500));
@@ -975,11 +1060,12 @@ pub fn block_process(pid: u32) -> Result<(), String> {
                let current_procs = enumerate_pids_fast();
 
               // Намери новите
-                let mut new_pids: std::collections::HashSet<u32> = std::collections::HashSet::new();
+                let mut new_pids: std::collections::HashSet<ProcessKey> = std::collections::HashSet::new();
                 for (pid, name, parent_pid) in &current_procs {
-                    new_pids.insert(*pid);
+                    let key = ProcessKey::capture(*pid);
+                    new_pids.insert(key);
 
-                    if known_pids.contains(pid) {
+                    if known_pids.contains(&key) {
                         continue; // Вече знаем за него
                     }
 
@@ -989,6 +1075,12 @@ pub fn block_process(pid: u32) -> Result<(), String> {
                         .map(|(_, n, _)| n.as_str())
                         .unwrap_or("unknown");
 
+                    // Linux няма ETW — спираме подозрителните процеси още тук, докато ги анализираме
+                    let suspended = cfg!(target_os = "linux")
+                        && is_suspicious_name(name)
+                        && crate::enforcement::presuspend(enforcement_mode())
+                        && crate::process_control::suspend(&key).is_ok();
+
                     let cmdline = if is_suspicious_name(name) {
                         get_process_cmdline(*pid)
                     } else {
@@ -998,7 +1090,7 @@ pub fn block_process(pid: u32) -> Result<(), String> {
                     let decision = analyze_process(name, &cmdline, parent_name);
 
                     // Event Sequence Engine — проверяваме за suspicious chains
-                    let chain_decision = record_process_event(*pid, name, parent_name, &cmdline);
+                    let chain_decision = record_process_event(key, name, parent_name, &cmdline);
                     let decision = if chain_decision.as_ref().map(|d| d.is_threat).unwrap_or(false) {
                         chain_decision.unwrap()
                     } else {
@@ -1006,60 +1098,32 @@ pub fn block_process(pid: u32) -> Result<(), String> {
                     };
 
                     if decision.is_threat {
-                        println!(
-                            "🚨 THREAT: {} (PID {}) — {} [{}]",
-                            name, pid, decision.reason, decision.mitre
-                        );
-
-                        let blocking = {
-                            MONITOR_STATE.lock()
-                                .map(|s| s.blocking_enabled)
-                                .unwrap_or(false)
-                        };
-
-                        let (success, error) = if blocking && (decision.severity == "critical" || decision.severity == "high") {
-                            match block_process(*pid) {
-                                Ok(()) => {
-                                    println!("🚫 BLOCKED: {} (PID {})", name, pid);
-                                    (true, None)
-                                }
-                                Err(e) => {
-                                    println!("⚠️ Block failed: {}", e);
-                                    (false, Some(e))
-                                }
-                            }
-                        } else {
-                            (false, None)
-                        };
+                        warn!(pid, process = %name, mitre = %decision.mitre, "{}", decision.reason);
 
-                        let record = BlockedProcess {
-                            pid: *pid,
+                        let outcome = crate::response_playbooks::respond(&crate::response_playbooks::Detection {
+                            key,
                             process_name: name.clone(),
                             parent_name: parent_name.to_string(),
-                            reason: decision.reason.clone(),
-                            mitre_technique: decision.mitre.clone(),
+                            rule_id: decision.mitre.clone(),
                             severity: decision.severity.clone(),
-                            timestamp: chrono::Utc::now().to_rfc3339(),
-                            success,
-                            error,
-                        };
-
-                        if let Ok(mut state) = MONITOR_STATE.lock() {
-                            state.threats_detected += 1;
-                            if success { state.processes_blocked += 1; }
-                            state.blocked_processes.push(record);
-                            // Max 500 записа
-                            if state.blocked_processes.len() > 500 {
-                                state.blocked_processes.remove(0);
-                            }
+                            reason: decision.reason.clone(),
+                            source: "polling",
+                            command_line: Some(cmdline.clone()).filter(|c| !c.is_empty()),
+                            confidence: decision.confidence,
+                            suspended,
+                        });
+                        if suspended && !outcome.contained() {
+                            let _ = crate::process_control::resume(&key);
                         }
+                    } else if suspended {
+                        let _ = crate::process_control::resume(&key);
                     }
                 }
 
                 known_pids = new_pids;
             }
 
-            println!("🛑 Monitor loop stopped");
+            info!("Monitor loop stopped");
         });
     }
 
@@ -1068,20 +1132,42 @@ pub fn block_process(pid: u32) -> Result<(), String> {
         MONITOR_RUNNING.store(false, Ordering::SeqCst);
     }
 
-    /// Enable blocking
-    pub fn enable_blocking() {
+    /// Работи ли monitoring loop-ът
+    pub fn is_monitor_running() -> bool {
+        MONITOR_RUNNING.load(Ordering::SeqCst)
+    }
+
+    /// Enforcement mode — общ за polling, ETW и network детекторите
+    pub fn set_enforcement_mode(mode: EnforcementMode) {
         if let Ok(mut state) = MONITOR_STATE.lock() {
-            state.blocking_enabled = true;
-            println!("🚫 Runtime blocking: ENABLED");
+            state.mode = mode;
+            match mode {
+                EnforcementMode::Monitor => info!("Runtime blocking: DISABLED (detection only)"),
+                EnforcementMode::Block => info!("Runtime blocking: ENABLED"),
+                EnforcementMode::BlockWithPrompt => info!("Runtime blocking: ENABLED (prompt on ambiguous detections)"),
+            }
         }
     }
 
+    /// Enable blocking
+    pub fn enable_blocking() {
+        set_enforcement_mode(EnforcementMode::Block);
+    }
+
     /// Disable blocking
     pub fn disable_blocking() {
-        if let Ok(mut state) = MONITOR_STATE.lock() {
-            state.blocking_enabled = false;
-            println!("✅ Runtime blocking: DISABLED (detection only)");
-        }
+        set_enforcement_mode(EnforcementMode::Monitor);
+    }
+
+    pub fn enforcement_mode() -> EnforcementMode {
+        MONITOR_STATE.lock().map(|s| s.mode).unwrap_or_default()
+    }
+
+    /// (mode, threats detected, processes actually blocked)
+    pub fn get_enforcement_status() -> (EnforcementMode, u64, u64) {
+        MONITOR_STATE.lock()
+            .map(|s| (s.mode, s.threats_detected, s.processes_blocked))
+            .unwrap_or_default()
     }
 
     /// Вземи blocking статус
@@ -1089,7 +1175,7 @@ pub fn block_process(pid: u32) -> Result<(), String> {
         if let Ok(state) = MONITOR_STATE.lock() {
             let last_20: Vec<BlockedProcess> = state.blocked_processes
                 .iter().rev().take(20).cloned().collect();
-            (state.blocking_enabled, last_20, state.threats_detected)
+            (state.mode.blocks(), last_20, state.threats_detected)
         } else {
             (false, vec![], 0)
         }
@@ -1106,6 +1192,7 @@ pub fn get_process_cmdline_pub(pid: u32) -> String {
     get_process_cmdline(pid)
 }
 
+#[cfg(target_os = "windows")]
 pub fn get_process_exe_path(pid: u32) -> String {
     use windows::Win32::System::Threading::{OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION};
     use windows::Win32::Foundation::CloseHandle;
@@ -1134,11 +1221,18 @@ pub fn get_process_exe_path(pid: u32) -> String {
     }
 }
 
+#[cfg(not(target_os = "windows"))]
+pub fn get_process_exe_path(pid: u32) -> String {
+    std::fs::read_link(format!("/proc/{}/exe", pid))
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
 
 /// Записва event в sequence buffer и проверява за suspicious chains
-pub fn record_process_event(pid: u32, name: &str, parent_name: &str, cmdline: &str) -> Option<ThreatDecision> {
+pub fn record_process_event(key: ProcessKey, name: &str, parent_name: &str, cmdline: &str) -> Option<ThreatDecision> {
     let event = ProcessEvent {
-        pid,
+        key,
         name: name.to_string(),
         parent_name: parent_name.to_string(),
         cmdline: cmdline.to_string(),
@@ -1170,6 +1264,7 @@ pub fn record_process_event(pid: u32, name: &str, parent_name: &str, cmdline: &s
                 reason: format!("Suspicious chain: Office app → {}", name),
                 mitre: "T1566".to_string(),
                 severity: "critical".to_string(),
+                confidence: Confidence::Heuristic,
             });
         }
     }
@@ -1185,6 +1280,7 @@ pub fn record_process_event(pid: u32, name: &str, parent_name: &str, cmdline: &s
             reason: format!("Suspicious chain: Browser → {}", name),
             mitre: "T1059".to_string(),
             severity: "high".to_string(),
+            confidence: Confidence::Heuristic,
         });
     }
 
@@ -1198,6 +1294,7 @@ if is_ps_parent && is_cmd_or_net {
         reason: "Suspicious chain: PowerShell → Net (lateral movement)".to_string(),
         mitre: "T1021".to_string(),
         severity: "critical".to_string(),
+        confidence: Confidence::Heuristic,
     });
 }
 
@@ -1211,15 +1308,16 @@ if is_ps_parent && is_cmd_or_net {
             reason: format!("Suspicious chain: WMI → {}", name),
             mitre: "T1047".to_string(),
             severity: "critical".to_string(),
+            confidence: Confidence::Heuristic,
         });
     }
 
     None
 }
 
-    /// Записва блокиран процес от ETW монитора
+    /// Записва ръчно действие (remote command) — детекциите минават през response_playbooks
     pub fn record_blocked_process(
-    pid: u32,
+    key: ProcessKey,
     name: &str,
     parent: &str,
     reason: &str,
@@ -1228,8 +1326,9 @@ if is_ps_parent && is_cmd_or_net {
     success: bool,
     error: Option<String>,
     ) {
-    let record = BlockedProcess {
-        pid,
+    store_blocked_process(BlockedProcess {
+        pid: key.pid,
+        process_key: Some(key),
         process_name: name.to_string(),
         parent_name: parent.to_string(),
         reason: reason.to_string(),
@@ -1238,15 +1337,43 @@ if is_ps_parent && is_cmd_or_net {
         timestamp: chrono::Utc::now().to_rfc3339(),
         success,
         error,
-    };
+        // Cmdline само ако PID-ът още е същият процес
+        command_line: Some(key).filter(|k| k.is_current())
+            .map(|k| get_process_cmdline(k.pid))
+            .filter(|c| !c.is_empty()),
+        confidence: None,
+    });
+}
+
+    /// Записва детекция / блокиране в state + telemetry
+    pub fn store_blocked_process(record: BlockedProcess) {
+    crate::telemetry_schema::publish_detection(crate::siem_forwarder::SecurityEvent::from_blocked_process(&record));
 
     if let Ok(mut state) = MONITOR_STATE.lock() {
         state.threats_detected += 1;
-        if success { state.processes_blocked += 1; }
+        if record.success { state.processes_blocked += 1; }
         state.blocked_processes.push(record);
         if state.blocked_processes.len() > 500 {
             state.blocked_processes.remove(0);
         }
     }
 }
-    
\ No newline at end of file
+    
+
+lazy_static::lazy_static! {
+    static ref NETWORK_ALERTS: Mutex<Vec<NetworkAlert>> = Mutex::new(Vec::new());
+}
+
+/// Записва DNS / network alert (последните 500)
+pub fn record_network_alert(alert: NetworkAlert) {
+    if let Ok(mut alerts) = NETWORK_ALERTS.lock() {
+        alerts.push(alert);
+        if alerts.len() > 500 {
+            alerts.remove(0);
+        }
+    }
+}
+
+pub fn get_network_alerts() -> Vec<NetworkAlert> {
+    NETWORK_ALERTS.lock().map(|a| a.clone()).unwrap_or_default()
+}
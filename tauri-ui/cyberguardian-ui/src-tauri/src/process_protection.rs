@@ -4,6 +4,7 @@ use std::thread;
 use std::time::Duration;
 use std::fs;
 use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
 
 #[cfg(target_os = "windows")]
 use windows::Win32::Foundation::HANDLE;
@@ -20,6 +21,9 @@ pub struct ProtectionStatus {
     pub config_integrity_enabled: bool,
     pub username: String,
     pub recommendations: Vec<String>,
+    /// Host is network-isolated (only the backend is reachable)
+    #[serde(default)]
+    pub network_isolated: bool,
 }
 
 /// Global protection state
@@ -75,7 +79,7 @@ impl ProcessProtection {
         if !Self::check_admin_privileges() {
             return Err("Administrator privileges required".to_string());
         }
-        println!("⚠️ Anti-termination: Using service-based protection (safer than critical process)");
+        warn!("Anti-termination: Using service-based protection (safer than critical process)");
         self.is_protected = true;
         Ok(())
     }
@@ -89,16 +93,16 @@ impl ProcessProtection {
         if self.self_healing_enabled {
             return Ok(());
         }
-        println!("🔄 Starting self-healing watchdog...");
+        info!("Starting self-healing watchdog...");
         let handle = thread::spawn(|| {
             loop {
                 thread::sleep(Duration::from_secs(10));
-                println!("❤️ Heartbeat check OK");
+                info!("Heartbeat check OK");
             }
         });
         self.watchdog_handle = Some(handle);
         self.self_healing_enabled = true;
-        println!("✅ Self-healing enabled");
+        info!("Self-healing enabled");
         Ok(())
     }
 
@@ -106,9 +110,9 @@ impl ProcessProtection {
         if self.config_integrity_enabled {
             return Ok(());
         }
-        println!("🔐 Enabling config integrity monitoring...");
+        info!("Enabling config integrity monitoring...");
         self.config_integrity_enabled = true;
-        println!("✅ Config integrity monitoring enabled");
+        info!("Config integrity monitoring enabled");
         Ok(())
     }
 
@@ -128,7 +132,7 @@ impl ProcessProtection {
         }
 
         if errors.is_empty() {
-            println!("✅ Maximum protection enabled");
+            info!("Maximum protection enabled");
             Ok(())
         } else {
             Err(format!("Some features failed: {}", errors.join(", ")))
@@ -139,7 +143,7 @@ impl ProcessProtection {
         self.is_protected = false;
         self.self_healing_enabled = false;
         self.config_integrity_enabled = false;
-        println!("🛑 Protection disabled");
+        info!("Protection disabled");
         Ok(())
     }
 
@@ -170,6 +174,7 @@ impl ProcessProtection {
             config_integrity_enabled: self.config_integrity_enabled,
             username: Self::get_username(),
             recommendations,
+            network_isolated: crate::network_isolation::is_isolated(),
         }
     }
 }
@@ -205,7 +210,7 @@ pub fn init_protection() -> Result<(), String> {
     let mut state = PROTECTION_STATE.lock().unwrap();
     if state.is_none() {
         *state = Some(ProcessProtection::new());
-        println!("✅ Process protection initialized");
+        info!("Process protection initialized");
     }
     Ok(())
 }
@@ -225,6 +230,7 @@ pub fn get_protection_status() -> ProtectionStatus {
             config_integrity_enabled: false,
             username: ProcessProtection::get_username(),
             recommendations: vec!["Initialize protection first".to_string()],
+            network_isolated: crate::network_isolation::is_isolated(),
         }
     }
 }
@@ -275,7 +281,7 @@ pub fn install_as_service() -> Result<(), String> {
     }
     #[cfg(windows)]
     {
-        println!("📦 Installing CyberGuardian as Windows service...");
+        info!("Installing CyberGuardian as Windows service...");
         crate::windows_service::install_service()
     }
     #[cfg(not(windows))]
@@ -0,0 +1,759 @@
+//! Quarantine Retention & Auto-Purge Policy Engine
+//! Enforces max age, total size and item count limits on quarantined files
+//! and Deep Quarantine backups, writing an audit entry for every deletion
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use chrono::{DateTime, Utc};
+use tracing::{info, warn};
+
+use crate::deep_quarantine::{get_backup_dir, DeepQuarantineBackup};
+
+/// Largest file accepted into the local quarantine
+const MAX_QUARANTINE_BYTES: u64 = 1024 * 1024 * 1024;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+/// Retention policy (persisted in retention_policy.json)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    pub enabled: bool,
+    pub max_age_days: u32,
+    pub max_total_size_mb: u64,   // 0 = unlimited
+    pub max_items: usize,         // 0 = unlimited
+    pub keep_critical_longer: bool,
+    pub critical_max_age_days: u32,
+    pub check_interval_minutes: u64,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        RetentionPolicy {
+            enabled: false,
+            max_age_days: 30,
+            max_total_size_mb: 1024,
+            max_items: 500,
+            keep_critical_longer: true,
+            critical_max_age_days: 90,
+            check_interval_minutes: 60,
+        }
+    }
+}
+
+/// Item under retention control
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionItem {
+    pub path: String,
+    pub kind: String,          // "deep_quarantine_backup" | "quarantined_file"
+    pub threat_level: String,  // "critical" | "high" | "medium" | "low" | "unknown"
+    pub size_bytes: u64,
+    pub created_at: String,
+}
+
+/// Item selected for deletion and the rule that selected it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurgeCandidate {
+    pub item: RetentionItem,
+    pub reason: String,        // "max_age" | "max_items" | "max_total_size"
+}
+
+/// Audit entry written for every purge (purge_audit.jsonl)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurgeAuditEntry {
+    pub purged_at: String,
+    pub path: String,
+    pub kind: String,
+    pub threat_level: String,
+    pub size_bytes: u64,
+    pub created_at: String,
+    pub reason: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// Result of a single retention run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurgeReport {
+    pub ran_at: String,
+    pub dry_run: bool,
+    pub evaluated_items: usize,
+    pub candidates: Vec<PurgeCandidate>,
+    pub purged_count: usize,
+    pub freed_bytes: u64,
+    pub errors: Vec<String>,
+}
+
+static SCHEDULER_RUNNING: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    static ref LAST_REPORT: Mutex<Option<PurgeReport>> = Mutex::new(None);
+}
+
+// ============================================================================
+// PATHS + POLICY PERSISTENCE
+// ============================================================================
+
+fn get_data_dir() -> PathBuf {
+    let mut path = dirs::data_local_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push("CyberGuardian");
+    path
+}
+
+/// Local quarantine directory (AppData/Local/CyberGuardian/quarantine)
+pub fn get_quarantine_dir() -> PathBuf {
+    get_data_dir().join("quarantine")
+}
+
+fn get_policy_path() -> PathBuf {
+    get_data_dir().join("retention_policy.json")
+}
+
+fn get_audit_log_path() -> PathBuf {
+    get_data_dir().join("purge_audit.jsonl")
+}
+
+pub fn load_policy() -> RetentionPolicy {
+    fs::read_to_string(get_policy_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_policy(policy: &RetentionPolicy) -> Result<(), String> {
+    fs::create_dir_all(get_data_dir())
+        .map_err(|e| format!("Failed to create data directory: {}", e))?;
+    let json = serde_json::to_string_pretty(policy)
+        .map_err(|e| format!("Failed to serialize retention policy: {}", e))?;
+    fs::write(get_policy_path(), json)
+        .map_err(|e| format!("Failed to write retention policy: {}", e))
+}
+
+fn validate_policy(policy: &RetentionPolicy) -> Result<(), String> {
+    if policy.max_age_days == 0 {
+        return Err("max_age_days must be at least 1".to_string());
+    }
+    if policy.keep_critical_longer && policy.critical_max_age_days < policy.max_age_days {
+        return Err("critical_max_age_days must not be shorter than max_age_days".to_string());
+    }
+    if policy.check_interval_minutes == 0 {
+        return Err("check_interval_minutes must be at least 1".to_string());
+    }
+    Ok(())
+}
+
+// ============================================================================
+// POLICY EVALUATION
+// ============================================================================
+
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+fn is_critical(item: &RetentionItem) -> bool {
+    item.threat_level.eq_ignore_ascii_case("critical")
+}
+
+/// Select items that violate the policy.
+/// Age limits are applied first, then count/size caps purge the oldest
+/// remaining items (non-critical before critical when keep_critical_longer).
+pub fn select_for_purge(
+    items: &[RetentionItem],
+    policy: &RetentionPolicy,
+    now: DateTime<Utc>,
+) -> Vec<PurgeCandidate> {
+    let mut candidates = Vec::new();
+    let mut remaining: Vec<(&RetentionItem, DateTime<Utc>)> = Vec::new();
+
+    for item in items {
+        // Items with unreadable timestamps are treated as brand new
+        let created = parse_timestamp(&item.created_at).unwrap_or(now);
+        let max_age_days = if policy.keep_critical_longer && is_critical(item) {
+            policy.critical_max_age_days
+        } else {
+            policy.max_age_days
+        };
+
+        if now.signed_duration_since(created) > chrono::Duration::days(max_age_days as i64) {
+            candidates.push(PurgeCandidate {
+                item: item.clone(),
+                reason: "max_age".to_string(),
+            });
+        } else {
+            remaining.push((item, created));
+        }
+    }
+
+    // Purge order: non-critical first (if protected), then oldest first
+    remaining.sort_by(|(a, a_time), (b, b_time)| {
+        let a_protected = policy.keep_critical_longer && is_critical(a);
+        let b_protected = policy.keep_critical_longer && is_critical(b);
+        a_protected.cmp(&b_protected).then(a_time.cmp(b_time))
+    });
+
+    let max_bytes = policy.max_total_size_mb.saturating_mul(1024 * 1024);
+    let mut count = remaining.len();
+    let mut total_bytes: u64 = remaining.iter().map(|(i, _)| i.size_bytes).sum();
+
+    for (item, _) in remaining {
+        let over_count = policy.max_items > 0 && count > policy.max_items;
+        let over_size = max_bytes > 0 && total_bytes > max_bytes;
+        if !over_count && !over_size {
+            break;
+        }
+
+        candidates.push(PurgeCandidate {
+            item: item.clone(),
+            reason: if over_count { "max_items" } else { "max_total_size" }.to_string(),
+        });
+        count -= 1;
+        total_bytes = total_bytes.saturating_sub(item.size_bytes);
+    }
+
+    candidates
+}
+
+// ============================================================================
+// ITEM COLLECTION
+// ============================================================================
+
+fn file_modified_rfc3339(path: &Path) -> String {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map(|t| DateTime::<Utc>::from(t).to_rfc3339())
+        .unwrap_or_else(|_| Utc::now().to_rfc3339())
+}
+
+/// Sidecar metadata for quarantined files (<file>.meta.json)
+#[derive(Debug, Deserialize)]
+struct QuarantineSidecar {
+    threat_level: Option<String>,
+    quarantined_at: Option<String>,
+}
+
+fn sidecar_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".meta.json");
+    path.with_file_name(name)
+}
+
+fn collect_backup_items(items: &mut Vec<RetentionItem>) {
+    let Ok(entries) = fs::read_dir(get_backup_dir()) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|s| s.to_str()) != Some("json") {
+            continue;
+        }
+
+        let size_bytes = entry.metadata().map(|m| m.len()).unwrap_or(0);
+        let backup = fs::read_to_string(&path)
+            .ok()
+            .and_then(|s| serde_json::from_str::<DeepQuarantineBackup>(&s).ok());
+
+        let (threat_level, created_at) = match backup {
+            Some(b) => (b.threat_level, b.backed_up_at),
+            None => ("unknown".to_string(), file_modified_rfc3339(&path)),
+        };
+
+        items.push(RetentionItem {
+            path: path.to_string_lossy().to_string(),
+            kind: "deep_quarantine_backup".to_string(),
+            threat_level,
+            size_bytes,
+            created_at,
+        });
+    }
+}
+
+fn collect_quarantined_files(items: &mut Vec<RetentionItem>) {
+    let Ok(entries) = fs::read_dir(get_quarantine_dir()) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_file() || path.to_string_lossy().ends_with(".meta.json") {
+            continue;
+        }
+
+        let size_bytes = entry.metadata().map(|m| m.len()).unwrap_or(0);
+        let sidecar = fs::read_to_string(sidecar_path(&path))
+            .ok()
+            .and_then(|s| serde_json::from_str::<QuarantineSidecar>(&s).ok());
+
+        let threat_level = sidecar.as_ref()
+            .and_then(|s| s.threat_level.clone())
+            .unwrap_or_else(|| "unknown".to_string())
+            .to_lowercase();
+        let created_at = sidecar
+            .and_then(|s| s.quarantined_at)
+            .unwrap_or_else(|| file_modified_rfc3339(&path));
+
+        items.push(RetentionItem {
+            path: path.to_string_lossy().to_string(),
+            kind: "quarantined_file".to_string(),
+            threat_level,
+            size_bytes,
+            created_at,
+        });
+    }
+}
+
+/// Collect all items under retention control
+pub fn collect_items() -> Vec<RetentionItem> {
+    let mut items = Vec::new();
+    collect_backup_items(&mut items);
+    collect_quarantined_files(&mut items);
+    items
+}
+
+// ============================================================================
+// PURGE EXECUTION + AUDIT
+// ============================================================================
+
+fn append_audit_entry(entry: &PurgeAuditEntry) -> Result<(), String> {
+    fs::create_dir_all(get_data_dir())
+        .map_err(|e| format!("Failed to create data directory: {}", e))?;
+    let line = serde_json::to_string(entry)
+        .map_err(|e| format!("Failed to serialize audit entry: {}", e))?;
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(get_audit_log_path())
+        .map_err(|e| format!("Failed to open purge audit log: {}", e))?;
+    writeln!(file, "{}", line)
+        .map_err(|e| format!("Failed to write purge audit log: {}", e))
+}
+
+fn purge_item(item: &RetentionItem) -> Result<(), String> {
+    let path = Path::new(&item.path);
+    fs::remove_file(path).map_err(|e| format!("Failed to delete {}: {}", item.path, e))?;
+
+    if item.kind == "quarantined_file" {
+        let _ = fs::remove_file(sidecar_path(path));
+    }
+    Ok(())
+}
+
+/// Evaluate the policy and (unless dry_run) delete the selected items
+pub fn run_retention(dry_run: bool) -> PurgeReport {
+    let policy = load_policy();
+    let items = collect_items();
+    let candidates = select_for_purge(&items, &policy, Utc::now());
+
+    let mut report = PurgeReport {
+        ran_at: Utc::now().to_rfc3339(),
+        dry_run,
+        evaluated_items: items.len(),
+        candidates: candidates.clone(),
+        purged_count: 0,
+        freed_bytes: 0,
+        errors: Vec::new(),
+    };
+
+    if dry_run {
+        return report;
+    }
+
+    for candidate in &candidates {
+        let result = purge_item(&candidate.item);
+
+        let entry = PurgeAuditEntry {
+            purged_at: Utc::now().to_rfc3339(),
+            path: candidate.item.path.clone(),
+            kind: candidate.item.kind.clone(),
+            threat_level: candidate.item.threat_level.clone(),
+            size_bytes: candidate.item.size_bytes,
+            created_at: candidate.item.created_at.clone(),
+            reason: candidate.reason.clone(),
+            success: result.is_ok(),
+            error: result.clone().err(),
+        };
+
+        match result {
+            Ok(()) => {
+                report.purged_count += 1;
+                report.freed_bytes += candidate.item.size_bytes;
+                info!("Retention purge ({}): {}", candidate.reason, candidate.item.path);
+            }
+            Err(e) => {
+                warn!("Retention purge failed: {}", e);
+                report.errors.push(e);
+            }
+        }
+
+        if let Err(e) = append_audit_entry(&entry) {
+            warn!("{}", e);
+            report.errors.push(e);
+        }
+    }
+
+    if let Ok(mut last) = LAST_REPORT.lock() {
+        *last = Some(report.clone());
+    }
+
+    report
+}
+
+/// Read the newest purge audit entries (newest first)
+pub fn read_audit_log(limit: usize) -> Vec<PurgeAuditEntry> {
+    let Ok(contents) = fs::read_to_string(get_audit_log_path()) else {
+        return Vec::new();
+    };
+
+    contents
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str::<PurgeAuditEntry>(line).ok())
+        .take(limit)
+        .collect()
+}
+
+// ============================================================================
+// LOCAL QUARANTINE
+// ============================================================================
+
+/// File moved into the local quarantine (also written as its .meta.json sidecar)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalQuarantineRecord {
+    pub original_path: String,
+    pub quarantined_path: String,
+    pub sha256: String,
+    pub size_bytes: u64,
+    pub threat_level: String,
+    pub reason: String,
+    pub quarantined_at: String,
+}
+
+/// All locally quarantined files (from their .meta.json sidecars)
+pub fn list_local_quarantine() -> Vec<LocalQuarantineRecord> {
+    let entries = match fs::read_dir(get_quarantine_dir()) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    entries
+        .flatten()
+        .filter(|e| e.file_name().to_string_lossy().ends_with(".meta.json"))
+        .filter_map(|e| fs::read_to_string(e.path()).ok())
+        .filter_map(|s| serde_json::from_str(&s).ok())
+        .collect()
+}
+
+/// Stream `source` through SHA-256, also copying it into `copy_to` when given.
+/// Fails once more than `max_bytes` have been read (the file may grow after the size check)
+fn stream_file(source: &Path, mut copy_to: Option<&mut fs::File>, max_bytes: u64) -> Result<(u64, String), String> {
+    use sha2::{Digest, Sha256};
+    use std::io::Read;
+
+    let mut input = fs::File::open(source)
+        .map_err(|e| format!("Failed to read {}: {}", source.display(), e))?
+        .take(max_bytes.saturating_add(1));
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    let mut total = 0u64;
+    loop {
+        let n = input.read(&mut buf).map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+        if n == 0 {
+            break;
+        }
+        total += n as u64;
+        if total > max_bytes {
+            return Err(format!("File too large to quarantine (max {} bytes)", max_bytes));
+        }
+        hasher.update(&buf[..n]);
+        if let Some(output) = copy_to.as_mut() {
+            output.write_all(&buf[..n]).map_err(|e| format!("Failed to copy to quarantine: {}", e))?;
+        }
+    }
+    Ok((total, format!("{:x}", hasher.finalize())))
+}
+
+/// Move `source` to `target` without loading it into memory: rename on the same
+/// volume, otherwise a streamed copy + delete. Returns (size, sha256)
+fn move_into_quarantine(source: &Path, target: &Path, max_bytes: u64) -> Result<(u64, String), String> {
+    let size = fs::metadata(source)
+        .map_err(|e| format!("Cannot stat {}: {}", source.display(), e))?
+        .len();
+    if size > max_bytes {
+        return Err(format!("File too large to quarantine ({} bytes, max {})", size, max_bytes));
+    }
+
+    if fs::rename(source, target).is_ok() {
+        return stream_file(target, None, u64::MAX);
+    }
+
+    // rename fails across volumes → copy to a .partial file first so a failed copy leaves nothing behind
+    let partial = target.with_extension("partial");
+    let copy = || -> Result<(u64, String), String> {
+        let mut output = fs::File::create(&partial)
+            .map_err(|e| format!("Failed to create {:?}: {}", partial, e))?;
+        let result = stream_file(source, Some(&mut output), max_bytes)?;
+        fs::rename(&partial, target).map_err(|e| format!("Failed to copy to quarantine: {}", e))?;
+        Ok(result)
+    };
+    let result = copy().inspect_err(|_| {
+        let _ = fs::remove_file(&partial);
+    })?;
+    fs::remove_file(source).map_err(|e| {
+        let _ = fs::remove_file(target);
+        format!("Failed to remove original {}: {}", source.display(), e)
+    })?;
+    Ok(result)
+}
+
+/// Move a file into the local quarantine directory (picked up by retention)
+pub fn quarantine_file_locally(
+    file_path: &str,
+    threat_level: &str,
+    reason: &str,
+) -> Result<LocalQuarantineRecord, String> {
+    let source = Path::new(file_path);
+    if !source.is_file() {
+        return Err(format!("Not a file: {}", file_path));
+    }
+
+    let dir = get_quarantine_dir();
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create quarantine dir: {}", e))?;
+
+    let file_name = source.file_name().unwrap_or_default().to_string_lossy();
+    let target = dir.join(format!("{}_{}.quarantine", Utc::now().timestamp_millis(), file_name));
+    let (size_bytes, sha256) = move_into_quarantine(source, &target, MAX_QUARANTINE_BYTES)?;
+
+    let record = LocalQuarantineRecord {
+        original_path: file_path.to_string(),
+        quarantined_path: target.to_string_lossy().to_string(),
+        sha256,
+        size_bytes,
+        threat_level: threat_level.to_lowercase(),
+        reason: reason.to_string(),
+        quarantined_at: Utc::now().to_rfc3339(),
+    };
+
+    let sidecar = serde_json::to_string_pretty(&record)
+        .map_err(|e| format!("Failed to serialize sidecar: {}", e))?;
+    fs::write(sidecar_path(&target), sidecar)
+        .map_err(|e| format!("Failed to write sidecar: {}", e))?;
+
+    info!("Quarantined locally: {} → {}", file_path, record.quarantined_path);
+    Ok(record)
+}
+
+// ============================================================================
+// SCHEDULER
+// ============================================================================
+
+/// Start the background retention scheduler (re-reads the policy every minute)
+pub fn start_retention_scheduler() {
+    if SCHEDULER_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    std::thread::spawn(|| {
+        info!("Quarantine retention scheduler started");
+        let mut last_run: Option<std::time::Instant> = None;
+
+        while SCHEDULER_RUNNING.load(Ordering::SeqCst) {
+            let policy = load_policy();
+            let interval = std::time::Duration::from_secs(policy.check_interval_minutes.max(1) * 60);
+            let due = last_run.map(|t| t.elapsed() >= interval).unwrap_or(true);
+
+            if policy.enabled && due {
+                let report = run_retention(false);
+                if report.purged_count > 0 {
+                    info!(
+                        "Retention run: purged {} items ({} KB freed)",
+                        report.purged_count,
+                        report.freed_bytes / 1024
+                    );
+                }
+                last_run = Some(std::time::Instant::now());
+            }
+
+            std::thread::sleep(std::time::Duration::from_secs(60));
+        }
+
+        info!("Quarantine retention scheduler stopped");
+    });
+}
+
+pub fn stop_retention_scheduler() {
+    SCHEDULER_RUNNING.store(false, Ordering::SeqCst);
+}
+
+// ============================================================================
+// TAURI COMMANDS
+// ============================================================================
+
+#[tauri::command]
+pub fn get_retention_policy() -> RetentionPolicy {
+    load_policy()
+}
+
+#[tauri::command]
+pub fn set_retention_policy(policy: RetentionPolicy) -> Result<RetentionPolicy, String> {
+    validate_policy(&policy)?;
+    save_policy(&policy)?;
+    info!("Retention policy updated (enabled: {})", policy.enabled);
+    Ok(policy)
+}
+
+#[tauri::command]
+pub fn preview_retention_purge() -> PurgeReport {
+    run_retention(true)
+}
+
+#[tauri::command]
+pub fn run_retention_purge() -> PurgeReport {
+    run_retention(false)
+}
+
+#[tauri::command]
+pub fn get_purge_audit_log(limit: Option<usize>) -> Vec<PurgeAuditEntry> {
+    read_audit_log(limit.unwrap_or(100))
+}
+
+#[tauri::command]
+pub fn get_last_retention_report() -> Option<PurgeReport> {
+    LAST_REPORT.lock().ok().and_then(|r| r.clone())
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(name: &str, level: &str, size_bytes: u64, age_days: i64, now: DateTime<Utc>) -> RetentionItem {
+        RetentionItem {
+            path: name.to_string(),
+            kind: "quarantined_file".to_string(),
+            threat_level: level.to_string(),
+            size_bytes,
+            created_at: (now - chrono::Duration::days(age_days)).to_rfc3339(),
+        }
+    }
+
+    fn unlimited_policy() -> RetentionPolicy {
+        RetentionPolicy {
+            enabled: true,
+            max_age_days: 30,
+            max_total_size_mb: 0,
+            max_items: 0,
+            keep_critical_longer: true,
+            critical_max_age_days: 90,
+            check_interval_minutes: 60,
+        }
+    }
+
+    #[test]
+    fn test_max_age_purges_old_items() {
+        let now = Utc::now();
+        let items = vec![
+            item("old", "medium", 10, 45, now),
+            item("fresh", "medium", 10, 5, now),
+        ];
+
+        let purged = select_for_purge(&items, &unlimited_policy(), now);
+        assert_eq!(purged.len(), 1);
+        assert_eq!(purged[0].item.path, "old");
+        assert_eq!(purged[0].reason, "max_age");
+    }
+
+    #[test]
+    fn test_critical_items_kept_longer() {
+        let now = Utc::now();
+        let items = vec![
+            item("critical_45d", "critical", 10, 45, now),
+            item("critical_120d", "critical", 10, 120, now),
+        ];
+
+        let purged = select_for_purge(&items, &unlimited_policy(), now);
+        assert_eq!(purged.len(), 1);
+        assert_eq!(purged[0].item.path, "critical_120d");
+
+        let mut policy = unlimited_policy();
+        policy.keep_critical_longer = false;
+        assert_eq!(select_for_purge(&items, &policy, now).len(), 2);
+    }
+
+    #[test]
+    fn test_max_items_purges_oldest_non_critical_first() {
+        let now = Utc::now();
+        let items = vec![
+            item("critical_old", "critical", 10, 20, now),
+            item("low_old", "low", 10, 10, now),
+            item("low_new", "low", 10, 1, now),
+        ];
+
+        let mut policy = unlimited_policy();
+        policy.max_items = 2;
+
+        let purged = select_for_purge(&items, &policy, now);
+        assert_eq!(purged.len(), 1);
+        assert_eq!(purged[0].item.path, "low_old");
+        assert_eq!(purged[0].reason, "max_items");
+    }
+
+    #[test]
+    fn test_max_total_size() {
+        let now = Utc::now();
+        let mb = 1024 * 1024;
+        let items = vec![
+            item("a", "high", 3 * mb, 3, now),
+            item("b", "high", 3 * mb, 2, now),
+            item("c", "high", 3 * mb, 1, now),
+        ];
+
+        let mut policy = unlimited_policy();
+        policy.max_total_size_mb = 5;
+
+        let purged: Vec<String> = select_for_purge(&items, &policy, now)
+            .into_iter()
+            .map(|c| c.item.path)
+            .collect();
+        assert_eq!(purged, vec!["a".to_string(), "b".to_string()]);
+    }
+
+    #[test]
+    fn test_move_into_quarantine_streams_and_caps() {
+        let dir = std::env::temp_dir().join(format!("cg_quarantine_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("payload.bin");
+        let target = dir.join("payload.quarantine");
+        fs::write(&source, b"hello").unwrap();
+
+        // Over the cap: refused, original untouched
+        assert!(move_into_quarantine(&source, &target, 4).is_err());
+        assert!(source.exists() && !target.exists());
+
+        let (size, sha256) = move_into_quarantine(&source, &target, 1024).unwrap();
+        assert_eq!(size, 5);
+        assert_eq!(sha256, "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824");
+        assert!(!source.exists() && target.exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_validate_policy() {
+        assert!(validate_policy(&RetentionPolicy::default()).is_ok());
+
+        let policy = RetentionPolicy {
+            critical_max_age_days: 7,
+            ..RetentionPolicy::default()
+        };
+        assert!(validate_policy(&policy).is_err());
+    }
+}
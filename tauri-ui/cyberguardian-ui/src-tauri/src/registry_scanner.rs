@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use chrono::Utc;
+use tracing::error;
 
 #[cfg(target_os = "windows")]
 use winreg::enums::*;
@@ -103,7 +104,7 @@ pub fn scan_registry() -> Result<Vec<RegistryEntry>, String> {
         match scan_key(&hive, hive_name, key_path) {
             Ok(mut entries) => suspicious_entries.append(&mut entries),
             Err(e) => {
-                eprintln!("Error scanning {}: {}", key_path, e);
+                error!("Error scanning {}: {}", key_path, e);
                 continue;
             }
         }
@@ -0,0 +1,830 @@
+//! Remote Response Commands
+//! Long-polls the backend for signed, expiring response commands (kill,
+//! suspend/resume, kill tree, quarantine, deep analysis, scan, collect file,
+//! isolate), executes each one at most once, reports results asynchronously
+//! and keeps a JSONL command history
+
+use base64::{engine::general_purpose::STANDARD as B64, Engine};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::backend_client::{BackendClient, ReqwestBackendClient};
+use crate::decision_prompt::DecisionChoice;
+use crate::process_identity::ProcessKey;
+use crate::outbound_spool::{self, SpoolEndpoint};
+
+/// Max size of a file copied by `collect_file`
+const MAX_COLLECT_BYTES: u64 = 10 * 1024 * 1024;
+/// Raw bytes per uploaded chunk of a collected file
+const UPLOAD_CHUNK_BYTES: u64 = 256 * 1024;
+/// Commands valid for longer than this are rejected
+const MAX_COMMAND_LIFETIME_HOURS: i64 = 24;
+/// Tolerated clock skew between backend and agent
+const MAX_CLOCK_SKEW_SECS: i64 = 300;
+/// Long-poll wait requested from the backend
+const POLL_WAIT_SECS: u64 = 25;
+const POLL_ERROR_BACKOFF: Duration = Duration::from_secs(15);
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+/// What the backend asks the agent to do
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CommandAction {
+    /// `start_time` (optional) pins the exact process instance — see `ProcessKey`
+    KillProcess {
+        pid: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        start_time: Option<u64>,
+    },
+    QuarantineFile { path: String },
+    DeepAnalysis { path: String },
+    StartScan { profile: String },
+    CollectFile { path: String },
+    IsolateHost,
+    UnisolateHost,
+    SuspendProcess {
+        pid: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        start_time: Option<u64>,
+    },
+    ResumeProcess {
+        pid: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        start_time: Option<u64>,
+    },
+    KillProcessTree {
+        pid: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        start_time: Option<u64>,
+    },
+    /// Answer a pending allow/deny prompt for a suspended process
+    ResolveDecision { decision_id: String, choice: DecisionChoice },
+    ListDecisions,
+}
+
+impl CommandAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CommandAction::KillProcess { .. } => "kill_process",
+            CommandAction::QuarantineFile { .. } => "quarantine_file",
+            CommandAction::DeepAnalysis { .. } => "deep_analysis",
+            CommandAction::StartScan { .. } => "start_scan",
+            CommandAction::CollectFile { .. } => "collect_file",
+            CommandAction::IsolateHost => "isolate_host",
+            CommandAction::UnisolateHost => "unisolate_host",
+            CommandAction::SuspendProcess { .. } => "suspend_process",
+            CommandAction::ResumeProcess { .. } => "resume_process",
+            CommandAction::KillProcessTree { .. } => "kill_process_tree",
+            CommandAction::ResolveDecision { .. } => "resolve_decision",
+            CommandAction::ListDecisions => "list_decisions",
+        }
+    }
+}
+
+/// Signed command as delivered by the backend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteCommand {
+    pub command_id: String,
+    pub agent_id: String,
+    pub action: CommandAction,
+    #[serde(default)]
+    pub reason: Option<String>,
+    pub issued_at: String,
+    pub expires_at: String,
+    /// Base64 ed25519 signature of `signing_message()`
+    pub signature: String,
+}
+
+impl RemoteCommand {
+    /// `command_id|agent_id|issued_at|expires_at|<action as compact JSON, sorted keys>`
+    pub fn signing_message(&self) -> String {
+        let action = serde_json::to_value(&self.action)
+            .map(|v| v.to_string())
+            .unwrap_or_default();
+        format!(
+            "{}|{}|{}|{}|{}",
+            self.command_id, self.agent_id, self.issued_at, self.expires_at, action
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandStatus {
+    /// Claimed and about to run — superseded by the final entry
+    Accepted,
+    Succeeded,
+    Failed,
+    /// Bad signature, wrong agent, disabled, malformed
+    Rejected,
+    Expired,
+    /// Already executed — not run again
+    Duplicate,
+}
+
+impl CommandStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CommandStatus::Accepted => "accepted",
+            CommandStatus::Succeeded => "succeeded",
+            CommandStatus::Failed => "failed",
+            CommandStatus::Rejected => "rejected",
+            CommandStatus::Expired => "expired",
+            CommandStatus::Duplicate => "duplicate",
+        }
+    }
+}
+
+/// One line of command_history.jsonl
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandHistoryEntry {
+    pub command_id: String,
+    pub action: Option<CommandAction>,
+    pub reason: Option<String>,
+    pub issued_at: Option<String>,
+    pub received_at: String,
+    pub completed_at: String,
+    pub status: CommandStatus,
+    pub detail: String,
+    pub result: Option<Value>,
+}
+
+// ============================================================================
+// GLOBAL STATE + HISTORY
+// ============================================================================
+
+lazy_static::lazy_static! {
+    /// Command IDs already accepted (seeded from the history file)
+    static ref SEEN_COMMANDS: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+    static ref HISTORY_LOCK: Mutex<()> = Mutex::new(());
+}
+
+static POLLER_RUNNING: AtomicBool = AtomicBool::new(false);
+
+fn get_history_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("CyberGuardian")
+        .join("command_history.jsonl")
+}
+
+fn append_history(entry: &CommandHistoryEntry) {
+    let _guard = HISTORY_LOCK.lock();
+    let path = get_history_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let Ok(line) = serde_json::to_string(entry) else {
+        return;
+    };
+    match fs::OpenOptions::new().create(true).append(true).open(&path) {
+        Ok(mut file) => {
+            let _ = writeln!(file, "{}", line);
+        }
+        Err(e) => warn!("Failed to write command history: {}", e),
+    }
+}
+
+/// Most recent history entries, newest first
+pub fn read_history(limit: usize) -> Vec<CommandHistoryEntry> {
+    let _guard = HISTORY_LOCK.lock();
+    latest_entries(&fs::read_to_string(get_history_path()).unwrap_or_default(), limit)
+}
+
+/// Newest first; an `Accepted` line is hidden once its final entry exists, so
+/// only commands interrupted mid-action (crash, kill) stay "accepted"
+fn latest_entries(content: &str, limit: usize) -> Vec<CommandHistoryEntry> {
+    let mut finished = HashSet::new();
+    content.lines()
+        .rev()
+        .filter_map(|l| serde_json::from_str::<CommandHistoryEntry>(l).ok())
+        .filter(|e| match e.status {
+            CommandStatus::Accepted => !finished.contains(&e.command_id),
+            _ => {
+                finished.insert(e.command_id.clone());
+                true
+            }
+        })
+        .take(limit)
+        .collect()
+}
+
+/// Mark a command as seen; false if it was accepted before
+fn claim_command(command_id: &str) -> bool {
+    let mut guard = match SEEN_COMMANDS.lock() {
+        Ok(g) => g,
+        Err(_) => return false,
+    };
+    let seen = guard.get_or_insert_with(|| {
+        let _history = HISTORY_LOCK.lock();
+        fs::read_to_string(get_history_path())
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|l| serde_json::from_str::<CommandHistoryEntry>(l).ok())
+            .filter(|e| e.status != CommandStatus::Duplicate)
+            .map(|e| e.command_id)
+            .collect()
+    });
+    seen.insert(command_id.to_string())
+}
+
+// ============================================================================
+// VALIDATION
+// ============================================================================
+
+fn verify_signature(public_key: &str, message: &str, signature: &str) -> bool {
+    let key: Option<[u8; 32]> = B64.decode(public_key).ok().and_then(|b| b.try_into().ok());
+    let sig: Option<[u8; 64]> = B64.decode(signature).ok().and_then(|b| b.try_into().ok());
+    match (key.and_then(|k| VerifyingKey::from_bytes(&k).ok()), sig) {
+        (Some(key), Some(sig)) => key.verify(message.as_bytes(), &Signature::from_bytes(&sig)).is_ok(),
+        _ => false,
+    }
+}
+
+/// Check addressing, signature and validity window
+pub fn validate_command(
+    command: &RemoteCommand,
+    agent_id: &str,
+    public_key: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<(), (CommandStatus, String)> {
+    let reject = |msg: &str| Err((CommandStatus::Rejected, msg.to_string()));
+
+    if command.agent_id != agent_id {
+        return reject("Command addressed to a different agent");
+    }
+    let Some(public_key) = public_key else {
+        return reject("No command signing key provisioned in agent config");
+    };
+    if !verify_signature(public_key, &command.signing_message(), &command.signature) {
+        return reject("Invalid command signature");
+    }
+
+    let parse = |s: &str| DateTime::parse_from_rfc3339(s).map(|t| t.with_timezone(&Utc));
+    let (Ok(issued_at), Ok(expires_at)) = (parse(&command.issued_at), parse(&command.expires_at)) else {
+        return reject("Invalid command timestamps");
+    };
+    if issued_at > now + ChronoDuration::seconds(MAX_CLOCK_SKEW_SECS) {
+        return reject("Command issued in the future");
+    }
+    if expires_at - issued_at > ChronoDuration::hours(MAX_COMMAND_LIFETIME_HOURS) {
+        return reject("Command lifetime exceeds 24h");
+    }
+    if now > expires_at {
+        return Err((CommandStatus::Expired, format!("Command expired at {}", command.expires_at)));
+    }
+    Ok(())
+}
+
+// ============================================================================
+// EXECUTION
+// ============================================================================
+
+fn kill_process(key: ProcessKey, reason: &str) -> Result<Value, String> {
+    let pid = key.pid;
+    let name = crate::process_monitor::get_running_processes()
+        .ok()
+        .and_then(|procs| procs.into_iter().find(|p| p.pid == pid))
+        .map(|p| p.name)
+        .unwrap_or_default();
+
+    let result = crate::process_monitor::block_process(&key);
+    crate::process_monitor::record_blocked_process(
+        key,
+        &name,
+        "",
+        reason,
+        "",
+        "high",
+        result.is_ok(),
+        result.as_ref().err().cloned(),
+    );
+    result.map(|_| serde_json::json!({ "pid": pid, "name": name }))
+}
+
+fn get_collected_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("CyberGuardian")
+        .join("collected")
+}
+
+/// Copy a file into `dir` (streamed and hashed). Only path, size and hash go
+/// into the result / history — never the content
+fn collect_file_to(path: &str, dir: &std::path::Path) -> Result<Value, String> {
+    use sha2::{Digest, Sha256};
+    use std::io::Read;
+
+    let size = fs::metadata(path).map_err(|e| format!("Cannot stat {}: {}", path, e))?.len();
+    if size > MAX_COLLECT_BYTES {
+        return Err(format!("File too large to collect ({} bytes, max {})", size, MAX_COLLECT_BYTES));
+    }
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create collection dir: {}", e))?;
+
+    let file_name = std::path::Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "file".to_string());
+    let stored = dir.join(format!("{}_{}", Utc::now().timestamp_millis(), file_name));
+    let partial = stored.with_extension("partial");
+
+    let copy = || -> Result<(u64, String), String> {
+        // The file may grow after the size check — never copy more than the cap
+        let mut input = fs::File::open(path)
+            .map_err(|e| format!("Failed to read {}: {}", path, e))?
+            .take(MAX_COLLECT_BYTES + 1);
+        let mut output = fs::File::create(&partial)
+            .map_err(|e| format!("Failed to create {:?}: {}", partial, e))?;
+        let mut hasher = Sha256::new();
+        let mut buf = [0u8; 64 * 1024];
+        let mut copied = 0u64;
+        loop {
+            let n = input.read(&mut buf).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+            if n == 0 {
+                break;
+            }
+            copied += n as u64;
+            if copied > MAX_COLLECT_BYTES {
+                return Err(format!("File too large to collect (max {} bytes)", MAX_COLLECT_BYTES));
+            }
+            hasher.update(&buf[..n]);
+            output.write_all(&buf[..n]).map_err(|e| format!("Failed to write {:?}: {}", partial, e))?;
+        }
+        Ok((copied, format!("{:x}", hasher.finalize())))
+    };
+    let (copied, sha256) = copy().inspect_err(|_| {
+        let _ = fs::remove_file(&partial);
+    })?;
+    fs::rename(&partial, &stored).map_err(|e| {
+        let _ = fs::remove_file(&partial);
+        format!("Failed to store collected file: {}", e)
+    })?;
+
+    Ok(serde_json::json!({
+        "path": path,
+        "size_bytes": copied,
+        "sha256": sha256,
+        "stored_as": stored.to_string_lossy(),
+    }))
+}
+
+/// Upload a collected file to the backend in chunks. Chunks go out directly
+/// while the backend answers; from the first transient failure on (or while
+/// older chunks are still queued) the rest is spooled and finishes after an
+/// offline period. Returns the upload id and how many chunks were spooled
+fn upload_collected(client: Option<&dyn BackendClient>, collected: &Value) -> Result<Value, String> {
+    use std::io::Read;
+
+    let stored = collected["stored_as"].as_str().ok_or("Collected file has no local copy")?;
+    let size = collected["size_bytes"].as_u64().unwrap_or(0);
+    let chunk_count = size.div_ceil(UPLOAD_CHUNK_BYTES).max(1);
+    let upload_id = uuid::Uuid::new_v4().to_string();
+
+    let mut input = fs::File::open(stored).map_err(|e| format!("Failed to read {}: {}", stored, e))?;
+    let mut offline = client.is_none() || outbound_spool::has_pending(SpoolEndpoint::FileUpload);
+    let mut spooled = 0u64;
+    for chunk_index in 0..chunk_count {
+        let mut data = Vec::new();
+        (&mut input)
+            .take(UPLOAD_CHUNK_BYTES)
+            .read_to_end(&mut data)
+            .map_err(|e| format!("Failed to read {}: {}", stored, e))?;
+        let chunk = serde_json::json!({
+            "upload_id": upload_id,
+            "path": collected["path"],
+            "size_bytes": size,
+            "sha256": collected["sha256"],
+            "chunk_index": chunk_index,
+            "chunk_count": chunk_count,
+            "data_base64": B64.encode(&data),
+        });
+
+        if let (false, Some(client)) = (offline, client) {
+            match client.upload_file_chunk(&chunk) {
+                Ok(()) => continue,
+                Err(e) if e.is_retryable() => {
+                    warn!("Upload {} interrupted ({}), spooling remaining chunks", upload_id, e);
+                    offline = true;
+                }
+                Err(e) => return Err(format!("Backend rejected upload {}: {}", upload_id, e)),
+            }
+        }
+        outbound_spool::enqueue(SpoolEndpoint::FileUpload, chunk)?;
+        spooled += 1;
+    }
+
+    info!("Collected file upload {}: {} chunks, {} spooled", upload_id, chunk_count, spooled);
+    Ok(serde_json::json!({
+        "upload_id": upload_id,
+        "chunk_count": chunk_count,
+        "spooled_chunks": spooled,
+    }))
+}
+
+fn collect_file(path: &str) -> Result<Value, String> {
+    let mut result = collect_file_to(path, &get_collected_dir())?;
+    let client = ReqwestBackendClient::from_config()
+        .inspect_err(|e| warn!("Backend client unavailable ({}), spooling upload", e))
+        .ok();
+    let upload = upload_collected(client.as_ref().map(|c| c as &dyn BackendClient), &result)?;
+    if let (Some(map), Value::Object(upload)) = (result.as_object_mut(), upload) {
+        map.extend(upload);
+    }
+    Ok(result)
+}
+
+/// Map a command onto the existing agent functions
+fn execute(action: &CommandAction, reason: &str) -> Result<Value, String> {
+    match action {
+        CommandAction::KillProcess { pid, start_time } => kill_process(ProcessKey::resolve(*pid, *start_time), reason),
+        CommandAction::QuarantineFile { path } => {
+            crate::quarantine_retention::quarantine_file_locally(path, "high", reason)
+                .and_then(|r| serde_json::to_value(r).map_err(|e| e.to_string()))
+        }
+        CommandAction::DeepAnalysis { path } => {
+            crate::deep_quarantine::perform_deep_analysis(path)
+                .and_then(|r| serde_json::to_value(r).map_err(|e| e.to_string()))
+        }
+        CommandAction::StartScan { profile } => crate::local_scan::run_local_scan(profile),
+        CommandAction::CollectFile { path } => collect_file(path),
+        CommandAction::IsolateHost => crate::network_isolation::isolate(reason)
+            .and_then(|s| serde_json::to_value(s).map_err(|e| e.to_string())),
+        CommandAction::UnisolateHost => crate::network_isolation::unisolate()
+            .and_then(|s| serde_json::to_value(s).map_err(|e| e.to_string())),
+        CommandAction::SuspendProcess { pid, start_time } => {
+            crate::process_control::suspend(&ProcessKey::resolve(*pid, *start_time))
+                .map(|_| serde_json::json!({ "pid": pid, "suspended": true }))
+        }
+        CommandAction::ResumeProcess { pid, start_time } => {
+            crate::process_control::resume(&ProcessKey::resolve(*pid, *start_time))
+                .map(|_| serde_json::json!({ "pid": pid, "suspended": false }))
+        }
+        CommandAction::KillProcessTree { pid, start_time } => {
+            crate::process_control::kill_tree(&ProcessKey::resolve(*pid, *start_time))
+                .and_then(|r| serde_json::to_value(r).map_err(|e| e.to_string()))
+        }
+        CommandAction::ResolveDecision { decision_id, choice } => {
+            crate::decision_prompt::resolve(decision_id, *choice, "remote")
+                .and_then(|r| serde_json::to_value(r).map_err(|e| e.to_string()))
+        }
+        CommandAction::ListDecisions => serde_json::to_value(crate::decision_prompt::pending())
+            .map_err(|e| e.to_string()),
+    }
+}
+
+/// Validate, execute (once) and record a command; returns the history entry
+pub fn handle_command(command: &RemoteCommand, agent_id: &str) -> CommandHistoryEntry {
+    let received_at = Utc::now().to_rfc3339();
+    let reason = command.reason.clone()
+        .unwrap_or_else(|| format!("Remote command {}", command.command_id));
+
+    let entry = |status, detail: String, result| CommandHistoryEntry {
+        command_id: command.command_id.clone(),
+        action: Some(command.action.clone()),
+        reason: command.reason.clone(),
+        issued_at: Some(command.issued_at.clone()),
+        received_at: received_at.clone(),
+        completed_at: Utc::now().to_rfc3339(),
+        status,
+        detail,
+        result,
+    };
+
+    let config = crate::agent_config::current();
+    let (status, detail, result) = if !config.allow_remote_commands {
+        (CommandStatus::Rejected, "Remote commands are disabled on this agent".to_string(), None)
+    } else if let Err((status, detail)) = validate_command(
+        command,
+        agent_id,
+        config.command_public_key.as_deref(),
+        Utc::now(),
+    ) {
+        (status, detail, None)
+    } else if !claim_command(&command.command_id) {
+        (CommandStatus::Duplicate, "Command already executed".to_string(), None)
+    } else {
+        // Persist the claim before acting: a crash mid-action (isolate, kill tree...)
+        // must not run the same signed command again after restart
+        append_history(&entry(CommandStatus::Accepted, "Executing".to_string(), None));
+        info!("Executing remote command {} ({})", command.command_id, command.action.name());
+        match execute(&command.action, &reason) {
+            Ok(value) => (CommandStatus::Succeeded, "OK".to_string(), Some(value)),
+            Err(e) => (CommandStatus::Failed, e, None),
+        }
+    };
+
+    let entry = entry(status, detail, result);
+    append_history(&entry);
+    entry
+}
+
+/// Send a result to the backend (spooled if offline), signed by the agent key
+fn report_result(client: &dyn BackendClient, agent_id: &str, entry: &CommandHistoryEntry) {
+    let message = format!(
+        "result|{}|{}|{}|{}",
+        agent_id, entry.command_id, entry.status.as_str(), entry.completed_at
+    );
+    let payload = serde_json::json!({
+        "agent_id": agent_id,
+        "command_id": entry.command_id,
+        "status": entry.status,
+        "detail": entry.detail,
+        "result": entry.result,
+        "completed_at": entry.completed_at,
+        "signature": crate::agent_identity::sign(&message).unwrap_or_default(),
+    });
+
+    if outbound_spool::has_pending(SpoolEndpoint::CommandResult) {
+        let _ = outbound_spool::enqueue(SpoolEndpoint::CommandResult, payload);
+        return;
+    }
+    match client.report_command_result(&payload) {
+        Ok(()) => {}
+        Err(e) if e.is_retryable() => {
+            warn!("Command result upload failed ({}), spooling", e);
+            let _ = outbound_spool::enqueue(SpoolEndpoint::CommandResult, payload);
+        }
+        Err(e) => error!("Backend rejected command result {}: {}", entry.command_id, e),
+    }
+}
+
+/// Parse one raw command from the poll response and run it in the background
+fn dispatch(raw: Value, agent_id: String) {
+    let command: RemoteCommand = match serde_json::from_value(raw.clone()) {
+        Ok(c) => c,
+        Err(e) => {
+            warn!("Malformed remote command: {}", e);
+            if let Some(command_id) = raw.get("command_id").and_then(|v| v.as_str()) {
+                let now = Utc::now().to_rfc3339();
+                let entry = CommandHistoryEntry {
+                    command_id: command_id.to_string(),
+                    action: None,
+                    reason: None,
+                    issued_at: None,
+                    received_at: now.clone(),
+                    completed_at: now,
+                    status: CommandStatus::Rejected,
+                    detail: format!("Malformed command: {}", e),
+                    result: None,
+                };
+                append_history(&entry);
+                if let Ok(client) = ReqwestBackendClient::from_config() {
+                    report_result(&client, &agent_id, &entry);
+                }
+            }
+            return;
+        }
+    };
+
+    // Scans and deep analysis take a while — don't hold up the poll loop
+    std::thread::spawn(move || {
+        let entry = handle_command(&command, &agent_id);
+        info!("Remote command {} → {:?}", entry.command_id, entry.status);
+        match ReqwestBackendClient::from_config() {
+            Ok(client) => report_result(&client, &agent_id, &entry),
+            Err(e) => {
+                warn!("Cannot report command result: {}", e);
+            }
+        }
+    });
+}
+
+// ============================================================================
+// POLLER
+// ============================================================================
+
+/// Start the long-poll loop (idle until the agent is enrolled)
+pub fn start_command_poller() {
+    if POLLER_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    std::thread::spawn(|| {
+        info!("Remote command poller started");
+        while POLLER_RUNNING.load(Ordering::SeqCst) {
+            let config = crate::agent_config::current();
+            if !config.allow_remote_commands
+                || config.command_public_key.is_none()
+                || !crate::agent_identity::is_registered()
+            {
+                std::thread::sleep(POLL_ERROR_BACKOFF);
+                continue;
+            }
+            let agent_id = match crate::agent_identity::agent_id() {
+                Ok(id) => id,
+                Err(e) => {
+                    warn!("Command poller: {}", e);
+                    std::thread::sleep(POLL_ERROR_BACKOFF);
+                    continue;
+                }
+            };
+
+            // Server must answer before the client times out
+            let wait = POLL_WAIT_SECS.min(config.request_timeout_secs.saturating_sub(5));
+            let result = ReqwestBackendClient::from_config()
+                .and_then(|client| client.poll_commands(&agent_id, wait));
+
+            match result {
+                Ok(commands) => {
+                    for raw in commands {
+                        dispatch(raw, agent_id.clone());
+                    }
+                    if wait == 0 {
+                        std::thread::sleep(Duration::from_secs(5));
+                    }
+                }
+                Err(e) => {
+                    warn!("Command poll failed: {}", e);
+                    std::thread::sleep(POLL_ERROR_BACKOFF);
+                }
+            }
+        }
+        info!("Remote command poller stopped");
+    });
+}
+
+pub fn stop_command_poller() {
+    POLLER_RUNNING.store(false, Ordering::SeqCst);
+}
+
+// ============================================================================
+// TAURI COMMANDS
+// ============================================================================
+
+/// Remote command audit log (newest first)
+#[tauri::command]
+pub fn get_command_history(limit: Option<usize>) -> Vec<CommandHistoryEntry> {
+    read_history(limit.unwrap_or(100))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    fn signed(key: &SigningKey, mut command: RemoteCommand) -> RemoteCommand {
+        command.signature = B64.encode(key.sign(command.signing_message().as_bytes()).to_bytes());
+        command
+    }
+
+    fn command(now: DateTime<Utc>) -> RemoteCommand {
+        RemoteCommand {
+            command_id: "cmd-1".into(),
+            agent_id: "agent-1".into(),
+            action: CommandAction::KillProcess { pid: 4242, start_time: None },
+            reason: Some("analyst request".into()),
+            issued_at: now.to_rfc3339(),
+            expires_at: (now + ChronoDuration::minutes(10)).to_rfc3339(),
+            signature: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_valid_signed_command_is_accepted() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let public = B64.encode(key.verifying_key().to_bytes());
+        let now = Utc::now();
+        let cmd = signed(&key, command(now));
+        assert!(validate_command(&cmd, "agent-1", Some(&public), now).is_ok());
+    }
+
+    #[test]
+    fn test_tampered_or_misaddressed_commands_are_rejected() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let public = B64.encode(key.verifying_key().to_bytes());
+        let now = Utc::now();
+
+        let mut tampered = signed(&key, command(now));
+        tampered.action = CommandAction::KillProcess { pid: 4, start_time: None };
+        let err = validate_command(&tampered, "agent-1", Some(&public), now).unwrap_err();
+        assert_eq!(err.0, CommandStatus::Rejected);
+
+        let cmd = signed(&key, command(now));
+        assert_eq!(validate_command(&cmd, "agent-2", Some(&public), now).unwrap_err().0, CommandStatus::Rejected);
+        assert_eq!(validate_command(&cmd, "agent-1", None, now).unwrap_err().0, CommandStatus::Rejected);
+
+        let other = SigningKey::from_bytes(&[9u8; 32]);
+        let other_public = B64.encode(other.verifying_key().to_bytes());
+        assert_eq!(validate_command(&cmd, "agent-1", Some(&other_public), now).unwrap_err().0, CommandStatus::Rejected);
+    }
+
+    #[test]
+    fn test_expiry_and_lifetime_limits() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let public = B64.encode(key.verifying_key().to_bytes());
+        let now = Utc::now();
+
+        let cmd = signed(&key, command(now));
+        let later = now + ChronoDuration::minutes(11);
+        assert_eq!(validate_command(&cmd, "agent-1", Some(&public), later).unwrap_err().0, CommandStatus::Expired);
+
+        let mut long_lived = command(now);
+        long_lived.expires_at = (now + ChronoDuration::hours(48)).to_rfc3339();
+        let long_lived = signed(&key, long_lived);
+        assert_eq!(validate_command(&long_lived, "agent-1", Some(&public), now).unwrap_err().0, CommandStatus::Rejected);
+    }
+
+    #[test]
+    fn test_collect_file_keeps_content_out_of_result() {
+        let dir = std::env::temp_dir().join(format!("cg_collect_{}", std::process::id()));
+        let source = dir.join("evidence.bin");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&source, b"payload bytes").unwrap();
+
+        let result = collect_file_to(source.to_str().unwrap(), &dir.join("out")).unwrap();
+        assert_eq!(result["size_bytes"], 13);
+        assert!(result.get("content_base64").is_none());
+        let stored = result["stored_as"].as_str().unwrap();
+        assert_eq!(fs::read(stored).unwrap(), b"payload bytes");
+        assert!(!serde_json::to_string(&result).unwrap().contains("payload"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_collected_file_is_uploaded_in_chunks() {
+        use crate::backend_client::mock::{MockBackend, MockResponse};
+        use crate::backend_client::FILE_UPLOAD_PATH;
+
+        let dir = std::env::temp_dir().join(format!("cg_upload_{}", std::process::id()));
+        let source = dir.join("evidence.bin");
+        fs::create_dir_all(&dir).unwrap();
+        let content: Vec<u8> = (0..UPLOAD_CHUNK_BYTES * 2 + 10).map(|i| (i % 251) as u8).collect();
+        fs::write(&source, &content).unwrap();
+        let collected = collect_file_to(source.to_str().unwrap(), &dir.join("out")).unwrap();
+
+        let mock = MockBackend::start();
+        mock.on(FILE_UPLOAD_PATH, vec![MockResponse::ok(serde_json::json!({}))]);
+        let upload = upload_collected(Some(&mock.client()), &collected).unwrap();
+        assert_eq!(upload["chunk_count"], 3);
+        assert_eq!(upload["spooled_chunks"], 0);
+
+        let mut received = Vec::new();
+        for (i, request) in mock.requests_to(FILE_UPLOAD_PATH).iter().enumerate() {
+            let chunk = request.json();
+            assert_eq!(chunk["upload_id"], upload["upload_id"]);
+            assert_eq!(chunk["chunk_index"], i);
+            assert_eq!(chunk["sha256"], collected["sha256"]);
+            received.extend(B64.decode(chunk["data_base64"].as_str().unwrap()).unwrap());
+        }
+        assert_eq!(received, content);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_accepted_entry_is_superseded() {
+        let line = |id: &str, status: CommandStatus| serde_json::to_string(&CommandHistoryEntry {
+            command_id: id.into(),
+            action: Some(CommandAction::IsolateHost),
+            reason: None,
+            issued_at: None,
+            received_at: String::new(),
+            completed_at: String::new(),
+            status,
+            detail: String::new(),
+            result: None,
+        }).unwrap();
+        let content = [
+            line("c1", CommandStatus::Accepted),
+            line("c1", CommandStatus::Succeeded),
+            line("c2", CommandStatus::Accepted), // interrupted by a crash
+        ].join("\n");
+
+        let entries = latest_entries(&content, 10);
+        let statuses: Vec<(&str, CommandStatus)> = entries.iter().map(|e| (e.command_id.as_str(), e.status)).collect();
+        assert_eq!(statuses, [("c2", CommandStatus::Accepted), ("c1", CommandStatus::Succeeded)]);
+    }
+
+    #[test]
+    fn test_action_wire_format() {
+        let action: CommandAction = serde_json::from_str(r#"{"type":"start_scan","profile":"quick"}"#).unwrap();
+        assert_eq!(action, CommandAction::StartScan { profile: "quick".into() });
+        assert_eq!(action.name(), "start_scan");
+
+        let isolate: CommandAction = serde_json::from_str(r#"{"type":"isolate_host"}"#).unwrap();
+        assert_eq!(isolate, CommandAction::IsolateHost);
+        assert_eq!(serde_json::to_string(&CommandAction::UnisolateHost).unwrap(), r#"{"type":"unisolate_host"}"#);
+
+        // start_time is optional and omitted when absent, so older signed payloads still verify
+        let kill = CommandAction::KillProcess { pid: 7, start_time: None };
+        assert_eq!(serde_json::to_string(&kill).unwrap(), r#"{"type":"kill_process","pid":7}"#);
+        let pinned: CommandAction = serde_json::from_str(r#"{"type":"kill_process","pid":7,"start_time":133}"#).unwrap();
+        assert_eq!(pinned, CommandAction::KillProcess { pid: 7, start_time: Some(133) });
+
+        let decide: CommandAction = serde_json::from_str(r#"{"type":"resolve_decision","decision_id":"d1","choice":"allow_once"}"#).unwrap();
+        assert_eq!(decide, CommandAction::ResolveDecision { decision_id: "d1".into(), choice: DecisionChoice::AllowOnce });
+        assert_eq!(decide.name(), "resolve_decision");
+    }
+}
@@ -0,0 +1,585 @@
+//! Response Playbooks
+//! Maps detections to ordered response actions. A playbook matches on rule id
+//! (MITRE technique; "T1071" also covers "T1071.004") or severity — rule-id
+//! playbooks win over severity playbooks, then list order decides. Every
+//! detection path (polling monitor, ETW, DNS / network detectors) goes through
+//! `respond`, which runs or audits the actions and records the detection.
+//! Whether enforcement actions run is decided by the shared enforcement mode
+
+use crate::decision_prompt::{self, DecisionChoice, DecisionResolution};
+use crate::enforcement::{self, Confidence, EnforcementMode, Verdict};
+use crate::process_identity::ProcessKey;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, RwLock};
+use tracing::{info, warn};
+
+const MAX_HISTORY: usize = 200;
+const SEVERITIES: [&str; 4] = ["critical", "high", "medium", "low"];
+
+// ============================================================================
+// SETTINGS
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseAction {
+    AlertOnly,
+    Suspend,
+    Kill,
+    KillTree,
+    QuarantineImage,
+    IsolateHost,
+    CollectTriage,
+}
+
+impl ResponseAction {
+    /// Actions that change the host (everything except alert / triage collection)
+    pub fn is_enforcement(&self) -> bool {
+        !matches!(self, ResponseAction::AlertOnly | ResponseAction::CollectTriage)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Playbook {
+    pub id: String,
+    pub enabled: bool,
+    /// MITRE technique ids, case-insensitive; a parent technique covers its sub-techniques
+    pub rule_ids: Vec<String>,
+    pub severities: Vec<String>,
+    /// Executed in order; a failed action does not stop the ones after it
+    pub actions: Vec<ResponseAction>,
+    /// Log what would happen without touching the host
+    pub dry_run: bool,
+}
+
+impl Default for Playbook {
+    fn default() -> Self {
+        Playbook {
+            id: String::new(),
+            enabled: true,
+            rule_ids: Vec::new(),
+            severities: Vec::new(),
+            actions: vec![ResponseAction::AlertOnly],
+            dry_run: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlaybookSettings {
+    /// Global dry run — every playbook only audits
+    pub audit_mode: bool,
+    pub playbooks: Vec<Playbook>,
+}
+
+impl Default for PlaybookSettings {
+    fn default() -> Self {
+        PlaybookSettings {
+            audit_mode: false,
+            playbooks: vec![
+                // C2 / DNS detections are alert-only: the process is usually a browser or updater
+                Playbook {
+                    id: "network-alert".to_string(),
+                    rule_ids: vec!["T1071".to_string()],
+                    ..Playbook::default()
+                },
+                Playbook {
+                    id: "block-critical-high".to_string(),
+                    severities: vec!["critical".to_string(), "high".to_string()],
+                    actions: vec![ResponseAction::Kill],
+                    ..Playbook::default()
+                },
+            ],
+        }
+    }
+}
+
+impl PlaybookSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        let mut ids = std::collections::HashSet::new();
+        for p in &self.playbooks {
+            if p.id.trim().is_empty() {
+                return Err("Playbook id must not be empty".to_string());
+            }
+            if !ids.insert(p.id.as_str()) {
+                return Err(format!("Duplicate playbook id: {}", p.id));
+            }
+            if p.rule_ids.is_empty() && p.severities.is_empty() {
+                return Err(format!("Playbook {} needs at least one rule id or severity", p.id));
+            }
+            if let Some(s) = p.severities.iter().find(|s| !SEVERITIES.contains(&s.to_lowercase().as_str())) {
+                return Err(format!("Playbook {}: unknown severity '{}'", p.id, s));
+            }
+            if p.actions.is_empty() {
+                return Err(format!("Playbook {} has no actions", p.id));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn get_settings_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("CyberGuardian")
+        .join("response_playbooks.json")
+}
+
+fn load_settings() -> PlaybookSettings {
+    fs::read_to_string(get_settings_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save_settings(settings: &PlaybookSettings) -> Result<(), String> {
+    let path = get_settings_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create settings dir: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write settings: {}", e))
+}
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// One detection from any source, handed to `respond`
+#[derive(Debug, Clone)]
+pub struct Detection {
+    pub key: ProcessKey,
+    pub process_name: String,
+    pub parent_name: String,
+    /// MITRE technique of the rule that fired
+    pub rule_id: String,
+    pub severity: String,
+    pub reason: String,
+    /// "polling" | "etw" | "network"
+    pub source: &'static str,
+    /// Already read by the detector; otherwise captured before any action runs
+    pub command_line: Option<String>,
+    pub confidence: Confidence,
+    /// Detector already suspended the process (ETW pre-analysis)
+    pub suspended: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionStatus {
+    Done,
+    Failed,
+    DryRun,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ActionResult {
+    pub action: ResponseAction,
+    pub status: ActionStatus,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResponseOutcome {
+    pub playbook_id: String,
+    pub pid: u32,
+    pub process_key: ProcessKey,
+    pub process_name: String,
+    pub rule_id: String,
+    pub severity: String,
+    pub source: String,
+    pub mode: EnforcementMode,
+    pub verdict: Verdict,
+    pub dry_run: bool,
+    pub results: Vec<ActionResult>,
+    /// Process held suspended until the user decides
+    pub pending_decision: Option<String>,
+    pub decision: Option<DecisionResolution>,
+    pub timestamp: String,
+}
+
+impl ResponseOutcome {
+    /// Process was killed or deliberately left suspended — callers must not resume it
+    pub fn contained(&self) -> bool {
+        self.pending_decision.is_some() || self.results.iter().any(|r| r.status == ActionStatus::Done && matches!(
+            r.action,
+            ResponseAction::Suspend | ResponseAction::Kill | ResponseAction::KillTree
+        ))
+    }
+
+    fn enforced(&self) -> bool {
+        self.results.iter().any(|r| r.status == ActionStatus::Done && r.action.is_enforcement())
+    }
+
+    fn errors(&self) -> Option<String> {
+        let errors: Vec<String> = self.results.iter()
+            .filter(|r| r.status == ActionStatus::Failed)
+            .map(|r| format!("{:?}: {}", r.action, r.detail.as_deref().unwrap_or("failed")))
+            .collect();
+        (!errors.is_empty()).then(|| errors.join("; "))
+    }
+}
+
+// ============================================================================
+// PLAYBOOK SELECTION
+// ============================================================================
+
+fn rule_matches(pattern: &str, rule_id: &str) -> bool {
+    let (pattern, rule_id) = (pattern.to_uppercase(), rule_id.to_uppercase());
+    rule_id == pattern || rule_id.starts_with(&format!("{}.", pattern))
+}
+
+/// Rule-id playbooks first, then severity playbooks, list order within each
+pub fn select_playbook<'a>(settings: &'a PlaybookSettings, rule_id: &str, severity: &str) -> Option<&'a Playbook> {
+    let enabled = || settings.playbooks.iter().filter(|p| p.enabled);
+    enabled()
+        .find(|p| p.rule_ids.iter().any(|r| rule_matches(r, rule_id)))
+        .or_else(|| enabled().find(|p| p.severities.iter().any(|s| s.eq_ignore_ascii_case(severity))))
+}
+
+// ============================================================================
+// ACTIONS
+// ============================================================================
+
+#[cfg(windows)]
+fn process_cmdline(pid: u32) -> String {
+    crate::process_monitor::get_process_cmdline_pub(pid)
+}
+
+#[cfg(not(windows))]
+fn process_cmdline(pid: u32) -> String {
+    fs::read(format!("/proc/{}/cmdline", pid))
+        .map(|b| String::from_utf8_lossy(&b).replace('\0', " ").trim().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(windows)]
+fn process_image(pid: u32) -> String {
+    crate::process_monitor::get_process_exe_path(pid)
+}
+
+#[cfg(not(windows))]
+fn process_image(pid: u32) -> String {
+    fs::read_link(format!("/proc/{}/exe", pid))
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Process context read before any action can kill the process
+#[derive(Clone)]
+struct Context {
+    command_line: String,
+    image_path: String,
+}
+
+fn triage_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("CyberGuardian")
+        .join("triage")
+}
+
+/// Detection + process details + image hash → triage/<timestamp>_<pid>.json
+fn collect_triage(detection: &Detection, ctx: &Context) -> Result<String, String> {
+    use sha2::{Digest, Sha256};
+
+    let image_sha256 = fs::read(&ctx.image_path).ok().map(|d| format!("{:x}", Sha256::digest(&d)));
+    let bundle = serde_json::json!({
+        "collected_at": chrono::Utc::now().to_rfc3339(),
+        "hostname": crate::agent_identity::hostname(),
+        "process": {
+            "pid": detection.key.pid,
+            "uid": detection.key.to_string(),
+            "name": detection.process_name,
+            "parent_name": detection.parent_name,
+            "command_line": ctx.command_line,
+            "image_path": ctx.image_path,
+            "image_sha256": image_sha256,
+            "running": detection.key.is_current(),
+        },
+        "detection": {
+            "rule_id": detection.rule_id,
+            "severity": detection.severity,
+            "reason": detection.reason,
+            "source": detection.source,
+        },
+    });
+
+    let dir = triage_dir();
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create triage dir: {}", e))?;
+    let path = dir.join(format!("{}_{}.json", chrono::Utc::now().timestamp_millis(), detection.key.pid));
+    let json = serde_json::to_string_pretty(&bundle)
+        .map_err(|e| format!("Failed to serialize triage: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write triage: {}", e))?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+fn execute(action: ResponseAction, detection: &Detection, ctx: &Context, playbook_id: &str) -> Result<Option<String>, String> {
+    use crate::process_control;
+
+    match action {
+        ResponseAction::AlertOnly => Ok(None),
+        ResponseAction::Suspend => process_control::suspend(&detection.key).map(|_| None),
+        ResponseAction::Kill => process_control::terminate(&detection.key).map(|_| None),
+        ResponseAction::KillTree => process_control::kill_tree(&detection.key)
+            .map(|r| Some(format!("{} killed, {} failed", r.killed.len(), r.failed.len()))),
+        ResponseAction::QuarantineImage => {
+            if ctx.image_path.is_empty() {
+                return Err("Image path unknown".to_string());
+            }
+            crate::quarantine_retention::quarantine_file_locally(&ctx.image_path, &detection.severity, &detection.reason)
+                .map(|r| Some(r.quarantined_path))
+        }
+        ResponseAction::IsolateHost => {
+            let reason = format!("Playbook {}: {} ({})", playbook_id, detection.reason, detection.process_name);
+            crate::network_isolation::isolate(&reason).map(|_| None)
+        }
+        ResponseAction::CollectTriage => collect_triage(detection, ctx).map(Some),
+    }
+}
+
+// ============================================================================
+// ENGINE
+// ============================================================================
+
+lazy_static::lazy_static! {
+    static ref SETTINGS: RwLock<PlaybookSettings> = RwLock::new(load_settings());
+    static ref HISTORY: Mutex<VecDeque<ResponseOutcome>> = Mutex::new(VecDeque::new());
+}
+
+#[derive(Clone)]
+struct Plan {
+    playbook_id: String,
+    actions: Vec<ResponseAction>,
+    dry_run: bool,
+    mode: EnforcementMode,
+    verdict: Verdict,
+}
+
+/// Run the matching playbook for a detection and record it. Enforcement actions
+/// only execute when the enforcement mode's verdict is `Enforce`, or after the
+/// user confirms a `Prompt`
+pub fn respond(detection: &Detection) -> ResponseOutcome {
+    let settings = SETTINGS.read().unwrap().clone();
+    let (playbook_id, actions, playbook_dry_run) = match select_playbook(&settings, &detection.rule_id, &detection.severity) {
+        Some(p) => (p.id.clone(), p.actions.clone(), p.dry_run),
+        None => ("default".to_string(), vec![ResponseAction::AlertOnly], false),
+    };
+    let mode = crate::process_monitor::enforcement_mode();
+    let plan = Plan {
+        playbook_id,
+        actions,
+        dry_run: settings.audit_mode || playbook_dry_run,
+        mode,
+        verdict: enforcement::decide(mode, &detection.severity, detection.confidence),
+    };
+
+    let ctx = Context {
+        command_line: detection.command_line.clone()
+            .unwrap_or_else(|| if detection.key.is_current() { process_cmdline(detection.key.pid) } else { String::new() }),
+        image_path: if detection.key.is_current() { process_image(detection.key.pid) } else { String::new() },
+    };
+
+    if decision_prompt::is_excepted(&detection.rule_id, &detection.process_name, &ctx.image_path) {
+        info!(pid = detection.key.pid, process = %detection.process_name, rule = %detection.rule_id, "Allowed by exception");
+        return finish(detection, ctx, plan, Some("allowed by exception"), None);
+    }
+
+    if plan.verdict == Verdict::Prompt && !plan.dry_run && plan.actions.iter().any(|a| a.is_enforcement()) {
+        if let Some(outcome) = prompt(detection, &ctx, &plan) {
+            return outcome;
+        }
+    }
+
+    let held_back = enforcement::audit_reason(plan.mode, plan.verdict);
+    finish(detection, ctx, plan, held_back, None)
+}
+
+/// Hold the process suspended and let the user decide. None when nobody can be asked
+fn prompt(detection: &Detection, ctx: &Context, plan: &Plan) -> Option<ResponseOutcome> {
+    if !detection.suspended {
+        if let Err(e) = crate::process_control::suspend(&detection.key) {
+            warn!(pid = detection.key.pid, process = %detection.process_name, "Cannot hold process for a decision: {}", e);
+            return None;
+        }
+    }
+
+    let (held, held_ctx, held_plan) = (detection.clone(), ctx.clone(), plan.clone());
+    let input = decision_prompt::PromptInput {
+        key: detection.key,
+        process_name: detection.process_name.clone(),
+        parent_name: detection.parent_name.clone(),
+        image_path: ctx.image_path.clone(),
+        command_line: ctx.command_line.clone(),
+        rule_id: detection.rule_id.clone(),
+        severity: detection.severity.clone(),
+        reason: detection.reason.clone(),
+        playbook_id: plan.playbook_id.clone(),
+        actions: plan.actions.clone(),
+    };
+    let decision_id = decision_prompt::request(input, move |resolution| {
+        let held_back = if resolution.choice == DecisionChoice::Kill {
+            None
+        } else {
+            let _ = crate::process_control::resume(&held.key);
+            Some("allowed by user")
+        };
+        finish(&held, held_ctx, held_plan, held_back, Some(resolution.clone()));
+    });
+
+    let Some(decision_id) = decision_id else {
+        if !detection.suspended {
+            let _ = crate::process_control::resume(&detection.key);
+        }
+        return None;
+    };
+    Some(ResponseOutcome {
+        playbook_id: plan.playbook_id.clone(),
+        pid: detection.key.pid,
+        process_key: detection.key,
+        process_name: detection.process_name.clone(),
+        rule_id: detection.rule_id.clone(),
+        severity: detection.severity.clone(),
+        source: detection.source.to_string(),
+        mode: plan.mode,
+        verdict: plan.verdict,
+        dry_run: false,
+        results: Vec::new(),
+        pending_decision: Some(decision_id),
+        decision: None,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+/// Run (or audit, when `held_back` says why not) the plan's actions and record the detection
+fn finish(detection: &Detection, ctx: Context, plan: Plan, held_back: Option<&str>, decision: Option<DecisionResolution>) -> ResponseOutcome {
+    let playbook_id = plan.playbook_id;
+    let mut results = Vec::with_capacity(plan.actions.len());
+    for action in plan.actions {
+        let audit = if plan.dry_run { Some("dry run") } else { held_back.filter(|_| action.is_enforcement()) };
+        let result = if let Some(why) = audit {
+            info!(playbook = playbook_id, ?action, pid = detection.key.pid, process = %detection.process_name, "Action not executed: {}", why);
+            ActionResult { action, status: ActionStatus::DryRun, detail: Some(why.to_string()) }
+        } else {
+            match execute(action, detection, &ctx, &playbook_id) {
+                Ok(detail) => {
+                    if action != ResponseAction::AlertOnly {
+                        info!(playbook = playbook_id, ?action, pid = detection.key.pid, process = %detection.process_name, "Response action done");
+                    }
+                    ActionResult { action, status: ActionStatus::Done, detail }
+                }
+                Err(e) => {
+                    warn!(playbook = playbook_id, ?action, pid = detection.key.pid, process = %detection.process_name, "Response action failed: {}", e);
+                    ActionResult { action, status: ActionStatus::Failed, detail: Some(e) }
+                }
+            }
+        };
+        results.push(result);
+    }
+
+    let outcome = ResponseOutcome {
+        playbook_id,
+        pid: detection.key.pid,
+        process_key: detection.key,
+        process_name: detection.process_name.clone(),
+        rule_id: detection.rule_id.clone(),
+        severity: detection.severity.clone(),
+        source: detection.source.to_string(),
+        mode: plan.mode,
+        verdict: plan.verdict,
+        dry_run: plan.dry_run,
+        results,
+        pending_decision: None,
+        decision,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    };
+
+    crate::process_monitor::store_blocked_process(crate::process_monitor::BlockedProcess {
+        pid: detection.key.pid,
+        process_key: Some(detection.key),
+        process_name: detection.process_name.clone(),
+        parent_name: detection.parent_name.clone(),
+        reason: detection.reason.clone(),
+        mitre_technique: detection.rule_id.clone(),
+        severity: detection.severity.clone(),
+        timestamp: outcome.timestamp.clone(),
+        success: outcome.enforced(),
+        error: outcome.errors(),
+        command_line: Some(ctx.command_line).filter(|c| !c.is_empty()),
+        confidence: Some(detection.confidence),
+    });
+
+    if let Ok(mut history) = HISTORY.lock() {
+        history.push_back(outcome.clone());
+        while history.len() > MAX_HISTORY {
+            history.pop_front();
+        }
+    }
+    outcome
+}
+
+// ============================================================================
+// TAURI COMMANDS
+// ============================================================================
+
+#[tauri::command]
+pub fn get_response_playbooks() -> PlaybookSettings {
+    SETTINGS.read().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn set_response_playbooks(settings: PlaybookSettings) -> Result<PlaybookSettings, String> {
+    settings.validate()?;
+    save_settings(&settings)?;
+    *SETTINGS.write().unwrap() = settings.clone();
+    info!("Response playbooks updated ({} playbooks, audit mode {})", settings.playbooks.len(), settings.audit_mode);
+    Ok(settings)
+}
+
+#[tauri::command]
+pub fn get_response_history() -> Vec<ResponseOutcome> {
+    HISTORY.lock().map(|h| h.iter().rev().cloned().collect()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule_playbooks_win_over_severity() {
+        let settings = PlaybookSettings::default();
+        assert!(settings.validate().is_ok());
+
+        assert_eq!(select_playbook(&settings, "T1071.004", "critical").unwrap().id, "network-alert");
+        assert_eq!(select_playbook(&settings, "t1071", "high").unwrap().id, "network-alert");
+        assert_eq!(select_playbook(&settings, "T1003", "Critical").unwrap().id, "block-critical-high");
+        assert!(select_playbook(&settings, "T10710", "medium").is_none());
+
+        let mut disabled = settings.clone();
+        disabled.playbooks[0].enabled = false;
+        assert_eq!(select_playbook(&disabled, "T1071", "critical").unwrap().id, "block-critical-high");
+    }
+
+    #[test]
+    fn test_settings_validation_and_wire_format() {
+        let settings: PlaybookSettings = serde_json::from_str(
+            r#"{"playbooks":[{"id":"lsass","rule_ids":["T1003"],"actions":["collect_triage","kill_tree","isolate_host"],"dry_run":true}]}"#,
+        ).unwrap();
+        let p = &settings.playbooks[0];
+        assert!(p.enabled && p.dry_run && !settings.audit_mode);
+        assert_eq!(p.actions, vec![ResponseAction::CollectTriage, ResponseAction::KillTree, ResponseAction::IsolateHost]);
+        assert!(settings.validate().is_ok());
+
+        let mut bad = settings.clone();
+        bad.playbooks.push(p.clone());
+        assert!(bad.validate().unwrap_err().contains("Duplicate"));
+        bad.playbooks[1] = Playbook { id: "x".to_string(), severities: vec!["urgent".to_string()], ..Playbook::default() };
+        assert!(bad.validate().unwrap_err().contains("unknown severity"));
+    }
+}
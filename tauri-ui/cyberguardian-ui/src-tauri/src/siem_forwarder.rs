@@ -0,0 +1,887 @@
+//! SIEM Forwarder
+//! Forwards detections (blocked processes, file verdicts, scanner findings) to
+//! customer SIEMs as RFC 5424 syslog over UDP / TCP / TLS, formatted as CEF or LEEF
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::telemetry_schema::{normalize, TelemetryEvent, TelemetrySchema};
+
+const VENDOR: &str = "CyberGuardian";
+const PRODUCT: &str = "CyberGuardian Agent";
+const APP_NAME: &str = "cyberguardian";
+/// security/authorization messages (RFC 5424 facility 10)
+const SYSLOG_FACILITY: u8 = 10;
+/// Keep UDP datagrams below common path MTU limits
+const MAX_UDP_MESSAGE: usize = 8192;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Scanner entries below this risk score are inventory, not findings (Medium and up)
+const MIN_FINDING_RISK: u32 = 40;
+
+// ============================================================================
+// SECURITY EVENTS
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    /// Agent severity strings ("CRITICAL", "high", ...)
+    pub fn parse(s: &str) -> Severity {
+        match s.to_lowercase().as_str() {
+            "critical" => Severity::Critical,
+            "high" => Severity::High,
+            "medium" => Severity::Medium,
+            _ => Severity::Low,
+        }
+    }
+
+    /// 0-100 risk / threat score
+    pub fn from_score(score: f64) -> Severity {
+        if score >= 90.0 {
+            Severity::Critical
+        } else if score >= 70.0 {
+            Severity::High
+        } else if score >= 40.0 {
+            Severity::Medium
+        } else {
+            Severity::Low
+        }
+    }
+
+    /// CEF / LEEF severity (0-10)
+    fn numeric(&self) -> u8 {
+        match self {
+            Severity::Low => 3,
+            Severity::Medium => 5,
+            Severity::High => 8,
+            Severity::Critical => 10,
+        }
+    }
+
+    /// RFC 5424 severity
+    fn syslog(&self) -> u8 {
+        match self {
+            Severity::Low => 5,      // notice
+            Severity::Medium => 4,   // warning
+            Severity::High => 3,     // error
+            Severity::Critical => 2, // critical
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    ProcessBlocked,
+    FileVerdict,
+    RegistryFinding,
+    ServiceFinding,
+    TaskFinding,
+}
+
+impl EventKind {
+    /// CEF Signature ID / LEEF EventID
+    fn signature_id(&self) -> &'static str {
+        match self {
+            EventKind::ProcessBlocked => "CG-PROC-BLOCK",
+            EventKind::FileVerdict => "CG-FILE-VERDICT",
+            EventKind::RegistryFinding => "CG-REG-FINDING",
+            EventKind::ServiceFinding => "CG-SVC-FINDING",
+            EventKind::TaskFinding => "CG-TASK-FINDING",
+        }
+    }
+
+    fn category(&self) -> &'static str {
+        match self {
+            EventKind::ProcessBlocked => "process",
+            EventKind::FileVerdict => "file",
+            EventKind::RegistryFinding | EventKind::ServiceFinding | EventKind::TaskFinding => "persistence",
+        }
+    }
+}
+
+/// Normalized detection event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecurityEvent {
+    pub kind: EventKind,
+    pub name: String,
+    pub severity: Severity,
+    pub mitre_technique: Option<String>,
+    pub timestamp: String,
+    pub pid: Option<u32>,
+    /// "pid@start_time" — survives PID reuse (see `ProcessKey`)
+    #[serde(default)]
+    pub process_uid: Option<String>,
+    pub process_name: Option<String>,
+    pub parent_name: Option<String>,
+    pub file_path: Option<String>,
+    pub threat_score: Option<f64>,
+    /// "blocked", "quarantined", "detected", "block_failed", ...
+    pub outcome: String,
+    pub reason: String,
+}
+
+/// Autorun / service / task scan entry worth reporting as a detection
+fn is_finding(risk_score: u32, indicators: &[String]) -> bool {
+    risk_score >= MIN_FINDING_RISK && !indicators.is_empty()
+}
+
+impl SecurityEvent {
+    fn new(kind: EventKind, name: String, severity: Severity, outcome: &str, reason: String) -> Self {
+        SecurityEvent {
+            kind,
+            name,
+            severity,
+            mitre_technique: None,
+            timestamp: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
+            pid: None,
+            process_uid: None,
+            process_name: None,
+            parent_name: None,
+            file_path: None,
+            threat_score: None,
+            outcome: outcome.to_string(),
+            reason,
+        }
+    }
+
+    pub fn from_blocked_process(record: &crate::process_monitor::BlockedProcess) -> Self {
+        let outcome = if record.success { "blocked" } else { "block_failed" };
+        SecurityEvent {
+            mitre_technique: Some(record.mitre_technique.clone()).filter(|t| !t.is_empty()),
+            timestamp: record.timestamp.clone(),
+            pid: Some(record.pid),
+            process_uid: record.process_key.filter(|k| k.is_known()).map(|k| k.to_string()),
+            process_name: Some(record.process_name.clone()),
+            parent_name: Some(record.parent_name.clone()),
+            ..Self::new(
+                EventKind::ProcessBlocked,
+                format!("Malicious process {}", record.process_name),
+                Severity::parse(&record.severity),
+                outcome,
+                record.reason.clone(),
+            )
+        }
+    }
+
+    /// `outcome`: "quarantined", "quarantine_failed" or "allowed"
+    pub fn file_verdict(file_path: &str, threat_score: f64, outcome: &str) -> Self {
+        SecurityEvent {
+            file_path: Some(file_path.to_string()),
+            threat_score: Some(threat_score),
+            ..Self::new(
+                EventKind::FileVerdict,
+                "File scan verdict".to_string(),
+                Severity::from_score(threat_score),
+                outcome,
+                format!("Backend threat score {:.1}", threat_score),
+            )
+        }
+    }
+
+    /// Scanner entries → detections; `None` for plain inventory (low risk, no indicators)
+    pub fn from_registry(entry: &crate::registry_scanner::RegistryEntry) -> Option<Self> {
+        if !is_finding(entry.risk_score, &entry.indicators) {
+            return None;
+        }
+        Some(SecurityEvent {
+            mitre_technique: Some("T1547.001".to_string()),
+            file_path: Some(entry.value_data.clone()),
+            threat_score: Some(entry.risk_score as f64),
+            ..Self::new(
+                EventKind::RegistryFinding,
+                format!("Suspicious autorun {}\\{}\\{}", entry.hive, entry.key_path, entry.value_name),
+                Severity::from_score(entry.risk_score as f64),
+                "detected",
+                entry.indicators.join("; "),
+            )
+        })
+    }
+
+    pub fn from_service(entry: &crate::service_scanner::ServiceEntry) -> Option<Self> {
+        if !is_finding(entry.risk_score, &entry.indicators) {
+            return None;
+        }
+        Some(SecurityEvent {
+            mitre_technique: Some("T1543.003".to_string()),
+            file_path: Some(entry.binary_path.clone()),
+            threat_score: Some(entry.risk_score as f64),
+            ..Self::new(
+                EventKind::ServiceFinding,
+                format!("Suspicious service {}", entry.service_name),
+                Severity::from_score(entry.risk_score as f64),
+                "detected",
+                entry.indicators.join("; "),
+            )
+        })
+    }
+
+    pub fn from_task(entry: &crate::task_scanner::TaskEntry) -> Option<Self> {
+        if !is_finding(entry.risk_score, &entry.indicators) {
+            return None;
+        }
+        Some(SecurityEvent {
+            mitre_technique: Some("T1053.005".to_string()),
+            file_path: Some(entry.path.clone()),
+            threat_score: Some(entry.risk_score as f64),
+            ..Self::new(
+                EventKind::TaskFinding,
+                format!("Suspicious scheduled task {}", entry.task_name),
+                Severity::from_score(entry.risk_score as f64),
+                "detected",
+                entry.indicators.join("; "),
+            )
+        })
+    }
+
+    /// Stable rule / signature identifier for this event kind
+    pub fn kind_id(&self) -> &'static str {
+        self.kind.signature_id()
+    }
+
+    /// 0-10 severity (CEF / LEEF / ECS `event.severity`)
+    pub fn severity_numeric(&self) -> u8 {
+        self.severity.numeric()
+    }
+
+    /// Millisecond epoch (CEF `rt`, LEEF `devTime`)
+    fn epoch_millis(&self) -> i64 {
+        chrono::DateTime::parse_from_rfc3339(&self.timestamp)
+            .map(|t| t.timestamp_millis())
+            .unwrap_or_else(|_| chrono::Utc::now().timestamp_millis())
+    }
+
+    /// RFC 5424 TIMESTAMP: UTC, at most 6 fractional digits (records carry nanoseconds)
+    fn syslog_timestamp(&self) -> String {
+        chrono::DateTime::parse_from_rfc3339(&self.timestamp)
+            .map(|t| t.with_timezone(&chrono::Utc))
+            .unwrap_or_else(|_| chrono::Utc::now())
+            .to_rfc3339_opts(chrono::SecondsFormat::Micros, true)
+    }
+}
+
+// ============================================================================
+// FORMATTING
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SiemFormat {
+    Cef,
+    Leef,
+    /// One normalized JSON document (see `schema`) as the syslog MSG
+    Json,
+}
+
+fn cef_header_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+fn cef_ext_escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('=', "\\=")
+        .replace('\r', "\\r")
+        .replace('\n', "\\n")
+}
+
+fn leef_escape(s: &str) -> String {
+    s.replace(['\t', '\r', '\n'], " ").replace('|', "\\|")
+}
+
+/// Shared (key, value) fields in standard CEF names; LEEF renames a few
+fn event_fields(event: &SecurityEvent, host: &str) -> Vec<(&'static str, String)> {
+    let mut fields = vec![
+        ("rt", event.epoch_millis().to_string()),
+        ("dvchost", host.to_string()),
+        ("cat", event.kind.category().to_string()),
+        ("act", event.outcome.clone()),
+        ("reason", event.reason.clone()),
+    ];
+    if let Some(pid) = event.pid {
+        fields.push(("spid", pid.to_string()));
+    }
+    if let Some(name) = &event.process_name {
+        fields.push(("sproc", name.clone()));
+    }
+    if let Some(parent) = &event.parent_name {
+        fields.push(("cs2Label", "parentProcess".to_string()));
+        fields.push(("cs2", parent.clone()));
+    }
+    if let Some(path) = &event.file_path {
+        fields.push(("filePath", path.clone()));
+    }
+    if let Some(technique) = &event.mitre_technique {
+        fields.push(("cs1Label", "mitreTechnique".to_string()));
+        fields.push(("cs1", technique.clone()));
+    }
+    if let Some(score) = event.threat_score {
+        fields.push(("cn1Label", "threatScore".to_string()));
+        fields.push(("cn1", format!("{:.0}", score)));
+    }
+    fields
+}
+
+pub fn format_cef(event: &SecurityEvent, host: &str) -> String {
+    let extension = event_fields(event, host)
+        .into_iter()
+        .map(|(k, v)| format!("{}={}", k, cef_ext_escape(&v)))
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!(
+        "CEF:0|{}|{}|{}|{}|{}|{}|{}",
+        cef_header_escape(VENDOR),
+        cef_header_escape(PRODUCT),
+        env!("CARGO_PKG_VERSION"),
+        event.kind.signature_id(),
+        cef_header_escape(&event.name),
+        event.severity.numeric(),
+        extension
+    )
+}
+
+/// LEEF 2.0, tab-delimited attributes
+pub fn format_leef(event: &SecurityEvent, host: &str) -> String {
+    let mut attributes = vec![
+        ("devTime".to_string(), event.epoch_millis().to_string()),
+        ("devTimeFormat".to_string(), "Milliseconds".to_string()),
+        ("sev".to_string(), event.severity.numeric().to_string()),
+        ("identHostName".to_string(), host.to_string()),
+        ("name".to_string(), event.name.clone()),
+    ];
+    for (key, value) in event_fields(event, host) {
+        let key = match key {
+            "rt" | "dvchost" | "cs1Label" | "cs2Label" | "cn1Label" => continue,
+            "cs1" => "mitreTechnique",
+            "cs2" => "parentProcess",
+            "cn1" => "threatScore",
+            "sproc" => "processName",
+            "spid" => "processId",
+            "act" => "action",
+            other => other,
+        };
+        attributes.push((key.to_string(), value));
+    }
+    let body = attributes.into_iter()
+        .map(|(k, v)| format!("{}={}", k, leef_escape(&v)))
+        .collect::<Vec<_>>()
+        .join("\t");
+    format!(
+        "LEEF:2.0|{}|{}|{}|{}|\t|{}",
+        VENDOR,
+        PRODUCT,
+        env!("CARGO_PKG_VERSION"),
+        event.kind.signature_id(),
+        body
+    )
+}
+
+/// RFC 5424 frame: `<PRI>1 TIMESTAMP HOST APP PROCID MSGID - MSG`
+pub fn format_syslog(event: &SecurityEvent, format: SiemFormat, schema: TelemetrySchema, host: &str) -> String {
+    let pri = SYSLOG_FACILITY as u32 * 8 + event.severity.syslog() as u32;
+    let msg = match format {
+        SiemFormat::Cef => format_cef(event, host),
+        SiemFormat::Leef => format_leef(event, host),
+        SiemFormat::Json => {
+            let schema = match schema {
+                TelemetrySchema::Native => TelemetrySchema::Ocsf,
+                other => other,
+            };
+            normalize(&TelemetryEvent::Detection(event), schema).to_string()
+        }
+    };
+    format!(
+        "<{}>1 {} {} {} {} {} - {}",
+        pri,
+        event.syslog_timestamp(),
+        if host.is_empty() { "-" } else { host },
+        APP_NAME,
+        std::process::id(),
+        event.kind.signature_id(),
+        msg
+    )
+}
+
+// ============================================================================
+// DESTINATIONS
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Transport {
+    Udp,
+    Tcp,
+    Tls,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SiemDestination {
+    pub name: String,
+    pub host: String,
+    pub port: u16,
+    pub transport: Transport,
+    pub format: SiemFormat,
+    /// Schema for the JSON format (OCSF or ECS)
+    #[serde(default = "default_schema")]
+    pub schema: TelemetrySchema,
+    #[serde(default = "default_min_severity")]
+    pub min_severity: Severity,
+    /// PEM bundle used to verify the syslog server (TLS only)
+    #[serde(default)]
+    pub ca_bundle_path: Option<String>,
+    /// Apply outbound redaction (see redaction) before forwarding
+    #[serde(default = "default_true")]
+    pub redact: bool,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_schema() -> TelemetrySchema {
+    TelemetrySchema::Ocsf
+}
+
+fn default_min_severity() -> Severity {
+    Severity::Medium
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// SIEM settings (persisted in siem_settings.json)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SiemSettings {
+    pub enabled: bool,
+    pub destinations: Vec<SiemDestination>,
+}
+
+impl SiemSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        for dest in &self.destinations {
+            if dest.host.trim().is_empty() || dest.port == 0 {
+                return Err(format!("Destination '{}' needs a host and port", dest.name));
+            }
+            if let Some(ca) = &dest.ca_bundle_path {
+                if !PathBuf::from(ca).exists() {
+                    return Err(format!("CA bundle not found: {}", ca));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn get_settings_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("CyberGuardian")
+        .join("siem_settings.json")
+}
+
+fn load_settings() -> SiemSettings {
+    fs::read_to_string(get_settings_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save_settings(settings: &SiemSettings) -> Result<(), String> {
+    let path = get_settings_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create settings dir: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write settings: {}", e))
+}
+
+enum Connection {
+    Udp(UdpSocket),
+    Tcp(TcpStream),
+    Tls(Box<native_tls::TlsStream<TcpStream>>),
+}
+
+fn resolve(dest: &SiemDestination) -> Result<SocketAddr, String> {
+    (dest.host.as_str(), dest.port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Cannot resolve {}: {}", dest.host, e))?
+        .next()
+        .ok_or_else(|| format!("No address for {}", dest.host))
+}
+
+fn connect_tcp(dest: &SiemDestination) -> Result<TcpStream, String> {
+    let addr = resolve(dest)?;
+    let stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)
+        .map_err(|e| format!("Connect to {}:{} failed: {}", dest.host, dest.port, e))?;
+    let _ = stream.set_write_timeout(Some(CONNECT_TIMEOUT));
+    Ok(stream)
+}
+
+fn connect(dest: &SiemDestination) -> Result<Connection, String> {
+    match dest.transport {
+        Transport::Udp => {
+            // Local socket must match the target's address family (IPv6 collectors)
+            let addr = resolve(dest)?;
+            let local: IpAddr = if addr.is_ipv6() { Ipv6Addr::UNSPECIFIED.into() } else { Ipv4Addr::UNSPECIFIED.into() };
+            let socket = UdpSocket::bind((local, 0)).map_err(|e| format!("UDP bind failed: {}", e))?;
+            socket.connect(addr)
+                .map_err(|e| format!("UDP connect to {}:{} failed: {}", dest.host, dest.port, e))?;
+            Ok(Connection::Udp(socket))
+        }
+        Transport::Tcp => Ok(Connection::Tcp(connect_tcp(dest)?)),
+        Transport::Tls => {
+            let mut builder = native_tls::TlsConnector::builder();
+            if let Some(ca) = &dest.ca_bundle_path {
+                let pem = fs::read(ca).map_err(|e| format!("Failed to read CA bundle: {}", e))?;
+                let cert = native_tls::Certificate::from_pem(&pem)
+                    .map_err(|e| format!("Invalid CA bundle: {}", e))?;
+                builder.add_root_certificate(cert);
+            }
+            let connector = builder.build().map_err(|e| format!("TLS setup failed: {}", e))?;
+            let stream = connector.connect(&dest.host, connect_tcp(dest)?)
+                .map_err(|e| format!("TLS handshake with {} failed: {}", dest.host, e))?;
+            Ok(Connection::Tls(Box::new(stream)))
+        }
+    }
+}
+
+impl Connection {
+    /// UDP: one datagram per message; TCP/TLS: RFC 6587 octet counting
+    fn send(&mut self, message: &str) -> Result<(), String> {
+        let result = match self {
+            Connection::Udp(socket) => socket.send(truncate_utf8(message, MAX_UDP_MESSAGE).as_bytes()).map(|_| ()),
+            Connection::Tcp(stream) => stream.write_all(format!("{} {}", message.len(), message).as_bytes()),
+            Connection::Tls(stream) => stream.write_all(format!("{} {}", message.len(), message).as_bytes()),
+        };
+        result.map_err(|e| format!("Send failed: {}", e))
+    }
+}
+
+/// Longest prefix of at most `max` bytes that ends on a char boundary
+fn truncate_utf8(message: &str, max: usize) -> &str {
+    if message.len() <= max {
+        return message;
+    }
+    let mut end = max;
+    while !message.is_char_boundary(end) {
+        end -= 1;
+    }
+    &message[..end]
+}
+
+/// Format and send one event to one destination (blocking; opens its own connection)
+pub fn send_event(dest: &SiemDestination, event: &SecurityEvent, host: &str) -> Result<(), String> {
+    let message = format_syslog(&prepare(dest, event), dest.format, dest.schema, host);
+    connect(dest)?.send(&message)
+}
+
+fn prepare(dest: &SiemDestination, event: &SecurityEvent) -> SecurityEvent {
+    if !dest.redact {
+        return event.clone();
+    }
+    serde_json::to_value(event)
+        .ok()
+        .and_then(|v| serde_json::from_value(crate::redaction::redact_outbound(&v)).ok())
+        .unwrap_or_else(|| event.clone())
+}
+
+// ============================================================================
+// FORWARDER WORKER
+// ============================================================================
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DestinationStatus {
+    pub name: String,
+    pub sent: u64,
+    pub failed: u64,
+    pub last_error: Option<String>,
+}
+
+lazy_static::lazy_static! {
+    static ref SETTINGS: RwLock<SiemSettings> = RwLock::new(load_settings());
+    static ref SENDER: Mutex<Option<Sender<SecurityEvent>>> = Mutex::new(None);
+    static ref STATUS: Mutex<Vec<DestinationStatus>> = Mutex::new(Vec::new());
+}
+
+fn update_status(name: &str, result: &Result<(), String>) {
+    let mut status = STATUS.lock().unwrap();
+    let idx = match status.iter().position(|s| s.name == name) {
+        Some(idx) => idx,
+        None => {
+            status.push(DestinationStatus { name: name.to_string(), ..Default::default() });
+            status.len() - 1
+        }
+    };
+    match result {
+        Ok(()) => status[idx].sent += 1,
+        Err(e) => {
+            status[idx].failed += 1;
+            status[idx].last_error = Some(e.clone());
+        }
+    }
+}
+
+/// Worker thread: keeps one connection per destination, reconnects once on failure
+fn run_worker(rx: mpsc::Receiver<SecurityEvent>) {
+    let host = crate::agent_identity::hostname();
+    let mut connections: Vec<(String, Connection)> = Vec::new();
+
+    for event in rx {
+        let settings = SETTINGS.read().unwrap().clone();
+        if !settings.enabled {
+            continue;
+        }
+        for dest in settings.destinations.iter().filter(|d| d.enabled && event.severity >= d.min_severity) {
+            let message = format_syslog(&prepare(dest, &event), dest.format, dest.schema, &host);
+            let key = format!("{}|{}:{}|{:?}", dest.name, dest.host, dest.port, dest.transport);
+
+            let mut result = Err(String::new());
+            for _attempt in 0..2 {
+                if !connections.iter().any(|(k, _)| *k == key) {
+                    match connect(dest) {
+                        Ok(conn) => connections.push((key.clone(), conn)),
+                        Err(e) => {
+                            result = Err(e);
+                            break;
+                        }
+                    }
+                }
+                let conn = connections.iter_mut().find(|(k, _)| *k == key).map(|(_, c)| c);
+                result = conn.map(|c| c.send(&message)).unwrap_or_else(|| Err("no connection".into()));
+                if result.is_ok() {
+                    break;
+                }
+                // Stale TCP/TLS session — drop it and retry once with a fresh connection
+                connections.retain(|(k, _)| *k != key);
+            }
+            if let Err(e) = &result {
+                warn!("SIEM forward to '{}' failed: {}", dest.name, e);
+            }
+            update_status(&dest.name, &result);
+        }
+    }
+}
+
+/// Queue an event for all configured SIEM destinations (never blocks detection paths)
+pub fn forward(event: SecurityEvent) {
+    if !SETTINGS.read().unwrap().enabled {
+        return;
+    }
+    let mut sender = SENDER.lock().unwrap();
+    if sender.is_none() {
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || run_worker(rx));
+        *sender = Some(tx);
+        info!("SIEM forwarder started");
+    }
+    if let Some(tx) = sender.as_ref() {
+        if tx.send(event).is_err() {
+            *sender = None;
+        }
+    }
+}
+
+// ============================================================================
+// TAURI COMMANDS
+// ============================================================================
+
+#[tauri::command]
+pub fn get_siem_settings() -> SiemSettings {
+    SETTINGS.read().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn set_siem_settings(settings: SiemSettings) -> Result<SiemSettings, String> {
+    settings.validate()?;
+    save_settings(&settings)?;
+    *SETTINGS.write().unwrap() = settings.clone();
+    info!("SIEM settings updated ({} destinations)", settings.destinations.len());
+    Ok(settings)
+}
+
+#[tauri::command]
+pub fn get_siem_status() -> Vec<DestinationStatus> {
+    STATUS.lock().unwrap().clone()
+}
+
+/// Send a test event directly to every destination and report per-destination results
+#[tauri::command]
+pub fn send_siem_test_event() -> Vec<DestinationStatus> {
+    let settings = SETTINGS.read().unwrap().clone();
+    let host = crate::agent_identity::hostname();
+    let event = SecurityEvent::new(
+        EventKind::FileVerdict,
+        "CyberGuardian SIEM test event".to_string(),
+        Severity::Low,
+        "test",
+        "Connectivity test".to_string(),
+    );
+    settings.destinations.iter()
+        .map(|dest| {
+            let result = send_event(dest, &event, &host);
+            DestinationStatus {
+                name: dest.name.clone(),
+                sent: result.is_ok() as u64,
+                failed: result.is_err() as u64,
+                last_error: result.err(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    fn blocked_event() -> SecurityEvent {
+        SecurityEvent {
+            mitre_technique: Some("T1003".into()),
+            pid: Some(4242),
+            process_name: Some("mimikatz.exe".into()),
+            parent_name: Some("cmd.exe".into()),
+            ..SecurityEvent::new(
+                EventKind::ProcessBlocked,
+                "Malicious process mimikatz.exe".into(),
+                Severity::Critical,
+                "blocked",
+                "Credential dumping tool | a=b".into(),
+            )
+        }
+    }
+
+    fn destination(port: u16, transport: Transport, format: SiemFormat) -> SiemDestination {
+        SiemDestination {
+            name: "local".into(),
+            host: "127.0.0.1".into(),
+            port,
+            transport,
+            format,
+            schema: TelemetrySchema::Ocsf,
+            min_severity: Severity::Low,
+            ca_bundle_path: None,
+            redact: false,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn test_scan_inventory_is_not_a_detection() {
+        let entry = |risk_score: u32, indicators: &[&str]| crate::registry_scanner::RegistryEntry {
+            id: "1".into(),
+            hive: "HKCU".into(),
+            key_path: r"Software\Microsoft\Windows\CurrentVersion\Run".into(),
+            value_name: "Updater".into(),
+            value_data: r"C:\Users\Public\upd.exe".into(),
+            value_type: "REG_SZ".into(),
+            risk_score,
+            indicators: indicators.iter().map(|i| i.to_string()).collect(),
+            scanned_at: String::new(),
+        };
+        assert!(SecurityEvent::from_registry(&entry(0, &[])).is_none());
+        assert!(SecurityEvent::from_registry(&entry(20, &["Public folder"])).is_none());
+        assert!(SecurityEvent::from_registry(&entry(70, &[])).is_none());
+        let finding = SecurityEvent::from_registry(&entry(70, &["Public folder"])).unwrap();
+        assert_eq!(finding.severity, Severity::High);
+    }
+
+    #[test]
+    fn test_cef_maps_mitre_and_severity() {
+        let cef = format_cef(&blocked_event(), "host1");
+        assert!(cef.starts_with("CEF:0|CyberGuardian|CyberGuardian Agent|"));
+        assert!(cef.contains("|CG-PROC-BLOCK|Malicious process mimikatz.exe|10|"));
+        assert!(cef.contains("cs1Label=mitreTechnique cs1=T1003"));
+        assert!(cef.contains("spid=4242 sproc=mimikatz.exe"));
+        assert!(cef.contains(r"reason=Credential dumping tool | a\=b"));
+
+        let leef = format_leef(&blocked_event(), "host1");
+        assert!(leef.starts_with("LEEF:2.0|CyberGuardian|CyberGuardian Agent|"));
+        assert!(leef.contains("\tsev=10\t") && leef.contains("\tmitreTechnique=T1003"));
+
+        // facility 10 * 8 + critical (2)
+        assert!(format_syslog(&blocked_event(), SiemFormat::Cef, TelemetrySchema::Ocsf, "host1").starts_with("<82>1 "));
+    }
+
+    #[test]
+    fn test_syslog_header_format() {
+        let event = SecurityEvent { timestamp: "2026-03-01T10:20:30.123456789+02:00".into(), ..blocked_event() };
+        let frame = format_syslog(&event, SiemFormat::Cef, TelemetrySchema::Ocsf, "host1");
+        let header: Vec<&str> = frame.splitn(8, ' ').collect();
+        assert_eq!(header[0], "<82>1");
+        assert_eq!(header[1], "2026-03-01T08:20:30.123456Z");
+        assert_eq!(header[2..6], ["host1", APP_NAME, &std::process::id().to_string(), "CG-PROC-BLOCK"]);
+        assert_eq!(header[6], "-");
+        assert!(header[7].starts_with("CEF:0|"));
+
+        // Fresh events are already in the RFC 5424 shape
+        let fresh = blocked_event().timestamp;
+        assert_eq!(fresh.len(), "2026-03-01T08:20:30.123456Z".len());
+        assert!(fresh.ends_with('Z'));
+    }
+
+    #[test]
+    fn test_udp_delivery_to_local_listener() {
+        let listener = UdpSocket::bind("127.0.0.1:0").unwrap();
+        listener.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        send_event(&destination(port, Transport::Udp, SiemFormat::Leef), &blocked_event(), "host1").unwrap();
+
+        let mut buf = [0u8; 8192];
+        let n = listener.recv(&mut buf).unwrap();
+        let msg = String::from_utf8_lossy(&buf[..n]);
+        assert!(msg.starts_with("<82>1 ") && msg.contains(" host1 cyberguardian ") && msg.contains("LEEF:2.0|"));
+
+        // IPv6 collector (skipped where the host has no IPv6 loopback)
+        if let Ok(listener) = UdpSocket::bind("[::1]:0") {
+            listener.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+            let dest = SiemDestination {
+                host: "::1".into(),
+                ..destination(listener.local_addr().unwrap().port(), Transport::Udp, SiemFormat::Cef)
+            };
+            send_event(&dest, &blocked_event(), "host1").unwrap();
+            let n = listener.recv(&mut buf).unwrap();
+            assert!(String::from_utf8_lossy(&buf[..n]).contains("CEF:0|"));
+        }
+
+        // Oversized datagrams are cut on a char boundary, never inside one
+        let text = "грешка".repeat(10);
+        assert_eq!(truncate_utf8(&text, 5), "гр");
+        assert_eq!(truncate_utf8(&text, 6), "гре");
+        assert_eq!(truncate_utf8("short", MAX_UDP_MESSAGE), "short");
+    }
+
+    #[test]
+    fn test_tcp_delivery_uses_octet_counting() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let reader = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut received = String::new();
+            stream.read_to_string(&mut received).unwrap();
+            received
+        });
+
+        send_event(&destination(port, Transport::Tcp, SiemFormat::Cef), &blocked_event(), "host1").unwrap();
+
+        let received = reader.join().unwrap();
+        let (len, msg) = received.split_once(' ').unwrap();
+        assert_eq!(len.parse::<usize>().unwrap(), msg.len());
+        assert!(msg.contains("CEF:0|"));
+    }
+}
@@ -0,0 +1,563 @@
+//! STIX 2.1 Export
+//! Builds a STIX bundle (indicator, malware, observed-data, sighting, attack-pattern,
+//! relationship) from blocked processes, DNS/network alerts, locally quarantined
+//! files and deep analysis results — for sharing IOCs with partners / CERT (NIS2)
+
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use uuid::Uuid;
+use tracing::info;
+
+use crate::deep_quarantine::DeepAnalysisResult;
+use crate::enforcement::Confidence;
+use crate::process_monitor::{BlockedProcess, NetworkAlert};
+use crate::quarantine_retention::LocalQuarantineRecord;
+
+/// STIX 2.1 namespace for deterministic SCO ids (spec section 2.9)
+const STIX_SCO_NAMESPACE: Uuid = Uuid::from_u128(0x00abedb4_aa42_466c_9c01_fed23315a9b7);
+/// Our namespace for deterministic SDO / relationship ids (same IOC → same id across exports)
+const CG_NAMESPACE: Uuid = Uuid::from_u128(0x6c1f0a52_8d7e_4b7b_9a3e_2f4c5d6e7f80);
+
+/// Deep analysis results below this risk score are not exported
+const MIN_ANALYSIS_RISK: u32 = 40;
+
+/// OS binaries / LOLBins: detections on them describe behaviour, the name is no IOC
+const SYSTEM_BINARIES: [&str; 33] = [
+    "cmd.exe", "powershell.exe", "pwsh.exe", "wscript.exe", "cscript.exe", "mshta.exe",
+    "rundll32.exe", "regsvr32.exe", "certutil.exe", "bitsadmin.exe", "wmic.exe",
+    "reg.exe", "net.exe", "net1.exe", "sc.exe", "schtasks.exe", "vssadmin.exe",
+    "wevtutil.exe", "bcdedit.exe", "msiexec.exe", "svchost.exe", "explorer.exe",
+    "conhost.exe", "taskkill.exe", "whoami.exe", "bash", "sh", "dash", "zsh", "python3",
+    "perl", "curl", "wget",
+];
+
+fn is_system_binary(name: &str) -> bool {
+    SYSTEM_BINARIES.contains(&file_name(name).to_lowercase().as_str())
+}
+
+/// Names for the techniques our rules emit (others fall back to the id)
+fn technique_name(id: &str) -> &str {
+    match id {
+        "T1003" => "OS Credential Dumping",
+        "T1047" => "Windows Management Instrumentation",
+        "T1053.005" => "Scheduled Task",
+        "T1059.001" => "PowerShell",
+        "T1070" => "Indicator Removal",
+        "T1071" => "Application Layer Protocol",
+        "T1071.004" => "DNS",
+        "T1136" => "Create Account",
+        "T1204" => "User Execution",
+        "T1218" => "System Binary Proxy Execution",
+        "T1218.005" => "Mshta",
+        "T1543" => "Create or Modify System Process",
+        "T1543.003" => "Windows Service",
+        "T1547.001" => "Registry Run Keys / Startup Folder",
+        "T1562" => "Impair Defenses",
+        other => other,
+    }
+}
+
+// ============================================================================
+// SOURCES
+// ============================================================================
+
+/// Everything the bundle is built from
+#[derive(Debug, Default, Clone)]
+pub struct StixSources {
+    pub blocked_processes: Vec<BlockedProcess>,
+    pub network_alerts: Vec<NetworkAlert>,
+    pub quarantined_files: Vec<LocalQuarantineRecord>,
+    pub deep_analyses: Vec<DeepAnalysisResult>,
+}
+
+fn is_after(timestamp: &str, since: Option<DateTime<Utc>>) -> bool {
+    match since {
+        None => true,
+        Some(since) => DateTime::parse_from_rfc3339(timestamp)
+            .map(|t| t.with_timezone(&Utc) >= since)
+            .unwrap_or(true),
+    }
+}
+
+impl StixSources {
+    /// Gather stored detections (optionally only those at/after `since`)
+    pub fn collect(since: Option<DateTime<Utc>>) -> Self {
+        let (_, blocked, _) = crate::process_monitor::get_blocking_status();
+        let deep_analyses: Vec<DeepAnalysisResult> = crate::deep_quarantine::list_backups()
+            .map(|list| list.backups.into_iter().map(|b| b.analysis_data).collect())
+            .unwrap_or_default();
+
+        StixSources {
+            blocked_processes: blocked.into_iter().filter(|b| is_after(&b.timestamp, since)).collect(),
+            network_alerts: crate::process_monitor::get_network_alerts()
+                .into_iter()
+                .filter(|a| is_after(&a.timestamp, since))
+                .collect(),
+            quarantined_files: crate::quarantine_retention::list_local_quarantine()
+                .into_iter()
+                .filter(|q| is_after(&q.quarantined_at, since))
+                .collect(),
+            deep_analyses: deep_analyses.into_iter()
+                .filter(|a| a.risk_score >= MIN_ANALYSIS_RISK && is_after(&a.analyzed_at, since))
+                .collect(),
+        }
+    }
+}
+
+// ============================================================================
+// BUNDLE BUILDER
+// ============================================================================
+
+/// STIX timestamp: UTC with millisecond precision and `Z`
+fn stix_time(timestamp: &str) -> String {
+    DateTime::parse_from_rfc3339(timestamp)
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now())
+        .format("%Y-%m-%dT%H:%M:%S%.3fZ")
+        .to_string()
+}
+
+/// Escape a value for use inside a STIX pattern string literal
+fn pattern_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit(['\\', '/']).next().unwrap_or(path)
+}
+
+struct BundleBuilder {
+    objects: Vec<Value>,
+    ids: HashSet<String>,
+    identity_id: String,
+    created: String,
+}
+
+impl BundleBuilder {
+    fn new(producer: &str, now: &str) -> Self {
+        let created = stix_time(now);
+        let identity_id = format!("identity--{}", Uuid::new_v5(&CG_NAMESPACE, producer.as_bytes()));
+        let mut builder = BundleBuilder { objects: Vec::new(), ids: HashSet::new(), identity_id: identity_id.clone(), created };
+        builder.push(json!({
+            "type": "identity",
+            "id": identity_id,
+            "name": producer,
+            "identity_class": "system",
+        }), true);
+        builder
+    }
+
+    /// Add an object once; SDOs get the common properties
+    fn push(&mut self, mut object: Value, sdo: bool) -> String {
+        let id = object["id"].as_str().unwrap_or_default().to_string();
+        if !self.ids.insert(id.clone()) {
+            return id;
+        }
+        object["spec_version"] = json!("2.1");
+        if sdo {
+            object["created"] = json!(self.created);
+            object["modified"] = json!(self.created);
+            if object["type"] != "identity" {
+                object["created_by_ref"] = json!(self.identity_id);
+            }
+        }
+        self.objects.push(object);
+        id
+    }
+
+    fn sdo_id(kind: &str, key: &str) -> String {
+        format!("{}--{}", kind, Uuid::new_v5(&CG_NAMESPACE, format!("{}|{}", kind, key).as_bytes()))
+    }
+
+    /// SCO id from its id-contributing properties (serde_json maps serialize with sorted keys)
+    fn sco_id(kind: &str, contributing: &Value) -> String {
+        format!("{}--{}", kind, Uuid::new_v5(&STIX_SCO_NAMESPACE, contributing.to_string().as_bytes()))
+    }
+
+    fn sco(&mut self, kind: &str, contributing: Value, extra: Value) -> String {
+        let id = Self::sco_id(kind, &contributing);
+        let mut object = json!({ "type": kind, "id": id });
+        for fields in [contributing, extra] {
+            if let Value::Object(map) = fields {
+                object.as_object_mut().expect("object").extend(map);
+            }
+        }
+        self.push(object, false)
+    }
+
+    fn attack_pattern(&mut self, technique: &str) -> Option<String> {
+        if technique.is_empty() {
+            return None;
+        }
+        let url = format!("https://attack.mitre.org/techniques/{}/", technique.replace('.', "/"));
+        Some(self.push(json!({
+            "type": "attack-pattern",
+            "id": Self::sdo_id("attack-pattern", technique),
+            "name": technique_name(technique),
+            "external_references": [{
+                "source_name": "mitre-attack",
+                "external_id": technique,
+                "url": url,
+            }],
+        }), true))
+    }
+
+    fn malware(&mut self, name: &str, description: &str, sample_refs: &[String]) -> String {
+        let mut object = json!({
+            "type": "malware",
+            "id": Self::sdo_id("malware", &name.to_lowercase()),
+            "name": name,
+            "description": description,
+            "is_family": false,
+            "malware_types": ["unknown"],
+        });
+        if !sample_refs.is_empty() {
+            object["sample_refs"] = json!(sample_refs);
+        }
+        self.push(object, true)
+    }
+
+    fn indicator(&mut self, name: &str, pattern: &str, valid_from: &str) -> String {
+        self.push(json!({
+            "type": "indicator",
+            "id": Self::sdo_id("indicator", pattern),
+            "name": name,
+            "indicator_types": ["malicious-activity"],
+            "pattern": pattern,
+            "pattern_type": "stix",
+            "pattern_version": "2.1",
+            "valid_from": stix_time(valid_from),
+        }), true)
+    }
+
+    fn observed(&mut self, key: &str, timestamp: &str, refs: Vec<String>) -> String {
+        let at = stix_time(timestamp);
+        self.push(json!({
+            "type": "observed-data",
+            "id": Self::sdo_id("observed-data", &format!("{}|{}", key, at)),
+            "first_observed": at,
+            "last_observed": at,
+            "number_observed": 1,
+            "object_refs": refs,
+        }), true)
+    }
+
+    fn sighting(&mut self, sighting_of: &str, observed: &str, timestamp: &str) {
+        let at = stix_time(timestamp);
+        let where_sighted = self.identity_id.clone();
+        self.push(json!({
+            "type": "sighting",
+            "id": Self::sdo_id("sighting", &format!("{}|{}", sighting_of, observed)),
+            "first_seen": at,
+            "last_seen": at,
+            "count": 1,
+            "sighting_of_ref": sighting_of,
+            "observed_data_refs": [observed],
+            "where_sighted_refs": [where_sighted],
+        }), true);
+    }
+
+    fn relate(&mut self, source: &str, relationship: &str, target: &str) {
+        self.push(json!({
+            "type": "relationship",
+            "id": Self::sdo_id("relationship", &format!("{}|{}|{}", source, relationship, target)),
+            "relationship_type": relationship,
+            "source_ref": source,
+            "target_ref": target,
+        }), true);
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Every detection becomes observed-data plus a sighting of its technique;
+    /// malware + indicators only for named tools caught by a definite rule
+    fn add_blocked_process(&mut self, record: &BlockedProcess) {
+        let technique = self.attack_pattern(&record.mitre_technique);
+
+        let mut process = json!({ "type": "process", "id": format!("process--{}", Uuid::new_v4()), "pid": record.pid });
+        if let Some(cmd) = &record.command_line {
+            process["command_line"] = json!(cmd);
+        }
+        // Processes have no id-contributing properties, so the spec wants a UUIDv4
+        let process_id = self.push(process, false);
+        let observed = self.observed(
+            &format!("process|{}|{}", record.pid, record.process_name),
+            &record.timestamp,
+            vec![process_id],
+        );
+        if let Some(technique) = &technique {
+            self.sighting(technique, &observed, &record.timestamp);
+        }
+
+        if record.confidence != Some(Confidence::Definite) || is_system_binary(&record.process_name) {
+            return;
+        }
+        let malware = self.malware(&record.process_name, &record.reason, &[]);
+        if let Some(technique) = &technique {
+            self.relate(&malware, "uses", technique);
+        }
+        let mut patterns = vec![(
+            format!("Process {}", record.process_name),
+            format!("[process:name = '{}']", pattern_escape(&record.process_name)),
+        )];
+        if let Some(cmd) = &record.command_line {
+            patterns.push((
+                format!("Command line of {}", record.process_name),
+                format!("[process:command_line = '{}']", pattern_escape(cmd)),
+            ));
+        }
+        for (name, pattern) in patterns {
+            let indicator = self.indicator(&name, &pattern, &record.timestamp);
+            self.relate(&indicator, "indicates", &malware);
+            self.relate(&indicator, "based-on", &observed);
+        }
+    }
+
+    fn add_network_alert(&mut self, alert: &NetworkAlert) {
+        let technique = self.attack_pattern(&alert.mitre_technique);
+        let (kind, label) = if alert.kind == "dns" {
+            ("domain-name", "Domain")
+        } else if alert.indicator.contains(':') {
+            ("ipv6-addr", "IP")
+        } else {
+            ("ipv4-addr", "IP")
+        };
+        let sco = self.sco(kind, json!({ "value": alert.indicator }), json!({}));
+        let observed = self.observed(&sco, &alert.timestamp, vec![sco.clone()]);
+        let indicator = self.indicator(
+            &format!("{} {} ({})", label, alert.indicator, alert.process_name),
+            &format!("[{}:value = '{}']", kind, pattern_escape(&alert.indicator)),
+            &alert.timestamp,
+        );
+        self.relate(&indicator, "based-on", &observed);
+        if let Some(technique) = &technique {
+            self.relate(&indicator, "indicates", technique);
+        }
+    }
+
+    fn add_file(&mut self, name: &str, algorithm: &str, hash: &str, size: Option<u64>, description: &str, at: &str) {
+        let extra = match size {
+            Some(size) => json!({ "name": name, "size": size }),
+            None => json!({ "name": name }),
+        };
+        let file = self.sco("file", json!({ "hashes": { algorithm: hash } }), extra);
+        let malware = self.malware(name, description, std::slice::from_ref(&file));
+        let observed = self.observed(&file, at, vec![file.clone()]);
+        let pattern_key = if algorithm == "MD5" { "MD5".to_string() } else { format!("'{}'", algorithm) };
+        let indicator = self.indicator(
+            &format!("File {} ({})", name, algorithm),
+            &format!("[file:hashes.{} = '{}']", pattern_key, pattern_escape(hash)),
+            at,
+        );
+        self.relate(&indicator, "indicates", &malware);
+        self.relate(&indicator, "based-on", &observed);
+    }
+
+    fn add_quarantined_file(&mut self, record: &LocalQuarantineRecord) {
+        self.add_file(
+            file_name(&record.original_path),
+            "SHA-256",
+            &record.sha256,
+            Some(record.size_bytes),
+            &format!("Quarantined ({}): {}", record.threat_level, record.reason),
+            &record.quarantined_at,
+        );
+    }
+
+    fn add_deep_analysis(&mut self, analysis: &DeepAnalysisResult) {
+        let Some(md5) = &analysis.stages.file_analysis.hash_md5 else {
+            return;
+        };
+        self.add_file(
+            file_name(&analysis.target_path),
+            "MD5",
+            md5,
+            Some(analysis.stages.file_analysis.size_bytes),
+            &format!(
+                "Deep analysis {}: {} risk ({}); {}",
+                analysis.analysis_id,
+                analysis.threat_level,
+                analysis.risk_score,
+                analysis.stages.file_analysis.indicators.join("; ")
+            ),
+            &analysis.analyzed_at,
+        );
+    }
+}
+
+/// Build a STIX 2.1 bundle from `sources`
+pub fn build_bundle(sources: &StixSources, producer: &str, now: &str) -> Value {
+    let mut builder = BundleBuilder::new(producer, now);
+    for record in &sources.blocked_processes {
+        builder.add_blocked_process(record);
+    }
+    for alert in &sources.network_alerts {
+        builder.add_network_alert(alert);
+    }
+    for record in &sources.quarantined_files {
+        builder.add_quarantined_file(record);
+    }
+    for analysis in &sources.deep_analyses {
+        builder.add_deep_analysis(analysis);
+    }
+    json!({
+        "type": "bundle",
+        "id": format!("bundle--{}", Uuid::new_v4()),
+        "objects": builder.objects,
+    })
+}
+
+// ============================================================================
+// TAURI COMMANDS
+// ============================================================================
+
+/// Export detections as a STIX 2.1 bundle.
+/// `since` (RFC 3339) limits the time range; `output_path` also writes the bundle to disk;
+/// `redact` (default true) applies the outbound redaction rules first; pass false only
+/// for trusted recipients — redaction also rewrites patterns, so matching gets weaker
+#[tauri::command]
+pub fn export_stix_bundle(
+    since: Option<String>,
+    output_path: Option<String>,
+    redact: Option<bool>,
+) -> Result<Value, String> {
+    let since = since
+        .map(|s| DateTime::parse_from_rfc3339(&s)
+            .map(|t| t.with_timezone(&Utc))
+            .map_err(|e| format!("Invalid 'since' timestamp: {}", e)))
+        .transpose()?;
+
+    let sources = StixSources::collect(since);
+    let producer = format!("CyberGuardian Agent ({})", crate::agent_identity::hostname());
+    let mut bundle = build_bundle(&sources, &producer, &Utc::now().to_rfc3339());
+    if redact.unwrap_or(true) {
+        bundle = crate::redaction::redact_outbound(&bundle);
+    }
+
+    if let Some(path) = output_path {
+        let json = serde_json::to_string_pretty(&bundle)
+            .map_err(|e| format!("Failed to serialize bundle: {}", e))?;
+        std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+        info!("STIX bundle written to {}", path);
+    }
+
+    info!("STIX export: {} objects", bundle["objects"].as_array().map(|o| o.len()).unwrap_or(0));
+    Ok(bundle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sources() -> StixSources {
+        StixSources {
+            blocked_processes: vec![BlockedProcess {
+                pid: 4242,
+                process_key: None,
+                process_name: "mimikatz.exe".into(),
+                parent_name: "cmd.exe".into(),
+                reason: "Credential dumping tool".into(),
+                mitre_technique: "T1003".into(),
+                severity: "critical".into(),
+                timestamp: "2026-10-01T10:00:00Z".into(),
+                success: true,
+                error: None,
+                command_line: Some(r"C:\tools\mimikatz.exe 'sekurlsa::logonpasswords'".into()),
+                confidence: Some(Confidence::Definite),
+            }, BlockedProcess {
+                pid: 5151,
+                process_key: None,
+                process_name: "powershell.exe".into(),
+                parent_name: "winword.exe".into(),
+                reason: "Suspicious chain: winword.exe → powershell.exe".into(),
+                mitre_technique: "T1059.001".into(),
+                severity: "high".into(),
+                timestamp: "2026-10-01T10:01:00Z".into(),
+                success: false,
+                error: None,
+                command_line: Some("powershell.exe -enc SQBFAFgA".into()),
+                confidence: Some(Confidence::Heuristic),
+            }],
+            network_alerts: vec![NetworkAlert {
+                kind: "dns".into(),
+                indicator: "xk2j9qpz7vbn4mrt8wla.example".into(),
+                port: None,
+                pid: 77,
+                process_name: "powershell.exe".into(),
+                mitre_technique: "T1071.004".into(),
+                reason: "Suspicious DNS query".into(),
+                timestamp: "2026-10-01T10:05:00Z".into(),
+                evidence: None,
+            }],
+            quarantined_files: vec![LocalQuarantineRecord {
+                original_path: r"C:\Temp\invoice.exe".into(),
+                quarantined_path: r"C:\Q\1_invoice.exe.quarantine".into(),
+                sha256: "a".repeat(64),
+                size_bytes: 2048,
+                threat_level: "high".into(),
+                reason: "Backend verdict".into(),
+                quarantined_at: "2026-10-01T11:00:00Z".into(),
+            }],
+            deep_analyses: Vec::new(),
+        }
+    }
+
+    fn of_type<'a>(bundle: &'a Value, kind: &str) -> Vec<&'a Value> {
+        bundle["objects"].as_array().unwrap().iter().filter(|o| o["type"] == kind).collect()
+    }
+
+    #[test]
+    fn test_bundle_objects_and_links() {
+        let bundle = build_bundle(&sources(), "CyberGuardian Agent (test)", "2026-10-02T00:00:00Z");
+        assert_eq!(bundle["type"], "bundle");
+
+        let patterns: Vec<&str> = of_type(&bundle, "indicator").iter().map(|i| i["pattern"].as_str().unwrap()).collect();
+        assert!(patterns.contains(&"[process:name = 'mimikatz.exe']"));
+        assert!(patterns.contains(&r"[process:command_line = 'C:\\tools\\mimikatz.exe \'sekurlsa::logonpasswords\'']"));
+        assert!(patterns.contains(&"[domain-name:value = 'xk2j9qpz7vbn4mrt8wla.example']"));
+        assert!(patterns.contains(&format!("[file:hashes.'SHA-256' = '{}']", "a".repeat(64)).as_str()));
+
+        let techniques: Vec<&str> = of_type(&bundle, "attack-pattern").iter()
+            .map(|a| a["external_references"][0]["external_id"].as_str().unwrap())
+            .collect();
+        assert_eq!(techniques, vec!["T1003", "T1059.001", "T1071.004"]);
+        assert_eq!(of_type(&bundle, "malware").len(), 2);
+        assert_eq!(of_type(&bundle, "observed-data").len(), 4);
+
+        // The LOLBin / heuristic hit is a sighting, not an IOC
+        assert!(!patterns.iter().any(|p| p.contains("powershell")));
+        assert!(of_type(&bundle, "malware").iter().all(|m| m["name"] != "powershell.exe"));
+        assert_eq!(of_type(&bundle, "sighting").len(), 2);
+        for process in of_type(&bundle, "process") {
+            assert_eq!(Uuid::parse_str(&process["id"].as_str().unwrap()["process--".len()..]).unwrap().get_version_num(), 4);
+            assert!(process.get("x_cyberguardian_key").is_none());
+        }
+
+        // Every relationship points at objects inside the bundle
+        let ids: HashSet<&str> = bundle["objects"].as_array().unwrap().iter().map(|o| o["id"].as_str().unwrap()).collect();
+        for rel in of_type(&bundle, "relationship") {
+            assert!(ids.contains(rel["source_ref"].as_str().unwrap()));
+            assert!(ids.contains(rel["target_ref"].as_str().unwrap()));
+        }
+        for sdo in of_type(&bundle, "indicator") {
+            assert_eq!(sdo["spec_version"], "2.1");
+            assert!(sdo["created_by_ref"].as_str().unwrap().starts_with("identity--"));
+        }
+    }
+
+    #[test]
+    fn test_ids_are_deterministic() {
+        let a = build_bundle(&sources(), "p", "2026-10-02T00:00:00Z");
+        let b = build_bundle(&sources(), "p", "2026-10-03T00:00:00Z");
+        // Process SCOs carry random ids by spec; everything else must be stable
+        let ids = |bundle: &Value| -> Vec<String> {
+            bundle["objects"].as_array().unwrap().iter()
+                .filter(|o| o["type"] != "process")
+                .map(|o| o["id"].as_str().unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(ids(&a), ids(&b));
+        assert_ne!(a["id"], b["id"]);
+    }
+}
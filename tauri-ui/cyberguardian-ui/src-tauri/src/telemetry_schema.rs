@@ -0,0 +1,719 @@
+//! Telemetry Normalization
+//! Maps agent telemetry (processes, file activity, network connections, detections)
+//! to OCSF 1.1 classes or Elastic ECS 8.x. Used by the inventory uploader, the SIEM
+//! forwarder (JSON format) and the local NDJSON file sink
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Mutex, RwLock};
+use tracing::{info, warn};
+
+use crate::api_client::ProcessInfo;
+use crate::siem_forwarder::{EventKind, SecurityEvent, Severity};
+
+const OCSF_VERSION: &str = "1.1.0";
+const ECS_VERSION: &str = "8.11.0";
+
+// ============================================================================
+// SCHEMAS + EVENT TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TelemetrySchema {
+    /// Agent's own struct shapes (what the backend has always received)
+    Native,
+    Ocsf,
+    Ecs,
+}
+
+impl TelemetrySchema {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TelemetrySchema::Native => "native",
+            TelemetrySchema::Ocsf => "ocsf",
+            TelemetrySchema::Ecs => "ecs",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProcessActivity {
+    Launch,
+    Terminate,
+    /// Seen in an inventory snapshot
+    Observed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileActivity {
+    Create,
+    Update,
+    Delete,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileEvent {
+    pub path: String,
+    pub size: Option<u64>,
+    pub sha256: Option<String>,
+    pub activity: FileActivity,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkConnection {
+    pub pid: u32,
+    pub process_name: String,
+    /// "tcp" / "udp"
+    pub protocol: String,
+    pub remote_ip: String,
+    pub remote_port: Option<u16>,
+    pub local_port: Option<u16>,
+    /// "outbound" / "inbound"
+    pub direction: String,
+    pub timestamp: String,
+}
+
+pub enum TelemetryEvent<'a> {
+    Process(&'a ProcessInfo, ProcessActivity),
+    File(&'a FileEvent),
+    Network(&'a NetworkConnection),
+    Detection(&'a SecurityEvent),
+}
+
+// ============================================================================
+// SHARED HELPERS
+// ============================================================================
+
+fn now_rfc3339() -> String {
+    chrono::Utc::now().to_rfc3339()
+}
+
+fn epoch_millis(timestamp: &str) -> i64 {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .map(|t| t.timestamp_millis())
+        .unwrap_or_else(|_| chrono::Utc::now().timestamp_millis())
+}
+
+fn file_name(path: &str) -> String {
+    path.rsplit(['\\', '/']).next().unwrap_or(path).to_string()
+}
+
+fn os_type() -> &'static str {
+    match std::env::consts::OS {
+        "windows" => "windows",
+        "macos" => "macos",
+        _ => "linux",
+    }
+}
+
+/// Drop nulls so optional fields don't show up as `"x": null`
+fn prune(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| (k, prune(v)))
+                .collect::<Map<_, _>>()
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(prune).collect()),
+        other => other,
+    }
+}
+
+// ============================================================================
+// OCSF
+// ============================================================================
+
+fn ocsf_severity_id(severity: Severity) -> u8 {
+    match severity {
+        Severity::Low => 2,
+        Severity::Medium => 3,
+        Severity::High => 4,
+        Severity::Critical => 5,
+    }
+}
+
+/// class_uid, class_name, category_uid, category_name, activity_id, activity_name, severity_id, time
+fn ocsf_base(
+    class: (u32, &str, u32, &str),
+    activity: (u32, &str),
+    severity_id: u8,
+    timestamp: &str,
+) -> Value {
+    let (class_uid, class_name, category_uid, category_name) = class;
+    json!({
+        "class_uid": class_uid,
+        "class_name": class_name,
+        "category_uid": category_uid,
+        "category_name": category_name,
+        "activity_id": activity.0,
+        "activity_name": activity.1,
+        "type_uid": class_uid * 100 + activity.0,
+        "severity_id": severity_id,
+        "time": epoch_millis(timestamp),
+        "metadata": {
+            "version": OCSF_VERSION,
+            "product": {
+                "name": "CyberGuardian Agent",
+                "vendor_name": "CyberGuardian",
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+        },
+        "device": {
+            "hostname": crate::agent_identity::hostname(),
+            "os": { "name": std::env::consts::OS, "type": os_type() },
+            "uid": crate::agent_identity::agent_id().ok(),
+        },
+    })
+}
+
+fn merge(mut base: Value, extra: Value) -> Value {
+    if let (Some(target), Value::Object(fields)) = (base.as_object_mut(), extra) {
+        target.extend(fields);
+    }
+    prune(base)
+}
+
+/// Stable process entity id — (pid, start time), None when the start time is unknown
+fn process_uid(p: &ProcessInfo) -> Option<String> {
+    (p.start_time != 0).then(|| crate::process_identity::ProcessKey::new(p.pid, p.start_time).to_string())
+}
+
+fn ocsf_process(p: &ProcessInfo) -> Value {
+    json!({
+        "pid": p.pid,
+        "uid": process_uid(p),
+        "name": p.name,
+        "file": { "path": p.exe_path, "name": file_name(&p.exe_path) },
+        "user": { "name": p.username },
+        "parent_process": { "pid": p.parent_pid },
+    })
+}
+
+fn to_ocsf(event: &TelemetryEvent) -> Value {
+    match event {
+        TelemetryEvent::Process(p, activity) => {
+            let activity = match activity {
+                ProcessActivity::Launch => (1, "Launch"),
+                ProcessActivity::Terminate => (2, "Terminate"),
+                ProcessActivity::Observed => (99, "Other"),
+            };
+            merge(
+                ocsf_base((1007, "Process Activity", 1, "System Activity"), activity, 1, &now_rfc3339()),
+                json!({
+                    "process": ocsf_process(p),
+                    "actor": { "process": { "pid": p.parent_pid } },
+                    "unmapped": {
+                        "cpu_percent": p.cpu_percent,
+                        "memory_mb": p.memory_mb,
+                        "thread_count": p.thread_count,
+                    },
+                }),
+            )
+        }
+        TelemetryEvent::File(f) => {
+            let activity = match f.activity {
+                FileActivity::Create => (1, "Create"),
+                FileActivity::Update => (3, "Update"),
+                FileActivity::Delete => (4, "Delete"),
+            };
+            let hashes = f.sha256.as_ref()
+                .map(|h| json!([{ "algorithm_id": 3, "algorithm": "SHA-256", "value": h }]));
+            merge(
+                ocsf_base((1001, "File System Activity", 1, "System Activity"), activity, 1, &f.timestamp),
+                json!({
+                    "file": {
+                        "path": f.path,
+                        "name": file_name(&f.path),
+                        "size": f.size,
+                        "hashes": hashes,
+                    },
+                }),
+            )
+        }
+        TelemetryEvent::Network(n) => merge(
+            ocsf_base((4001, "Network Activity", 4, "Network Activity"), (1, "Open"), 1, &n.timestamp),
+            json!({
+                "dst_endpoint": { "ip": n.remote_ip, "port": n.remote_port },
+                "src_endpoint": { "port": n.local_port },
+                "connection_info": {
+                    "protocol_name": n.protocol,
+                    "direction": n.direction,
+                    "direction_id": if n.direction == "inbound" { 1 } else { 2 },
+                },
+                "actor": { "process": { "pid": n.pid, "name": n.process_name } },
+            }),
+        ),
+        TelemetryEvent::Detection(d) => {
+            let (disposition_id, disposition) = match d.outcome.as_str() {
+                "allowed" => (1, "Allowed"),
+                "blocked" => (2, "Blocked"),
+                "quarantined" => (3, "Quarantined"),
+                _ => (15, "Detected"),
+            };
+            let attacks = d.mitre_technique.as_ref()
+                .map(|t| json!([{ "technique": { "uid": t }, "version": "v14" }]));
+            let process = d.pid.map(|pid| json!({
+                "pid": pid,
+                "uid": d.process_uid,
+                "name": d.process_name,
+                "parent_process": { "name": d.parent_name },
+            }));
+            merge(
+                ocsf_base((2004, "Detection Finding", 2, "Findings"), (1, "Create"), ocsf_severity_id(d.severity), &d.timestamp),
+                json!({
+                    "finding_info": {
+                        "uid": format!("{}-{}", d.kind_id(), epoch_millis(&d.timestamp)),
+                        "title": d.name,
+                        "desc": d.reason,
+                        "types": [d.kind_id()],
+                        "attacks": attacks,
+                    },
+                    "disposition_id": disposition_id,
+                    "disposition": disposition,
+                    "risk_score": d.threat_score,
+                    "evidences": [{
+                        "process": process,
+                        "file": d.file_path.as_ref().map(|p| json!({ "path": p, "name": file_name(p) })),
+                    }],
+                }),
+            )
+        }
+    }
+}
+
+// ============================================================================
+// ECS
+// ============================================================================
+
+fn ecs_base(kind: &str, category: &[&str], event_type: &[&str], action: &str, timestamp: &str) -> Value {
+    json!({
+        "@timestamp": timestamp,
+        "ecs": { "version": ECS_VERSION },
+        "event": {
+            "kind": kind,
+            "category": category,
+            "type": event_type,
+            "action": action,
+            "module": "cyberguardian",
+        },
+        "host": {
+            "hostname": crate::agent_identity::hostname(),
+            "os": { "type": os_type() },
+        },
+        "agent": {
+            "type": "cyberguardian",
+            "version": env!("CARGO_PKG_VERSION"),
+            "id": crate::agent_identity::agent_id().ok(),
+        },
+    })
+}
+
+/// Deep-merge `extra` into `base` (ECS objects like `event` are shared)
+fn deep_merge(base: &mut Value, extra: Value) {
+    match (base, extra) {
+        (Value::Object(target), Value::Object(fields)) => {
+            for (k, v) in fields {
+                match target.get_mut(&k) {
+                    Some(existing) if existing.is_object() && v.is_object() => deep_merge(existing, v),
+                    _ => {
+                        target.insert(k, v);
+                    }
+                }
+            }
+        }
+        (slot, v) => *slot = v,
+    }
+}
+
+fn ecs_with(mut base: Value, extra: Value) -> Value {
+    deep_merge(&mut base, extra);
+    prune(base)
+}
+
+fn to_ecs(event: &TelemetryEvent) -> Value {
+    match event {
+        TelemetryEvent::Process(p, activity) => {
+            let (event_type, action) = match activity {
+                ProcessActivity::Launch => ("start", "process-started"),
+                ProcessActivity::Terminate => ("end", "process-stopped"),
+                ProcessActivity::Observed => ("info", "process-observed"),
+            };
+            ecs_with(
+                ecs_base("event", &["process"], &[event_type], action, &now_rfc3339()),
+                json!({
+                    "process": {
+                        "pid": p.pid,
+                        "entity_id": process_uid(p),
+                        "name": p.name,
+                        "executable": p.exe_path,
+                        "parent": { "pid": p.parent_pid },
+                        "thread": { "count": p.thread_count },
+                        "cpu": { "pct": p.cpu_percent / 100.0 },
+                        "memory": { "mb": p.memory_mb },
+                    },
+                    "user": { "name": p.username },
+                }),
+            )
+        }
+        TelemetryEvent::File(f) => {
+            let (event_type, action) = match f.activity {
+                FileActivity::Create => ("creation", "file-created"),
+                FileActivity::Update => ("change", "file-modified"),
+                FileActivity::Delete => ("deletion", "file-deleted"),
+            };
+            ecs_with(
+                ecs_base("event", &["file"], &[event_type], action, &f.timestamp),
+                json!({
+                    "file": {
+                        "path": f.path,
+                        "name": file_name(&f.path),
+                        "size": f.size,
+                        "hash": { "sha256": f.sha256 },
+                    },
+                }),
+            )
+        }
+        TelemetryEvent::Network(n) => ecs_with(
+            ecs_base("event", &["network"], &["connection", "start"], "connection-attempted", &n.timestamp),
+            json!({
+                "destination": { "ip": n.remote_ip, "port": n.remote_port },
+                "source": { "port": n.local_port },
+                "network": { "transport": n.protocol, "direction": n.direction },
+                "process": { "pid": n.pid, "name": n.process_name },
+            }),
+        ),
+        TelemetryEvent::Detection(d) => {
+            let category: &[&str] = match d.kind {
+                EventKind::ProcessBlocked => &["intrusion_detection", "process"],
+                EventKind::FileVerdict => &["malware", "file"],
+                _ => &["intrusion_detection", "configuration"],
+            };
+            let outcome = match d.outcome.as_str() {
+                "blocked" | "quarantined" | "allowed" | "detected" => "success",
+                _ => "failure",
+            };
+            let mut extra = json!({
+                "event": {
+                    "severity": d.severity_numeric(),
+                    "outcome": outcome,
+                    "reason": d.reason,
+                    "risk_score": d.threat_score,
+                },
+                "rule": { "name": d.name, "id": d.kind_id() },
+                "message": format!("{}: {}", d.name, d.reason),
+                "file": d.file_path.as_ref().map(|p| json!({ "path": p, "name": file_name(p) })),
+                "process": d.pid.map(|pid| json!({
+                    "pid": pid,
+                    "entity_id": d.process_uid,
+                    "name": d.process_name,
+                    "parent": { "name": d.parent_name },
+                })),
+            });
+            if let Some(technique) = &d.mitre_technique {
+                extra["threat"] = json!({
+                    "framework": "MITRE ATT&CK",
+                    "technique": { "id": [technique] },
+                });
+            }
+            ecs_with(ecs_base("alert", category, &["indicator"], &d.outcome, &d.timestamp), extra)
+        }
+    }
+}
+
+/// Normalize one telemetry event into `schema`
+pub fn normalize(event: &TelemetryEvent, schema: TelemetrySchema) -> Value {
+    match schema {
+        TelemetrySchema::Ocsf => to_ocsf(event),
+        TelemetrySchema::Ecs => to_ecs(event),
+        TelemetrySchema::Native => match event {
+            TelemetryEvent::Process(p, _) => serde_json::to_value(p),
+            TelemetryEvent::File(f) => serde_json::to_value(f),
+            TelemetryEvent::Network(n) => serde_json::to_value(n),
+            TelemetryEvent::Detection(d) => serde_json::to_value(d),
+        }
+        .unwrap_or(Value::Null),
+    }
+}
+
+// ============================================================================
+// SETTINGS
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NdjsonSinkSettings {
+    pub enabled: bool,
+    pub schema: TelemetrySchema,
+    /// Defaults to <data dir>/CyberGuardian/telemetry/events.ndjson
+    pub path: Option<String>,
+    pub max_file_mb: u64,
+    /// Rotated files kept (events.ndjson.1 .. .N)
+    pub max_files: u32,
+}
+
+impl Default for NdjsonSinkSettings {
+    fn default() -> Self {
+        NdjsonSinkSettings {
+            enabled: false,
+            schema: TelemetrySchema::Ocsf,
+            path: None,
+            max_file_mb: 50,
+            max_files: 5,
+        }
+    }
+}
+
+/// Telemetry settings (persisted in telemetry_settings.json)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TelemetrySettings {
+    /// Shape of process entries in inventory uploads
+    pub upload_schema: TelemetrySchema,
+    pub sink: NdjsonSinkSettings,
+}
+
+impl Default for TelemetrySettings {
+    fn default() -> Self {
+        TelemetrySettings {
+            upload_schema: TelemetrySchema::Native,
+            sink: NdjsonSinkSettings::default(),
+        }
+    }
+}
+
+fn get_settings_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("CyberGuardian")
+        .join("telemetry_settings.json")
+}
+
+fn default_sink_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("CyberGuardian")
+        .join("telemetry")
+        .join("events.ndjson")
+}
+
+fn load_settings() -> TelemetrySettings {
+    fs::read_to_string(get_settings_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save_settings(settings: &TelemetrySettings) -> Result<(), String> {
+    let path = get_settings_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create settings dir: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write settings: {}", e))
+}
+
+lazy_static::lazy_static! {
+    static ref SETTINGS: RwLock<TelemetrySettings> = RwLock::new(load_settings());
+    static ref SINK_LOCK: Mutex<()> = Mutex::new(());
+}
+
+pub fn upload_schema() -> TelemetrySchema {
+    SETTINGS.read().unwrap().upload_schema
+}
+
+// ============================================================================
+// NDJSON SINK
+// ============================================================================
+
+/// Shift events.ndjson → .1 → .2 ... dropping the oldest
+fn rotate(path: &std::path::Path, max_files: u32) {
+    let rotated = |n: u32| PathBuf::from(format!("{}.{}", path.display(), n));
+    let _ = fs::remove_file(rotated(max_files));
+    for n in (1..max_files).rev() {
+        let _ = fs::rename(rotated(n), rotated(n + 1));
+    }
+    if max_files > 0 {
+        let _ = fs::rename(path, rotated(1));
+    } else {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// Append one normalized line to the sink file (rotating by size)
+pub fn write_ndjson(sink: &NdjsonSinkSettings, event: &TelemetryEvent) -> Result<(), String> {
+    let path = sink.path.as_ref().map(PathBuf::from).unwrap_or_else(default_sink_path);
+    let mut line = serde_json::to_string(&crate::redaction::redact_outbound(&normalize(event, sink.schema)))
+        .map_err(|e| format!("Failed to serialize event: {}", e))?;
+    line.push('\n');
+
+    let _guard = SINK_LOCK.lock().unwrap();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create sink dir: {}", e))?;
+    }
+    let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    if size > 0 && size + line.len() as u64 > sink.max_file_mb * 1024 * 1024 {
+        rotate(&path, sink.max_files);
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut f| f.write_all(line.as_bytes()))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Write `event` to the NDJSON sink if enabled
+pub fn emit(event: TelemetryEvent) {
+    let sink = SETTINGS.read().unwrap().sink.clone();
+    if !sink.enabled {
+        return;
+    }
+    if let Err(e) = write_ndjson(&sink, &event) {
+        warn!("Telemetry sink: {}", e);
+    }
+}
+
+/// Single entry point for detections: local sink + SIEM forwarding
+pub fn publish_detection(event: SecurityEvent) {
+    emit(TelemetryEvent::Detection(&event));
+    crate::siem_forwarder::forward(event);
+}
+
+// ============================================================================
+// TAURI COMMANDS
+// ============================================================================
+
+#[tauri::command]
+pub fn get_telemetry_settings() -> TelemetrySettings {
+    SETTINGS.read().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn set_telemetry_settings(settings: TelemetrySettings) -> Result<TelemetrySettings, String> {
+    if settings.sink.max_file_mb == 0 {
+        return Err("max_file_mb must be at least 1".to_string());
+    }
+    save_settings(&settings)?;
+    *SETTINGS.write().unwrap() = settings.clone();
+    info!("Telemetry settings updated (upload: {}, sink: {})",
+        settings.upload_schema.as_str(),
+        if settings.sink.enabled { settings.sink.schema.as_str() } else { "off" });
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process() -> ProcessInfo {
+        ProcessInfo {
+            pid: 321,
+            name: "powershell.exe".into(),
+            parent_pid: 100,
+            thread_count: 12,
+            exe_path: r"C:\Windows\System32\WindowsPowerShell\v1.0\powershell.exe".into(),
+            cpu_percent: 4.0,
+            memory_mb: 80.0,
+            username: "SYSTEM".into(),
+            start_time: 133_500_000_000_000_000,
+        }
+    }
+
+    fn detection() -> SecurityEvent {
+        let mut event = SecurityEvent::file_verdict(r"C:\Temp\evil.exe", 95.0, "quarantined");
+        event.mitre_technique = Some("T1204".into());
+        event
+    }
+
+    #[test]
+    fn test_ocsf_classes() {
+        let p = normalize(&TelemetryEvent::Process(&process(), ProcessActivity::Launch), TelemetrySchema::Ocsf);
+        assert_eq!(p["class_uid"], 1007);
+        assert_eq!(p["type_uid"], 100701);
+        assert_eq!(p["process"]["file"]["name"], "powershell.exe");
+        assert_eq!(p["process"]["uid"], "321@133500000000000000");
+
+        let d = normalize(&TelemetryEvent::Detection(&detection()), TelemetrySchema::Ocsf);
+        assert_eq!(d["class_uid"], 2004);
+        assert_eq!(d["severity_id"], 5);
+        assert_eq!(d["disposition_id"], 3);
+        assert_eq!(d["finding_info"]["attacks"][0]["technique"]["uid"], "T1204");
+        assert_eq!(d["evidences"][0]["file"]["name"], "evil.exe");
+
+        let conn = NetworkConnection {
+            pid: 321,
+            process_name: "powershell.exe".into(),
+            protocol: "tcp".into(),
+            remote_ip: "203.0.113.9".into(),
+            remote_port: Some(443),
+            local_port: None,
+            direction: "outbound".into(),
+            timestamp: now_rfc3339(),
+        };
+        let n = normalize(&TelemetryEvent::Network(&conn), TelemetrySchema::Ocsf);
+        assert_eq!(n["class_uid"], 4001);
+        assert_eq!(n["dst_endpoint"]["port"], 443);
+        assert!(n["src_endpoint"].get("port").is_none());
+    }
+
+    #[test]
+    fn test_ecs_fields() {
+        let d = normalize(&TelemetryEvent::Detection(&detection()), TelemetrySchema::Ecs);
+        assert_eq!(d["event"]["kind"], "alert");
+        assert_eq!(d["event"]["module"], "cyberguardian");
+        assert_eq!(d["event"]["severity"], 10);
+        assert_eq!(d["threat"]["technique"]["id"][0], "T1204");
+        assert_eq!(d["file"]["path"], r"C:\Temp\evil.exe");
+
+        let f = FileEvent {
+            path: "/tmp/a.sh".into(),
+            size: Some(10),
+            sha256: Some("ab".into()),
+            activity: FileActivity::Delete,
+            timestamp: now_rfc3339(),
+        };
+        let f = normalize(&TelemetryEvent::File(&f), TelemetrySchema::Ecs);
+        assert_eq!(f["event"]["type"][0], "deletion");
+        assert_eq!(f["file"]["hash"]["sha256"], "ab");
+    }
+
+    #[test]
+    fn test_ndjson_sink_appends_and_rotates() {
+        let dir = std::env::temp_dir().join(format!("cg_sink_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("events.ndjson");
+        let sink = NdjsonSinkSettings {
+            enabled: true,
+            schema: TelemetrySchema::Ecs,
+            path: Some(path.to_string_lossy().to_string()),
+            max_file_mb: 1,
+            max_files: 2,
+        };
+
+        let p = process();
+        for _ in 0..3 {
+            write_ndjson(&sink, &TelemetryEvent::Process(&p, ProcessActivity::Observed)).unwrap();
+        }
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(content.lines().count(), 3);
+        for line in content.lines() {
+            let v: Value = serde_json::from_str(line).unwrap();
+            assert_eq!(v["process"]["pid"], 321);
+        }
+
+        rotate(&path, sink.max_files);
+        write_ndjson(&sink, &TelemetryEvent::Process(&p, ProcessActivity::Observed)).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 1);
+        assert!(dir.join("events.ndjson.1").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,781 @@
+//! Threat Intelligence
+//! Imports local STIX 2.1 bundles, MISP JSON events and plain-text IOC lists into
+//! fast lookup indexes (domains incl. parent domains, IPs, CIDRs, hashes, URLs)
+//! with per-IOC expiry and source attribution. Detectors query it via `lookup_*`
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use tracing::info;
+
+/// Used when a feed carries no expiry of its own
+const DEFAULT_TTL_DAYS: i64 = 90;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IocType {
+    Domain,
+    Ip,
+    Cidr,
+    Md5,
+    Sha1,
+    Sha256,
+    Url,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedFormat {
+    Stix,
+    Misp,
+    Plain,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ioc {
+    pub ioc_type: IocType,
+    /// Normalized value (lowercase domains / hashes, canonical IPs and CIDRs)
+    pub value: String,
+    pub source: String,
+    #[serde(default)]
+    pub description: String,
+    pub added_at: String,
+    pub expires_at: Option<String>,
+}
+
+/// One indicator as read from a feed, before source attribution
+#[derive(Debug, Clone)]
+pub struct ParsedIoc {
+    pub ioc_type: IocType,
+    pub value: String,
+    pub description: String,
+    /// Feed-provided expiry (STIX `valid_until`)
+    pub valid_until: Option<String>,
+}
+
+impl ParsedIoc {
+    fn new((ioc_type, value): (IocType, String), description: &str) -> Self {
+        ParsedIoc { ioc_type, value, description: description.to_string(), valid_until: None }
+    }
+}
+
+/// Returned to detectors when something matches
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IntelMatch {
+    pub ioc_type: IocType,
+    /// The IOC that matched (e.g. the parent domain or the CIDR)
+    pub indicator: String,
+    pub source: String,
+    pub description: String,
+    pub expires_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntelSource {
+    pub name: String,
+    pub format: FeedFormat,
+    pub path: String,
+    pub imported_at: String,
+    pub ioc_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportSummary {
+    pub source: String,
+    pub format: FeedFormat,
+    pub imported: usize,
+    pub skipped: usize,
+    pub by_type: BTreeMap<String, usize>,
+}
+
+// ============================================================================
+// NORMALIZATION / PARSING
+// ============================================================================
+
+/// Undo common defanging: hxxp, [.], (.), [:]
+fn refang(value: &str) -> String {
+    value.trim()
+        .replace("[.]", ".")
+        .replace("(.)", ".")
+        .replace("[:]", ":")
+        .replace("hxxp", "http")
+        .replace("hXXp", "http")
+}
+
+fn is_hex(s: &str) -> bool {
+    s.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn looks_like_domain(s: &str) -> bool {
+    let labels: Vec<&str> = s.split('.').collect();
+    labels.len() >= 2
+        && labels.iter().all(|l| {
+            !l.is_empty() && l.len() <= 63
+                && l.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                && !l.starts_with('-')
+        })
+        && labels.last().map(|tld| tld.chars().any(|c| c.is_ascii_alphabetic())).unwrap_or(false)
+}
+
+/// Canonical CIDR string, or None if invalid
+fn parse_cidr(s: &str) -> Option<(IpAddr, u8)> {
+    let (addr, prefix) = s.split_once('/')?;
+    let addr: IpAddr = addr.trim().parse().ok()?;
+    let prefix: u8 = prefix.trim().parse().ok()?;
+    let max = if addr.is_ipv4() { 32 } else { 128 };
+    (prefix <= max).then(|| (mask(addr, prefix), prefix))
+}
+
+fn mask(addr: IpAddr, prefix: u8) -> IpAddr {
+    match addr {
+        IpAddr::V4(v4) => {
+            let bits = u32::from(v4);
+            let masked = if prefix == 0 { 0 } else { bits & (u32::MAX << (32 - prefix as u32)) };
+            IpAddr::V4(masked.into())
+        }
+        IpAddr::V6(v6) => {
+            let bits = u128::from(v6);
+            let masked = if prefix == 0 { 0 } else { bits & (u128::MAX << (128 - prefix as u32)) };
+            IpAddr::V6(masked.into())
+        }
+    }
+}
+
+/// Detect the type of a bare indicator and normalize it
+pub fn classify(raw: &str) -> Option<(IocType, String)> {
+    let value = refang(raw);
+    if value.is_empty() {
+        return None;
+    }
+    let lower = value.to_lowercase();
+
+    if lower.contains("://") {
+        return Some((IocType::Url, lower));
+    }
+    if let Some((addr, prefix)) = parse_cidr(&value) {
+        return Some((IocType::Cidr, format!("{}/{}", addr, prefix)));
+    }
+    if let Ok(ip) = value.parse::<IpAddr>() {
+        return Some((IocType::Ip, ip.to_string()));
+    }
+    if is_hex(&lower) {
+        return match lower.len() {
+            32 => Some((IocType::Md5, lower)),
+            40 => Some((IocType::Sha1, lower)),
+            64 => Some((IocType::Sha256, lower)),
+            _ => None,
+        };
+    }
+    let domain = lower.trim_end_matches('.').trim_start_matches("*.").to_string();
+    looks_like_domain(&domain).then_some((IocType::Domain, domain))
+}
+
+/// Plain text: one IOC per line; `#` / `//` comments; extra columns after whitespace or comma are ignored
+pub fn parse_plain(text: &str) -> (Vec<ParsedIoc>, usize) {
+    let mut iocs = Vec::new();
+    let mut skipped = 0;
+    for line in text.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() || line.starts_with("//") {
+            continue;
+        }
+        let token = line.split(|c: char| c.is_whitespace() || c == ',' || c == ';').next().unwrap_or("");
+        match classify(token) {
+            Some(ioc) => iocs.push(ParsedIoc::new(ioc, "")),
+            None => skipped += 1,
+        }
+    }
+    (iocs, skipped)
+}
+
+/// STIX 2.1: indicators with simple `[object:property = 'value']` comparisons
+/// (OR/AND-joined comparisons each become one IOC)
+pub fn parse_stix(bundle: &Value) -> (Vec<ParsedIoc>, usize) {
+    let comparison = regex::Regex::new(
+        r"([a-z0-9\-]+):([A-Za-z0-9_.'\-]+)\s*=\s*'((?:[^'\\]|\\.)*)'"
+    ).expect("stix comparison regex");
+
+    let mut iocs = Vec::new();
+    let mut skipped = 0;
+    let objects = bundle.get("objects").and_then(|o| o.as_array()).cloned().unwrap_or_default();
+
+    for object in objects.iter().filter(|o| o["type"] == "indicator") {
+        if object.get("pattern_type").and_then(|p| p.as_str()).unwrap_or("stix") != "stix" {
+            skipped += 1;
+            continue;
+        }
+        if object.get("revoked").and_then(|r| r.as_bool()).unwrap_or(false) {
+            skipped += 1;
+            continue;
+        }
+        let pattern = object["pattern"].as_str().unwrap_or("");
+        let description = object.get("name").or_else(|| object.get("description"))
+            .and_then(|d| d.as_str()).unwrap_or("").to_string();
+        let valid_until = object.get("valid_until").and_then(|v| v.as_str()).map(String::from);
+
+        let mut found = false;
+        for caps in comparison.captures_iter(pattern) {
+            let value = caps[3].replace("\\'", "'").replace("\\\\", "\\");
+            let expected = match (&caps[1], &caps[2]) {
+                ("domain-name", "value") => Some(IocType::Domain),
+                ("ipv4-addr", "value") | ("ipv6-addr", "value") => None, // IP or CIDR
+                ("url", "value") => Some(IocType::Url),
+                ("file", p) if p.contains("MD5") => Some(IocType::Md5),
+                ("file", p) if p.contains("SHA-1") || p.contains("SHA1") => Some(IocType::Sha1),
+                ("file", p) if p.contains("SHA-256") || p.contains("SHA256") => Some(IocType::Sha256),
+                _ => {
+                    skipped += 1;
+                    continue;
+                }
+            };
+            match classify(&value) {
+                Some((t, v)) if expected.map(|e| e == t).unwrap_or(matches!(t, IocType::Ip | IocType::Cidr)) => {
+                    iocs.push(ParsedIoc { valid_until: valid_until.clone(), ..ParsedIoc::new((t, v), &description) });
+                    found = true;
+                }
+                _ => skipped += 1,
+            }
+        }
+        if !found && pattern.is_empty() {
+            skipped += 1;
+        }
+    }
+    (iocs, skipped)
+}
+
+fn misp_events(feed: &Value) -> Vec<&Value> {
+    match feed {
+        Value::Array(items) => items.iter().flat_map(misp_events).collect(),
+        Value::Object(map) => {
+            if let Some(event) = map.get("Event") {
+                vec![event]
+            } else if let Some(response) = map.get("response") {
+                misp_events(response)
+            } else if map.contains_key("Attribute") {
+                vec![feed]
+            } else {
+                Vec::new()
+            }
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// MISP JSON: single event, `{"response": [...]}` or a list; attributes incl. those inside objects.
+/// Only `to_ids` attributes are imported
+pub fn parse_misp(feed: &Value) -> (Vec<ParsedIoc>, usize) {
+    let mut iocs = Vec::new();
+    let mut skipped = 0;
+
+    for event in misp_events(feed) {
+        let info = event.get("info").and_then(|i| i.as_str()).unwrap_or("").to_string();
+        let mut attributes: Vec<&Value> = event.get("Attribute").and_then(|a| a.as_array())
+            .map(|a| a.iter().collect()).unwrap_or_default();
+        if let Some(objects) = event.get("Object").and_then(|o| o.as_array()) {
+            for object in objects {
+                if let Some(attrs) = object.get("Attribute").and_then(|a| a.as_array()) {
+                    attributes.extend(attrs.iter());
+                }
+            }
+        }
+
+        for attr in attributes {
+            let to_ids = match attr.get("to_ids") {
+                Some(Value::Bool(b)) => *b,
+                Some(Value::String(s)) => s == "1" || s.eq_ignore_ascii_case("true"),
+                Some(Value::Number(n)) => n.as_u64() == Some(1),
+                _ => true,
+            };
+            if !to_ids {
+                skipped += 1;
+                continue;
+            }
+            let attr_type = attr.get("type").and_then(|t| t.as_str()).unwrap_or("");
+            let value = attr.get("value").and_then(|v| v.as_str()).unwrap_or("");
+            let comment = attr.get("comment").and_then(|c| c.as_str()).unwrap_or("");
+            let description = if comment.is_empty() { info.clone() } else { format!("{} — {}", info, comment) };
+
+            // Composite types: "filename|sha256", "domain|ip", "ip-dst|port"
+            let values: Vec<&str> = match attr_type {
+                "ip-dst|port" | "ip-src|port" => value.split('|').take(1).collect(),
+                t if t.starts_with("filename|") => value.split('|').skip(1).collect(),
+                t if t.contains('|') => value.split('|').collect(),
+                _ => vec![value],
+            };
+            let supported = matches!(
+                attr_type.split('|').next_back().unwrap_or(""),
+                "domain" | "hostname" | "ip-dst" | "ip-src" | "ip" | "url" | "uri" | "link"
+                    | "md5" | "sha1" | "sha256" | "port"
+            );
+            if !supported {
+                skipped += 1;
+                continue;
+            }
+            for v in values {
+                match classify(v) {
+                    Some(ioc) => iocs.push(ParsedIoc::new(ioc, &description)),
+                    None => skipped += 1,
+                }
+            }
+        }
+    }
+    (iocs, skipped)
+}
+
+// ============================================================================
+// INDEX
+// ============================================================================
+
+#[derive(Default)]
+pub struct IntelIndex {
+    /// exact value → entries (domains, IPs, hashes, URLs)
+    exact: HashMap<(IocType, String), Vec<usize>>,
+    /// prefix length → masked network → entries
+    v4_cidrs: BTreeMap<u8, HashMap<u32, Vec<usize>>>,
+    v6_cidrs: BTreeMap<u8, HashMap<u128, Vec<usize>>>,
+    iocs: Vec<Ioc>,
+}
+
+fn is_expired(ioc: &Ioc, now: DateTime<Utc>) -> bool {
+    ioc.expires_at.as_ref()
+        .and_then(|e| DateTime::parse_from_rfc3339(e).ok())
+        .map(|e| e.with_timezone(&Utc) <= now)
+        .unwrap_or(false)
+}
+
+impl IntelIndex {
+    pub fn build(iocs: Vec<Ioc>) -> Self {
+        let mut index = IntelIndex { iocs, ..Default::default() };
+        for (i, ioc) in index.iocs.iter().enumerate() {
+            if ioc.ioc_type == IocType::Cidr {
+                match parse_cidr(&ioc.value) {
+                    Some((IpAddr::V4(net), prefix)) => index.v4_cidrs.entry(prefix).or_default()
+                        .entry(u32::from(net)).or_default().push(i),
+                    Some((IpAddr::V6(net), prefix)) => index.v6_cidrs.entry(prefix).or_default()
+                        .entry(u128::from(net)).or_default().push(i),
+                    None => {}
+                }
+            } else {
+                index.exact.entry((ioc.ioc_type, ioc.value.clone())).or_default().push(i);
+            }
+        }
+        index
+    }
+
+    fn matches(&self, ids: &[usize], now: DateTime<Utc>) -> Vec<IntelMatch> {
+        ids.iter()
+            .map(|&i| &self.iocs[i])
+            .filter(|ioc| !is_expired(ioc, now))
+            .map(|ioc| IntelMatch {
+                ioc_type: ioc.ioc_type,
+                indicator: ioc.value.clone(),
+                source: ioc.source.clone(),
+                description: ioc.description.clone(),
+                expires_at: ioc.expires_at.clone(),
+            })
+            .collect()
+    }
+
+    fn exact(&self, ioc_type: IocType, value: &str, now: DateTime<Utc>) -> Vec<IntelMatch> {
+        self.exact.get(&(ioc_type, value.to_string()))
+            .map(|ids| self.matches(ids, now))
+            .unwrap_or_default()
+    }
+
+    /// Exact domain or any parent domain (a.b.evil.com matches evil.com)
+    pub fn lookup_domain(&self, domain: &str, now: DateTime<Utc>) -> Vec<IntelMatch> {
+        let domain = domain.trim_end_matches('.').to_lowercase();
+        let mut out = Vec::new();
+        let mut rest = domain.as_str();
+        loop {
+            out.extend(self.exact(IocType::Domain, rest, now));
+            match rest.split_once('.') {
+                Some((_, parent)) if parent.contains('.') => rest = parent,
+                _ => break,
+            }
+        }
+        out
+    }
+
+    /// Exact IP or any containing CIDR
+    pub fn lookup_ip(&self, ip: IpAddr, now: DateTime<Utc>) -> Vec<IntelMatch> {
+        let mut out = self.exact(IocType::Ip, &ip.to_string(), now);
+        match ip {
+            IpAddr::V4(v4) => {
+                for (&prefix, nets) in &self.v4_cidrs {
+                    if let IpAddr::V4(net) = mask(IpAddr::V4(v4), prefix) {
+                        if let Some(ids) = nets.get(&u32::from(net)) {
+                            out.extend(self.matches(ids, now));
+                        }
+                    }
+                }
+            }
+            IpAddr::V6(v6) => {
+                for (&prefix, nets) in &self.v6_cidrs {
+                    if let IpAddr::V6(net) = mask(IpAddr::V6(v6), prefix) {
+                        if let Some(ids) = nets.get(&u128::from(net)) {
+                            out.extend(self.matches(ids, now));
+                        }
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    pub fn lookup_hash(&self, hash: &str, now: DateTime<Utc>) -> Vec<IntelMatch> {
+        match classify(hash) {
+            Some((t @ (IocType::Md5 | IocType::Sha1 | IocType::Sha256), v)) => self.exact(t, &v, now),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Exact URL, plus its host as a domain / IP
+    pub fn lookup_url(&self, url: &str, now: DateTime<Utc>) -> Vec<IntelMatch> {
+        let lower = url.trim().to_lowercase();
+        let mut out = self.exact(IocType::Url, &lower, now);
+        let host = lower.split("://").nth(1).unwrap_or(&lower)
+            .split(['/', '?', '#']).next().unwrap_or("");
+        let host = host.rsplit('@').next().unwrap_or(host);
+        let host = match host.strip_prefix('[') {
+            Some(v6) => v6.split(']').next().unwrap_or(""),
+            None => host.split(':').next().unwrap_or(""),
+        };
+        match host.parse::<IpAddr>() {
+            Ok(ip) => out.extend(self.lookup_ip(ip, now)),
+            Err(_) if !host.is_empty() => out.extend(self.lookup_domain(host, now)),
+            Err(_) => {}
+        }
+        out
+    }
+
+    pub fn active_count(&self, now: DateTime<Utc>) -> usize {
+        self.iocs.iter().filter(|i| !is_expired(i, now)).count()
+    }
+}
+
+// ============================================================================
+// STORE
+// ============================================================================
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct IntelStore {
+    sources: Vec<IntelSource>,
+    iocs: Vec<Ioc>,
+}
+
+fn get_store_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("CyberGuardian")
+        .join("threat_intel")
+        .join("intel_store.json")
+}
+
+fn load_store() -> IntelStore {
+    fs::read_to_string(get_store_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save_store(store: &IntelStore) -> Result<(), String> {
+    let path = get_store_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create intel dir: {}", e))?;
+    }
+    let json = serde_json::to_string(store).map_err(|e| format!("Failed to serialize intel: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write intel store: {}", e))
+}
+
+struct IntelState {
+    store: IntelStore,
+    index: IntelIndex,
+}
+
+impl IntelState {
+    fn load() -> Self {
+        let store = load_store();
+        let index = IntelIndex::build(store.iocs.clone());
+        IntelState { store, index }
+    }
+
+    fn rebuild(&mut self) {
+        self.index = IntelIndex::build(self.store.iocs.clone());
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref INTEL: RwLock<IntelState> = RwLock::new(IntelState::load());
+}
+
+fn detect_format(path: &str, content: &str) -> FeedFormat {
+    let trimmed = content.trim_start();
+    if trimmed.starts_with('{') || trimmed.starts_with('[') {
+        if let Ok(json) = serde_json::from_str::<Value>(content) {
+            if json.get("type").and_then(|t| t.as_str()) == Some("bundle") {
+                return FeedFormat::Stix;
+            }
+            if !misp_events(&json).is_empty() {
+                return FeedFormat::Misp;
+            }
+        }
+    }
+    if path.to_lowercase().ends_with(".stix.json") {
+        FeedFormat::Stix
+    } else {
+        FeedFormat::Plain
+    }
+}
+
+/// Parse feed content into IOCs attributed to `source`
+pub fn parse_feed(
+    content: &str,
+    format: FeedFormat,
+    source: &str,
+    ttl_days: i64,
+    now: DateTime<Utc>,
+) -> Result<(Vec<Ioc>, usize), String> {
+    let default_expiry = (now + Duration::days(ttl_days)).to_rfc3339();
+    let (parsed, skipped) = match format {
+        FeedFormat::Plain => parse_plain(content),
+        FeedFormat::Stix => {
+            let json: Value = serde_json::from_str(content).map_err(|e| format!("Invalid STIX JSON: {}", e))?;
+            parse_stix(&json)
+        }
+        FeedFormat::Misp => {
+            let json: Value = serde_json::from_str(content).map_err(|e| format!("Invalid MISP JSON: {}", e))?;
+            parse_misp(&json)
+        }
+    };
+
+    let iocs = parsed.into_iter().map(|p| Ioc {
+        ioc_type: p.ioc_type,
+        value: p.value,
+        source: source.to_string(),
+        description: p.description,
+        added_at: now.to_rfc3339(),
+        expires_at: Some(p.valid_until.unwrap_or_else(|| default_expiry.clone())),
+    });
+
+    // Drop duplicates within one feed
+    let mut seen = HashSet::new();
+    let iocs: Vec<Ioc> = iocs.filter(|i| seen.insert((i.ioc_type, i.value.clone()))).collect();
+    Ok((iocs, skipped))
+}
+
+// ============================================================================
+// PUBLIC API (detectors)
+// ============================================================================
+
+pub fn lookup_domain(domain: &str) -> Vec<IntelMatch> {
+    INTEL.read().unwrap().index.lookup_domain(domain, Utc::now())
+}
+
+pub fn lookup_ip(ip: IpAddr) -> Vec<IntelMatch> {
+    INTEL.read().unwrap().index.lookup_ip(ip, Utc::now())
+}
+
+pub fn lookup_hash(hash: &str) -> Vec<IntelMatch> {
+    INTEL.read().unwrap().index.lookup_hash(hash, Utc::now())
+}
+
+pub fn lookup_url(url: &str) -> Vec<IntelMatch> {
+    INTEL.read().unwrap().index.lookup_url(url, Utc::now())
+}
+
+/// Short "source: description" for alert reasons
+pub fn describe(matches: &[IntelMatch]) -> String {
+    matches.iter()
+        .map(|m| if m.description.is_empty() { m.source.clone() } else { format!("{}: {}", m.source, m.description) })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+// ============================================================================
+// TAURI COMMANDS
+// ============================================================================
+
+/// Import a local feed file. `format`: "stix" | "misp" | "plain" (auto-detected if omitted).
+/// Re-importing the same source name replaces its previous IOCs
+#[tauri::command]
+pub fn import_threat_intel(
+    path: String,
+    format: Option<FeedFormat>,
+    source_name: Option<String>,
+    ttl_days: Option<i64>,
+) -> Result<ImportSummary, String> {
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let format = format.unwrap_or_else(|| detect_format(&path, &content));
+    let source = source_name.unwrap_or_else(|| {
+        PathBuf::from(&path).file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| path.clone())
+    });
+    let ttl_days = ttl_days.unwrap_or(DEFAULT_TTL_DAYS).max(1);
+
+    let (iocs, skipped) = parse_feed(&content, format, &source, ttl_days, Utc::now())?;
+    let mut by_type = BTreeMap::new();
+    for ioc in &iocs {
+        *by_type.entry(format!("{:?}", ioc.ioc_type).to_lowercase()).or_insert(0) += 1;
+    }
+    let summary = ImportSummary { source: source.clone(), format, imported: iocs.len(), skipped, by_type };
+
+    let mut state = INTEL.write().unwrap();
+    state.store.iocs.retain(|i| i.source != source);
+    state.store.sources.retain(|s| s.name != source);
+    state.store.sources.push(IntelSource {
+        name: source.clone(),
+        format,
+        path,
+        imported_at: Utc::now().to_rfc3339(),
+        ioc_count: iocs.len(),
+    });
+    state.store.iocs.extend(iocs);
+    save_store(&state.store)?;
+    state.rebuild();
+
+    info!("Threat intel: imported {} IOCs from '{}' ({} skipped)", summary.imported, source, skipped);
+    Ok(summary)
+}
+
+#[tauri::command]
+pub fn list_intel_sources() -> Vec<IntelSource> {
+    INTEL.read().unwrap().store.sources.clone()
+}
+
+#[tauri::command]
+pub fn remove_intel_source(name: String) -> Result<usize, String> {
+    let mut state = INTEL.write().unwrap();
+    let before = state.store.iocs.len();
+    state.store.iocs.retain(|i| i.source != name);
+    state.store.sources.retain(|s| s.name != name);
+    save_store(&state.store)?;
+    state.rebuild();
+    Ok(before - state.store.iocs.len())
+}
+
+/// Drop expired IOCs from the store
+#[tauri::command]
+pub fn prune_expired_intel() -> Result<usize, String> {
+    let now = Utc::now();
+    let mut state = INTEL.write().unwrap();
+    let before = state.store.iocs.len();
+    state.store.iocs.retain(|i| !is_expired(i, now));
+    let removed = before - state.store.iocs.len();
+    if removed > 0 {
+        save_store(&state.store)?;
+        state.rebuild();
+    }
+    Ok(removed)
+}
+
+/// Look up any indicator (domain, IP, hash or URL) against loaded intel
+#[tauri::command]
+pub fn check_indicator(value: String) -> Vec<IntelMatch> {
+    match classify(&value) {
+        Some((IocType::Domain, v)) => lookup_domain(&v),
+        Some((IocType::Ip, v)) => v.parse().map(lookup_ip).unwrap_or_default(),
+        Some((IocType::Md5 | IocType::Sha1 | IocType::Sha256, v)) => lookup_hash(&v),
+        Some((IocType::Url, v)) => lookup_url(&v),
+        _ => Vec::new(),
+    }
+}
+
+#[tauri::command]
+pub fn get_intel_stats() -> Value {
+    let state = INTEL.read().unwrap();
+    let now = Utc::now();
+    serde_json::json!({
+        "sources": state.store.sources.len(),
+        "total_iocs": state.store.iocs.len(),
+        "active_iocs": state.index.active_count(now),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index_from(content: &str, format: FeedFormat, now: DateTime<Utc>) -> IntelIndex {
+        IntelIndex::build(parse_feed(content, format, "feed", 30, now).unwrap().0)
+    }
+
+    #[test]
+    fn test_plain_list_and_lookups() {
+        let now = Utc::now();
+        let feed = "# test feed\nevil[.]com\n203.0.113.7\n198.51.100.0/24, c2 range\n\
+                    hxxp://bad.example/payload.exe\n44d88612fea8a8f36de82e1278abb02f\nnot an ioc\n";
+        let (parsed, skipped) = parse_plain(feed);
+        assert_eq!(parsed.len(), 5);
+        assert_eq!(skipped, 1);
+
+        let index = index_from(feed, FeedFormat::Plain, now);
+        assert_eq!(index.lookup_domain("cdn.EVIL.com.", now)[0].indicator, "evil.com");
+        assert!(index.lookup_domain("notevil.com", now).is_empty());
+        assert_eq!(index.lookup_ip("198.51.100.42".parse().unwrap(), now)[0].indicator, "198.51.100.0/24");
+        assert_eq!(index.lookup_ip("203.0.113.7".parse().unwrap(), now)[0].source, "feed");
+        assert!(index.lookup_ip("203.0.113.8".parse().unwrap(), now).is_empty());
+        assert_eq!(index.lookup_hash("44D88612FEA8A8F36DE82E1278ABB02F", now).len(), 1);
+        assert_eq!(index.lookup_url("http://bad.example/payload.exe", now).len(), 1);
+        assert_eq!(index.lookup_url("https://x.evil.com/login", now)[0].indicator, "evil.com");
+
+        // Expired after the TTL
+        assert!(index.lookup_domain("evil.com", now + Duration::days(31)).is_empty());
+    }
+
+    #[test]
+    fn test_stix_bundle_import() {
+        let bundle = serde_json::json!({
+            "type": "bundle",
+            "objects": [
+                { "type": "indicator", "name": "C2", "pattern_type": "stix",
+                  "pattern": "[domain-name:value = 'c2.bad.net'] OR [ipv4-addr:value = '192.0.2.0/28']",
+                  "valid_until": "2099-01-01T00:00:00Z" },
+                { "type": "indicator", "pattern_type": "stix",
+                  "pattern": format!("[file:hashes.'SHA-256' = '{}']", "b".repeat(64)) },
+                { "type": "indicator", "pattern_type": "sigma", "pattern": "title: x" },
+                { "type": "malware", "name": "x" }
+            ]
+        });
+        let now = Utc::now();
+        let index = index_from(&bundle.to_string(), FeedFormat::Stix, now);
+        let hit = &index.lookup_domain("c2.bad.net", now)[0];
+        assert_eq!(hit.description, "C2");
+        assert_eq!(hit.expires_at.as_deref(), Some("2099-01-01T00:00:00Z"));
+        assert_eq!(index.lookup_ip("192.0.2.9".parse().unwrap(), now).len(), 1);
+        assert_eq!(index.lookup_hash(&"b".repeat(64), now).len(), 1);
+        assert_eq!(detect_format("feed.json", &bundle.to_string()), FeedFormat::Stix);
+    }
+
+    #[test]
+    fn test_misp_event_import() {
+        let event = serde_json::json!({
+            "Event": {
+                "info": "Phishing wave",
+                "Attribute": [
+                    { "type": "domain", "value": "login-update.example", "to_ids": true },
+                    { "type": "ip-dst|port", "value": "203.0.113.50|8443", "to_ids": "1" },
+                    { "type": "comment", "value": "ignore me", "to_ids": false }
+                ],
+                "Object": [{ "Attribute": [
+                    { "type": "filename|sha256", "value": format!("inv.exe|{}", "c".repeat(64)), "to_ids": true }
+                ]}]
+            }
+        });
+        let content = event.to_string();
+        assert_eq!(detect_format("event.json", &content), FeedFormat::Misp);
+        let now = Utc::now();
+        let index = index_from(&content, FeedFormat::Misp, now);
+        assert_eq!(index.lookup_domain("login-update.example", now)[0].description, "Phishing wave");
+        assert_eq!(index.lookup_ip("203.0.113.50".parse().unwrap(), now).len(), 1);
+        assert_eq!(index.lookup_hash(&"c".repeat(64), now).len(), 1);
+    }
+}
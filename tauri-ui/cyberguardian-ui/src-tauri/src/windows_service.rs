@@ -11,6 +11,7 @@ use windows::Win32::System::Services::{
     SERVICE_CONTROL_STOP, SERVICE_RUNNING, SERVICE_QUERY_STATUS,
     SERVICE_START, SERVICE_STOP,
 };
+use tracing::{error, info};
 
 
 /// Service configuration
@@ -19,10 +20,10 @@ const SERVICE_DISPLAY_NAME: PCWSTR = w!("CyberGuardian XDR Desktop Agent");
 
 /// Install CyberGuardian as a Windows Service
 pub fn install_service() -> Result<(), String> {
-    println!("🚀 install_service() CALLED - Starting installation...");
+    info!("install_service() CALLED - Starting installation...");
     
     unsafe {
-        println!("📂 Opening Service Control Manager...");
+        info!("Opening Service Control Manager...");
         
         // Open Service Control Manager
         let scm = OpenSCManagerW(
@@ -31,33 +32,33 @@ pub fn install_service() -> Result<(), String> {
             SC_MANAGER_ALL_ACCESS,
         ).map_err(|e| {
             let msg = format!("Failed to open Service Control Manager: {:?}", e);
-            println!("❌ SCM Error: {}", msg);
+            error!("SCM Error: {}", msg);
             msg
         })?;
 
         if scm.is_invalid() {
-            println!("❌ Invalid SCM handle");
+            error!("Invalid SCM handle");
             return Err("Invalid SCM handle".to_string());
         }
         
-        println!("✅ SCM opened successfully");
+        info!("SCM opened successfully");
 
         // Get current executable path
-        println!("📍 Getting executable path...");
+        info!("Getting executable path...");
         let exe_path = std::env::current_exe()
             .map_err(|e| {
                 let msg = format!("Failed to get executable path: {}", e);
-                println!("❌ {}", msg);
+                error!("{}", msg);
                 msg
             })?;
         
         let exe_path_str = exe_path.to_string_lossy().to_string();
-        println!("✅ Executable path: {}", exe_path_str);
+        info!("Executable path: {}", exe_path_str);
         
         let mut exe_path_wide: Vec<u16> = exe_path_str.encode_utf16().collect();
         exe_path_wide.push(0); // Null terminator
 
-        println!("🔨 Creating service...");
+        info!("Creating service...");
         
         // Create the service
         let service = CreateServiceW(
@@ -82,16 +83,16 @@ pub fn install_service() -> Result<(), String> {
             Ok(service_handle) => {
                 if !service_handle.is_invalid() {
                     let _ = CloseServiceHandle(service_handle);
-                    println!("✅ Service installed successfully: CyberGuardianXDR");
+                    info!("Service installed successfully: CyberGuardianXDR");
                     Ok(())
                 } else {
-                    println!("❌ Invalid service handle");
+                    error!("Invalid service handle");
                     Err("Invalid service handle".to_string())
                 }
             }
             Err(e) => {
                 let msg = format!("Failed to create service: {:?}", e);
-                println!("❌ CreateService Error: {}", msg);
+                error!("CreateService Error: {}", msg);
                 Err(msg)
             }
         }
@@ -135,7 +136,7 @@ pub fn uninstall_service() -> Result<(), String> {
 
                 match result {
                     Ok(_) => {
-                        println!("✅ Service uninstalled successfully");
+                        info!("Service uninstalled successfully");
                         Ok(())
                     }
                     Err(e) => Err(format!("Failed to delete service: {:?}", e))
@@ -175,7 +176,7 @@ pub fn start_service() -> Result<(), String> {
 
                 match result {
                     Ok(_) => {
-                        println!("✅ Service started successfully");
+                        info!("Service started successfully");
                         Ok(())
                     }
                     Err(e) => Err(format!("Failed to start service: {:?}", e))
@@ -224,7 +225,7 @@ unsafe fn stop_service_internal(service_handle: windows::Win32::System::Services
 
     match result {
         Ok(_) => {
-            println!("✅ Service stopped successfully");
+            info!("Service stopped successfully");
             Ok(())
         }
         Err(e) => Err(format!("Failed to stop service: {:?}", e))